@@ -2,16 +2,29 @@ use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
     metadata::{Metadata, MetadataAccount},
-    token::{transfer, Mint, Token, TokenAccount, Transfer},
+    token::Mint,
+    token_interface::{
+        transfer_checked, Mint as MintInterface, TokenAccount, TokenInterface, TransferChecked,
+    },
 };
 
 use crate::{
-    state::{Collection, Emission, RewardType, Staker},
+    state::{Collection, Emission, RewardStep, RewardType, Staker},
     utils::calc_total_emission,
-    StakeError,
+    StakeError, MULTIPLIER_BASE,
 };
 
 #[derive(Accounts)]
+#[instruction(
+    reward_type: RewardType,
+    reward: Option<u64>,
+    start_time: Option<i64>,
+    duration: Option<i64>,
+    minimum_period: Option<i64>,
+    starting_balance: Option<u64>,
+    reward_schedule: Vec<RewardStep>,
+    shared: bool
+)]
 pub struct AddEmission<'info> {
     #[account(
         has_one = authority @ StakeError::Unauthorized
@@ -26,7 +39,10 @@ pub struct AddEmission<'info> {
             collection.collection_mint.as_ref(),
             b"collection"
         ],
-        bump = collection.bump
+        bump = collection.bump,
+        realloc = Collection::LEN,
+        realloc::payer = authority,
+        realloc::zero = false
     )]
     pub collection: Account<'info, Collection>,
 
@@ -47,7 +63,7 @@ pub struct AddEmission<'info> {
     #[account(
         init,
         payer = authority,
-        space = std::mem::size_of::<Emission>()
+        space = std::mem::size_of::<Emission>() + reward_schedule.len() * 16
     )]
     pub emission: Account<'info, Emission>,
 
@@ -55,9 +71,10 @@ pub struct AddEmission<'info> {
         init_if_needed,
         payer = authority,
         associated_token::mint = token_mint,
-        associated_token::authority = token_authority
+        associated_token::authority = token_authority,
+        associated_token::token_program = token_program
     )]
-    pub stake_token_vault: Option<Box<Account<'info, TokenAccount>>>,
+    pub stake_token_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
 
     /// CHECK: This account is not read or written
     #[account(
@@ -71,31 +88,37 @@ pub struct AddEmission<'info> {
     pub token_authority: UncheckedAccount<'info>,
 
     #[account()]
-    pub token_mint: Option<Box<Account<'info, Mint>>>,
+    pub token_mint: Option<Box<InterfaceAccount<'info, MintInterface>>>,
 
     #[account(
         mut,
         associated_token::mint = token_mint,
-        associated_token::authority = authority
+        associated_token::authority = authority,
+        associated_token::token_program = token_program
     )]
-    pub token_account: Option<Box<Account<'info, TokenAccount>>>,
+    pub token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
 
     #[account(mut)]
     pub authority: Signer<'info>,
 
     pub system_program: Program<'info, System>,
-    pub token_program: Program<'info, Token>,
+    pub token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
 }
 
 impl<'info> AddEmission<'info> {
-    pub fn transfer_token_ctx(&self) -> CpiContext<'_, '_, '_, 'info, Transfer<'info>> {
-        let cpi_accounts = Transfer {
+    pub fn transfer_token_ctx(&self) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let cpi_accounts = TransferChecked {
             from: self
                 .token_account
                 .as_ref()
                 .expect("token_account is expected")
                 .to_account_info(),
+            mint: self
+                .token_mint
+                .as_ref()
+                .expect("token_mint is expected")
+                .to_account_info(),
             to: self
                 .stake_token_vault
                 .as_ref()
@@ -108,6 +131,33 @@ impl<'info> AddEmission<'info> {
 
         CpiContext::new(cpi_program, cpi_accounts)
     }
+
+    /// Transfers into the vault, returning the amount received after any transfer fee
+    pub fn transfer_to_vault(&mut self, amount: u64) -> Result<u64> {
+        let decimals = self
+            .token_mint
+            .as_ref()
+            .expect("token_mint is expected")
+            .decimals;
+        let stake_token_vault = self
+            .stake_token_vault
+            .as_mut()
+            .expect("stake_token_vault is expected");
+        let balance_before = stake_token_vault.amount;
+
+        transfer_checked(self.transfer_token_ctx(), amount, decimals)?;
+
+        let stake_token_vault = self
+            .stake_token_vault
+            .as_mut()
+            .expect("stake_token_vault is expected");
+        stake_token_vault.reload()?;
+
+        stake_token_vault
+            .amount
+            .checked_sub(balance_before)
+            .ok_or(error!(StakeError::ProgramSubError))
+    }
 }
 
 pub fn add_emission_handler(
@@ -118,6 +168,8 @@ pub fn add_emission_handler(
     duration: Option<i64>,
     minimum_period: Option<i64>,
     starting_balance: Option<u64>,
+    reward_schedule: Vec<RewardStep>,
+    shared: bool,
 ) -> Result<()> {
     let staker = &ctx.accounts.staker;
     let current_time = Clock::get().unwrap().unix_timestamp;
@@ -137,9 +189,61 @@ pub fn add_emission_handler(
         max_stakers_count, ..
     } = *ctx.accounts.collection;
 
+    let is_pool = collection.is_pool();
+    let stake_unit = collection.stake_unit()?;
+
+    // rewards in the staker's own token follow its vault or mint mode, any other
+    // mint can only be paid from the vault
+    let token_mint = ctx
+        .accounts
+        .token_mint
+        .as_ref()
+        .map(|token_mint| token_mint.key());
+    let token_vault = match token_mint {
+        Some(token_mint) => staker.token_mint != Some(token_mint) || staker.token_vault,
+        None => staker.token_vault,
+    };
+
+    // token pools pay in proportion to the deposited amount, so only token emissions apply
+    if is_pool {
+        require!(
+            matches!(reward_type, RewardType::Token),
+            StakeError::InvalidPoolEmission
+        );
+    }
+
+    // scheduled reward changes apply to the per item reward, so pools can't use them
+    if !reward_schedule.is_empty() {
+        require!(
+            matches!(reward_type, RewardType::Token) && !is_pool,
+            StakeError::InvalidRewardSchedule
+        );
+
+        let mut previous_time = start_time;
+        for step in reward_schedule.iter() {
+            require_gt!(step.time, previous_time, StakeError::InvalidRewardSchedule);
+            previous_time = step.time;
+        }
+
+        if let Some(end_time) = end_time {
+            require_gt!(end_time, previous_time, StakeError::InvalidRewardSchedule);
+        }
+    }
+
+    // a shared emission settles against the stake record's single reward debt, so it has to
+    // be the primary token emission
+    if shared {
+        require!(
+            matches!(reward_type, RewardType::Token)
+                && !is_pool
+                && Option::is_none(&collection.token_emission),
+            StakeError::InvalidSharedEmission
+        );
+    }
+
     match reward_type.clone() {
         RewardType::Selection { options } => {
-            if staker.token_vault {
+            if token_vault {
                 require!(Option::is_some(&duration), StakeError::DurationRequired);
                 require_gt!(duration.unwrap(), 0, StakeError::DurationTooShort);
                 require_gte!(
@@ -192,7 +296,7 @@ pub fn add_emission_handler(
             collection.selection_emission = Some(emission.key());
         }
         RewardType::Token => {
-            if staker.token_vault {
+            if token_vault {
                 require!(Option::is_some(&duration), StakeError::DurationRequired);
                 require_gt!(duration.unwrap(), 0, StakeError::DurationTooShort);
                 require_gte!(
@@ -202,9 +306,10 @@ pub fn add_emission_handler(
                 );
                 require!(Option::is_some(&end_time), StakeError::StakeEndTimeRequired);
             }
+            // pools settle a single emission in claim_pool and unstake_tokens
             require!(
-                Option::is_none(&collection.token_emission),
-                StakeError::SelectionEmissionExists
+                !is_pool || Option::is_none(&collection.token_emission),
+                StakeError::TooManyEmissions
             );
             require!(Option::is_some(&reward), StakeError::RewardRequired);
             require!(
@@ -212,7 +317,7 @@ pub fn add_emission_handler(
                 StakeError::TokenMintRequired
             );
             let collection = &mut ctx.accounts.collection;
-            collection.token_emission = Some(emission.key());
+            collection.add_token_emission(emission.key())?;
         }
         RewardType::Distribution => {
             require!(
@@ -230,7 +335,6 @@ pub fn add_emission_handler(
             let collection = &mut ctx.accounts.collection;
             collection.points_emission = Some(emission.key());
         }
-        _ => {}
     }
 
     let is_token = match reward_type.clone() {
@@ -241,26 +345,29 @@ pub fn add_emission_handler(
 
     let balance_increase: u64 = match reward_type {
         RewardType::Token => {
-            if staker.token_vault {
+            if token_vault && is_pool {
+                // the staked amount isn't capped, so pools are funded with a starting balance
+                require!(starting_balance > Some(0), StakeError::InvalidEmission);
+                ctx.accounts.transfer_to_vault(starting_balance.unwrap())?
+            } else if token_vault {
+                // a shared reward is paid once however many items are staked
                 let total_emission = calc_total_emission(
                     reward.unwrap(),
-                    max_stakers_count,
+                    &reward_schedule,
+                    if shared { 1 } else { max_stakers_count },
                     start_time,
                     end_time.unwrap(),
+                    MULTIPLIER_BASE,
                 )?;
 
-                transfer(ctx.accounts.transfer_token_ctx(), total_emission)?;
-
-                total_emission
+                ctx.accounts.transfer_to_vault(total_emission)?
             } else {
                 0
             }
         }
         RewardType::Selection { options: _ } => {
-            if staker.token_vault {
-                let amount = starting_balance.unwrap();
-                transfer(ctx.accounts.transfer_token_ctx(), amount)?;
-                amount
+            if token_vault {
+                ctx.accounts.transfer_to_vault(starting_balance.unwrap())?
             } else {
                 0
             }
@@ -279,7 +386,16 @@ pub fn add_emission_handler(
         minimum_period,
     );
 
-    if is_token && staker.token_vault {
+    emission.stake_unit = stake_unit;
+    emission.reward_schedule = reward_schedule;
+    emission.shared = shared;
+
+    if is_token {
+        emission.token_mint = token_mint;
+        emission.token_vault = token_vault;
+    }
+
+    if is_token && token_vault {
         emission.increase_current_balance(balance_increase)?;
     }
 
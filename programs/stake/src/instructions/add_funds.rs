@@ -1,5 +1,7 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{transfer, Mint, Token, TokenAccount, Transfer};
+use anchor_spl::token_interface::{
+    transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked,
+};
 
 use crate::{
     state::{Collection, Emission, RewardType, Staker},
@@ -36,21 +38,23 @@ pub struct AddFunds<'info> {
     #[account(
         // address = emission.reward_type.reward_token.unwrap() @ StakeError::InvalidRewardToken
     )]
-    pub reward_mint: Account<'info, Mint>,
+    pub reward_mint: InterfaceAccount<'info, Mint>,
 
     #[account(
         mut,
         associated_token::mint = reward_mint,
-        associated_token::authority = authority
+        associated_token::authority = authority,
+        associated_token::token_program = token_program
     )]
-    pub token_account: Account<'info, TokenAccount>,
+    pub token_account: InterfaceAccount<'info, TokenAccount>,
 
     #[account(
         mut,
         associated_token::mint = reward_mint,
-        associated_token::authority = token_authority
+        associated_token::authority = token_authority,
+        associated_token::token_program = token_program
     )]
-    pub stake_token_vault: Account<'info, TokenAccount>,
+    pub stake_token_vault: InterfaceAccount<'info, TokenAccount>,
 
     /// CHECK: the account is not read or written
     #[account(
@@ -60,13 +64,14 @@ pub struct AddFunds<'info> {
     pub token_authority: UncheckedAccount<'info>,
 
     pub authority: Signer<'info>,
-    pub token_program: Program<'info, Token>,
+    pub token_program: Interface<'info, TokenInterface>,
 }
 
 impl<'info> AddFunds<'info> {
-    pub fn transfer_token_ctx(&self) -> CpiContext<'_, '_, '_, 'info, Transfer<'info>> {
-        let cpi_accounts = Transfer {
+    pub fn transfer_token_ctx(&self) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let cpi_accounts = TransferChecked {
             from: self.token_account.to_account_info(),
+            mint: self.reward_mint.to_account_info(),
             to: self.stake_token_vault.to_account_info(),
             authority: self.authority.to_account_info(),
         };
@@ -75,6 +80,20 @@ impl<'info> AddFunds<'info> {
 
         CpiContext::new(cpi_program, cpi_accounts)
     }
+
+    /// Transfers into the vault, returning the amount received after any transfer fee
+    pub fn transfer_to_vault(&mut self, amount: u64) -> Result<u64> {
+        let balance_before = self.stake_token_vault.amount;
+
+        transfer_checked(self.transfer_token_ctx(), amount, self.reward_mint.decimals)?;
+
+        self.stake_token_vault.reload()?;
+
+        self.stake_token_vault
+            .amount
+            .checked_sub(balance_before)
+            .ok_or(error!(StakeError::ProgramSubError))
+    }
 }
 
 pub fn add_funds_handler(ctx: Context<AddFunds>, amount: u64) -> Result<()> {
@@ -84,9 +103,8 @@ pub fn add_funds_handler(ctx: Context<AddFunds>, amount: u64) -> Result<()> {
 
     require_eq!(status, true, StakeError::StakeInactive);
 
-    require_keys_eq!(
-        collection.token_emission.expect("token_emission expected"),
-        emission.key(),
+    require!(
+        collection.is_token_emission(&emission.key()),
         StakeError::InvalidEmission
     );
 
@@ -98,19 +116,19 @@ pub fn add_funds_handler(ctx: Context<AddFunds>, amount: u64) -> Result<()> {
         }
     }
 
-    require_keys_eq!(
-        emission.token_mint.unwrap(),
-        ctx.accounts.reward_mint.key(),
+    require!(
+        emission.reward_mint(&ctx.accounts.staker) == Some(ctx.accounts.reward_mint.key()),
         StakeError::InvalidRewardToken
     );
 
-    require_keys_neq!(
-        ctx.accounts.reward_mint.key(),
-        ctx.accounts.staker.token_mint.unwrap_or(Pubkey::default()),
+    // emissions minting their rewards have no vault balance to top up
+    require!(
+        emission.is_vault_funded(&ctx.accounts.staker),
         StakeError::InvalidEmission
     );
 
-    transfer(ctx.accounts.transfer_token_ctx(), amount)?;
+    let received = ctx.accounts.transfer_to_vault(amount)?;
+
     let emission = &mut ctx.accounts.emission;
-    emission.increase_current_balance(amount)
+    emission.increase_current_balance(received)
 }
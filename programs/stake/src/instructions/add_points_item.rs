@@ -0,0 +1,166 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::{
+    state::{PointsItem, PointsItemKind, Staker},
+    StakeError,
+};
+
+#[derive(Accounts)]
+pub struct AddPointsItem<'info> {
+    #[account(
+        has_one = authority @ StakeError::Unauthorized
+    )]
+    pub staker: Account<'info, Staker>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = PointsItem::LEN
+    )]
+    pub points_item: Account<'info, PointsItem>,
+
+    pub token_mint: Option<Box<InterfaceAccount<'info, Mint>>>,
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = authority,
+        associated_token::token_program = token_program
+    )]
+    pub token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        associated_token::mint = token_mint,
+        associated_token::authority = token_authority,
+        associated_token::token_program = token_program
+    )]
+    pub stake_token_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// CHECK: This account is not read or written
+    #[account(
+        seeds = [
+            b"STAKE",
+            staker.key().as_ref(),
+            b"token-authority"
+        ],
+        bump = staker.token_auth_bump
+    )]
+    pub token_authority: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+impl<'info> AddPointsItem<'info> {
+    pub fn transfer_token_ctx(&self) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let cpi_accounts = TransferChecked {
+            from: self
+                .token_account
+                .as_ref()
+                .expect("token_account is expected")
+                .to_account_info(),
+            mint: self
+                .token_mint
+                .as_ref()
+                .expect("token_mint is expected")
+                .to_account_info(),
+            to: self
+                .stake_token_vault
+                .as_ref()
+                .expect("stake_token_vault is expected")
+                .to_account_info(),
+            authority: self.authority.to_account_info(),
+        };
+
+        let cpi_program = self.token_program.to_account_info();
+
+        CpiContext::new(cpi_program, cpi_accounts)
+    }
+
+    /// Transfers into the vault, returning the amount received after any transfer fee
+    pub fn transfer_to_vault(&mut self, amount: u64) -> Result<u64> {
+        let decimals = self
+            .token_mint
+            .as_ref()
+            .expect("token_mint is expected")
+            .decimals;
+        let balance_before = self
+            .stake_token_vault
+            .as_ref()
+            .expect("stake_token_vault is expected")
+            .amount;
+
+        transfer_checked(self.transfer_token_ctx(), amount, decimals)?;
+
+        let stake_token_vault = self
+            .stake_token_vault
+            .as_mut()
+            .expect("stake_token_vault is expected");
+        stake_token_vault.reload()?;
+
+        stake_token_vault
+            .amount
+            .checked_sub(balance_before)
+            .ok_or(error!(StakeError::ProgramSubError))
+    }
+}
+
+/// Token bundles are funded for their whole supply up front, so redemptions never
+/// draw on the balances held for emissions
+pub fn add_points_item_handler(
+    ctx: Context<AddPointsItem>,
+    kind: PointsItemKind,
+    price: u64,
+    supply: Option<u64>,
+) -> Result<()> {
+    require_gt!(price, 0, StakeError::InvalidPointsItem);
+
+    let balance = match kind {
+        PointsItemKind::Token { mint, amount } => {
+            require_gt!(amount, 0, StakeError::InvalidPointsItem);
+
+            let supply = supply.ok_or(StakeError::InvalidPointsItem)?;
+            require_gt!(supply, 0, StakeError::InvalidPointsItem);
+
+            let token_mint = ctx
+                .accounts
+                .token_mint
+                .as_ref()
+                .ok_or(StakeError::FundingAccountsRequired)?;
+            require_keys_eq!(token_mint.key(), mint, StakeError::InvalidRewardToken);
+
+            require!(
+                ctx.accounts.token_account.is_some() && ctx.accounts.stake_token_vault.is_some(),
+                StakeError::FundingAccountsRequired
+            );
+
+            let total = amount
+                .checked_mul(supply)
+                .ok_or(StakeError::ProgramMulError)?;
+            let received = ctx.accounts.transfer_to_vault(total)?;
+
+            // transfer fees can leave the vault short of the item's supply
+            require_gte!(received, total, StakeError::InsufficientBalanceInVault);
+
+            received
+        }
+        PointsItemKind::Whitelist => 0,
+    };
+
+    let points_item = &mut ctx.accounts.points_item;
+
+    **points_item = PointsItem::init(ctx.accounts.staker.key(), kind, price, supply);
+    points_item.current_balance = balance;
+
+    Ok(())
+}
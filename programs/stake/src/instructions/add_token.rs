@@ -1,9 +1,9 @@
 use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token::{
-        set_authority, spl_token::instruction::AuthorityType, Mint, SetAuthority, Token,
-        TokenAccount,
+    token_interface::{
+        set_authority, spl_token_2022::instruction::AuthorityType, Mint, SetAuthority,
+        TokenAccount, TokenInterface,
     },
 };
 
@@ -22,21 +22,23 @@ pub struct AddToken<'info> {
     pub staker: Account<'info, Staker>,
 
     #[account(mut)]
-    pub token_mint: Account<'info, Mint>,
+    pub token_mint: InterfaceAccount<'info, Mint>,
 
     #[account(
         associated_token::mint = token_mint,
-        associated_token::authority = authority
+        associated_token::authority = authority,
+        associated_token::token_program = token_program
     )]
-    pub token_account: Account<'info, TokenAccount>,
+    pub token_account: InterfaceAccount<'info, TokenAccount>,
 
     #[account(
         init_if_needed,
         payer = authority,
         associated_token::mint = token_mint,
-        associated_token::authority = token_authority
+        associated_token::authority = token_authority,
+        associated_token::token_program = token_program
     )]
-    pub token_vault: Option<Account<'info, TokenAccount>>,
+    pub token_vault: Option<InterfaceAccount<'info, TokenAccount>>,
 
     /// CHECK: this account is not read or written to
     #[account(
@@ -54,7 +56,7 @@ pub struct AddToken<'info> {
 
     pub system_program: Program<'info, System>,
 
-    pub token_program: Program<'info, Token>,
+    pub token_program: Interface<'info, TokenInterface>,
 
     pub associated_token_program: Program<'info, AssociatedToken>,
 }
@@ -3,7 +3,7 @@ use anchor_lang::prelude::*;
 use crate::{
     state::{Collection, Emission, RewardType, Staker},
     utils::{calc_actual_balance, calc_total_emission},
-    StakeError,
+    StakeError, MULTIPLIER_BASE,
 };
 
 #[derive(Accounts)]
@@ -60,7 +60,7 @@ pub fn change_reward_handler(ctx: Context<ChangeReward>, new_reward: u64) -> Res
     } = **collection;
 
     match emission.reward_type {
-        RewardType::Token => require!(Option::is_some(&emission.token_mint), StakeError::InvalidEmission),
+        RewardType::Token => require!(Option::is_some(&emission.reward_mint(staker)), StakeError::InvalidEmission),
         _ => return err!(StakeError::InvalidEmission),
     }
 
@@ -80,8 +80,42 @@ pub fn change_reward_handler(ctx: Context<ChangeReward>, new_reward: u64) -> Res
 
     require_eq!(staking_status, true, StakeError::StakeInactive);
 
+    // shared emissions have already taken what was earned out of the balance,
+    // so it only has to cover the rest of the budget at the new reward
+    if emission.shared {
+        let token_vault = emission.is_vault_funded(staker);
+        emission.update_reward_per_item(current_time, token_vault)?;
+        emission.apply_due_steps(current_time);
+
+        let new_emission = calc_total_emission(
+            new_reward,
+            &emission.reward_schedule,
+            1,
+            current_time,
+            end_time.expect("expected end date to be set"),
+            MULTIPLIER_BASE,
+        )?;
+
+        require_gte!(
+            emission.current_balance,
+            new_emission,
+            StakeError::InsufficientBalanceInVault
+        );
+
+        emission.change_reward(new_reward, current_time);
+        return Ok(());
+    }
+
+    // token pools and emissions with multipliers or lock tiers weight by the
+    // staked amount rather than the number of stakers
+    let stakers_count = if collection.is_pool() || emission.stake_unit() > 1 {
+        emission.staked_items
+    } else {
+        current_stakers_count
+    };
+
     let (current_actual_balance, _accrued_reward, new_staked_weight) = calc_actual_balance(
-        current_stakers_count,
+        stakers_count,
         staked_weight,
         current_reward,
         last_reward_change_time,
@@ -89,14 +123,33 @@ pub fn change_reward_handler(ctx: Context<ChangeReward>, new_reward: u64) -> Res
         current_time,
         current_balance,
         None,
+        emission.stake_unit(),
+        &emission.reward_schedule,
     )?;
 
-    let new_emission = calc_total_emission(
-        new_reward,
-        max_stakers_count,
-        current_time,
-        end_time.expect("expected end date to be set"),
-    )?;
+    // steps that already took effect become part of the reward history, the
+    // steps still ahead keep applying after the new reward
+    emission.apply_due_steps(current_time);
+
+    let new_emission = if collection.is_pool() {
+        calc_total_emission(
+            emission.reward_for_amount(new_reward, emission.staked_items)?,
+            &[],
+            1,
+            current_time,
+            end_time.expect("expected end date to be set"),
+            MULTIPLIER_BASE,
+        )?
+    } else {
+        calc_total_emission(
+            new_reward,
+            &emission.reward_schedule,
+            max_stakers_count,
+            current_time,
+            end_time.expect("expected end date to be set"),
+            emission.max_multiplier(),
+        )?
+    };
 
     require_gte!(
         current_actual_balance,
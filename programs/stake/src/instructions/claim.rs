@@ -1,11 +1,11 @@
 use anchor_lang::prelude::*;
 use anchor_spl::{
-    token::{ Mint, TokenAccount, Token, Transfer, MintTo, mint_to, transfer},
+    token_interface::{ Mint, TokenAccount, TokenInterface, TransferChecked, MintTo, mint_to, transfer_checked},
     associated_token::AssociatedToken,
 };
 use solana_program::program_option::COption;
 
-use crate::{ state::{ Staker, Collection, ProgramConfig, StakeRecord, RewardType, NftRecord, Subscription, Emission }, StakeError, utils::{ calc_tx_fee, calc_reward}, constants::FEES_WALLET, STAKING_ENDS };
+use crate::{ state::{ Staker, Collection, ProgramConfig, StakeRecord, RewardType, NftRecord, Subscription, Emission, VestingRecord, Distribution }, StakeError, utils::{ calc_tx_fee, calc_reward, RewardAccounts }, constants::FEES_WALLET, STAKING_ENDS };
 
 #[derive(Accounts)]
 pub struct Claim<'info> {
@@ -67,24 +67,74 @@ pub struct Claim<'info> {
 
     #[account(
         mut,
-        address = staker.token_mint.unwrap()
+        address = emission.reward_mint(&staker).unwrap() @ StakeError::InvalidRewardToken
     )]
-    pub token_mint: Option<Box<Account<'info, Mint>>>,
+    pub token_mint: Option<Box<InterfaceAccount<'info, Mint>>>,
 
     #[account(
         mut,
         associated_token::mint = token_mint,
-        associated_token::authority = token_authority
+        associated_token::authority = token_authority,
+        associated_token::token_program = token_program
     )]
-    pub stake_token_vault: Option<Box<Account<'info, TokenAccount>>>,
+    pub stake_token_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
 
     #[account(
         init_if_needed,
         payer = owner,
         associated_token::mint = token_mint,
-        associated_token::authority = owner
+        associated_token::authority = owner,
+        associated_token::token_program = token_program
     )]
-    pub reward_receive_account: Option<Box<Account<'info, TokenAccount>>>,
+    pub reward_receive_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = VestingRecord::LEN,
+        seeds = [
+            b"STAKE",
+            staker.key().as_ref(),
+            owner.key().as_ref(),
+            b"vesting-record",
+        ],
+        bump
+    )]
+    pub vesting_record: Option<Box<Account<'info, VestingRecord>>>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        seeds = [
+            b"STAKE",
+            staker.key().as_ref(),
+            owner.key().as_ref(),
+            b"vesting-vault",
+        ],
+        bump,
+        token::mint = token_mint,
+        token::authority = token_authority,
+        token::token_program = token_program
+    )]
+    pub vesting_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// pro-rata distribution linked to a distribution emission
+    #[account(
+        mut,
+        constraint = emission.distribution == Some(distribution.key()) @ StakeError::DistributionModeMismatch
+    )]
+    pub distribution: Option<Box<Account<'info, Distribution>>>,
+
+    #[account(
+        mut,
+        token::mint = token_mint,
+        token::authority = distribution_vault_authority,
+        token::token_program = token_program
+    )]
+    pub distribution_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// CHECK: checked against the distribution's vault authority when paying
+    pub distribution_vault_authority: Option<UncheckedAccount<'info>>,
 
     /// CHECK: this account is not read or written
     #[account(
@@ -100,15 +150,16 @@ pub struct Claim<'info> {
     #[account(mut)]
     pub owner: Signer<'info>,
 
-    pub token_program: Program<'info, Token>,
+    pub token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
 }
 
 impl<'info> Claim<'info> {
-    pub fn transfer_token_ctx(&self) -> CpiContext<'_, '_, '_, 'info, Transfer<'info>> {
-        let cpi_accounts = Transfer {
+    pub fn transfer_token_ctx(&self) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let cpi_accounts = TransferChecked {
             from: self.stake_token_vault.as_ref().expect("stake_token_vault expected").to_account_info(),
+            mint: self.token_mint.as_ref().expect("token_mint expected").to_account_info(),
             to: self.reward_receive_account.as_ref().expect("reward_receive_account expected").to_account_info(),
             authority: self.token_authority.as_ref().to_account_info(),
         };
@@ -129,9 +180,97 @@ impl<'info> Claim<'info> {
 
         CpiContext::new(cpi_program, cpi_accounts)
     }
+
+    /// Moves the emission's claimed rewards into the owner's vesting vault, released by `withdraw_vested`
+    pub fn vest_rewards(&self, emission: &Emission, amount: u64, signer: &[&[&[u8]]]) -> Result<()> {
+        let vesting_vault = self.vesting_vault.as_ref().ok_or(StakeError::VestingAccountsRequired)?.to_account_info();
+        let mint = self.token_mint.as_ref().ok_or(StakeError::VestingAccountsRequired)?;
+        let authority = self.token_authority.to_account_info();
+        let cpi_program = self.token_program.to_account_info();
+
+        require!(emission.reward_mint(&self.staker) == Some(mint.key()), StakeError::VestingMintMismatch);
+
+        if emission.is_vault_funded(&self.staker) {
+            let cpi_accounts = TransferChecked {
+                from: self.stake_token_vault.as_ref().expect("stake_token_vault expected").to_account_info(),
+                mint: mint.to_account_info(),
+                to: vesting_vault,
+                authority,
+            };
+
+            transfer_checked(CpiContext::new_with_signer(cpi_program, cpi_accounts, signer), amount, mint.decimals)
+        } else {
+            let cpi_accounts = MintTo {
+                mint: mint.to_account_info(),
+                to: vesting_vault,
+                authority,
+            };
+
+            mint_to(CpiContext::new_with_signer(cpi_program, cpi_accounts, signer), amount)
+        }
+    }
+
+    /// Pays the share of a pro-rata distribution the NFT has been owed since it was last settled
+    pub fn pay_distribution(&mut self) -> Result<()> {
+        let current_time = Clock::get().unwrap().unix_timestamp;
+        let owed = self.emission.distribution_owed(self.stake_record.distribution_debt)?;
+        self.stake_record.distribution_debt = self.emission.reward_per_item;
+
+        if owed == 0 {
+            return Ok(());
+        }
+
+        let distribution = self.distribution.as_mut().ok_or(StakeError::DistributionAccountsRequired)?;
+        require!(!distribution.has_ended(current_time), StakeError::DistributionEnded);
+
+        match distribution.token_mint {
+            None => {
+                distribution.sub_lamports(owed)?;
+                self.owner.add_lamports(owed)?;
+            }
+            Some(_) => {
+                let staker_key = self.staker.key();
+                let distribution_key = distribution.key();
+                let authority_seed = &[
+                    &b"STAKE"[..],
+                    staker_key.as_ref(),
+                    distribution_key.as_ref(),
+                    &b"distribution-vault"[..],
+                    &[distribution.vault_authority_bump],
+                ];
+
+                let (Some(distribution_vault), Some(vault_authority), Some(mint), Some(reward_receive_account)) = (
+                    self.distribution_vault.as_ref(),
+                    self.distribution_vault_authority.as_ref(),
+                    self.token_mint.as_ref(),
+                    self.reward_receive_account.as_ref(),
+                ) else {
+                    return err!(StakeError::DistributionAccountsRequired);
+                };
+
+                let vault_authority_key = Pubkey::create_program_address(authority_seed, &crate::ID)
+                    .map_err(|_| StakeError::DistributionAccountsRequired)?;
+                require_keys_eq!(vault_authority.key(), vault_authority_key, StakeError::Unauthorized);
+
+                let cpi_accounts = TransferChecked {
+                    from: distribution_vault.to_account_info(),
+                    mint: mint.to_account_info(),
+                    to: reward_receive_account.to_account_info(),
+                    authority: vault_authority.to_account_info(),
+                };
+                let cpi_program = self.token_program.to_account_info();
+
+                transfer_checked(CpiContext::new_with_signer(cpi_program, cpi_accounts, &[&authority_seed[..]]), owed, mint.decimals)?;
+            }
+        }
+
+        distribution.add_to_claimed(owed);
+
+        Ok(())
+    }
 }
 
-pub fn claim_handler(ctx: Context<Claim>) -> Result<()> {
+pub fn claim_handler<'info>(ctx: Context<'_, '_, 'info, 'info, Claim<'info>>) -> Result<()> {
     let current_time = Clock::get().unwrap().unix_timestamp;
     let staker = &ctx.accounts.staker;
     let collection = &ctx.accounts.collection;
@@ -156,6 +295,7 @@ pub fn claim_handler(ctx: Context<Claim>) -> Result<()> {
     } = ***collection;
 
     require_eq!(collection_status, true, StakeError::CollectionInactive);
+    require!(!stake_record.is_unbonding(), StakeError::Unbonding);
     
     let authority_seed = &[
         &b"STAKE"[..],
@@ -186,9 +326,13 @@ pub fn claim_handler(ctx: Context<Claim>) -> Result<()> {
         reward_record,
         reward_change_time_record,
         end_time,
+        &emission.reward_schedule,
     )
     .unwrap();
 
+    let item_amount = emission.item_amount(stake_record.multiplier, stake_record.lock_boost)?;
+    reward_tokens = emission.reward_for_amount(reward_tokens, item_amount)?;
+
     if !is_eligible_for_reward {
         return err!(StakeError::MinimumPeriodNotReached);
     }
@@ -200,6 +344,12 @@ pub fn claim_handler(ctx: Context<Claim>) -> Result<()> {
                 staked_at,
                 StakeError::StakeOver
             );
+
+            // shared emissions pay the item's share of what the pool earned instead
+            if emission.shared {
+                let token_vault = emission.is_vault_funded(staker);
+                reward_tokens = emission.settle_shared_reward(&mut stake_record.reward_debt, item_amount, current_time, token_vault)?;
+            }
         }
         RewardType::Points => {
             nft_record
@@ -225,13 +375,118 @@ pub fn claim_handler(ctx: Context<Claim>) -> Result<()> {
     };
 
     if is_token && reward_tokens > 0 {
-        if staker.token_vault {
-            emission.decrease_current_balance(staked_at, current_time)?;
+        if emission.is_vault_funded(staker) {
+            emission.decrease_current_balance_for_amount(staked_at, current_time, item_amount)?;
         }
 
         if current_time < emission.end_time.unwrap_or(STAKING_ENDS) {
-            emission.update_staked_weight(staked_at, false)?;
-            emission.update_staked_weight(current_time, true)?;
+            emission.update_staked_weight_for_amount(staked_at, item_amount, false)?;
+            emission.update_staked_weight_for_amount(current_time, item_amount, true)?;
+        }
+    }
+
+    // the record's staked_at is reset below, so the other token emissions
+    // it earns from are settled in the same claim
+    if matches!(reward_type, RewardType::Token) {
+        let emission_key = emission.key();
+        let reward_mint = emission.reward_mint(staker);
+
+        let token_emission_keys: Vec<Pubkey> = collection.token_emissions().into_iter().filter(|key| *key != emission_key && stake_record.emissions.contains(key)).collect();
+
+        for key in token_emission_keys {
+            let account = ctx.remaining_accounts.iter().find(|acc| acc.key() == key).ok_or(StakeError::EmissionsMissing)?;
+            let mut token_emission = Account::<'info, Emission>::try_from(account)?;
+
+            let (reward, _, is_eligible) = calc_reward(
+                staked_at,
+                token_emission.minimum_period.unwrap_or(0),
+                &token_emission.reward,
+                &token_emission.reward_change_time,
+                token_emission.end_time,
+                &token_emission.reward_schedule,
+            )?;
+
+            if !is_eligible {
+                return err!(StakeError::MinimumPeriodNotReached);
+            }
+
+            let amount = token_emission.item_amount(stake_record.multiplier, stake_record.lock_boost)?;
+            let reward = token_emission.reward_for_amount(reward, amount)?;
+
+            let token_vault = token_emission.is_vault_funded(staker);
+
+            let reward = if token_emission.shared {
+                token_emission.settle_shared_reward(&mut stake_record.reward_debt, amount, current_time, token_vault)?
+            } else {
+                reward
+            };
+
+            if reward > 0 && token_emission.end_time.unwrap_or(STAKING_ENDS) >= staked_at {
+                if token_vault {
+                    token_emission.decrease_current_balance_for_amount(staked_at, current_time, amount)?;
+                }
+
+                if current_time < token_emission.end_time.unwrap_or(STAKING_ENDS) {
+                    token_emission.update_staked_weight_for_amount(staked_at, amount, false)?;
+                    token_emission.update_staked_weight_for_amount(current_time, amount, true)?;
+                }
+
+                let token_mint = token_emission.reward_mint(staker).ok_or(StakeError::NoRewardMint)?;
+
+                if Some(token_mint) == reward_mint {
+                    // paid through the named accounts, from the vault only if this emission is funded from it
+                    let receiver = match token_emission.vesting {
+                        Some(vesting) => {
+                            let vesting_record = ctx.accounts.vesting_record.as_mut().ok_or(StakeError::VestingAccountsRequired)?;
+
+                            if vesting_record.owner.eq(&Pubkey::default()) {
+                                ***vesting_record = VestingRecord::init(staker_key, claimer.key(), ctx.bumps.vesting_record);
+                            }
+
+                            vesting_record.deposit(reward, vesting, current_time)?;
+                            ctx.accounts.vesting_vault.as_ref().ok_or(StakeError::VestingAccountsRequired)?.to_account_info()
+                        }
+                        None => ctx.accounts.reward_receive_account.as_ref().ok_or(StakeError::RewardAccountsMissing)?.to_account_info(),
+                    };
+
+                    let reward_accounts = RewardAccounts::named(
+                        ctx.accounts.token_mint.as_ref().ok_or(StakeError::RewardAccountsMissing)?,
+                        ctx.accounts.stake_token_vault.as_ref().map(|vault| vault.to_account_info()),
+                        Some(receiver),
+                        token_vault,
+                    )?;
+
+                    reward_accounts.pay(
+                        reward_accounts.receiver.clone(),
+                        ctx.accounts.token_authority.to_account_info(),
+                        ctx.accounts.token_program.to_account_info(),
+                        reward,
+                        binding,
+                    )?;
+                } else {
+                    // rewards in other mints are paid through accounts found in remaining accounts
+                    require!(token_emission.vesting.is_none(), StakeError::VestingClaimRequired);
+
+                    let reward_accounts = RewardAccounts::find(
+                        ctx.remaining_accounts,
+                        &token_mint,
+                        &claimer.key(),
+                        &ctx.accounts.token_authority.key(),
+                        &ctx.accounts.token_program.key(),
+                        token_vault,
+                    )?;
+
+                    reward_accounts.pay(
+                        reward_accounts.receiver.clone(),
+                        ctx.accounts.token_authority.to_account_info(),
+                        ctx.accounts.token_program.to_account_info(),
+                        reward,
+                        binding,
+                    )?;
+                }
+            }
+
+            token_emission.exit(ctx.program_id)?;
         }
     }
     
@@ -268,13 +523,33 @@ pub fn claim_handler(ctx: Context<Claim>) -> Result<()> {
     }
 
     if is_token && reward_tokens > 0 {
-        if staker.token_vault {
-            transfer(ctx.accounts.transfer_token_ctx().with_signer(binding), reward_tokens)?;
+        if let Some(vesting) = ctx.accounts.emission.vesting {
+            let owner = ctx.accounts.owner.key();
+            let vesting_record = ctx
+                .accounts
+                .vesting_record
+                .as_mut()
+                .ok_or(StakeError::VestingAccountsRequired)?;
+
+            if vesting_record.owner.eq(&Pubkey::default()) {
+                ***vesting_record =
+                    VestingRecord::init(staker_key, owner, ctx.bumps.vesting_record);
+            }
+
+            vesting_record.deposit(reward_tokens, vesting, current_time)?;
+            ctx.accounts.vest_rewards(&ctx.accounts.emission, reward_tokens, binding)?;
+        } else if ctx.accounts.emission.is_vault_funded(staker) {
+            let decimals = ctx.accounts.token_mint.as_ref().expect("token_mint expected").decimals;
+            transfer_checked(ctx.accounts.transfer_token_ctx().with_signer(binding), reward_tokens, decimals)?;
         } else {
             mint_to(ctx.accounts.mint_token_ctx().with_signer(binding), reward_tokens)?;
         }
     }
 
+    if matches!(reward_type, RewardType::Distribution) && ctx.accounts.emission.distribution.is_some() {
+        ctx.accounts.pay_distribution()?;
+    }
+
     // distribution type stakers should not be reset, so as
     // to not lose eligible for reward status if min period.
     match reward_type {
@@ -0,0 +1,523 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{
+        mint_to, transfer_checked, Mint, MintTo, TokenAccount, TokenInterface, TransferChecked,
+    },
+};
+
+use crate::{
+    constants::FEES_WALLET,
+    state::{
+        Collection, Emission, NftRecord, ProgramConfig, RewardType, StakeRecord, Staker,
+        Subscription,
+    },
+    utils::{calc_reward, calc_tx_fee, RewardAccounts},
+    StakeError, STAKING_ENDS,
+};
+
+#[derive(Accounts)]
+pub struct ClaimAll<'info> {
+    #[account(
+        seeds = [b"program-config"],
+        bump
+    )]
+    pub program_config: Box<Account<'info, ProgramConfig>>,
+
+    #[account(mut)]
+    pub staker: Box<Account<'info, Staker>>,
+
+    #[account(
+        seeds = [
+            b"STAKE",
+            staker.key().as_ref(),
+            collection.collection_mint.as_ref(),
+            b"collection",
+        ],
+        bump = collection.bump,
+    )]
+    pub collection: Box<Account<'info, Collection>>,
+
+    #[account(mut, address = FEES_WALLET)]
+    pub fees_wallet: SystemAccount<'info>,
+
+    /// pays emissions rewarding in this mint, others are paid through remaining accounts
+    #[account(mut)]
+    pub token_mint: Option<Box<InterfaceAccount<'info, Mint>>>,
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = token_authority,
+        associated_token::token_program = token_program
+    )]
+    pub stake_token_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        associated_token::mint = token_mint,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program
+    )]
+    pub reward_receive_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// CHECK: this account is not read or written
+    #[account(
+        seeds = [
+            b"STAKE",
+            staker.key().as_ref(),
+            b"token-authority"
+        ],
+        bump = staker.token_auth_bump
+    )]
+    pub token_authority: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> ClaimAll<'info> {
+    pub fn transfer_token_ctx(&self) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let cpi_accounts = TransferChecked {
+            from: self
+                .stake_token_vault
+                .as_ref()
+                .expect("stake_token_vault expected")
+                .to_account_info(),
+            mint: self
+                .token_mint
+                .as_ref()
+                .expect("token_mint expected")
+                .to_account_info(),
+            to: self
+                .reward_receive_account
+                .as_ref()
+                .expect("reward_receive_account expected")
+                .to_account_info(),
+            authority: self.token_authority.to_account_info(),
+        };
+
+        let cpi_program = self.token_program.to_account_info();
+
+        CpiContext::new(cpi_program, cpi_accounts)
+    }
+
+    pub fn mint_token_ctx(&self) -> CpiContext<'_, '_, '_, 'info, MintTo<'info>> {
+        let cpi_accounts = MintTo {
+            mint: self
+                .token_mint
+                .as_ref()
+                .expect("token_mint expected")
+                .to_account_info(),
+            to: self
+                .reward_receive_account
+                .as_ref()
+                .expect("reward_receive_account expected")
+                .to_account_info(),
+            authority: self.token_authority.to_account_info(),
+        };
+
+        let cpi_program = self.token_program.to_account_info();
+
+        CpiContext::new(cpi_program, cpi_accounts)
+    }
+}
+
+/// Claims every emission passed in remaining accounts for every stake record passed in
+/// remaining accounts. Accounts are identified by type, nft records are only required
+/// for stake records using a points emission, along with the points emission itself.
+pub fn claim_all_handler<'info>(ctx: Context<'_, '_, 'info, 'info, ClaimAll<'info>>) -> Result<()> {
+    let current_time = Clock::get().unwrap().unix_timestamp;
+    let staker = &ctx.accounts.staker;
+    let collection = &ctx.accounts.collection;
+    let staker_key = staker.key();
+    let owner = ctx.accounts.owner.key();
+
+    let Staker {
+        is_active: staking_status,
+        token_auth_bump,
+        ..
+    } = ***staker;
+
+    require_eq!(staking_status, true, StakeError::StakeInactive);
+
+    let Collection {
+        is_active: collection_status,
+        ..
+    } = ***collection;
+
+    require_eq!(collection_status, true, StakeError::CollectionInactive);
+
+    let mut emissions: Vec<Account<'info, Emission>> = vec![];
+    let mut stake_records: Vec<Account<'info, StakeRecord>> = vec![];
+    let mut nft_records: Vec<Account<'info, NftRecord>> = vec![];
+
+    for account in ctx.remaining_accounts.iter() {
+        if let Ok(emission) = Account::<'info, Emission>::try_from(account) {
+            require_keys_eq!(
+                emission.collection,
+                collection.key(),
+                StakeError::InvalidEmission
+            );
+            emissions.push(emission);
+        } else if let Ok(stake_record) = Account::<'info, StakeRecord>::try_from(account) {
+            let stake_record_key = Pubkey::create_program_address(
+                &[
+                    b"STAKE",
+                    staker_key.as_ref(),
+                    stake_record.nft_mint.as_ref(),
+                    b"stake-record",
+                    &[stake_record.bump],
+                ],
+                ctx.program_id,
+            )
+            .map_err(|_| StakeError::UnexpectedRemainingAccounts)?;
+
+            require_keys_eq!(
+                stake_record.key(),
+                stake_record_key,
+                StakeError::UnexpectedRemainingAccounts
+            );
+            require_keys_eq!(stake_record.owner, owner, StakeError::Unauthorized);
+            require!(!stake_record.is_unbonding(), StakeError::Unbonding);
+            stake_records.push(stake_record);
+        } else if let Ok(nft_record) = Account::<'info, NftRecord>::try_from(account) {
+            let nft_record_key = Pubkey::create_program_address(
+                &[
+                    b"STAKE",
+                    staker_key.as_ref(),
+                    nft_record.nft_mint.as_ref(),
+                    b"nft-record",
+                    &[nft_record.bump],
+                ],
+                ctx.program_id,
+            )
+            .map_err(|_| StakeError::UnexpectedRemainingAccounts)?;
+
+            require_keys_eq!(
+                nft_record.key(),
+                nft_record_key,
+                StakeError::UnexpectedRemainingAccounts
+            );
+            nft_records.push(nft_record);
+        } else if account.owner == &ctx.accounts.token_program.key() {
+            // mints and token accounts for rewards in other mints
+            continue;
+        } else {
+            return err!(StakeError::UnexpectedRemainingAccounts);
+        }
+    }
+
+    // each record can only be passed once, otherwise it would be paid out more than once
+    let mut keys: Vec<Pubkey> = ctx.remaining_accounts.iter().map(|acc| acc.key()).collect();
+    keys.sort();
+    keys.dedup();
+    require_eq!(
+        keys.len(),
+        ctx.remaining_accounts.len(),
+        StakeError::UnexpectedRemainingAccounts
+    );
+
+    require!(!emissions.is_empty(), StakeError::EmissionsMissing);
+    require!(
+        emissions.iter().all(|emission| emission.vesting.is_none()),
+        StakeError::VestingClaimRequired
+    );
+    require!(
+        !stake_records.is_empty(),
+        StakeError::UnexpectedRemainingAccounts
+    );
+
+    let token_mint = ctx
+        .accounts
+        .token_mint
+        .as_ref()
+        .map(|token_mint| token_mint.key());
+
+    // emissions sharing a mint share its vault or mint mode
+    let token_mint_vault = emissions
+        .iter()
+        .find(|emission| token_mint.is_some() && emission.reward_mint(staker) == token_mint)
+        .map(|emission| emission.is_vault_funded(staker))
+        .unwrap_or(staker.token_vault);
+
+    let mut reward_tokens_total: u64 = 0;
+    // totals for rewards in mints other than `token_mint`
+    let mut other_rewards: Vec<(Pubkey, bool, u64)> = vec![];
+    let mut sol_total: u64 = 0;
+
+    for stake_record in stake_records.iter_mut() {
+        let StakeRecord {
+            staked_at,
+            can_claim_at,
+            pending_claim,
+            sol_balance,
+            ..
+        } = **stake_record;
+
+        // staked_at is reset for the whole record, so none of its token or points emissions can be
+        // left out, nor the nft record its points are credited to
+        require!(
+            stake_record
+                .emissions
+                .iter()
+                .filter(|key| {
+                    collection.is_token_emission(key) || collection.points_emission == Some(**key)
+                })
+                .all(|key| emissions.iter().any(|emission| emission.key() == *key)),
+            StakeError::EmissionsMissing
+        );
+
+        if collection
+            .points_emission
+            .is_some_and(|points_emission| stake_record.emissions.contains(&points_emission))
+        {
+            require!(
+                nft_records
+                    .iter()
+                    .any(|nft_record| nft_record.nft_mint == stake_record.nft_mint),
+                StakeError::NftRecordRequired
+            );
+        }
+
+        let mut reset_staked_at = false;
+        let mut claim_sol = false;
+
+        for emission in emissions.iter_mut() {
+            if !stake_record.emissions.contains(&emission.key()) {
+                continue;
+            }
+
+            let Emission {
+                end_time,
+                minimum_period,
+                ..
+            } = **emission;
+
+            let (reward_tokens, current_time, is_eligible_for_reward) = calc_reward(
+                staked_at,
+                minimum_period.unwrap_or(0),
+                &emission.reward,
+                &emission.reward_change_time,
+                end_time,
+                &emission.reward_schedule,
+            )?;
+
+            let item_amount =
+                emission.item_amount(stake_record.multiplier, stake_record.lock_boost)?;
+            let reward_tokens = emission.reward_for_amount(reward_tokens, item_amount)?;
+
+            // ineligible records are skipped so they don't block the rest of the batch
+            if !is_eligible_for_reward {
+                continue;
+            }
+
+            let reward_tokens = match emission.reward_type {
+                RewardType::Token => {
+                    if end_time.unwrap_or(STAKING_ENDS) < staked_at {
+                        continue;
+                    }
+
+                    // shared emissions pay the item's share of what the pool earned instead
+                    if emission.shared {
+                        let token_vault = emission.is_vault_funded(staker);
+                        emission.settle_shared_reward(
+                            &mut stake_record.reward_debt,
+                            item_amount,
+                            current_time,
+                            token_vault,
+                        )?
+                    } else {
+                        reward_tokens
+                    }
+                }
+                RewardType::Points => {
+                    let nft_record = nft_records
+                        .iter_mut()
+                        .find(|nft_record| nft_record.nft_mint == stake_record.nft_mint)
+                        .ok_or(StakeError::NftRecordRequired)?;
+
+                    nft_record.add_points(reward_tokens)?;
+                    reset_staked_at = true;
+                    continue;
+                }
+                RewardType::Selection { options: _ } => {
+                    if current_time < can_claim_at {
+                        continue;
+                    }
+                    pending_claim
+                }
+                RewardType::Distribution => {
+                    claim_sol = true;
+                    continue;
+                }
+            };
+
+            reset_staked_at = true;
+
+            if reward_tokens == 0 {
+                continue;
+            }
+
+            let token_vault = emission.is_vault_funded(staker);
+
+            if token_vault {
+                emission.decrease_current_balance_for_amount(
+                    staked_at,
+                    current_time,
+                    item_amount,
+                )?;
+            }
+
+            if current_time < end_time.unwrap_or(STAKING_ENDS) {
+                emission.update_staked_weight_for_amount(staked_at, item_amount, false)?;
+                emission.update_staked_weight_for_amount(current_time, item_amount, true)?;
+            }
+
+            if matches!(emission.reward_type, RewardType::Selection { options: _ }) {
+                stake_record.pending_claim = 0;
+            }
+
+            let reward_mint = emission
+                .reward_mint(staker)
+                .ok_or(StakeError::NoRewardMint)?;
+
+            if Some(reward_mint) == token_mint {
+                reward_tokens_total = reward_tokens_total
+                    .checked_add(reward_tokens)
+                    .ok_or(StakeError::ProgramAddError)?;
+            } else {
+                match other_rewards
+                    .iter_mut()
+                    .find(|(mint, _, _)| *mint == reward_mint)
+                {
+                    Some((_, _, total)) => {
+                        *total = total
+                            .checked_add(reward_tokens)
+                            .ok_or(StakeError::ProgramAddError)?;
+                    }
+                    None => other_rewards.push((reward_mint, token_vault, reward_tokens)),
+                }
+            }
+        }
+
+        // distribution type stakers should not be reset, so as
+        // to not lose eligible for reward status if min period.
+        if reset_staked_at {
+            stake_record.staked_at = current_time;
+        }
+
+        if claim_sol && sol_balance > 0 {
+            stake_record.sub_lamports(sol_balance)?;
+            stake_record.sol_balance = 0;
+            sol_total = sol_total
+                .checked_add(sol_balance)
+                .ok_or(StakeError::ProgramAddError)?;
+        }
+    }
+
+    if sol_total > 0 {
+        ctx.accounts.owner.add_lamports(sol_total)?;
+    }
+
+    let tx_fee = match staker.get_subscription() {
+        Subscription::Custom {
+            amount: _,
+            stake_fee: _,
+            unstake_fee: _,
+            claim_fee,
+        } => claim_fee,
+        _ => ctx.accounts.program_config.claim_fee,
+    };
+
+    let tx_fee = if ctx.accounts.program_config.claim_fee_per_record {
+        calc_tx_fee(staker, tx_fee)
+            .checked_mul(stake_records.len() as u64)
+            .ok_or(StakeError::ProgramMulError)?
+    } else {
+        calc_tx_fee(staker, tx_fee)
+    };
+
+    if tx_fee > 0 {
+        let ix = anchor_lang::solana_program::system_instruction::transfer(
+            &ctx.accounts.owner.key(),
+            &ctx.accounts.fees_wallet.key(),
+            tx_fee,
+        );
+
+        anchor_lang::solana_program::program::invoke(
+            &ix,
+            &[
+                ctx.accounts.owner.to_account_info(),
+                ctx.accounts.fees_wallet.to_account_info(),
+            ],
+        )?;
+    }
+
+    let authority_seed = &[
+        &b"STAKE"[..],
+        staker_key.as_ref(),
+        &b"token-authority"[..],
+        &[token_auth_bump],
+    ];
+
+    let binding = &[&authority_seed[..]];
+
+    // token rewards are paid out in a single transfer or mint per reward mint
+    if reward_tokens_total > 0 {
+        if token_mint_vault {
+            transfer_checked(
+                ctx.accounts.transfer_token_ctx().with_signer(binding),
+                reward_tokens_total,
+                ctx.accounts
+                    .token_mint
+                    .as_ref()
+                    .expect("token_mint expected")
+                    .decimals,
+            )?;
+        } else {
+            mint_to(
+                ctx.accounts.mint_token_ctx().with_signer(binding),
+                reward_tokens_total,
+            )?;
+        }
+    }
+
+    for (reward_mint, token_vault, total) in other_rewards {
+        let reward_accounts = RewardAccounts::find(
+            ctx.remaining_accounts,
+            &reward_mint,
+            &owner,
+            &ctx.accounts.token_authority.key(),
+            &ctx.accounts.token_program.key(),
+            token_vault,
+        )?;
+
+        reward_accounts.pay(
+            reward_accounts.receiver.clone(),
+            ctx.accounts.token_authority.to_account_info(),
+            ctx.accounts.token_program.to_account_info(),
+            total,
+            binding,
+        )?;
+    }
+
+    for emission in emissions.iter() {
+        emission.exit(ctx.program_id)?;
+    }
+
+    for stake_record in stake_records.iter() {
+        stake_record.exit(ctx.program_id)?;
+    }
+
+    for nft_record in nft_records.iter() {
+        nft_record.exit(ctx.program_id)?;
+    }
+
+    Ok(())
+}
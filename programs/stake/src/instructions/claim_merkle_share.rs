@@ -0,0 +1,204 @@
+use anchor_lang::{prelude::*, solana_program::keccak::hashv};
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::{
+    state::{ClaimBitmap, Distribution, StakeRecord, Staker},
+    utils::verify_merkle_proof,
+    StakeError,
+};
+
+#[derive(Accounts)]
+pub struct ClaimMerkleShare<'info> {
+    pub staker: Box<Account<'info, Staker>>,
+
+    #[account(
+        mut,
+        has_one = staker,
+        constraint = distribution.active @ StakeError::DistributionInactive
+    )]
+    pub distribution: Box<Account<'info, Distribution>>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"STAKE",
+            distribution.key().as_ref(),
+            b"claim-bitmap"
+        ],
+        bump = claim_bitmap.bump,
+        has_one = distribution
+    )]
+    pub claim_bitmap: Box<Account<'info, ClaimBitmap>>,
+
+    /// proves ownership of a staked NFT, for leaves keyed by mint
+    #[account(
+        seeds = [
+            b"STAKE",
+            staker.key().as_ref(),
+            stake_record.nft_mint.as_ref(),
+            b"stake-record",
+        ],
+        bump = stake_record.bump,
+        has_one = owner @ StakeError::Unauthorized
+    )]
+    pub stake_record: Option<Box<Account<'info, StakeRecord>>>,
+
+    /// proves ownership of an NFT held in the owner's wallet, for leaves keyed by mint
+    #[account(
+        constraint = nft_token.owner == owner.key() @ StakeError::Unauthorized,
+        constraint = nft_token.amount == 1 @ StakeError::TokenAccountEmpty
+    )]
+    pub nft_token: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(
+        constraint = distribution.token_mint == Some(token_mint.key()) @ StakeError::InvalidRewardToken
+    )]
+    pub token_mint: Option<Box<InterfaceAccount<'info, Mint>>>,
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = vault_authority,
+        associated_token::token_program = token_program
+    )]
+    pub token_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        associated_token::mint = token_mint,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program
+    )]
+    pub reward_receive_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// CHECK: this account is not read or written to
+    #[account(
+        seeds = [
+            b"STAKE",
+            staker.key().as_ref(),
+            distribution.key().as_ref(),
+            b"distribution-vault"
+        ],
+        bump = distribution.vault_authority_bump
+    )]
+    pub vault_authority: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+
+    pub associated_token_program: Option<Program<'info, AssociatedToken>>,
+}
+
+impl<'info> ClaimMerkleShare<'info> {
+    /// The key the leaf was issued to: an NFT mint when ownership of one is proven,
+    /// otherwise the owner's wallet
+    pub fn claimant(&self) -> Pubkey {
+        if let Some(stake_record) = &self.stake_record {
+            stake_record.nft_mint
+        } else if let Some(nft_token) = &self.nft_token {
+            nft_token.mint
+        } else {
+            self.owner.key()
+        }
+    }
+
+    pub fn transfer_token_ctx(
+        &self,
+    ) -> Result<CpiContext<'_, '_, '_, 'info, TransferChecked<'info>>> {
+        let (Some(token_mint), Some(token_vault), Some(receive_account), Some(token_program)) = (
+            self.token_mint.as_ref(),
+            self.token_vault.as_ref(),
+            self.reward_receive_account.as_ref(),
+            self.token_program.as_ref(),
+        ) else {
+            return err!(StakeError::DistributionAccountsRequired);
+        };
+
+        let cpi_accounts = TransferChecked {
+            from: token_vault.to_account_info(),
+            mint: token_mint.to_account_info(),
+            to: receive_account.to_account_info(),
+            authority: self.vault_authority.to_account_info(),
+        };
+
+        let cpi_program = token_program.to_account_info();
+        Ok(CpiContext::new(cpi_program, cpi_accounts))
+    }
+}
+
+pub fn claim_merkle_share_handler(
+    ctx: Context<ClaimMerkleShare>,
+    index: u32,
+    amount: u64,
+    proof: Vec<[u8; 32]>,
+) -> Result<()> {
+    let current_time = Clock::get().unwrap().unix_timestamp;
+    let distribution = &ctx.accounts.distribution;
+    let merkle_root = distribution.merkle_root.ok_or(StakeError::NoMerkleRoot)?;
+
+    require!(
+        !distribution.has_ended(current_time),
+        StakeError::DistributionEnded
+    );
+
+    let leaf = hashv(&[
+        &index.to_le_bytes(),
+        ctx.accounts.claimant().as_ref(),
+        &amount.to_le_bytes(),
+    ])
+    .to_bytes();
+
+    require!(
+        verify_merkle_proof(&proof, merkle_root.to_bytes(), leaf),
+        StakeError::InvalidProof
+    );
+
+    ctx.accounts.claim_bitmap.set_claimed(index)?;
+
+    if distribution.token_mint.is_none() {
+        ctx.accounts.distribution.sub_lamports(amount)?;
+        ctx.accounts.owner.add_lamports(amount)?;
+    } else {
+        let decimals = ctx
+            .accounts
+            .token_mint
+            .as_ref()
+            .ok_or(StakeError::DistributionAccountsRequired)?
+            .decimals;
+
+        let staker_key = ctx.accounts.staker.key();
+        let distribution_key = distribution.key();
+        let vault_authority_bump = distribution.vault_authority_bump;
+
+        let authority_seed = &[
+            &b"STAKE"[..],
+            staker_key.as_ref(),
+            distribution_key.as_ref(),
+            &b"distribution-vault"[..],
+            &[vault_authority_bump],
+        ];
+
+        transfer_checked(
+            ctx.accounts
+                .transfer_token_ctx()?
+                .with_signer(&[&authority_seed[..]]),
+            amount,
+            decimals,
+        )?;
+    }
+
+    // airdrop shares are funded up front, so claims count towards progress
+    let distribution = &mut ctx.accounts.distribution;
+    distribution.iterate_funded();
+    distribution.add_to_claimed(amount);
+
+    Ok(())
+}
@@ -0,0 +1,228 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{
+        mint_to, transfer_checked, Mint, MintTo, TokenAccount, TokenInterface, TransferChecked,
+    },
+};
+
+use crate::{
+    constants::FEES_WALLET,
+    state::{Collection, Emission, PoolRecord, ProgramConfig, Staker, Subscription},
+    utils::calc_tx_fee,
+    StakeError,
+};
+
+#[derive(Accounts)]
+pub struct ClaimPool<'info> {
+    #[account(
+        seeds = [b"program-config"],
+        bump
+    )]
+    pub program_config: Box<Account<'info, ProgramConfig>>,
+
+    pub staker: Box<Account<'info, Staker>>,
+
+    #[account(
+        seeds = [
+            b"STAKE",
+            staker.key().as_ref(),
+            collection.collection_mint.as_ref(),
+            b"collection",
+        ],
+        bump = collection.bump,
+        constraint = collection.is_pool() @ StakeError::NotATokenPool
+    )]
+    pub collection: Box<Account<'info, Collection>>,
+
+    #[account(
+        mut,
+        constraint = pool_record.emission == Some(emission.key()) @ StakeError::InvalidEmission
+    )]
+    pub emission: Box<Account<'info, Emission>>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"STAKE",
+            staker.key().as_ref(),
+            collection.key().as_ref(),
+            owner.key().as_ref(),
+            b"pool-record",
+        ],
+        bump = pool_record.bump,
+        has_one = owner @ StakeError::Unauthorized,
+    )]
+    pub pool_record: Box<Account<'info, PoolRecord>>,
+
+    #[account(
+        mut,
+        address = emission.reward_mint(&staker).unwrap_or_default() @ StakeError::InvalidRewardToken
+    )]
+    pub token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = token_authority,
+        associated_token::token_program = token_program
+    )]
+    pub stake_token_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        associated_token::mint = token_mint,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program
+    )]
+    pub reward_receive_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// CHECK: this account is not read or written
+    #[account(
+        seeds = [
+            b"STAKE",
+            staker.key().as_ref(),
+            b"token-authority"
+        ],
+        bump = staker.token_auth_bump
+    )]
+    pub token_authority: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(mut, address = FEES_WALLET)]
+    pub fees_wallet: SystemAccount<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> ClaimPool<'info> {
+    pub fn transfer_token_ctx(&self) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let cpi_accounts = TransferChecked {
+            from: self
+                .stake_token_vault
+                .as_ref()
+                .expect("stake_token_vault expected")
+                .to_account_info(),
+            mint: self.token_mint.to_account_info(),
+            to: self.reward_receive_account.to_account_info(),
+            authority: self.token_authority.to_account_info(),
+        };
+
+        let cpi_program = self.token_program.to_account_info();
+
+        CpiContext::new(cpi_program, cpi_accounts)
+    }
+
+    pub fn mint_token_ctx(&self) -> CpiContext<'_, '_, '_, 'info, MintTo<'info>> {
+        let cpi_accounts = MintTo {
+            mint: self.token_mint.to_account_info(),
+            to: self.reward_receive_account.to_account_info(),
+            authority: self.token_authority.to_account_info(),
+        };
+
+        let cpi_program = self.token_program.to_account_info();
+
+        CpiContext::new(cpi_program, cpi_accounts)
+    }
+}
+
+pub fn claim_pool_handler(ctx: Context<ClaimPool>) -> Result<()> {
+    let staker = &ctx.accounts.staker;
+    let staker_key = staker.key();
+    let current_time = Clock::get().unwrap().unix_timestamp;
+
+    let Staker {
+        is_active: staking_status,
+        token_auth_bump,
+        ..
+    } = **staker.as_ref();
+
+    require_eq!(staking_status, true, StakeError::StakeInactive);
+    require_eq!(
+        ctx.accounts.collection.is_active,
+        true,
+        StakeError::CollectionInactive
+    );
+
+    let tx_fee = match staker.get_subscription() {
+        Subscription::Custom {
+            amount: _,
+            stake_fee: _,
+            unstake_fee: _,
+            claim_fee,
+        } => claim_fee,
+        _ => ctx.accounts.program_config.claim_fee,
+    };
+
+    let tx_fee = calc_tx_fee(staker, tx_fee);
+
+    let token_vault = ctx.accounts.emission.is_vault_funded(staker);
+
+    let pool_record = &mut ctx.accounts.pool_record;
+    let emission = &mut ctx.accounts.emission;
+
+    let reward_eligible_time = pool_record
+        .staked_at
+        .checked_add(emission.minimum_period.unwrap_or(0))
+        .ok_or(StakeError::ProgramAddError)?;
+
+    require_gte!(
+        current_time,
+        reward_eligible_time,
+        StakeError::MinimumPeriodNotReached
+    );
+
+    pool_record.settle_rewards(emission, current_time, token_vault)?;
+
+    let reward_tokens = pool_record.pending_claim;
+    pool_record.pending_claim = 0;
+
+    require_gt!(reward_tokens, 0, StakeError::NoTokensToClaim);
+
+    let token_auth_seed = &[
+        &b"STAKE"[..],
+        staker_key.as_ref(),
+        &b"token-authority"[..],
+        &[token_auth_bump],
+    ];
+
+    if token_vault {
+        transfer_checked(
+            ctx.accounts
+                .transfer_token_ctx()
+                .with_signer(&[&token_auth_seed[..]]),
+            reward_tokens,
+            ctx.accounts.token_mint.decimals,
+        )?;
+    } else {
+        mint_to(
+            ctx.accounts
+                .mint_token_ctx()
+                .with_signer(&[&token_auth_seed[..]]),
+            reward_tokens,
+        )?;
+    }
+
+    if tx_fee > 0 {
+        let ix = anchor_lang::solana_program::system_instruction::transfer(
+            &ctx.accounts.owner.key(),
+            &ctx.accounts.fees_wallet.key(),
+            tx_fee,
+        );
+
+        anchor_lang::solana_program::program::invoke(
+            &ix,
+            &[
+                ctx.accounts.owner.to_account_info(),
+                ctx.accounts.fees_wallet.to_account_info(),
+            ],
+        )?;
+    }
+
+    Ok(())
+}
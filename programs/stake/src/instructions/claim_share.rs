@@ -0,0 +1,147 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::{
+    state::{Distribution, ShareRecord, Staker},
+    StakeError,
+};
+
+#[derive(Accounts)]
+pub struct ClaimShare<'info> {
+    pub staker: Account<'info, Staker>,
+
+    #[account(
+        mut,
+        has_one = staker,
+        constraint = distribution.active @ StakeError::DistributionInactive
+    )]
+    pub distribution: Account<'info, Distribution>,
+
+    #[account(
+        mut,
+        has_one = distribution,
+        has_one = owner @ StakeError::Unauthorized,
+        close = owner
+    )]
+    pub share_record: Account<'info, ShareRecord>,
+
+    #[account(
+        constraint = distribution.token_mint == Some(token_mint.key()) @ StakeError::InvalidRewardToken
+    )]
+    pub token_mint: Option<Box<InterfaceAccount<'info, Mint>>>,
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = vault_authority,
+        associated_token::token_program = token_program
+    )]
+    pub token_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        associated_token::mint = token_mint,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program
+    )]
+    pub reward_receive_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// CHECK: this account is not read or written to
+    #[account(
+        seeds = [
+            b"STAKE",
+            staker.key().as_ref(),
+            distribution.key().as_ref(),
+            b"distribution-vault"
+        ],
+        bump = distribution.vault_authority_bump
+    )]
+    pub vault_authority: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+
+    pub associated_token_program: Option<Program<'info, AssociatedToken>>,
+}
+
+impl<'info> ClaimShare<'info> {
+    pub fn transfer_token_ctx(
+        &self,
+    ) -> Result<CpiContext<'_, '_, '_, 'info, TransferChecked<'info>>> {
+        let (Some(token_mint), Some(token_vault), Some(receive_account), Some(token_program)) = (
+            self.token_mint.as_ref(),
+            self.token_vault.as_ref(),
+            self.reward_receive_account.as_ref(),
+            self.token_program.as_ref(),
+        ) else {
+            return err!(StakeError::DistributionAccountsRequired);
+        };
+
+        let cpi_accounts = TransferChecked {
+            from: token_vault.to_account_info(),
+            mint: token_mint.to_account_info(),
+            to: receive_account.to_account_info(),
+            authority: self.vault_authority.to_account_info(),
+        };
+
+        let cpi_program = token_program.to_account_info();
+        Ok(CpiContext::new(cpi_program, cpi_accounts))
+    }
+}
+
+pub fn claim_share_handler(ctx: Context<ClaimShare>) -> Result<()> {
+    let current_time = Clock::get().unwrap().unix_timestamp;
+    let distribution = &ctx.accounts.distribution;
+    let amount = ctx.accounts.share_record.amount;
+
+    require!(
+        !distribution.has_ended(current_time),
+        StakeError::DistributionEnded
+    );
+
+    // SOL shares hold their amount as lamports on the share record, which are paid out when it
+    // closes to the owner. Token shares are paid from the distribution vault.
+    if distribution.token_mint.is_some() {
+        let decimals = ctx
+            .accounts
+            .token_mint
+            .as_ref()
+            .ok_or(StakeError::DistributionAccountsRequired)?
+            .decimals;
+
+        let staker_key = ctx.accounts.staker.key();
+        let distribution_key = distribution.key();
+        let vault_authority_bump = distribution.vault_authority_bump;
+
+        let authority_seed = &[
+            &b"STAKE"[..],
+            staker_key.as_ref(),
+            distribution_key.as_ref(),
+            &b"distribution-vault"[..],
+            &[vault_authority_bump],
+        ];
+
+        transfer_checked(
+            ctx.accounts
+                .transfer_token_ctx()?
+                .with_signer(&[&authority_seed[..]]),
+            amount,
+            decimals,
+        )?;
+    }
+
+    let distribution = &mut ctx.accounts.distribution;
+
+    distribution.add_to_claimed(amount);
+    distribution.close_share_record();
+
+    Ok(())
+}
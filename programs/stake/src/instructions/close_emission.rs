@@ -1,7 +1,6 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{
-    mint_to, set_authority, spl_token::instruction::AuthorityType, transfer, Mint, MintTo,
-    SetAuthority, Token, TokenAccount, Transfer,
+use anchor_spl::token_interface::{
+    transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked,
 };
 
 use crate::{
@@ -38,23 +37,25 @@ pub struct CloseEmission<'info> {
 
     #[account(
         mut,
-        address = staker.token_mint.unwrap() @ StakeError::InvalidRewardToken
+        address = emission.reward_mint(&staker).unwrap_or_default() @ StakeError::InvalidRewardToken
     )]
-    pub token_mint: Option<Account<'info, Mint>>,
+    pub token_mint: Option<InterfaceAccount<'info, Mint>>,
 
     #[account(
         mut,
         associated_token::mint = token_mint,
-        associated_token::authority = authority
+        associated_token::authority = authority,
+        associated_token::token_program = token_program
     )]
-    pub token_account: Option<Account<'info, TokenAccount>>,
+    pub token_account: Option<InterfaceAccount<'info, TokenAccount>>,
 
     #[account(
         mut,
         associated_token::mint = token_mint,
         associated_token::authority = token_authority,
+        associated_token::token_program = token_program
     )]
-    pub stake_token_vault: Option<Account<'info, TokenAccount>>,
+    pub stake_token_vault: Option<InterfaceAccount<'info, TokenAccount>>,
 
     /// CHECK: This account is not read or written
     #[account(
@@ -69,18 +70,23 @@ pub struct CloseEmission<'info> {
 
     #[account(mut)]
     pub authority: Signer<'info>,
-    pub token_program: Program<'info, Token>,
+    pub token_program: Interface<'info, TokenInterface>,
     pub system_program: Program<'info, System>,
 }
 
 impl<'info> CloseEmission<'info> {
-    pub fn transfer_token_ctx(&self) -> CpiContext<'_, '_, '_, 'info, Transfer<'info>> {
-        let cpi_accounts = Transfer {
+    pub fn transfer_token_ctx(&self) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let cpi_accounts = TransferChecked {
             from: self
                 .stake_token_vault
                 .as_ref()
                 .expect("stake_token_vault expected")
                 .to_account_info(),
+            mint: self
+                .token_mint
+                .as_ref()
+                .expect("token_mint expected")
+                .to_account_info(),
             to: self
                 .token_account
                 .as_ref()
@@ -98,10 +104,17 @@ impl<'info> CloseEmission<'info> {
 pub fn close_emission_handler<'info>(
     ctx: Context<'_, '_, 'info, 'info, CloseEmission<'info>>,
 ) -> Result<()> {
+    let current_time = Clock::get().unwrap().unix_timestamp;
+
+    // shared emissions keep back what the staked items have earned up to now
+    let token_vault = ctx.accounts.emission.is_vault_funded(&ctx.accounts.staker);
+    ctx.accounts
+        .emission
+        .update_reward_per_item(current_time, token_vault)?;
+
     let staker = &ctx.accounts.staker;
     let collection = &ctx.accounts.collection;
     let emission = &ctx.accounts.emission;
-    let current_time = Clock::get().unwrap().unix_timestamp;
 
     let Staker {
         token_auth_bump, ..
@@ -126,6 +139,7 @@ pub fn close_emission_handler<'info>(
         &[token_auth_bump],
     ];
 
+    let token_vault = emission.is_vault_funded(staker);
     let mut tokens_to_reclaim: u64 = 0;
 
     match reward_type {
@@ -133,26 +147,38 @@ pub fn close_emission_handler<'info>(
             // require_eq!(emission.staked_items, 0, StakeError::CollectionHasStakers);
         }
         RewardType::Token => {
-            if staker.token_vault {
+            if token_vault {
                 // require_eq!(emission.staked_items, 0, StakeError::CollectionHasStakers);
 
-                let (current_actual_balance, _accrued_reward, _new_staked_weight) =
-                    calc_actual_balance(
-                        staked_items,
-                        staked_weight,
-                        current_reward,
-                        last_reward_change_time,
-                        end_time,
-                        current_time,
-                        emission.current_balance,
-                        None,
-                    )?;
+                let current_actual_balance = if emission.shared {
+                    emission.current_balance
+                } else {
+                    let (current_actual_balance, _accrued_reward, _new_staked_weight) =
+                        calc_actual_balance(
+                            staked_items,
+                            staked_weight,
+                            current_reward,
+                            last_reward_change_time,
+                            end_time,
+                            current_time,
+                            emission.current_balance,
+                            None,
+                            emission.stake_unit(),
+                            &emission.reward_schedule,
+                        )?;
+                    current_actual_balance
+                };
                 if current_actual_balance > 0 {
-                    transfer(
+                    transfer_checked(
                         ctx.accounts
                             .transfer_token_ctx()
                             .with_signer(&[&token_auth_seed[..]]),
                         current_actual_balance,
+                        ctx.accounts
+                            .token_mint
+                            .as_ref()
+                            .expect("token_mint expected")
+                            .decimals,
                     )?;
                     tokens_to_reclaim = current_actual_balance;
                 }
@@ -174,9 +200,9 @@ pub fn close_emission_handler<'info>(
             collection.points_emission = None;
         }
         RewardType::Token => {
-            collection.token_emission = None;
+            collection.remove_token_emission(&ctx.accounts.emission.key());
 
-            if staker.token_vault && tokens_to_reclaim > 0 {
+            if token_vault && tokens_to_reclaim > 0 {
                 let emission = &mut ctx.accounts.emission;
                 emission.current_balance = emission
                     .current_balance
@@ -2,6 +2,9 @@ use anchor_lang::{
     prelude::*,
     system_program::{transfer, Transfer},
 };
+use anchor_spl::token_interface::{
+    transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked,
+};
 
 use crate::{
     state::{Collection, Distribution, ShareRecord, StakeRecord, Staker},
@@ -19,7 +22,9 @@ pub struct Distribute<'info> {
     #[account(
         mut,
         has_one = staker,
-        constraint = distribution.shares_funded < distribution.num_shares @ StakeError::TotalSharesFunded
+        constraint = distribution.shares_funded < distribution.num_shares @ StakeError::TotalSharesFunded,
+        constraint = distribution.merkle_root.is_none() @ StakeError::MerkleDistribution,
+        constraint = distribution.emission.is_none() @ StakeError::DistributionModeMismatch
     )]
     pub distribution: Account<'info, Distribution>,
 
@@ -60,10 +65,45 @@ pub struct Distribute<'info> {
     )]
     pub collection: Account<'info, Collection>,
 
+    #[account(
+        constraint = distribution.token_mint == Some(token_mint.key()) @ StakeError::InvalidRewardToken
+    )]
+    pub token_mint: Option<Box<InterfaceAccount<'info, Mint>>>,
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = authority,
+        associated_token::token_program = token_program
+    )]
+    pub token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = vault_authority,
+        associated_token::token_program = token_program
+    )]
+    pub token_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// CHECK: this account is not read or written to
+    #[account(
+        seeds = [
+            b"STAKE",
+            staker.key().as_ref(),
+            distribution.key().as_ref(),
+            b"distribution-vault"
+        ],
+        bump = distribution.vault_authority_bump
+    )]
+    pub vault_authority: UncheckedAccount<'info>,
+
     #[account(mut)]
     pub authority: Signer<'info>,
 
     pub system_program: Program<'info, System>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
 }
 
 impl<'info> Distribute<'info> {
@@ -76,19 +116,57 @@ impl<'info> Distribute<'info> {
         let cpi_program = self.system_program.to_account_info();
         CpiContext::new(cpi_program, cpi_accounts)
     }
+
+    /// Transfers `amount` into the distribution vault, returning what the vault received
+    /// after any transfer fee
+    pub fn transfer_to_vault(&mut self, amount: u64) -> Result<u64> {
+        let (Some(token_mint), Some(token_account), Some(token_vault), Some(token_program)) = (
+            self.token_mint.as_ref(),
+            self.token_account.as_ref(),
+            self.token_vault.as_mut(),
+            self.token_program.as_ref(),
+        ) else {
+            return err!(StakeError::DistributionAccountsRequired);
+        };
+
+        let balance_before = token_vault.amount;
+
+        let cpi_accounts = TransferChecked {
+            from: token_account.to_account_info(),
+            mint: token_mint.to_account_info(),
+            to: token_vault.to_account_info(),
+            authority: self.authority.to_account_info(),
+        };
+
+        transfer_checked(
+            CpiContext::new(token_program.to_account_info(), cpi_accounts),
+            amount,
+            token_mint.decimals,
+        )?;
+
+        token_vault.reload()?;
+
+        token_vault
+            .amount
+            .checked_sub(balance_before)
+            .ok_or(error!(StakeError::ProgramSubError))
+    }
 }
 
+/// Funds a share for the stake record's owner. Token shares are recorded net of any transfer fee
 pub fn distribute_handler(ctx: Context<Distribute>, amount: u64) -> Result<()> {
     let distribution = &ctx.accounts.distribution;
 
-    if !Option::is_some(&distribution.token_mint) {
+    let amount = if !Option::is_some(&distribution.token_mint) {
         let to_transfer: u64 = amount
             .checked_sub(ctx.accounts.share_record.get_lamports())
             .ok_or(StakeError::ProgramSubError)?;
 
         transfer(ctx.accounts.distribute_ctx(), to_transfer)?;
+        amount
     } else {
-    }
+        ctx.accounts.transfer_to_vault(amount)?
+    };
 
     let share_record = &mut ctx.accounts.share_record;
 
@@ -102,6 +180,7 @@ pub fn distribute_handler(ctx: Context<Distribute>, amount: u64) -> Result<()> {
     let distribution = &mut ctx.accounts.distribution;
 
     distribution.iterate_funded();
+    distribution.open_share_record();
     distribution.add_to_total(amount);
 
     Ok(())
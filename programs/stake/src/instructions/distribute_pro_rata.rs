@@ -0,0 +1,169 @@
+use anchor_lang::{
+    prelude::*,
+    system_program::{self, Transfer},
+};
+use anchor_spl::token_interface::{
+    transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked,
+};
+
+use crate::{
+    state::{Collection, Distribution, Emission, Staker},
+    StakeError,
+};
+
+#[derive(Accounts)]
+pub struct DistributeProRata<'info> {
+    #[account(
+        has_one = authority @ StakeError::Unauthorized
+    )]
+    pub staker: Box<Account<'info, Staker>>,
+
+    #[account(
+        has_one = staker,
+    )]
+    pub collection: Box<Account<'info, Collection>>,
+
+    #[account(
+        mut,
+        constraint = collection.distribution_emission == Some(emission.key()) @ StakeError::InvalidEmission,
+        realloc = emission.current_len(),
+        realloc::payer = authority,
+        realloc::zero = false
+    )]
+    pub emission: Box<Account<'info, Emission>>,
+
+    #[account(
+        mut,
+        has_one = staker,
+        has_one = collection,
+        constraint = distribution.merkle_root.is_none() && distribution.shares_funded == 0 @ StakeError::DistributionModeMismatch
+    )]
+    pub distribution: Box<Account<'info, Distribution>>,
+
+    #[account(
+        constraint = distribution.token_mint == Some(token_mint.key()) @ StakeError::InvalidRewardToken
+    )]
+    pub token_mint: Option<Box<InterfaceAccount<'info, Mint>>>,
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = authority,
+        associated_token::token_program = token_program
+    )]
+    pub token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = vault_authority,
+        associated_token::token_program = token_program
+    )]
+    pub token_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// CHECK: this account is not read or written to
+    #[account(
+        seeds = [
+            b"STAKE",
+            staker.key().as_ref(),
+            distribution.key().as_ref(),
+            b"distribution-vault"
+        ],
+        bump = distribution.vault_authority_bump
+    )]
+    pub vault_authority: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+}
+
+impl<'info> DistributeProRata<'info> {
+    pub fn transfer_sol_ctx(&self) -> CpiContext<'_, '_, '_, 'info, Transfer<'info>> {
+        let cpi_accounts = Transfer {
+            from: self.authority.to_account_info(),
+            to: self.distribution.to_account_info(),
+        };
+
+        let cpi_program = self.system_program.to_account_info();
+        CpiContext::new(cpi_program, cpi_accounts)
+    }
+
+    /// Transfers `amount` into the distribution vault, returning what the vault received
+    /// after any transfer fee
+    pub fn transfer_to_vault(&mut self, amount: u64) -> Result<u64> {
+        let (Some(token_mint), Some(token_account), Some(token_vault), Some(token_program)) = (
+            self.token_mint.as_ref(),
+            self.token_account.as_ref(),
+            self.token_vault.as_mut(),
+            self.token_program.as_ref(),
+        ) else {
+            return err!(StakeError::DistributionAccountsRequired);
+        };
+
+        let balance_before = token_vault.amount;
+
+        let cpi_accounts = TransferChecked {
+            from: token_account.to_account_info(),
+            mint: token_mint.to_account_info(),
+            to: token_vault.to_account_info(),
+            authority: self.authority.to_account_info(),
+        };
+
+        transfer_checked(
+            CpiContext::new(token_program.to_account_info(), cpi_accounts),
+            amount,
+            token_mint.decimals,
+        )?;
+
+        token_vault.reload()?;
+
+        token_vault
+            .amount
+            .checked_sub(balance_before)
+            .ok_or(error!(StakeError::ProgramSubError))
+    }
+}
+
+/// Splits `amount` evenly between the NFTs staked right now, which pull their share in `claim`.
+/// The first deposit links the distribution to the collection's distribution emission, so the
+/// emission pays in the distribution's token from then on. Shares still unclaimed when an NFT
+/// unstakes are paid out as it leaves the emission, cNFTs have to claim theirs first
+pub fn distribute_pro_rata_handler(ctx: Context<DistributeProRata>, amount: u64) -> Result<()> {
+    let distribution_key = ctx.accounts.distribution.key();
+    let emission_key = ctx.accounts.emission.key();
+
+    let linked_distribution = ctx
+        .accounts
+        .emission
+        .distribution
+        .unwrap_or(distribution_key);
+    let linked_emission = ctx.accounts.distribution.emission.unwrap_or(emission_key);
+
+    require!(
+        linked_distribution == distribution_key && linked_emission == emission_key,
+        StakeError::DistributionModeMismatch
+    );
+
+    // token distributions share what the vault received, net of any transfer fee
+    let amount = if ctx.accounts.distribution.token_mint.is_none() {
+        system_program::transfer(ctx.accounts.transfer_sol_ctx(), amount)?;
+        amount
+    } else {
+        ctx.accounts.transfer_to_vault(amount)?
+    };
+
+    let distribution = &mut ctx.accounts.distribution;
+    distribution.emission = Some(emission_key);
+    distribution.add_to_total(amount);
+
+    let emission = &mut ctx.accounts.emission;
+    if emission.distribution.is_none() {
+        emission.link_distribution(distribution_key);
+    }
+    emission.token_mint = distribution.token_mint;
+    emission.add_distribution(amount)
+}
@@ -3,7 +3,7 @@ use anchor_lang::prelude::*;
 use crate::{
     state::{Collection, Emission, RewardType, Staker},
     utils::{calc_actual_balance, calc_total_emission},
-    StakeError,
+    StakeError, MULTIPLIER_BASE,
 };
 
 #[derive(Accounts)]
@@ -82,8 +82,45 @@ pub fn extend_emission_handler(ctx: Context<ExtendEmission>, new_ending_time: i6
         StakeError::InvalidStakeEndTime
     );
 
+    // shared emissions have already taken what was earned out of the balance,
+    // so it only has to cover the rest of the budget up to the new end
+    if emission.shared {
+        let emission = &mut ctx.accounts.emission;
+        let token_vault = emission.is_vault_funded(staker);
+        emission.update_reward_per_item(current_time, token_vault)?;
+
+        let new_emission = calc_total_emission(
+            current_reward,
+            &emission.reward_schedule,
+            1,
+            current_time,
+            new_ending_time,
+            MULTIPLIER_BASE,
+        )?;
+
+        require_gte!(
+            emission.current_balance,
+            new_emission,
+            StakeError::InsufficientBalanceInVault
+        );
+
+        // nothing was funded between the old end and now, so nothing is earned for it
+        emission.reward_per_item_updated_at = current_time;
+        emission.extend_staking(new_ending_time);
+
+        return Ok(());
+    }
+
+    // token pools and emissions with multipliers or lock tiers weight by the
+    // staked amount rather than the number of stakers
+    let stakers_count = if collection.is_pool() || emission.stake_unit() > 1 {
+        emission.staked_items
+    } else {
+        current_stakers_count
+    };
+
     let (current_actual_balance, _accrued_reward, new_staked_weight) = calc_actual_balance(
-        current_stakers_count,
+        stakers_count,
         staked_weight,
         current_reward,
         last_reward_change_time,
@@ -91,14 +128,29 @@ pub fn extend_emission_handler(ctx: Context<ExtendEmission>, new_ending_time: i6
         current_time,
         current_balance,
         Some(new_ending_time),
+        emission.stake_unit(),
+        &emission.reward_schedule,
     )?;
 
-    let new_emission = calc_total_emission(
-        current_reward,
-        max_stakers_count,
-        current_time,
-        new_ending_time,
-    )?;
+    let new_emission = if collection.is_pool() {
+        calc_total_emission(
+            emission.reward_for_amount(current_reward, emission.staked_items)?,
+            &[],
+            1,
+            current_time,
+            new_ending_time,
+            MULTIPLIER_BASE,
+        )?
+    } else {
+        calc_total_emission(
+            current_reward,
+            &emission.reward_schedule,
+            max_stakers_count,
+            current_time,
+            new_ending_time,
+            emission.max_multiplier(),
+        )?
+    };
 
     require_gte!(
         current_actual_balance,
@@ -11,15 +11,14 @@ use anchor_spl::{
         },
         Metadata, MetadataAccount, TokenRecordAccount,
     },
-    token::{
-        close_account, mint_to, transfer, CloseAccount, Mint, MintTo, Token, TokenAccount, Transfer,
-    },
+    token::{close_account, CloseAccount, Mint, Token, TokenAccount},
+    token_interface::{self, TokenInterface},
 };
 
 use crate::{
     constants::FEES_WALLET,
     state::{Collection, Emission, NftRecord, ProgramConfig, StakeRecord, Staker, Subscription},
-    utils::{calc_reward, calc_tx_fee},
+    utils::{calc_reward, calc_tx_fee, settle_distribution, RewardAccounts},
     StakeError,
 };
 
@@ -78,22 +77,24 @@ pub struct ForceUnstake<'info> {
     pub nft_record: Option<Box<Account<'info, NftRecord>>>,
 
     #[account(mut)]
-    pub reward_mint: Option<Box<Account<'info, Mint>>>,
+    pub reward_mint: Option<Box<InterfaceAccount<'info, token_interface::Mint>>>,
 
     #[account(
         mut,
         associated_token::mint = reward_mint,
-        associated_token::authority = token_authority
+        associated_token::authority = token_authority,
+        associated_token::token_program = reward_token_program
     )]
-    pub stake_token_vault: Option<Box<Account<'info, TokenAccount>>>,
+    pub stake_token_vault: Option<Box<InterfaceAccount<'info, token_interface::TokenAccount>>>,
 
     #[account(
         init_if_needed,
         payer = authority,
         associated_token::mint = reward_mint,
-        associated_token::authority = owner
+        associated_token::authority = owner,
+        associated_token::token_program = reward_token_program
     )]
-    pub reward_receive_account: Option<Box<Account<'info, TokenAccount>>>,
+    pub reward_receive_account: Option<Box<InterfaceAccount<'info, token_interface::TokenAccount>>>,
 
     #[account(
         mint::decimals = 0,
@@ -165,6 +166,7 @@ pub struct ForceUnstake<'info> {
     pub authority: Signer<'info>,
 
     pub token_program: Program<'info, Token>,
+    pub reward_token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
     pub metadata_program: Program<'info, Metadata>,
@@ -177,52 +179,42 @@ pub struct ForceUnstake<'info> {
 }
 
 impl<'info> ForceUnstake<'info> {
-    pub fn transfer_token_ctx(&self) -> CpiContext<'_, '_, '_, 'info, Transfer<'info>> {
-        let cpi_accounts = Transfer {
-            from: self
-                .stake_token_vault
-                .as_ref()
-                .expect("stake_token_vault missing")
-                .to_account_info(),
-            to: self
-                .reward_receive_account
-                .as_ref()
-                .expect("reward_receive_account missing")
-                .to_account_info(),
-            authority: self
-                .token_authority
-                .as_ref()
-                .expect("token_authority expected")
-                .to_account_info(),
-        };
-
-        let cpi_program = self.token_program.to_account_info();
-
-        CpiContext::new(cpi_program, cpi_accounts)
-    }
-
-    pub fn mint_token_ctx(&self) -> CpiContext<'_, '_, '_, 'info, MintTo<'info>> {
-        let cpi_accounts = MintTo {
-            mint: self
-                .reward_mint
-                .as_ref()
-                .expect("reward_mint expected")
-                .to_account_info(),
-            to: self
-                .reward_receive_account
-                .as_ref()
-                .expect("reward_receive_account expected")
-                .to_account_info(),
-            authority: self
-                .token_authority
-                .as_ref()
-                .expect("token_authority expected")
-                .to_account_info(),
-        };
-
-        let cpi_program = self.token_program.to_account_info();
-
-        CpiContext::new(cpi_program, cpi_accounts)
+    /// Accounts paying the emission's rewards, the named reward accounts when it pays in
+    /// `reward_mint`, otherwise accounts passed in remaining accounts
+    pub fn reward_accounts(
+        &self,
+        emission: &Emission,
+        remaining_accounts: &[AccountInfo<'info>],
+    ) -> Result<RewardAccounts<'info>> {
+        let mint = emission
+            .reward_mint(&self.staker)
+            .ok_or(StakeError::NoRewardMint)?;
+        let token_vault = emission.is_vault_funded(&self.staker);
+
+        match &self.reward_mint {
+            Some(reward_mint) if reward_mint.key() == mint => RewardAccounts::named(
+                reward_mint,
+                self.stake_token_vault
+                    .as_ref()
+                    .map(|vault| vault.to_account_info()),
+                self.reward_receive_account
+                    .as_ref()
+                    .map(|receiver| receiver.to_account_info()),
+                token_vault,
+            ),
+            _ => RewardAccounts::find(
+                remaining_accounts,
+                &mint,
+                &self.owner.key(),
+                &self
+                    .token_authority
+                    .as_ref()
+                    .ok_or(StakeError::RewardAccountsMissing)?
+                    .key(),
+                &self.reward_token_program.key(),
+                token_vault,
+            ),
+        }
     }
 
     pub fn close_account_ctx(&self) -> CpiContext<'_, '_, '_, 'info, CloseAccount<'info>> {
@@ -382,31 +374,32 @@ pub fn force_unstake_handler<'info>(
 
     let binding = &[&authority_seed[..]];
 
+    let token_authority = ctx
+        .accounts
+        .token_authority
+        .as_ref()
+        .map(|token_authority| token_authority.to_account_info());
+
     let staked_at = stake_record.staked_at;
 
     if stake_record.can_claim_at > current_time {
         return err!(StakeError::MinimumPeriodNotReached);
     }
 
-    if stake_record
-        .emissions
-        .contains(&collection.token_emission.unwrap_or(Pubkey::default()))
+    for token_emission_key in collection
+        .token_emissions()
+        .into_iter()
+        .filter(|key| stake_record.emissions.contains(key))
     {
         let account = ctx
             .remaining_accounts
             .iter()
-            .find(|acc| collection.token_emission.unwrap().to_bytes() == acc.key().to_bytes())
-            .unwrap();
+            .find(|acc| token_emission_key == acc.key())
+            .ok_or(StakeError::EmissionsMissing)?;
 
         let mut token_emission =
             Account::<'info, Emission>::try_from(account).expect("Expected emission to be passed");
 
-        require_keys_eq!(
-            token_emission.key(),
-            collection.token_emission.unwrap(),
-            StakeError::InvalidEmission
-        );
-
         let Emission {
             end_time,
             minimum_period,
@@ -416,31 +409,56 @@ pub fn force_unstake_handler<'info>(
         let reward_record = &token_emission.reward;
         let reward_change_time_record = &token_emission.reward_change_time;
 
-        let (mut reward_tokens, current_time, is_eligible_for_reward) = calc_reward(
+        let (reward_tokens, current_time, is_eligible_for_reward) = calc_reward(
             staked_at,
             minimum_period.unwrap_or(0),
             reward_record,
             reward_change_time_record,
             end_time,
+            &token_emission.reward_schedule,
         )
         .unwrap();
 
+        let amount =
+            token_emission.item_amount(stake_record.multiplier, stake_record.lock_boost)?;
+        let reward_tokens = token_emission.reward_for_amount(reward_tokens, amount)?;
+
+        // shared emissions pay the item's share of what the pool earned instead
+        let reward_tokens = if token_emission.shared {
+            let mut reward_debt = stake_record.reward_debt;
+            let token_vault = token_emission.is_vault_funded(staker);
+            token_emission.settle_shared_reward(
+                &mut reward_debt,
+                amount,
+                current_time,
+                token_vault,
+            )?
+        } else {
+            reward_tokens
+        };
+
         if is_eligible_for_reward && reward_tokens > 0 {
-            if staker.token_vault {
-                transfer(
-                    ctx.accounts.transfer_token_ctx().with_signer(binding),
-                    reward_tokens,
-                )?;
-                token_emission.decrease_current_balance(staked_at, current_time)?;
-            } else {
-                mint_to(
-                    ctx.accounts.mint_token_ctx().with_signer(binding),
-                    reward_tokens,
+            let reward_accounts = ctx
+                .accounts
+                .reward_accounts(&token_emission, ctx.remaining_accounts)?;
+            reward_accounts.pay(
+                reward_accounts.receiver.clone(),
+                token_authority.clone().expect("token_authority expected"),
+                ctx.accounts.reward_token_program.to_account_info(),
+                reward_tokens,
+                binding,
+            )?;
+
+            if token_emission.is_vault_funded(staker) {
+                token_emission.decrease_current_balance_for_amount(
+                    staked_at,
+                    current_time,
+                    amount,
                 )?;
             }
         }
 
-        token_emission.decrease_staked_items()?;
+        token_emission.decrease_staked_amount(amount)?;
 
         token_emission.exit(ctx.program_id)?;
     }
@@ -467,50 +485,66 @@ pub fn force_unstake_handler<'info>(
             StakeError::InvalidEmission
         );
 
+        settle_distribution(
+            &distribution_emission,
+            stake_record.distribution_debt,
+            &staker_key,
+            &ctx.accounts.owner.to_account_info(),
+            &ctx.accounts.reward_token_program.to_account_info(),
+            ctx.remaining_accounts,
+            current_time,
+        )?;
+
         distribution_emission.decrease_staked_items()?;
 
         distribution_emission.exit(ctx.program_id)?;
     }
 
-    if stake_record
-        .emissions
-        .contains(&collection.points_emission.unwrap_or(Pubkey::default()))
+    if let Some(points_emission_key) = collection
+        .points_emission
+        .filter(|key| stake_record.emissions.contains(key))
     {
-        // let account = ctx
-        //     .remaining_accounts
-        //     .iter()
-        //     .find(|acc| collection.points_emission.unwrap().to_bytes() == acc.key().to_bytes())
-        //     .unwrap();
-
-        // let mut points_emission =
-        //     Account::<'info, Emission>::try_from(account).expect("Expected emission to be passed");
-
-        // require_keys_eq!(
-        //     points_emission.key(),
-        //     collection.points_emission.unwrap(),
-        //     StakeError::InvalidEmission
-        // );
-
-        // let Emission {
-        //     end_time,
-        //     minimum_period,
-        //     token_vault,
-        //     ..
-        // } = *points_emission;
-
-        // let reward_record = &points_emission.reward;
-        // let reward_change_time_record = &points_emission.reward_change_time;
-
-        // let (mut reward_tokens, current_time, is_eligible_for_reward) = calc_reward(
-        //     staked_at,
-        //     minimum_period.unwrap_or(0),
-        //     reward_record,
-        //     reward_change_time_record,
-        //     end_time,
-        // )
-        // .unwrap();
-
-        // nft_record.unwrap().add_points(reward_tokens);
+        let account = ctx
+            .remaining_accounts
+            .iter()
+            .find(|acc| points_emission_key == acc.key())
+            .ok_or(StakeError::EmissionsMissing)?;
+
+        let mut points_emission = Account::<'info, Emission>::try_from(account)?;
+
+        let Emission {
+            end_time,
+            minimum_period,
+            ..
+        } = *points_emission;
+
+        let (points, _, is_eligible_for_reward) = calc_reward(
+            staked_at,
+            minimum_period.unwrap_or(0),
+            &points_emission.reward,
+            &points_emission.reward_change_time,
+            end_time,
+            &points_emission.reward_schedule,
+        )?;
+
+        let amount =
+            points_emission.item_amount(stake_record.multiplier, stake_record.lock_boost)?;
+        let points = points_emission.reward_for_amount(points, amount)?;
+
+        // points accrued since the last claim are credited before the stake record closes
+        let nft_record = ctx
+            .accounts
+            .nft_record
+            .as_mut()
+            .ok_or(StakeError::NftRecordRequired)?;
+
+        if is_eligible_for_reward && points > 0 {
+            nft_record.add_points(points)?;
+        }
+
+        points_emission.decrease_staked_amount(amount)?;
+
+        points_emission.exit(ctx.program_id)?;
     }
 
     if stake_record
@@ -545,17 +579,19 @@ pub fn force_unstake_handler<'info>(
         } = *selection_emission;
 
         if pending_claim > 0 {
-            if staker.token_vault {
-                transfer(
-                    ctx.accounts.transfer_token_ctx().with_signer(binding),
-                    pending_claim,
-                )?;
+            let reward_accounts = ctx
+                .accounts
+                .reward_accounts(&selection_emission, ctx.remaining_accounts)?;
+            reward_accounts.pay(
+                reward_accounts.receiver.clone(),
+                token_authority.clone().expect("token_authority expected"),
+                ctx.accounts.reward_token_program.to_account_info(),
+                pending_claim,
+                binding,
+            )?;
+
+            if selection_emission.is_vault_funded(staker) {
                 selection_emission.decrease_current_balance(staked_at, current_time)?;
-            } else {
-                mint_to(
-                    ctx.accounts.mint_token_ctx().with_signer(binding),
-                    pending_claim,
-                )?;
             }
         }
 
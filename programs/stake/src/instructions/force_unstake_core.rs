@@ -0,0 +1,439 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::{
+    constants::FEES_WALLET,
+    state::{
+        Collection, Emission, NftRecord, ProgramConfig, RewardType, StakeRecord, Staker,
+        Subscription,
+    },
+    utils::{
+        calc_reward, calc_tx_fee, settle_distribution, CoreAccounts, RewardAccounts, MPL_CORE_ID,
+    },
+    StakeError,
+};
+
+#[derive(Accounts)]
+pub struct ForceUnstakeCore<'info> {
+    #[account(
+        seeds = [b"program-config"],
+        bump
+    )]
+    pub program_config: Box<Account<'info, ProgramConfig>>,
+
+    #[account(
+        mut,
+        has_one = authority @ StakeError::Unauthorized
+    )]
+    pub staker: Box<Account<'info, Staker>>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"STAKE",
+            staker.key().as_ref(),
+            collection.collection_mint.as_ref(),
+            b"collection",
+        ],
+        bump = collection.bump
+    )]
+    pub collection: Box<Account<'info, Collection>>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"STAKE",
+            staker.key().as_ref(),
+            stake_record.nft_mint.as_ref(),
+            b"stake-record",
+        ],
+        bump = stake_record.bump,
+        has_one = owner @ StakeError::Unauthorized,
+        close = owner
+    )]
+    pub stake_record: Box<Account<'info, StakeRecord>>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"STAKE",
+            staker.key().as_ref(),
+            stake_record.nft_mint.as_ref(),
+            b"nft-record",
+        ],
+        bump = nft_record.bump,
+    )]
+    pub nft_record: Option<Box<Account<'info, NftRecord>>>,
+
+    #[account(mut)]
+    pub reward_mint: Option<Box<InterfaceAccount<'info, Mint>>>,
+
+    #[account(
+        mut,
+        associated_token::mint = reward_mint,
+        associated_token::authority = token_authority,
+        associated_token::token_program = token_program
+    )]
+    pub stake_token_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        associated_token::mint = reward_mint,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program
+    )]
+    pub reward_receive_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// CHECK: this account is constrained to a specific address
+    #[account(mut, address = FEES_WALLET)]
+    pub fees_wallet: SystemAccount<'info>,
+
+    /// CHECK: this account is not read or written
+    #[account(
+        seeds = [
+            b"STAKE",
+            staker.key().as_ref(),
+            b"token-authority",
+        ],
+        bump = staker.token_auth_bump
+    )]
+    pub token_authority: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: this account is not read or written
+    #[account(
+        seeds = [
+            b"STAKE",
+            staker.key().as_ref(),
+            b"nft-authority",
+        ],
+        bump = staker.nft_auth_bump
+    )]
+    pub nft_authority: UncheckedAccount<'info>,
+
+    /// CHECK: this account is deserialized and checked in the handler
+    #[account(mut, address = stake_record.nft_mint @ StakeError::InvalidAsset)]
+    pub asset: UncheckedAccount<'info>,
+
+    /// CHECK: this account is constrained to the collection and checked in CPI calls
+    #[account(mut, address = collection.collection_mint)]
+    pub core_collection: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub owner: SystemAccount<'info>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// CHECK: this account is constrained to a specific address
+    #[account(address = MPL_CORE_ID)]
+    pub core_program: UncheckedAccount<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> ForceUnstakeCore<'info> {
+    /// Accounts paying the emission's rewards, the named reward accounts when it pays in
+    /// `reward_mint`, otherwise accounts passed in remaining accounts
+    pub fn reward_accounts(
+        &self,
+        emission: &Emission,
+        remaining_accounts: &[AccountInfo<'info>],
+    ) -> Result<RewardAccounts<'info>> {
+        let mint = emission
+            .reward_mint(&self.staker)
+            .ok_or(StakeError::NoRewardMint)?;
+        let token_vault = emission.is_vault_funded(&self.staker);
+
+        match &self.reward_mint {
+            Some(reward_mint) if reward_mint.key() == mint => RewardAccounts::named(
+                reward_mint,
+                self.stake_token_vault
+                    .as_ref()
+                    .map(|vault| vault.to_account_info()),
+                self.reward_receive_account
+                    .as_ref()
+                    .map(|receiver| receiver.to_account_info()),
+                token_vault,
+            ),
+            _ => RewardAccounts::find(
+                remaining_accounts,
+                &mint,
+                &self.owner.key(),
+                &self
+                    .token_authority
+                    .as_ref()
+                    .ok_or(StakeError::RewardAccountsMissing)?
+                    .key(),
+                &self.token_program.key(),
+                token_vault,
+            ),
+        }
+    }
+
+    pub fn transfer_asset(&self) -> Result<()> {
+        let staker_key = self.staker.key();
+        let txn_signer: &[&[u8]; 4] = &[
+            &b"STAKE"[..],
+            staker_key.as_ref(),
+            &b"nft-authority"[..],
+            &[self.staker.nft_auth_bump],
+        ];
+
+        CoreAccounts {
+            core_program: &self.core_program.to_account_info(),
+            asset: &self.asset.to_account_info(),
+            collection: &self.core_collection.to_account_info(),
+            payer: &self.authority.to_account_info(),
+            authority: &self.nft_authority.to_account_info(),
+            system_program: &self.system_program.to_account_info(),
+        }
+        .transfer(&self.owner.to_account_info(), &[txn_signer])
+    }
+
+    /// Thaws the asset and returns the freeze delegate plugin authority to the owner.
+    /// The owner must sign to remove the plugin, so it is left on the asset.
+    pub fn thaw_asset(&self) -> Result<()> {
+        let staker_key = self.staker.key();
+        let txn_signer: &[&[u8]; 4] = &[
+            &b"STAKE"[..],
+            staker_key.as_ref(),
+            &b"nft-authority"[..],
+            &[self.staker.nft_auth_bump],
+        ];
+
+        let accounts = CoreAccounts {
+            core_program: &self.core_program.to_account_info(),
+            asset: &self.asset.to_account_info(),
+            collection: &self.core_collection.to_account_info(),
+            payer: &self.authority.to_account_info(),
+            authority: &self.nft_authority.to_account_info(),
+            system_program: &self.system_program.to_account_info(),
+        };
+
+        accounts.set_frozen(false, &[txn_signer])?;
+        accounts.revoke_freeze_delegate(&[txn_signer])
+    }
+}
+
+pub fn force_unstake_core_handler<'info>(
+    ctx: Context<'_, '_, 'info, 'info, ForceUnstakeCore<'info>>,
+) -> Result<()> {
+    let staker = &ctx.accounts.staker;
+    let staker_key = staker.key();
+    let token_auth_bump = staker.token_auth_bump;
+    let current_time = Clock::get().unwrap().unix_timestamp;
+    let stake_record = &ctx.accounts.stake_record;
+
+    let StakeRecord {
+        staked_at,
+        can_claim_at,
+        pending_claim,
+        ..
+    } = **stake_record.as_ref();
+
+    let Collection { custodial, .. } = **ctx.accounts.collection.as_ref();
+
+    if can_claim_at > current_time {
+        return err!(StakeError::MinimumPeriodNotReached);
+    }
+
+    let authority_seed = &[
+        &b"STAKE"[..],
+        staker_key.as_ref(),
+        &b"token-authority"[..],
+        &[token_auth_bump],
+    ];
+
+    let binding = &[&authority_seed[..]];
+
+    let token_authority = ctx
+        .accounts
+        .token_authority
+        .as_ref()
+        .map(|token_authority| token_authority.to_account_info());
+
+    // emissions are passed in remaining accounts, in the order they are recorded on the stake record
+    require_gte!(
+        ctx.remaining_accounts.len(),
+        stake_record.emissions.len(),
+        StakeError::EmissionsMissing
+    );
+
+    for (key, account) in stake_record
+        .emissions
+        .iter()
+        .zip(ctx.remaining_accounts.iter())
+    {
+        require_keys_eq!(account.key(), *key, StakeError::InvalidEmission);
+
+        let mut emission = Account::<'info, Emission>::try_from(account)?;
+
+        match emission.reward_type {
+            RewardType::Token => {
+                let Emission {
+                    end_time,
+                    minimum_period,
+                    ..
+                } = *emission;
+
+                let (reward_tokens, current_time, is_eligible_for_reward) = calc_reward(
+                    staked_at,
+                    minimum_period.unwrap_or(0),
+                    &emission.reward,
+                    &emission.reward_change_time,
+                    end_time,
+                    &emission.reward_schedule,
+                )?;
+
+                let amount =
+                    emission.item_amount(stake_record.multiplier, stake_record.lock_boost)?;
+                let mut reward_tokens = emission.reward_for_amount(reward_tokens, amount)?;
+
+                // shared emissions pay the item's share of what the pool earned instead
+                if emission.shared {
+                    let mut reward_debt = stake_record.reward_debt;
+                    let token_vault = emission.is_vault_funded(staker);
+                    reward_tokens = emission.settle_shared_reward(
+                        &mut reward_debt,
+                        amount,
+                        current_time,
+                        token_vault,
+                    )?;
+                }
+
+                if is_eligible_for_reward && reward_tokens > 0 {
+                    let reward_accounts = ctx
+                        .accounts
+                        .reward_accounts(&emission, ctx.remaining_accounts)?;
+                    reward_accounts.pay(
+                        reward_accounts.receiver.clone(),
+                        token_authority.clone().expect("token_authority expected"),
+                        ctx.accounts.token_program.to_account_info(),
+                        reward_tokens,
+                        binding,
+                    )?;
+
+                    if emission.is_vault_funded(staker) {
+                        emission.decrease_current_balance_for_amount(
+                            staked_at,
+                            current_time,
+                            amount,
+                        )?;
+                    }
+                }
+            }
+            RewardType::Selection { options: _ } => {
+                require_gte!(
+                    current_time,
+                    can_claim_at,
+                    StakeError::MinimumPeriodNotReached
+                );
+
+                if pending_claim > 0 {
+                    let reward_accounts = ctx
+                        .accounts
+                        .reward_accounts(&emission, ctx.remaining_accounts)?;
+                    reward_accounts.pay(
+                        reward_accounts.receiver.clone(),
+                        token_authority.clone().expect("token_authority expected"),
+                        ctx.accounts.token_program.to_account_info(),
+                        pending_claim,
+                        binding,
+                    )?;
+
+                    if emission.is_vault_funded(staker) {
+                        emission.decrease_current_balance(staked_at, current_time)?;
+                    }
+                }
+            }
+            RewardType::Points => {
+                let Emission {
+                    end_time,
+                    minimum_period,
+                    ..
+                } = *emission;
+
+                let (points, _, is_eligible_for_reward) = calc_reward(
+                    staked_at,
+                    minimum_period.unwrap_or(0),
+                    &emission.reward,
+                    &emission.reward_change_time,
+                    end_time,
+                    &emission.reward_schedule,
+                )?;
+
+                let amount =
+                    emission.item_amount(stake_record.multiplier, stake_record.lock_boost)?;
+                let points = emission.reward_for_amount(points, amount)?;
+
+                // points accrued since the last claim are credited before the stake record closes
+                let nft_record = ctx
+                    .accounts
+                    .nft_record
+                    .as_mut()
+                    .ok_or(StakeError::NftRecordRequired)?;
+
+                if is_eligible_for_reward && points > 0 {
+                    nft_record.add_points(points)?;
+                }
+            }
+            RewardType::Distribution => {
+                settle_distribution(
+                    &emission,
+                    stake_record.distribution_debt,
+                    &staker_key,
+                    &ctx.accounts.owner.to_account_info(),
+                    &ctx.accounts.token_program.to_account_info(),
+                    ctx.remaining_accounts,
+                    current_time,
+                )?;
+            }
+        }
+
+        let amount = emission.item_amount(stake_record.multiplier, stake_record.lock_boost)?;
+        emission.decrease_staked_amount(amount)?;
+        emission.exit(ctx.program_id)?;
+    }
+
+    if custodial {
+        ctx.accounts.transfer_asset()?;
+    } else {
+        ctx.accounts.thaw_asset()?;
+    }
+
+    let tx_fee = match staker.get_subscription() {
+        Subscription::Custom {
+            amount: _,
+            stake_fee: _,
+            unstake_fee,
+            claim_fee: _,
+        } => unstake_fee,
+        _ => ctx.accounts.program_config.unstake_fee,
+    };
+
+    let tx_fee = calc_tx_fee(staker, tx_fee);
+
+    if tx_fee > 0 {
+        let ix = anchor_lang::solana_program::system_instruction::transfer(
+            &ctx.accounts.authority.key(),
+            &ctx.accounts.fees_wallet.key(),
+            tx_fee,
+        );
+
+        anchor_lang::solana_program::program::invoke(
+            &ix,
+            &[
+                ctx.accounts.authority.to_account_info(),
+                ctx.accounts.fees_wallet.to_account_info(),
+            ],
+        )?;
+    }
+
+    ctx.accounts.collection.decrease_staker_count()?;
+    ctx.accounts.staker.decrease_staker_count()
+}
@@ -4,7 +4,7 @@ use anchor_spl::token::{
 };
 
 use crate::{
-    state::{collection, Collection, ProgramConfig, Staker},
+    state::{collection, Collection, CollectionKind, ProgramConfig, Staker},
     StakeError,
 };
 
@@ -80,6 +80,8 @@ pub fn init_collection_handler(
         ctx.accounts.collection_mint.key(),
         custodial,
         max_stakers_count,
+        CollectionKind::Nft,
+        start_time,
         ctx.bumps.collection,
     );
 
@@ -1,7 +1,7 @@
 use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token::{Mint, Token, TokenAccount, Transfer},
+    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
 };
 
 use crate::{
@@ -30,21 +30,24 @@ pub struct InitDistribution<'info> {
     pub distribution: Account<'info, Distribution>,
 
     #[account()]
-    pub token_mint: Option<Account<'info, Mint>>,
+    pub token_mint: Option<Box<InterfaceAccount<'info, Mint>>>,
 
     #[account(
+        mut,
         associated_token::mint = token_mint,
-        associated_token::authority = authority
+        associated_token::authority = authority,
+        associated_token::token_program = token_program
     )]
-    pub token_account: Option<Account<'info, TokenAccount>>,
+    pub token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
 
     #[account(
         init,
         payer = authority,
         associated_token::mint = token_mint,
-        associated_token::authority = vault_authority
+        associated_token::authority = vault_authority,
+        associated_token::token_program = token_program
     )]
-    pub token_vault: Option<Account<'info, TokenAccount>>,
+    pub token_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
 
     /// CHECK: this account is not read or written to
     #[account(
@@ -63,31 +66,44 @@ pub struct InitDistribution<'info> {
 
     pub system_program: Program<'info, System>,
 
-    pub token_program: Program<'info, Token>,
+    pub token_program: Interface<'info, TokenInterface>,
 
     pub associated_token_program: Program<'info, AssociatedToken>,
 }
 
 impl<'info> InitDistribution<'info> {
-    pub fn transfer_token_ctx(&self) -> CpiContext<'_, '_, '_, 'info, Transfer<'info>> {
-        let cpi_accounts = Transfer {
-            from: self
-                .token_account
-                .as_ref()
-                .expect("token_account expected")
-                .to_account_info(),
-            to: self
-                .token_vault
-                .as_ref()
-                .expect("token_vault expected")
-                .to_account_info(),
-
-            authority: self.vault_authority.as_ref().to_account_info(),
+    /// Transfers `amount` into the distribution vault, returning what the vault received
+    /// after any transfer fee
+    pub fn transfer_to_vault(&mut self, amount: u64) -> Result<u64> {
+        let (Some(token_mint), Some(token_account), Some(token_vault)) = (
+            self.token_mint.as_ref(),
+            self.token_account.as_ref(),
+            self.token_vault.as_mut(),
+        ) else {
+            return err!(StakeError::DistributionAccountsRequired);
+        };
+
+        let balance_before = token_vault.amount;
+
+        let cpi_accounts = TransferChecked {
+            from: token_account.to_account_info(),
+            mint: token_mint.to_account_info(),
+            to: token_vault.to_account_info(),
+            authority: self.authority.to_account_info(),
         };
 
-        let cpi_program = self.token_program.to_account_info();
+        transfer_checked(
+            CpiContext::new(self.token_program.to_account_info(), cpi_accounts),
+            amount,
+            token_mint.decimals,
+        )?;
 
-        CpiContext::new(cpi_program, cpi_accounts)
+        token_vault.reload()?;
+
+        token_vault
+            .amount
+            .checked_sub(balance_before)
+            .ok_or(error!(StakeError::ProgramSubError))
     }
 }
 
@@ -97,6 +113,7 @@ pub fn init_distribution_handler(
     uri: String,
     num_shares: u32,
     amount: u64,
+    ends_at: Option<i64>,
 ) -> Result<()> {
     let current_time = Clock::get().unwrap().unix_timestamp;
 
@@ -104,6 +121,17 @@ pub fn init_distribution_handler(
 
     require_gte!(amount, 0, StakeError::AmountTooLow);
 
+    if let Some(ends_at) = ends_at {
+        require_gt!(ends_at, current_time, StakeError::InvalidDistributionEnd);
+    }
+
+    // the distribution holds what the vault received, net of any transfer fee
+    let amount = if ctx.accounts.token_mint.is_some() && amount > 0 {
+        ctx.accounts.transfer_to_vault(amount)?
+    } else {
+        amount
+    };
+
     let distribution = &mut ctx.accounts.distribution;
 
     **distribution = Distribution::init(
@@ -116,6 +144,7 @@ pub fn init_distribution_handler(
         current_time,
         amount,
         ctx.bumps.vault_authority,
+        ends_at,
     );
 
     Ok(())
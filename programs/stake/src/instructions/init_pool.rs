@@ -0,0 +1,97 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::{
+    state::{Collection, CollectionKind, Staker},
+    StakeError,
+};
+
+#[derive(Accounts)]
+pub struct InitPool<'info> {
+    #[account(
+        mut,
+        realloc = staker.current_len() + 32,
+        realloc::payer = authority,
+        realloc::zero = false,
+        has_one = authority @ StakeError::Unauthorized
+    )]
+    pub staker: Box<Account<'info, Staker>>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = Collection::LEN,
+        seeds = [
+            b"STAKE",
+            staker.key().as_ref(),
+            stake_mint.key().as_ref(),
+            b"collection",
+        ],
+        bump
+    )]
+    pub collection: Box<Account<'info, Collection>>,
+
+    /// the fungible or LP token deposited into the pool
+    pub stake_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        associated_token::mint = stake_mint,
+        associated_token::authority = nft_authority,
+        associated_token::token_program = token_program
+    )]
+    pub pool_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// CHECK: this account is not read or written
+    #[account(
+        seeds = [
+            b"STAKE",
+            staker.key().as_ref(),
+            b"nft-authority",
+        ],
+        bump = staker.nft_auth_bump
+    )]
+    pub nft_authority: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+pub fn init_pool_handler(
+    ctx: Context<InitPool>,
+    staking_starts_at: Option<i64>,
+    max_stakers_count: u64,
+) -> Result<()> {
+    let current_time = Clock::get().unwrap().unix_timestamp;
+
+    let start_time = staking_starts_at.unwrap_or(current_time);
+
+    require_gte!(start_time, current_time, StakeError::StartTimeInPast);
+    require_gt!(max_stakers_count, 0, StakeError::NotEnoughStakers);
+
+    let collection = &mut ctx.accounts.collection;
+
+    ***collection = Collection::init(
+        ctx.accounts.staker.key(),
+        ctx.accounts.stake_mint.key(),
+        // deposits are always held in the pool vault
+        true,
+        max_stakers_count,
+        CollectionKind::Fungible {
+            decimals: ctx.accounts.stake_mint.decimals,
+        },
+        start_time,
+        ctx.bumps.collection,
+    );
+
+    let staker: &mut Account<'_, Staker> = &mut ctx.accounts.staker;
+    staker.add_collection(ctx.accounts.collection.key())
+}
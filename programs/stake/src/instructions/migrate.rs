@@ -0,0 +1,101 @@
+use anchor_lang::{prelude::*, system_program, Discriminator};
+
+use crate::{
+    state::{Collection, Emission, StakeRecord},
+    StakeError, MULTIPLIER_BASE,
+};
+
+#[derive(Accounts)]
+pub struct Migrate<'info> {
+    /// CHECK: checked in instruction, a collection, emission or stake record of the program
+    #[account(
+        mut,
+        owner = crate::ID @ StakeError::InvalidMigration
+    )]
+    pub account: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> Migrate<'info> {
+    /// Reads the account with the fields added since it was created as zeroes
+    pub fn read<T: AccountDeserialize>(&self) -> Result<T> {
+        let mut data = self.account.try_borrow_data()?.to_vec();
+        data.resize(data.len() + std::mem::size_of::<T>(), 0);
+
+        T::try_deserialize(&mut &data[..])
+    }
+
+    /// Grows the account to at least `len`, the payer covering the extra rent, and writes it back
+    pub fn write<T: AccountSerialize>(&self, account: &T, len: usize) -> Result<()> {
+        let info = self.account.to_account_info();
+
+        if len > info.data_len() {
+            let rent = Rent::get()?
+                .minimum_balance(len)
+                .saturating_sub(info.lamports());
+
+            if rent > 0 {
+                let cpi_accounts = system_program::Transfer {
+                    from: self.payer.to_account_info(),
+                    to: info.clone(),
+                };
+                let cpi_program = self.system_program.to_account_info();
+
+                system_program::transfer(CpiContext::new(cpi_program, cpi_accounts), rent)?;
+            }
+
+            info.realloc(len, false)?;
+        }
+
+        let mut data = info.try_borrow_mut_data()?;
+        account.try_serialize(&mut &mut data[..])
+    }
+}
+
+/// Brings a collection, emission or stake record created before its latest fields were added up
+/// to the current layout. New fields read as zero, so only those with a different default are set
+pub fn migrate_handler(ctx: Context<Migrate>) -> Result<()> {
+    let discriminator: [u8; 8] = ctx
+        .accounts
+        .account
+        .try_borrow_data()?
+        .get(..8)
+        .and_then(|discriminator| discriminator.try_into().ok())
+        .ok_or(StakeError::InvalidMigration)?;
+
+    match discriminator {
+        Collection::DISCRIMINATOR => {
+            let collection = ctx.accounts.read::<Collection>()?;
+            ctx.accounts.write(&collection, Collection::LEN)
+        }
+        Emission::DISCRIMINATOR => {
+            let mut emission = ctx.accounts.read::<Emission>()?;
+
+            // emissions from before pools have no stake unit, nothing else sets it to zero
+            if emission.stake_unit == 0 {
+                emission.stake_unit = 1;
+                emission.max_multiplier = MULTIPLIER_BASE;
+                emission.reward_per_item_updated_at = emission.start_time;
+            }
+
+            let len = emission.current_len();
+            ctx.accounts.write(&emission, len)
+        }
+        StakeRecord::DISCRIMINATOR => {
+            let mut stake_record = ctx.accounts.read::<StakeRecord>()?;
+
+            // records from before multipliers have no lock boost, tiers never boost below the base
+            if stake_record.lock_boost == 0 {
+                stake_record.multiplier = MULTIPLIER_BASE;
+                stake_record.lock_boost = MULTIPLIER_BASE;
+            }
+
+            ctx.accounts.write(&stake_record, StakeRecord::LEN)
+        }
+        _ => err!(StakeError::InvalidMigration),
+    }
+}
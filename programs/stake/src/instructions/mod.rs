@@ -1,53 +1,113 @@
 pub use add_emission::*;
 pub use add_funds::*;
+pub use add_points_item::*;
 pub use add_token::*;
 pub use change_reward::*;
 pub use claim::*;
+pub use claim_all::*;
+pub use claim_merkle_share::*;
+pub use claim_pool::*;
+pub use claim_share::*;
 pub use close::*;
 pub use close_collection::*;
 pub use close_emission::*;
 pub use delegate_stake::*;
 pub use distribute::*;
+pub use distribute_pro_rata::*;
 pub use extend_emission::*;
 pub use force_unstake::*;
+pub use force_unstake_core::*;
 pub use init::*;
 pub use init_collection::*;
 pub use init_distribution::*;
+pub use init_pool::*;
 pub use init_program_config::*;
+pub use migrate::*;
 pub use pay_subscription::*;
+pub use reclaim_distribution::*;
+pub use redeem_points::*;
 pub use remove_funds::*;
+pub use request_unstake::*;
 pub use resize::*;
+pub use set_distribution_root::*;
 pub use stake::*;
+pub use stake_cnft::*;
+pub use stake_core::*;
+pub use stake_many::*;
+pub use stake_tokens::*;
 pub use toggle_collection_active::*;
+pub use toggle_distribution_active::*;
 pub use toggle_stake_active::*;
 pub use unstake::*;
+pub use unstake_cnft::*;
+pub use unstake_core::*;
+pub use unstake_tokens::*;
+pub use update_allow_list::*;
+pub use update_collection_capacity::*;
+pub use update_early_unstake::*;
+pub use update_emission_multipliers::*;
+pub use update_lock_tiers::*;
 pub use update_program_config::*;
 pub use update_stake::*;
+pub use update_staking_starts_at::*;
 pub use update_theme::*;
+pub use update_unbonding_period::*;
+pub use update_vesting::*;
+pub use withdraw_vested::*;
 
 pub mod add_emission;
 pub mod add_funds;
+pub mod add_points_item;
 pub mod add_token;
 pub mod change_reward;
 pub mod claim;
+pub mod claim_all;
+pub mod claim_merkle_share;
+pub mod claim_pool;
+pub mod claim_share;
 pub mod close;
 pub mod close_collection;
 pub mod close_emission;
 pub mod delegate_stake;
 pub mod distribute;
+pub mod distribute_pro_rata;
 pub mod extend_emission;
 pub mod force_unstake;
+pub mod force_unstake_core;
 pub mod init;
 pub mod init_collection;
 pub mod init_distribution;
+pub mod init_pool;
 pub mod init_program_config;
+pub mod migrate;
 pub mod pay_subscription;
+pub mod reclaim_distribution;
+pub mod redeem_points;
 pub mod remove_funds;
+pub mod request_unstake;
 pub mod resize;
+pub mod set_distribution_root;
 pub mod stake;
+pub mod stake_cnft;
+pub mod stake_core;
+pub mod stake_many;
+pub mod stake_tokens;
 pub mod toggle_collection_active;
+pub mod toggle_distribution_active;
 pub mod toggle_stake_active;
 pub mod unstake;
+pub mod unstake_cnft;
+pub mod unstake_core;
+pub mod unstake_tokens;
+pub mod update_allow_list;
+pub mod update_collection_capacity;
+pub mod update_early_unstake;
+pub mod update_emission_multipliers;
+pub mod update_lock_tiers;
 pub mod update_program_config;
 pub mod update_stake;
+pub mod update_staking_starts_at;
 pub mod update_theme;
+pub mod update_unbonding_period;
+pub mod update_vesting;
+pub mod withdraw_vested;
@@ -0,0 +1,197 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{
+        close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TokenInterface,
+        TransferChecked,
+    },
+};
+
+use crate::{
+    state::{ClaimBitmap, Distribution, Emission, ShareRecord, Staker},
+    StakeError,
+};
+
+#[derive(Accounts)]
+pub struct ReclaimDistribution<'info> {
+    #[account(
+        has_one = authority @ StakeError::Unauthorized
+    )]
+    pub staker: Box<Account<'info, Staker>>,
+
+    #[account(
+        mut,
+        has_one = staker,
+        close = authority
+    )]
+    pub distribution: Box<Account<'info, Distribution>>,
+
+    /// distribution emission a pro-rata distribution is linked to
+    #[account(
+        mut,
+        constraint = distribution.emission == Some(emission.key()) @ StakeError::InvalidEmission
+    )]
+    pub emission: Option<Box<Account<'info, Emission>>>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"STAKE",
+            distribution.key().as_ref(),
+            b"claim-bitmap"
+        ],
+        bump = claim_bitmap.bump,
+        close = authority
+    )]
+    pub claim_bitmap: Option<Box<Account<'info, ClaimBitmap>>>,
+
+    #[account(
+        constraint = distribution.token_mint == Some(token_mint.key()) @ StakeError::InvalidRewardToken
+    )]
+    pub token_mint: Option<Box<InterfaceAccount<'info, Mint>>>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        associated_token::mint = token_mint,
+        associated_token::authority = authority,
+        associated_token::token_program = token_program
+    )]
+    pub token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = vault_authority,
+        associated_token::token_program = token_program
+    )]
+    pub token_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// CHECK: this account is not read or written to
+    #[account(
+        seeds = [
+            b"STAKE",
+            staker.key().as_ref(),
+            distribution.key().as_ref(),
+            b"distribution-vault"
+        ],
+        bump = distribution.vault_authority_bump
+    )]
+    pub vault_authority: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+
+    pub associated_token_program: Option<Program<'info, AssociatedToken>>,
+}
+
+impl<'info> ReclaimDistribution<'info> {
+    /// Empties the distribution vault into the authority's token account and closes it
+    pub fn sweep_vault(&self, signer: &[&[&[u8]]]) -> Result<()> {
+        let (Some(token_mint), Some(token_account), Some(token_vault), Some(token_program)) = (
+            self.token_mint.as_ref(),
+            self.token_account.as_ref(),
+            self.token_vault.as_ref(),
+            self.token_program.as_ref(),
+        ) else {
+            return err!(StakeError::DistributionAccountsRequired);
+        };
+
+        let cpi_program = token_program.to_account_info();
+
+        if token_vault.amount > 0 {
+            let cpi_accounts = TransferChecked {
+                from: token_vault.to_account_info(),
+                mint: token_mint.to_account_info(),
+                to: token_account.to_account_info(),
+                authority: self.vault_authority.to_account_info(),
+            };
+
+            transfer_checked(
+                CpiContext::new_with_signer(cpi_program.clone(), cpi_accounts, signer),
+                token_vault.amount,
+                token_mint.decimals,
+            )?;
+        }
+
+        let cpi_accounts = CloseAccount {
+            account: token_vault.to_account_info(),
+            destination: self.authority.to_account_info(),
+            authority: self.vault_authority.to_account_info(),
+        };
+
+        close_account(CpiContext::new_with_signer(
+            cpi_program,
+            cpi_accounts,
+            signer,
+        ))
+    }
+}
+
+/// Sweeps whatever is left of a distribution back to the staker authority once its claim
+/// deadline has passed. Unclaimed share records are passed in remaining accounts and closed
+/// along with the distribution, which can't be reclaimed while any are left open
+pub fn reclaim_distribution_handler<'info>(
+    ctx: Context<'_, '_, 'info, 'info, ReclaimDistribution<'info>>,
+) -> Result<()> {
+    let current_time = Clock::get().unwrap().unix_timestamp;
+    let distribution_key = ctx.accounts.distribution.key();
+
+    require!(
+        ctx.accounts.distribution.has_ended(current_time),
+        StakeError::DistributionNotEnded
+    );
+
+    for account in ctx.remaining_accounts.iter() {
+        let share_record = Account::<'info, ShareRecord>::try_from(account)?;
+        require_keys_eq!(
+            share_record.distribution,
+            distribution_key,
+            StakeError::UnexpectedRemainingAccounts
+        );
+        share_record.close(ctx.accounts.authority.to_account_info())?;
+        ctx.accounts.distribution.close_share_record();
+    }
+
+    let distribution = &ctx.accounts.distribution;
+
+    // share records left open would keep their rent, and for SOL their share, stranded
+    require_eq!(
+        distribution.share_records_open,
+        0,
+        StakeError::ShareRecordsOpen
+    );
+
+    if distribution.token_mint.is_some() {
+        let staker_key = ctx.accounts.staker.key();
+
+        let authority_seed = &[
+            &b"STAKE"[..],
+            staker_key.as_ref(),
+            distribution_key.as_ref(),
+            &b"distribution-vault"[..],
+            &[distribution.vault_authority_bump],
+        ];
+
+        ctx.accounts.sweep_vault(&[&authority_seed[..]])?;
+    }
+
+    // unlink so the emission can take on another pro-rata distribution
+    if distribution.emission.is_some() {
+        let emission = ctx
+            .accounts
+            .emission
+            .as_mut()
+            .ok_or(StakeError::EmissionsMissing)?;
+        emission.distribution = None;
+        emission.token_mint = None;
+    }
+
+    msg!("RECLAIMED {} FROM DISTRIBUTION", distribution.balance);
+
+    Ok(())
+}
@@ -0,0 +1,208 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::{
+    state::{NftRecord, PointsItem, PointsItemKind, PointsReceipt, StakeRecord, Staker},
+    StakeError,
+};
+
+#[derive(Accounts)]
+pub struct RedeemPoints<'info> {
+    pub staker: Box<Account<'info, Staker>>,
+
+    #[account(
+        mut,
+        has_one = staker
+    )]
+    pub points_item: Box<Account<'info, PointsItem>>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"STAKE",
+            staker.key().as_ref(),
+            nft_record.nft_mint.as_ref(),
+            b"nft-record",
+        ],
+        bump = nft_record.bump,
+    )]
+    pub nft_record: Box<Account<'info, NftRecord>>,
+
+    /// proves ownership of a staked NFT
+    #[account(
+        seeds = [
+            b"STAKE",
+            staker.key().as_ref(),
+            nft_record.nft_mint.as_ref(),
+            b"stake-record",
+        ],
+        bump = stake_record.bump,
+        has_one = owner @ StakeError::Unauthorized
+    )]
+    pub stake_record: Option<Box<Account<'info, StakeRecord>>>,
+
+    /// proves ownership of an NFT held in the owner's wallet
+    #[account(
+        constraint = nft_token.mint == nft_record.nft_mint @ StakeError::Unauthorized,
+        constraint = nft_token.owner == owner.key() @ StakeError::Unauthorized,
+        constraint = nft_token.amount == 1 @ StakeError::TokenAccountEmpty
+    )]
+    pub nft_token: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = PointsReceipt::LEN,
+        seeds = [
+            b"STAKE",
+            points_item.key().as_ref(),
+            &points_item.redeemed.to_le_bytes(),
+            b"points-receipt",
+        ],
+        bump
+    )]
+    pub points_receipt: Box<Account<'info, PointsReceipt>>,
+
+    pub reward_mint: Option<Box<InterfaceAccount<'info, Mint>>>,
+
+    #[account(
+        mut,
+        associated_token::mint = reward_mint,
+        associated_token::authority = token_authority,
+        associated_token::token_program = token_program
+    )]
+    pub stake_token_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        associated_token::mint = reward_mint,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program
+    )]
+    pub reward_receive_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// CHECK: this account is not read or written
+    #[account(
+        seeds = [
+            b"STAKE",
+            staker.key().as_ref(),
+            b"token-authority",
+        ],
+        bump = staker.token_auth_bump
+    )]
+    pub token_authority: Option<UncheckedAccount<'info>>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> RedeemPoints<'info> {
+    pub fn transfer_token_ctx(&self) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let cpi_accounts = TransferChecked {
+            from: self
+                .stake_token_vault
+                .as_ref()
+                .expect("stake_token_vault expected")
+                .to_account_info(),
+            mint: self
+                .reward_mint
+                .as_ref()
+                .expect("reward_mint expected")
+                .to_account_info(),
+            to: self
+                .reward_receive_account
+                .as_ref()
+                .expect("reward_receive_account expected")
+                .to_account_info(),
+            authority: self
+                .token_authority
+                .as_ref()
+                .expect("token_authority expected")
+                .to_account_info(),
+        };
+
+        let cpi_program = self.token_program.to_account_info();
+
+        CpiContext::new(cpi_program, cpi_accounts)
+    }
+}
+
+/// Spends an NFT's points on an item, token bundles are paid out here while other items
+/// are fulfilled off-chain from the receipt
+pub fn redeem_points_handler(ctx: Context<RedeemPoints>) -> Result<()> {
+    let staker = &ctx.accounts.staker;
+    let staker_key = staker.key();
+    let current_time = Clock::get().unwrap().unix_timestamp;
+
+    require!(
+        ctx.accounts.stake_record.is_some() || ctx.accounts.nft_token.is_some(),
+        StakeError::Unauthorized
+    );
+
+    let points_item = &mut ctx.accounts.points_item;
+    let price = points_item.price;
+    let index = points_item.redeemed;
+    let kind = points_item.kind;
+
+    let nft_record = &mut ctx.accounts.nft_record;
+    require_gte!(nft_record.points, price, StakeError::NotEnoughPoints);
+    nft_record.subtract_points(price)?;
+
+    points_item.redeem()?;
+
+    if let PointsItemKind::Token { mint, amount } = kind {
+        let reward_mint = ctx
+            .accounts
+            .reward_mint
+            .as_ref()
+            .ok_or(StakeError::RewardAccountsMissing)?;
+        require_keys_eq!(reward_mint.key(), mint, StakeError::InvalidRewardToken);
+
+        require!(
+            ctx.accounts.stake_token_vault.is_some()
+                && ctx.accounts.reward_receive_account.is_some()
+                && ctx.accounts.token_authority.is_some(),
+            StakeError::RewardAccountsMissing
+        );
+
+        let authority_seed = &[
+            &b"STAKE"[..],
+            staker_key.as_ref(),
+            &b"token-authority"[..],
+            &[staker.token_auth_bump],
+        ];
+
+        transfer_checked(
+            ctx.accounts
+                .transfer_token_ctx()
+                .with_signer(&[&authority_seed[..]]),
+            amount,
+            reward_mint.decimals,
+        )?;
+    }
+
+    let points_receipt = &mut ctx.accounts.points_receipt;
+
+    ***points_receipt = PointsReceipt {
+        staker: staker_key,
+        item: ctx.accounts.points_item.key(),
+        owner: ctx.accounts.owner.key(),
+        nft_mint: ctx.accounts.nft_record.nft_mint,
+        points: price,
+        index,
+        redeemed_at: current_time,
+        bump: ctx.bumps.points_receipt,
+    };
+
+    msg!("REDEEMED {} POINTS", price);
+
+    Ok(())
+}
@@ -1,5 +1,7 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{transfer, Mint, Token, TokenAccount, Transfer};
+use anchor_spl::token_interface::{
+    transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked,
+};
 
 use crate::{
     state::{Collection, Emission, Staker},
@@ -31,23 +33,25 @@ pub struct RemoveFunds<'info> {
     pub emission: Account<'info, Emission>,
 
     #[account(
-        address = emission.token_mint.unwrap() @ StakeError::InvalidRewardToken
+        address = emission.reward_mint(&staker).unwrap_or_default() @ StakeError::InvalidRewardToken
     )]
-    pub reward_mint: Account<'info, Mint>,
+    pub reward_mint: InterfaceAccount<'info, Mint>,
 
     #[account(
         mut,
         associated_token::mint = reward_mint,
-        associated_token::authority = authority
+        associated_token::authority = authority,
+        associated_token::token_program = token_program
     )]
-    pub token_account: Account<'info, TokenAccount>,
+    pub token_account: InterfaceAccount<'info, TokenAccount>,
 
     #[account(
         mut,
         associated_token::mint = reward_mint,
-        associated_token::authority = token_authority
+        associated_token::authority = token_authority,
+        associated_token::token_program = token_program
     )]
-    pub stake_token_vault: Account<'info, TokenAccount>,
+    pub stake_token_vault: InterfaceAccount<'info, TokenAccount>,
 
     /// CHECK: the account is not read or written
     #[account(
@@ -57,13 +61,14 @@ pub struct RemoveFunds<'info> {
     pub token_authority: UncheckedAccount<'info>,
 
     pub authority: Signer<'info>,
-    pub token_program: Program<'info, Token>,
+    pub token_program: Interface<'info, TokenInterface>,
 }
 
 impl<'info> RemoveFunds<'info> {
-    pub fn transfer_token_ctx(&self) -> CpiContext<'_, '_, '_, 'info, Transfer<'info>> {
-        let cpi_accounts = Transfer {
+    pub fn transfer_token_ctx(&self) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let cpi_accounts = TransferChecked {
             from: self.stake_token_vault.to_account_info(),
+            mint: self.reward_mint.to_account_info(),
             to: self.token_account.to_account_info(),
             authority: self.token_authority.to_account_info(),
         };
@@ -82,9 +87,13 @@ pub fn remove_funds_handler(ctx: Context<RemoveFunds>) -> Result<()> {
 
     require_eq!(collection.is_active, false, StakeError::CollectionActive);
     require!(
-        Option::is_some(&ctx.accounts.emission.token_mint),
+        Option::is_some(&emission.reward_mint(&ctx.accounts.staker)),
         StakeError::NoRewardMint
     );
+    require!(
+        emission.is_vault_funded(&ctx.accounts.staker),
+        StakeError::InvalidEmission
+    );
 
     require_gt!(emission.current_balance, 0, StakeError::NoTokensToClaim);
 
@@ -101,11 +110,12 @@ pub fn remove_funds_handler(ctx: Context<RemoveFunds>) -> Result<()> {
         &[token_auth_bump],
     ];
 
-    transfer(
+    transfer_checked(
         ctx.accounts
             .transfer_token_ctx()
             .with_signer(&[&token_auth_seed[..]]),
         emission.current_balance,
+        ctx.accounts.reward_mint.decimals,
     )?;
 
     ctx.accounts.emission.current_balance = 0;
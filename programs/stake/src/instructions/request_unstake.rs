@@ -0,0 +1,224 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::{
+    state::{Collection, NftRecord, StakeRecord, Staker, VestingRecord},
+    utils::SettleAccounts,
+    StakeError,
+};
+
+#[derive(Accounts)]
+pub struct RequestUnstake<'info> {
+    pub staker: Box<Account<'info, Staker>>,
+
+    #[account(
+        seeds = [
+            b"STAKE",
+            staker.key().as_ref(),
+            collection.collection_mint.as_ref(),
+            b"collection",
+        ],
+        bump = collection.bump
+    )]
+    pub collection: Box<Account<'info, Collection>>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"STAKE",
+            staker.key().as_ref(),
+            stake_record.nft_mint.as_ref(),
+            b"stake-record",
+        ],
+        bump = stake_record.bump,
+        has_one = owner @ StakeError::Unauthorized,
+    )]
+    pub stake_record: Box<Account<'info, StakeRecord>>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"STAKE",
+            staker.key().as_ref(),
+            stake_record.nft_mint.as_ref(),
+            b"nft-record",
+        ],
+        bump = nft_record.bump,
+    )]
+    pub nft_record: Option<Box<Account<'info, NftRecord>>>,
+
+    #[account(mut)]
+    pub reward_mint: Option<Box<InterfaceAccount<'info, Mint>>>,
+
+    #[account(
+        mut,
+        associated_token::mint = reward_mint,
+        associated_token::authority = token_authority,
+        associated_token::token_program = reward_token_program
+    )]
+    pub stake_token_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        associated_token::mint = reward_mint,
+        associated_token::authority = owner,
+        associated_token::token_program = reward_token_program
+    )]
+    pub reward_receive_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(
+        mut,
+        token::mint = reward_mint,
+        token::token_program = reward_token_program
+    )]
+    pub penalty_treasury: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = VestingRecord::LEN,
+        seeds = [
+            b"STAKE",
+            staker.key().as_ref(),
+            owner.key().as_ref(),
+            b"vesting-record",
+        ],
+        bump
+    )]
+    pub vesting_record: Option<Box<Account<'info, VestingRecord>>>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        seeds = [
+            b"STAKE",
+            staker.key().as_ref(),
+            owner.key().as_ref(),
+            b"vesting-vault",
+        ],
+        bump,
+        token::mint = reward_mint,
+        token::authority = token_authority,
+        token::token_program = reward_token_program
+    )]
+    pub vesting_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// CHECK: this account is not read or written
+    #[account(
+        seeds = [
+            b"STAKE",
+            staker.key().as_ref(),
+            b"token-authority",
+        ],
+        bump = staker.token_auth_bump
+    )]
+    pub token_authority: Option<UncheckedAccount<'info>>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub reward_token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> RequestUnstake<'info> {
+    /// Accounts settling the stake record's emissions
+    pub fn settle_accounts(&mut self, vesting_record_bump: u8) -> SettleAccounts<'_, 'info> {
+        SettleAccounts {
+            staker: &self.staker,
+            collection: &self.collection,
+            stake_record: &self.stake_record,
+            nft_record: self.nft_record.as_deref_mut(),
+            reward_mint: self.reward_mint.as_deref(),
+            stake_token_vault: self
+                .stake_token_vault
+                .as_ref()
+                .map(|vault| vault.to_account_info()),
+            reward_receive_account: self
+                .reward_receive_account
+                .as_ref()
+                .map(|receiver| receiver.to_account_info()),
+            penalty_treasury: self
+                .penalty_treasury
+                .as_ref()
+                .map(|treasury| treasury.to_account_info()),
+            vesting_record: self.vesting_record.as_deref_mut(),
+            vesting_record_bump,
+            vesting_vault: self
+                .vesting_vault
+                .as_ref()
+                .map(|vault| vault.to_account_info()),
+            token_authority: self
+                .token_authority
+                .as_ref()
+                .map(|token_authority| token_authority.to_account_info()),
+            owner: self.owner.to_account_info(),
+            token_program: self.reward_token_program.to_account_info(),
+        }
+    }
+}
+
+/// Settles the NFT's rewards and removes it from its emissions, the NFT itself is released
+/// by unstaking once the collection's unbonding period has passed
+pub fn request_unstake_handler<'info>(
+    ctx: Context<'_, '_, 'info, 'info, RequestUnstake<'info>>,
+) -> Result<()> {
+    let staker = &ctx.accounts.staker;
+    let staker_key = staker.key();
+    let token_auth_bump = staker.token_auth_bump;
+    let current_time = Clock::get().unwrap().unix_timestamp;
+    let collection = &ctx.accounts.collection;
+    let stake_record = &ctx.accounts.stake_record;
+
+    require!(!stake_record.is_unbonding(), StakeError::Unbonding);
+
+    // selection emissions can allow leaving early, this is checked when settling them below
+    if stake_record.can_claim_at > current_time
+        && !stake_record
+            .emissions
+            .contains(&collection.selection_emission.unwrap_or_default())
+    {
+        return err!(StakeError::MinimumPeriodNotReached);
+    }
+
+    require_gte!(
+        current_time,
+        stake_record.locked_until,
+        StakeError::StakeLocked
+    );
+
+    let authority_seed = &[
+        &b"STAKE"[..],
+        staker_key.as_ref(),
+        &b"token-authority"[..],
+        &[token_auth_bump],
+    ];
+
+    let binding = &[&authority_seed[..]];
+
+    let vesting_record_bump = ctx.bumps.vesting_record;
+
+    ctx.accounts
+        .settle_accounts(vesting_record_bump)
+        .settle_emissions(
+            ctx.remaining_accounts,
+            current_time,
+            binding,
+            true,
+            ctx.program_id,
+        )?;
+
+    let stake_record = &mut ctx.accounts.stake_record;
+
+    stake_record.emissions = vec![];
+    stake_record.pending_claim = 0;
+    stake_record.can_claim_at = 0;
+    stake_record.unbonding_at = Some(current_time);
+
+    Ok(())
+}
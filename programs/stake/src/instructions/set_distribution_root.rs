@@ -0,0 +1,161 @@
+use anchor_lang::{
+    prelude::*,
+    system_program::{self, Transfer},
+};
+use anchor_spl::token_interface::{
+    transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked,
+};
+
+use crate::{
+    state::{ClaimBitmap, Distribution, Staker},
+    StakeError,
+};
+
+#[derive(Accounts)]
+#[instruction(merkle_root: [u8; 32], num_shares: u32)]
+pub struct SetDistributionRoot<'info> {
+    #[account(
+        has_one = authority @ StakeError::Unauthorized
+    )]
+    pub staker: Account<'info, Staker>,
+
+    #[account(
+        mut,
+        has_one = staker,
+        constraint = distribution.merkle_root.is_none() && distribution.shares_funded == 0 @ StakeError::MerkleRootLocked,
+        constraint = distribution.emission.is_none() @ StakeError::DistributionModeMismatch
+    )]
+    pub distribution: Account<'info, Distribution>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = ClaimBitmap::len(num_shares),
+        seeds = [
+            b"STAKE",
+            distribution.key().as_ref(),
+            b"claim-bitmap"
+        ],
+        bump
+    )]
+    pub claim_bitmap: Account<'info, ClaimBitmap>,
+
+    #[account(
+        constraint = distribution.token_mint == Some(token_mint.key()) @ StakeError::InvalidRewardToken
+    )]
+    pub token_mint: Option<Box<InterfaceAccount<'info, Mint>>>,
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = authority,
+        associated_token::token_program = token_program
+    )]
+    pub token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = vault_authority,
+        associated_token::token_program = token_program
+    )]
+    pub token_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// CHECK: this account is not read or written to
+    #[account(
+        seeds = [
+            b"STAKE",
+            staker.key().as_ref(),
+            distribution.key().as_ref(),
+            b"distribution-vault"
+        ],
+        bump = distribution.vault_authority_bump
+    )]
+    pub vault_authority: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+}
+
+impl<'info> SetDistributionRoot<'info> {
+    pub fn transfer_sol_ctx(&self) -> CpiContext<'_, '_, '_, 'info, Transfer<'info>> {
+        let cpi_accounts = Transfer {
+            from: self.authority.to_account_info(),
+            to: self.distribution.to_account_info(),
+        };
+
+        let cpi_program = self.system_program.to_account_info();
+        CpiContext::new(cpi_program, cpi_accounts)
+    }
+
+    /// Transfers `amount` into the distribution vault, returning what the vault received
+    /// after any transfer fee
+    pub fn transfer_to_vault(&mut self, amount: u64) -> Result<u64> {
+        let (Some(token_mint), Some(token_account), Some(token_vault), Some(token_program)) = (
+            self.token_mint.as_ref(),
+            self.token_account.as_ref(),
+            self.token_vault.as_mut(),
+            self.token_program.as_ref(),
+        ) else {
+            return err!(StakeError::DistributionAccountsRequired);
+        };
+
+        let balance_before = token_vault.amount;
+
+        let cpi_accounts = TransferChecked {
+            from: token_account.to_account_info(),
+            mint: token_mint.to_account_info(),
+            to: token_vault.to_account_info(),
+            authority: self.authority.to_account_info(),
+        };
+
+        transfer_checked(
+            CpiContext::new(token_program.to_account_info(), cpi_accounts),
+            amount,
+            token_mint.decimals,
+        )?;
+
+        token_vault.reload()?;
+
+        token_vault
+            .amount
+            .checked_sub(balance_before)
+            .ok_or(error!(StakeError::ProgramSubError))
+    }
+}
+
+/// Turns the distribution into an airdrop, funded once here and claimed by holders with a proof.
+/// SOL is held on the distribution account and tokens in the distribution vault, net of any
+/// transfer fee
+pub fn set_distribution_root_handler(
+    ctx: Context<SetDistributionRoot>,
+    merkle_root: [u8; 32],
+    num_shares: u32,
+    amount: u64,
+) -> Result<()> {
+    require_gt!(num_shares, 0, StakeError::InvalidShareIndex);
+
+    let amount = if ctx.accounts.distribution.token_mint.is_none() {
+        system_program::transfer(ctx.accounts.transfer_sol_ctx(), amount)?;
+        amount
+    } else {
+        ctx.accounts.transfer_to_vault(amount)?
+    };
+
+    let distribution_key = ctx.accounts.distribution.key();
+    let claim_bitmap = &mut ctx.accounts.claim_bitmap;
+
+    **claim_bitmap = ClaimBitmap::init(distribution_key, num_shares, ctx.bumps.claim_bitmap);
+
+    let distribution = &mut ctx.accounts.distribution;
+
+    distribution.merkle_root = Some(Pubkey::new_from_array(merkle_root));
+    distribution.num_shares = num_shares;
+    distribution.add_to_total(amount);
+
+    Ok(())
+}
@@ -1,6 +1,6 @@
 use std::ops::Deref;
 
-use anchor_lang::prelude::*;
+use anchor_lang::{prelude::*, solana_program::keccak::hashv};
 use anchor_spl::{
     associated_token::AssociatedToken,
     metadata::{
@@ -20,11 +20,11 @@ use emission::Emission;
 use crate::{
     constants::FEES_WALLET,
     state::{
-        emission, Collection, NftRecord, ProgramConfig, RewardType, StakeRecord, Staker,
-        Subscription,
+        emission, Collection, MultiplierProof, NftRecord, ProgramConfig, RewardType, StakeRecord,
+        Staker, Subscription,
     },
-    utils::calc_tx_fee,
-    StakeError, STAKING_ENDS,
+    utils::{calc_tx_fee, verify_merkle_proof},
+    StakeError, MULTIPLIER_BASE, STAKING_ENDS,
 };
 
 #[derive(Accounts)]
@@ -102,8 +102,6 @@ pub struct Stake<'info> {
         ],
         seeds::program = Metadata::id(),
         bump,
-        constraint = Option::is_none(&nft_metadata.collection) || nft_metadata.collection.as_ref().unwrap().verified && nft_metadata.collection.as_ref().unwrap().key == collection.collection_mint @ StakeError::InvalidCollection,
-        constraint = Option::is_some(&nft_metadata.collection) || nft_metadata.creators.as_ref().unwrap().first().unwrap().address == collection.collection_mint && nft_metadata.creators.as_ref().unwrap().first().unwrap().verified @ StakeError::InvalidCreator
     )]
     nft_metadata: Box<Account<'info, MetadataAccount>>,
 
@@ -155,6 +153,46 @@ pub struct Stake<'info> {
 }
 
 impl<'info> Stake<'info> {
+    /// Checks the NFT against the collection's allow list when a proof is given,
+    /// otherwise against its verified collection or first creator
+    pub fn validate_nft(&self, proof: Option<Vec<[u8; 32]>>) -> Result<()> {
+        let nft_metadata = &self.nft_metadata;
+        let collection_mint = self.collection.collection_mint;
+
+        if let Some(proof) = proof {
+            let allow_list = self.collection.allow_list.ok_or(StakeError::NoAllowList)?;
+            let leaf = hashv(&[self.nft_mint.key().as_ref()]).to_bytes();
+
+            require!(
+                verify_merkle_proof(&proof, allow_list.to_bytes(), leaf),
+                StakeError::InvalidProof
+            );
+
+            return Ok(());
+        }
+
+        match nft_metadata.collection.as_ref() {
+            Some(nft_collection) => require!(
+                nft_collection.verified && nft_collection.key == collection_mint,
+                StakeError::InvalidCollection
+            ),
+            None => {
+                let creator = nft_metadata
+                    .creators
+                    .as_ref()
+                    .and_then(|creators| creators.first())
+                    .ok_or(StakeError::InvalidCreator)?;
+
+                require!(
+                    creator.verified && creator.address == collection_mint,
+                    StakeError::InvalidCreator
+                )
+            }
+        }
+
+        Ok(())
+    }
+
     pub fn transfer_nft(&self) -> Result<()> {
         let metadata_program = &self.metadata_program;
         let token = &self.nft_token.as_ref().to_account_info();
@@ -296,10 +334,17 @@ impl<'info> Stake<'info> {
 pub fn stake_handler<'info>(
     ctx: Context<'_, '_, 'info, 'info, Stake<'info>>,
     selection: Option<u64>,
+    proof: Option<Vec<[u8; 32]>>,
+    multiplier: Option<MultiplierProof>,
+    lock_tier: Option<u8>,
 ) -> Result<()> {
+    ctx.accounts.validate_nft(proof)?;
+
     let staker = &ctx.accounts.staker;
     let collection = &ctx.accounts.collection;
     let current_time = Clock::get().unwrap().unix_timestamp;
+
+    collection.check_live(&staker.authority, &ctx.accounts.signer.key(), current_time)?;
     let owner = ctx.accounts.signer.key();
     let nft_mint = &ctx.accounts.nft_mint;
     let nft_record_bump = ctx.bumps.nft_record;
@@ -314,28 +359,60 @@ pub fn stake_handler<'info>(
 
     let mut pending_claim: u64 = 0;
     let mut can_claim_at: i64 = 0;
+    let mut distribution_debt: u128 = 0;
+    let mut reward_debt: u128 = 0;
 
     let mut emissions: Vec<Pubkey> = vec![];
+    let mut verified_multiplier = MULTIPLIER_BASE;
+    let mut lock_boost = MULTIPLIER_BASE;
+    let mut locked_until: i64 = 0;
+
+    require!(
+        lock_tier.is_none() || collection.token_emission.is_some(),
+        StakeError::InvalidLockTier
+    );
 
-    if Option::is_some(&collection.token_emission) {
+    // the lock tier is chosen from the primary token emission, its boost applies to
+    // every token emission with lock tiers
+    for token_emission_key in collection.token_emissions() {
         let account = ctx
             .remaining_accounts
             .iter()
-            .find(|acc| collection.token_emission.unwrap().to_bytes() == acc.key().to_bytes())
-            .unwrap();
+            .find(|acc| token_emission_key == acc.key())
+            .ok_or(StakeError::EmissionsMissing)?;
 
         let mut token_emission =
             Account::<'info, Emission>::try_from(account).expect("Expected emission to be passed");
 
-        require_keys_eq!(
-            token_emission.key(),
-            collection.token_emission.unwrap(),
-            StakeError::InvalidEmission
-        );
-
         require!(token_emission.active, StakeError::EmissionNotActive);
-        token_emission.update_staked_weight(current_time, true)?;
-        token_emission.increase_staked_items()?;
+
+        let token_multiplier = token_emission.verify_multiplier(&nft_mint.key(), &multiplier)?;
+        if token_emission.merkle_root.is_some() {
+            verified_multiplier = token_multiplier;
+        }
+
+        match lock_tier {
+            Some(index) if collection.token_emission == Some(token_emission_key) => {
+                let tier = token_emission.lock_tier(index)?;
+                lock_boost = tier.boost;
+                locked_until = current_time
+                    .checked_add(tier.duration)
+                    .ok_or(StakeError::ProgramAddError)?;
+            }
+            _ => {}
+        }
+
+        let amount = token_emission.item_amount(token_multiplier, lock_boost)?;
+        token_emission.update_staked_weight_for_amount(current_time, amount, true)?;
+
+        // only the primary token emission can be shared
+        if token_emission.shared {
+            let token_vault = token_emission.is_vault_funded(staker);
+            token_emission.update_reward_per_item(current_time, token_vault)?;
+            reward_debt = token_emission.reward_per_item;
+        }
+
+        token_emission.increase_staked_amount(amount)?;
 
         emissions.push(token_emission.key());
 
@@ -360,14 +437,22 @@ pub fn stake_handler<'info>(
 
         require!(points_emission.active, StakeError::EmissionNotActive);
 
-        // let nft_record = &mut ctx.accounts.nft_record.as_ref().unwrap();
+        let nft_record = ctx
+            .accounts
+            .nft_record
+            .as_mut()
+            .ok_or(StakeError::NftRecordRequired)?;
 
-        // if nft_record.nft_mint.eq(&Pubkey::default()) {
-        //     ****nft_record = NftRecord::init(nft_mint.key(), nft_record_bump);
-        // }
+        if nft_record.nft_mint.eq(&Pubkey::default()) {
+            ***nft_record = NftRecord::init(nft_mint.key(), nft_record_bump);
+        }
 
-        points_emission.update_staked_weight(current_time, true)?;
-        points_emission.increase_staked_items()?;
+        let amount = points_emission.verify_multiplier(&nft_mint.key(), &multiplier)?;
+        if points_emission.merkle_root.is_some() {
+            verified_multiplier = amount;
+        }
+        points_emission.update_staked_weight_for_amount(current_time, amount, true)?;
+        points_emission.increase_staked_amount(amount)?;
 
         emissions.push(points_emission.key());
 
@@ -461,6 +546,7 @@ pub fn stake_handler<'info>(
         require!(distribution_emission.active, StakeError::EmissionNotActive);
 
         distribution_emission.increase_staked_items()?;
+        distribution_debt = distribution_emission.reward_per_item;
         emissions.push(distribution_emission.key());
         distribution_emission.exit(ctx.program_id)?;
     }
@@ -544,6 +630,12 @@ pub fn stake_handler<'info>(
         stake_record_bump,
     );
 
+    stake_record.multiplier = verified_multiplier;
+    stake_record.lock_boost = lock_boost;
+    stake_record.locked_until = locked_until;
+    stake_record.distribution_debt = distribution_debt;
+    stake_record.reward_debt = reward_debt;
+
     collection.increase_staker_count()?;
     let staker = &mut ctx.accounts.staker;
     staker.increase_staker_count()
@@ -0,0 +1,368 @@
+use anchor_lang::prelude::*;
+use mpl_bubblegum::{
+    hash::{hash_creators, hash_metadata},
+    instructions::TransferCpiBuilder,
+    programs::{MPL_BUBBLEGUM_ID, SPL_ACCOUNT_COMPRESSION_ID, SPL_NOOP_ID},
+    types::MetadataArgs,
+    utils::get_asset_id,
+};
+
+use crate::{
+    constants::FEES_WALLET,
+    state::{
+        Collection, Emission, MultiplierProof, NftRecord, ProgramConfig, RewardType, StakeRecord,
+        Staker, Subscription,
+    },
+    utils::calc_tx_fee,
+    StakeError, MULTIPLIER_BASE,
+};
+
+#[derive(Accounts)]
+#[instruction(root: [u8; 32], nonce: u64)]
+pub struct StakeCnft<'info> {
+    #[account(mut)]
+    pub staker: Box<Account<'info, Staker>>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"STAKE",
+            staker.key().as_ref(),
+            collection.collection_mint.as_ref(),
+            b"collection",
+        ],
+        bump = collection.bump
+    )]
+    pub collection: Box<Account<'info, Collection>>,
+
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = NftRecord::LEN,
+        seeds = [
+            b"STAKE",
+            staker.key().as_ref(),
+            get_asset_id(&merkle_tree.key(), nonce).as_ref(),
+            b"nft-record",
+        ],
+        bump,
+    )]
+    pub nft_record: Option<Box<Account<'info, NftRecord>>>,
+
+    #[account(
+        init,
+        payer = signer,
+        space = StakeRecord::LEN,
+        seeds = [
+            b"STAKE",
+            staker.key().as_ref(),
+            get_asset_id(&merkle_tree.key(), nonce).as_ref(),
+            b"stake-record",
+        ],
+        bump
+    )]
+    pub stake_record: Box<Account<'info, StakeRecord>>,
+
+    #[account(
+        seeds = [b"program-config"],
+        bump
+    )]
+    pub program_config: Box<Account<'info, ProgramConfig>>,
+
+    /// CHECK: this account is not read or written
+    #[account(
+        seeds = [
+            b"STAKE",
+            staker.key().as_ref(),
+            b"nft-authority",
+        ],
+        bump = staker.nft_auth_bump
+    )]
+    pub nft_authority: UncheckedAccount<'info>,
+
+    /// CHECK: checked in CPI call
+    #[account(
+        seeds = [merkle_tree.key().as_ref()],
+        seeds::program = bubblegum_program.key(),
+        bump
+    )]
+    pub tree_config: UncheckedAccount<'info>,
+
+    /// CHECK: checked in CPI call
+    #[account(mut)]
+    pub merkle_tree: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    #[account(mut, address = FEES_WALLET)]
+    pub fees_wallet: SystemAccount<'info>,
+
+    /// CHECK: this account is constrained to a specific address
+    #[account(address = MPL_BUBBLEGUM_ID)]
+    pub bubblegum_program: UncheckedAccount<'info>,
+    /// CHECK: this account is constrained to a specific address
+    #[account(address = SPL_NOOP_ID)]
+    pub log_wrapper: UncheckedAccount<'info>,
+    /// CHECK: this account is constrained to a specific address
+    #[account(address = SPL_ACCOUNT_COMPRESSION_ID)]
+    pub compression_program: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> StakeCnft<'info> {
+    /// Checks the leaf belongs to the collection, returning the data and creator hashes
+    /// used to rebuild the leaf in the transfer CPI
+    pub fn validate_cnft(&self, metadata: &MetadataArgs) -> Result<([u8; 32], [u8; 32])> {
+        let collection_mint = self.collection.collection_mint;
+
+        let is_verified_collection = metadata
+            .collection
+            .as_ref()
+            .map(|collection| collection.verified && collection.key == collection_mint)
+            .unwrap_or(false);
+
+        let is_verified_creator = metadata
+            .creators
+            .first()
+            .map(|creator| creator.verified && creator.address == collection_mint)
+            .unwrap_or(false);
+
+        require!(
+            is_verified_collection || is_verified_creator,
+            StakeError::InvalidCollection
+        );
+
+        Ok((hash_metadata(metadata)?, hash_creators(&metadata.creators)))
+    }
+
+    /// Compressed leaves cannot be frozen in place, so the leaf is always
+    /// transferred to the nft authority. The Merkle proof is checked in the CPI.
+    pub fn transfer_cnft(
+        &self,
+        proof: &[AccountInfo<'info>],
+        root: [u8; 32],
+        data_hash: [u8; 32],
+        creator_hash: [u8; 32],
+        nonce: u64,
+        index: u32,
+    ) -> Result<()> {
+        let bubblegum_program = self.bubblegum_program.to_account_info();
+        let signer = self.signer.to_account_info();
+        let nft_authority = self.nft_authority.to_account_info();
+
+        let proof_accounts: Vec<(&AccountInfo<'info>, bool, bool)> =
+            proof.iter().map(|node| (node, false, false)).collect();
+
+        TransferCpiBuilder::new(&bubblegum_program)
+            .tree_config(&self.tree_config.to_account_info())
+            .leaf_owner(&signer, true)
+            .leaf_delegate(&signer, false)
+            .new_leaf_owner(&nft_authority)
+            .merkle_tree(&self.merkle_tree.to_account_info())
+            .log_wrapper(&self.log_wrapper.to_account_info())
+            .compression_program(&self.compression_program.to_account_info())
+            .system_program(&self.system_program.to_account_info())
+            .root(root)
+            .data_hash(data_hash)
+            .creator_hash(creator_hash)
+            .nonce(nonce)
+            .index(index)
+            .add_remaining_accounts(&proof_accounts)
+            .invoke()?;
+
+        Ok(())
+    }
+}
+
+pub fn stake_cnft_handler<'info>(
+    ctx: Context<'_, '_, 'info, 'info, StakeCnft<'info>>,
+    root: [u8; 32],
+    nonce: u64,
+    index: u32,
+    metadata: Vec<u8>,
+    selection: Option<u64>,
+    multiplier: Option<MultiplierProof>,
+) -> Result<()> {
+    let staker = &ctx.accounts.staker;
+    let collection = &ctx.accounts.collection;
+    let current_time = Clock::get().unwrap().unix_timestamp;
+
+    collection.check_live(&staker.authority, &ctx.accounts.signer.key(), current_time)?;
+
+    let Staker {
+        is_active: staker_active,
+        ..
+    } = **staker.as_ref();
+
+    let Collection {
+        current_stakers_count: current_stakers,
+        max_stakers_count: max_stakers,
+        is_active: collection_is_active,
+        ..
+    } = **collection.as_ref();
+
+    if !staker_active {
+        require_keys_eq!(
+            staker.authority,
+            ctx.accounts.signer.key(),
+            StakeError::StakeInactive
+        )
+    }
+
+    if !collection_is_active {
+        require_keys_eq!(
+            staker.authority,
+            ctx.accounts.signer.key(),
+            StakeError::CollectionInactive
+        )
+    }
+
+    require_gt!(max_stakers, current_stakers, StakeError::MaxStakersReached);
+
+    let metadata =
+        MetadataArgs::try_from_slice(&metadata).map_err(|_| StakeError::InvalidCnftMetadata)?;
+
+    let (data_hash, creator_hash) = ctx.accounts.validate_cnft(&metadata)?;
+
+    // emissions are passed first, followed by the proof nodes for the leaf
+    let emission_keys = collection.emissions();
+    let has_points = Option::is_some(&collection.points_emission);
+
+    require_gte!(
+        ctx.remaining_accounts.len(),
+        emission_keys.len(),
+        StakeError::EmissionsMissing
+    );
+
+    let (emission_accounts, proof) = ctx.remaining_accounts.split_at(emission_keys.len());
+
+    let mut pending_claim: u64 = 0;
+    let mut can_claim_at: i64 = 0;
+    let mut distribution_debt: u128 = 0;
+    let mut reward_debt: u128 = 0;
+    let mut verified_multiplier = MULTIPLIER_BASE;
+
+    let asset_id = get_asset_id(&ctx.accounts.merkle_tree.key(), nonce);
+
+    for (key, account) in emission_keys.iter().zip(emission_accounts.iter()) {
+        require_keys_eq!(account.key(), *key, StakeError::InvalidEmission);
+
+        let mut emission = Account::<'info, Emission>::try_from(account)?;
+
+        require!(emission.active, StakeError::EmissionNotActive);
+
+        let item_multiplier = emission.verify_multiplier(&asset_id, &multiplier)?;
+        if emission.merkle_root.is_some() {
+            verified_multiplier = item_multiplier;
+        }
+
+        // lock tiers can only be chosen through `stake`, so these count without a boost
+        let amount = emission.item_amount(item_multiplier, MULTIPLIER_BASE)?;
+
+        match emission.reward_type.clone() {
+            RewardType::Token | RewardType::Points => {
+                emission.update_staked_weight_for_amount(current_time, amount, true)?;
+
+                if emission.shared {
+                    let token_vault = emission.is_vault_funded(staker);
+                    emission.update_reward_per_item(current_time, token_vault)?;
+                    reward_debt = emission.reward_per_item;
+                }
+            }
+            RewardType::Selection { options } => {
+                let index = selection.ok_or(StakeError::EmissionSelectionRequired)? as usize;
+                require_gt!(options.len(), index, StakeError::InvalidIndex);
+                let option = options[index];
+
+                let balance_owing = option
+                    .reward
+                    .checked_mul(option.duration as u64)
+                    .ok_or(StakeError::ProgramMulError)?;
+
+                require_gte!(
+                    emission.current_balance,
+                    balance_owing,
+                    StakeError::InsufficientBalanceInVault
+                );
+
+                emission.staked_weight = emission
+                    .staked_weight
+                    .checked_add(balance_owing.into())
+                    .ok_or(StakeError::ProgramAddError)?;
+
+                pending_claim = balance_owing;
+                can_claim_at = current_time + option.duration;
+            }
+            RewardType::Distribution => {
+                distribution_debt = emission.reward_per_item;
+            }
+        }
+
+        emission.increase_staked_amount(amount)?;
+        emission.exit(ctx.program_id)?;
+    }
+
+    ctx.accounts
+        .transfer_cnft(proof, root, data_hash, creator_hash, nonce, index)?;
+
+    if has_points {
+        let nft_record = ctx
+            .accounts
+            .nft_record
+            .as_mut()
+            .ok_or(StakeError::NftRecordRequired)?;
+
+        if nft_record.nft_mint == Pubkey::default() {
+            ***nft_record = NftRecord::init(asset_id, ctx.bumps.nft_record);
+        }
+    }
+
+    let tx_fee = match staker.get_subscription() {
+        Subscription::Custom {
+            amount: _,
+            stake_fee,
+            unstake_fee: _,
+            claim_fee: _,
+        } => stake_fee,
+        _ => ctx.accounts.program_config.stake_fee,
+    };
+
+    let tx_fee = calc_tx_fee(staker, tx_fee);
+
+    if tx_fee > 0 {
+        let ix = anchor_lang::solana_program::system_instruction::transfer(
+            &ctx.accounts.signer.key(),
+            &ctx.accounts.fees_wallet.key(),
+            tx_fee,
+        );
+
+        anchor_lang::solana_program::program::invoke(
+            &ix,
+            &[
+                ctx.accounts.signer.to_account_info(),
+                ctx.accounts.fees_wallet.to_account_info(),
+            ],
+        )?;
+    }
+
+    let stake_record = &mut ctx.accounts.stake_record;
+
+    ***stake_record = StakeRecord::init(
+        ctx.accounts.staker.key(),
+        ctx.accounts.signer.key(),
+        asset_id,
+        emission_keys,
+        current_time,
+        pending_claim,
+        can_claim_at,
+        ctx.bumps.stake_record,
+    );
+
+    stake_record.distribution_debt = distribution_debt;
+    stake_record.reward_debt = reward_debt;
+    stake_record.multiplier = verified_multiplier;
+
+    ctx.accounts.collection.increase_staker_count()?;
+    ctx.accounts.staker.increase_staker_count()
+}
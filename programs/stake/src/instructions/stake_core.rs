@@ -0,0 +1,353 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    constants::FEES_WALLET,
+    state::{
+        Collection, Emission, MultiplierProof, NftRecord, ProgramConfig, RewardType, StakeRecord,
+        Staker, Subscription,
+    },
+    utils::{calc_tx_fee, CoreAccounts, CoreAsset, CoreUpdateAuthority, MPL_CORE_ID},
+    StakeError, MULTIPLIER_BASE,
+};
+
+#[derive(Accounts)]
+pub struct StakeCore<'info> {
+    #[account(mut)]
+    pub staker: Box<Account<'info, Staker>>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"STAKE",
+            staker.key().as_ref(),
+            collection.collection_mint.as_ref(),
+            b"collection",
+        ],
+        bump = collection.bump
+    )]
+    pub collection: Box<Account<'info, Collection>>,
+
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = NftRecord::LEN,
+        seeds = [
+            b"STAKE",
+            staker.key().as_ref(),
+            asset.key().as_ref(),
+            b"nft-record",
+        ],
+        bump,
+    )]
+    pub nft_record: Option<Box<Account<'info, NftRecord>>>,
+
+    #[account(
+        init,
+        payer = signer,
+        space = StakeRecord::LEN,
+        seeds = [
+            b"STAKE",
+            staker.key().as_ref(),
+            asset.key().as_ref(),
+            b"stake-record",
+        ],
+        bump
+    )]
+    pub stake_record: Box<Account<'info, StakeRecord>>,
+
+    #[account(
+        seeds = [b"program-config"],
+        bump
+    )]
+    pub program_config: Box<Account<'info, ProgramConfig>>,
+
+    /// CHECK: this account is not read or written
+    #[account(
+        seeds = [
+            b"STAKE",
+            staker.key().as_ref(),
+            b"nft-authority",
+        ],
+        bump = staker.nft_auth_bump
+    )]
+    pub nft_authority: UncheckedAccount<'info>,
+
+    /// CHECK: this account is deserialized and checked in the handler
+    #[account(mut)]
+    pub asset: UncheckedAccount<'info>,
+
+    /// CHECK: this account is constrained to the collection and checked in CPI calls
+    #[account(mut, address = collection.collection_mint)]
+    pub core_collection: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    #[account(mut, address = FEES_WALLET)]
+    pub fees_wallet: SystemAccount<'info>,
+
+    /// CHECK: this account is constrained to a specific address
+    #[account(address = MPL_CORE_ID)]
+    pub core_program: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> StakeCore<'info> {
+    /// Checks the asset belongs to the Core collection and is owned by the signer
+    pub fn validate_asset(&self) -> Result<CoreAsset> {
+        let asset = CoreAsset::load(&self.asset.to_account_info())?;
+
+        require!(
+            asset.update_authority
+                == CoreUpdateAuthority::Collection(self.collection.collection_mint),
+            StakeError::InvalidCollection
+        );
+
+        require_keys_eq!(asset.owner, self.signer.key(), StakeError::Unauthorized);
+
+        Ok(asset)
+    }
+
+    pub fn transfer_asset(&self) -> Result<()> {
+        let signer = self.signer.to_account_info();
+
+        CoreAccounts {
+            core_program: &self.core_program.to_account_info(),
+            asset: &self.asset.to_account_info(),
+            collection: &self.core_collection.to_account_info(),
+            payer: &signer,
+            authority: &signer,
+            system_program: &self.system_program.to_account_info(),
+        }
+        .transfer(&self.nft_authority.to_account_info(), &[])
+    }
+
+    /// Freezes the asset in the owner's wallet using the freeze delegate plugin,
+    /// with the nft authority as the plugin authority
+    pub fn freeze_asset(&self, asset: &CoreAsset) -> Result<()> {
+        let staker_key = self.staker.key();
+        let signer = self.signer.to_account_info();
+        let nft_authority = self.nft_authority.to_account_info();
+        let core_program = self.core_program.to_account_info();
+        let asset_account = self.asset.to_account_info();
+        let collection = self.core_collection.to_account_info();
+        let system_program = self.system_program.to_account_info();
+
+        let owner_accounts = CoreAccounts {
+            core_program: &core_program,
+            asset: &asset_account,
+            collection: &collection,
+            payer: &signer,
+            authority: &signer,
+            system_program: &system_program,
+        };
+
+        match asset.freeze_delegate {
+            None => owner_accounts.add_freeze_delegate(nft_authority.key(), &[]),
+            Some(_) => {
+                // the plugin is left on the asset after a force unstake, so is re-approved and frozen
+                owner_accounts.approve_freeze_delegate(nft_authority.key(), &[])?;
+
+                let txn_signer: &[&[u8]; 4] = &[
+                    &b"STAKE"[..],
+                    staker_key.as_ref(),
+                    &b"nft-authority"[..],
+                    &[self.staker.nft_auth_bump],
+                ];
+
+                CoreAccounts {
+                    authority: &nft_authority,
+                    ..owner_accounts
+                }
+                .set_frozen(true, &[txn_signer])
+            }
+        }
+    }
+}
+
+pub fn stake_core_handler<'info>(
+    ctx: Context<'_, '_, 'info, 'info, StakeCore<'info>>,
+    selection: Option<u64>,
+    multiplier: Option<MultiplierProof>,
+) -> Result<()> {
+    let staker = &ctx.accounts.staker;
+    let collection = &ctx.accounts.collection;
+    let current_time = Clock::get().unwrap().unix_timestamp;
+
+    collection.check_live(&staker.authority, &ctx.accounts.signer.key(), current_time)?;
+
+    let Staker {
+        is_active: staker_active,
+        ..
+    } = **staker.as_ref();
+
+    let Collection {
+        custodial,
+        current_stakers_count: current_stakers,
+        max_stakers_count: max_stakers,
+        is_active: collection_is_active,
+        ..
+    } = **collection.as_ref();
+
+    if !staker_active {
+        require_keys_eq!(
+            staker.authority,
+            ctx.accounts.signer.key(),
+            StakeError::StakeInactive
+        )
+    }
+
+    if !collection_is_active {
+        require_keys_eq!(
+            staker.authority,
+            ctx.accounts.signer.key(),
+            StakeError::CollectionInactive
+        )
+    }
+
+    require_gt!(max_stakers, current_stakers, StakeError::MaxStakersReached);
+
+    let asset = ctx.accounts.validate_asset()?;
+
+    // emissions are passed in remaining accounts, in the order they are recorded on the stake record
+    let emission_keys = collection.emissions();
+    let has_points = Option::is_some(&collection.points_emission);
+
+    require_gte!(
+        ctx.remaining_accounts.len(),
+        emission_keys.len(),
+        StakeError::EmissionsMissing
+    );
+
+    let mut pending_claim: u64 = 0;
+    let mut can_claim_at: i64 = 0;
+    let mut distribution_debt: u128 = 0;
+    let mut reward_debt: u128 = 0;
+    let mut verified_multiplier = MULTIPLIER_BASE;
+
+    for (key, account) in emission_keys.iter().zip(ctx.remaining_accounts.iter()) {
+        require_keys_eq!(account.key(), *key, StakeError::InvalidEmission);
+
+        let mut emission = Account::<'info, Emission>::try_from(account)?;
+
+        require!(emission.active, StakeError::EmissionNotActive);
+
+        let item_multiplier = emission.verify_multiplier(&ctx.accounts.asset.key(), &multiplier)?;
+        if emission.merkle_root.is_some() {
+            verified_multiplier = item_multiplier;
+        }
+
+        // lock tiers can only be chosen through `stake`, so these count without a boost
+        let amount = emission.item_amount(item_multiplier, MULTIPLIER_BASE)?;
+
+        match emission.reward_type.clone() {
+            RewardType::Token | RewardType::Points => {
+                emission.update_staked_weight_for_amount(current_time, amount, true)?;
+
+                if emission.shared {
+                    let token_vault = emission.is_vault_funded(staker);
+                    emission.update_reward_per_item(current_time, token_vault)?;
+                    reward_debt = emission.reward_per_item;
+                }
+            }
+            RewardType::Selection { options } => {
+                let index = selection.ok_or(StakeError::EmissionSelectionRequired)? as usize;
+                require_gt!(options.len(), index, StakeError::InvalidIndex);
+                let option = options[index];
+
+                let balance_owing = option
+                    .reward
+                    .checked_mul(option.duration as u64)
+                    .ok_or(StakeError::ProgramMulError)?;
+
+                require_gte!(
+                    emission.current_balance,
+                    balance_owing,
+                    StakeError::InsufficientBalanceInVault
+                );
+
+                emission.staked_weight = emission
+                    .staked_weight
+                    .checked_add(balance_owing.into())
+                    .ok_or(StakeError::ProgramAddError)?;
+
+                pending_claim = balance_owing;
+                can_claim_at = current_time + option.duration;
+            }
+            RewardType::Distribution => {
+                distribution_debt = emission.reward_per_item;
+            }
+        }
+
+        emission.increase_staked_amount(amount)?;
+        emission.exit(ctx.program_id)?;
+    }
+
+    if custodial {
+        ctx.accounts.transfer_asset()?;
+    } else {
+        ctx.accounts.freeze_asset(&asset)?;
+    }
+
+    let asset_key = ctx.accounts.asset.key();
+
+    if has_points {
+        let nft_record = ctx
+            .accounts
+            .nft_record
+            .as_mut()
+            .ok_or(StakeError::NftRecordRequired)?;
+
+        if nft_record.nft_mint == Pubkey::default() {
+            ***nft_record = NftRecord::init(asset_key, ctx.bumps.nft_record);
+        }
+    }
+
+    let tx_fee = match staker.get_subscription() {
+        Subscription::Custom {
+            amount: _,
+            stake_fee,
+            unstake_fee: _,
+            claim_fee: _,
+        } => stake_fee,
+        _ => ctx.accounts.program_config.stake_fee,
+    };
+
+    let tx_fee = calc_tx_fee(staker, tx_fee);
+
+    if tx_fee > 0 {
+        let ix = anchor_lang::solana_program::system_instruction::transfer(
+            &ctx.accounts.signer.key(),
+            &ctx.accounts.fees_wallet.key(),
+            tx_fee,
+        );
+
+        anchor_lang::solana_program::program::invoke(
+            &ix,
+            &[
+                ctx.accounts.signer.to_account_info(),
+                ctx.accounts.fees_wallet.to_account_info(),
+            ],
+        )?;
+    }
+
+    let stake_record = &mut ctx.accounts.stake_record;
+
+    ***stake_record = StakeRecord::init(
+        ctx.accounts.staker.key(),
+        ctx.accounts.signer.key(),
+        asset_key,
+        emission_keys,
+        current_time,
+        pending_claim,
+        can_claim_at,
+        ctx.bumps.stake_record,
+    );
+
+    stake_record.distribution_debt = distribution_debt;
+    stake_record.reward_debt = reward_debt;
+    stake_record.multiplier = verified_multiplier;
+
+    ctx.accounts.collection.increase_staker_count()?;
+    ctx.accounts.staker.increase_staker_count()
+}
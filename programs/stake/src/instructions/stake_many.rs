@@ -0,0 +1,627 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::{create_idempotent, get_associated_token_address, AssociatedToken, Create},
+    metadata::{
+        mpl_token_metadata::{
+            instructions::{
+                DelegateStandardV1CpiBuilder, DelegateUtilityV1CpiBuilder, LockV1CpiBuilder,
+                TransferV1CpiBuilder,
+            },
+            types::TokenStandard,
+        },
+        MasterEditionAccount, Metadata, MetadataAccount,
+    },
+    token::{Mint, Token, TokenAccount},
+};
+
+use crate::{
+    constants::FEES_WALLET,
+    state::{
+        Collection, Emission, MultiplierProof, NftRecord, ProgramConfig, RewardType, StakeRecord,
+        Staker, Subscription,
+    },
+    utils::{calc_tx_fee, create_pda_account},
+    StakeError, MULTIPLIER_BASE,
+};
+
+#[derive(Accounts)]
+pub struct StakeMany<'info> {
+    #[account(mut)]
+    pub staker: Box<Account<'info, Staker>>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"STAKE",
+            staker.key().as_ref(),
+            collection.collection_mint.as_ref(),
+            b"collection",
+        ],
+        bump = collection.bump
+    )]
+    pub collection: Box<Account<'info, Collection>>,
+
+    #[account(
+        seeds = [b"program-config"],
+        bump
+    )]
+    pub program_config: Box<Account<'info, ProgramConfig>>,
+
+    /// CHECK: this account is not read or written
+    #[account(
+        seeds = [
+            b"STAKE",
+            staker.key().as_ref(),
+            b"nft-authority",
+        ],
+        bump = staker.nft_auth_bump
+    )]
+    pub nft_authority: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    #[account(mut, address = FEES_WALLET)]
+    pub fees_wallet: SystemAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    pub metadata_program: Program<'info, Metadata>,
+    /// CHECK: account checked in CPI
+    pub sysvar_instructions: AccountInfo<'info>,
+    /// CHECK: account checked in CPI
+    pub auth_rules: Option<AccountInfo<'info>>,
+    /// CHECK: account checked in CPI
+    pub auth_rules_program: Option<AccountInfo<'info>>,
+}
+
+/// The accounts for a single NFT, read from a fixed size group of remaining accounts.
+/// Optional accounts are passed as the program id, as with anchor optional accounts.
+pub struct StakeManyItem<'info> {
+    pub nft_mint: &'info AccountInfo<'info>,
+    pub nft_token: &'info AccountInfo<'info>,
+    pub nft_metadata: &'info AccountInfo<'info>,
+    pub nft_edition: &'info AccountInfo<'info>,
+    pub owner_token_record: Option<&'info AccountInfo<'info>>,
+    pub destination_token_record: Option<&'info AccountInfo<'info>>,
+    pub nft_custody: Option<&'info AccountInfo<'info>>,
+    pub stake_record: &'info AccountInfo<'info>,
+    pub nft_record: Option<&'info AccountInfo<'info>>,
+}
+
+impl<'info> StakeManyItem<'info> {
+    /// mint, token, metadata, edition, owner token record, destination token record,
+    /// custody, stake record, nft record
+    pub const LEN: usize = 9;
+
+    pub fn from_accounts(accounts: &'info [AccountInfo<'info>]) -> Self {
+        let optional = |account: &'info AccountInfo<'info>| {
+            if account.key() == crate::ID {
+                None
+            } else {
+                Some(account)
+            }
+        };
+
+        Self {
+            nft_mint: &accounts[0],
+            nft_token: &accounts[1],
+            nft_metadata: &accounts[2],
+            nft_edition: &accounts[3],
+            owner_token_record: optional(&accounts[4]),
+            destination_token_record: optional(&accounts[5]),
+            nft_custody: optional(&accounts[6]),
+            stake_record: &accounts[7],
+            nft_record: optional(&accounts[8]),
+        }
+    }
+}
+
+impl<'info> StakeMany<'info> {
+    pub fn validate_nft(
+        &self,
+        item: &StakeManyItem<'info>,
+    ) -> Result<Account<'info, MetadataAccount>> {
+        let nft_mint = Account::<Mint>::try_from(item.nft_mint)?;
+        require_eq!(nft_mint.decimals, 0, StakeError::TokenNotNFT);
+        require_eq!(nft_mint.supply, 1, StakeError::TokenNotNFT);
+
+        let nft_token = Account::<TokenAccount>::try_from(item.nft_token)?;
+        require_keys_eq!(
+            item.nft_token.key(),
+            get_associated_token_address(&self.signer.key(), &nft_mint.key()),
+            StakeError::TokenAccountEmpty
+        );
+        require_eq!(nft_token.amount, 1, StakeError::TokenAccountEmpty);
+
+        let (metadata_key, _) = Pubkey::find_program_address(
+            &[
+                b"metadata",
+                Metadata::id().as_ref(),
+                nft_mint.key().as_ref(),
+            ],
+            &Metadata::id(),
+        );
+        require_keys_eq!(
+            item.nft_metadata.key(),
+            metadata_key,
+            StakeError::InvalidCollection
+        );
+
+        let nft_metadata = Account::<MetadataAccount>::try_from(item.nft_metadata)?;
+        let collection_mint = self.collection.collection_mint;
+
+        match nft_metadata.collection.as_ref() {
+            Some(collection) => require!(
+                collection.verified && collection.key == collection_mint,
+                StakeError::InvalidCollection
+            ),
+            None => {
+                let first_creator = nft_metadata
+                    .creators
+                    .as_ref()
+                    .and_then(|creators| creators.first())
+                    .ok_or(StakeError::InvalidCreator)?;
+                require!(
+                    first_creator.verified && first_creator.address == collection_mint,
+                    StakeError::InvalidCreator
+                );
+            }
+        }
+
+        Account::<MasterEditionAccount>::try_from(item.nft_edition)?;
+
+        Ok(nft_metadata)
+    }
+
+    pub fn transfer_nft(&self, item: &StakeManyItem<'info>) -> Result<()> {
+        let nft_custody = item
+            .nft_custody
+            .ok_or(StakeError::UnexpectedRemainingAccounts)?;
+        let token_owner = &self.signer.to_account_info();
+        let spl_token_program = &self.token_program.to_account_info();
+
+        create_idempotent(CpiContext::new(
+            self.associated_token_program.to_account_info(),
+            Create {
+                payer: token_owner.clone(),
+                associated_token: nft_custody.clone(),
+                authority: self.nft_authority.to_account_info(),
+                mint: item.nft_mint.clone(),
+                system_program: self.system_program.to_account_info(),
+                token_program: spl_token_program.clone(),
+            },
+        ))?;
+
+        let mut cpi_transfer = TransferV1CpiBuilder::new(&self.metadata_program);
+
+        cpi_transfer
+            .token(item.nft_token)
+            .token_owner(token_owner)
+            .destination_token(nft_custody)
+            .destination_owner(&self.nft_authority)
+            .mint(item.nft_mint)
+            .metadata(item.nft_metadata)
+            .edition(Some(item.nft_edition))
+            .authority(token_owner)
+            .payer(token_owner)
+            .system_program(&self.system_program)
+            .sysvar_instructions(&self.sysvar_instructions)
+            .spl_token_program(spl_token_program)
+            .spl_ata_program(&self.associated_token_program)
+            .authorization_rules_program(self.auth_rules_program.as_ref())
+            .authorization_rules(self.auth_rules.as_ref())
+            .token_record(item.owner_token_record)
+            .destination_token_record(item.destination_token_record)
+            .amount(1);
+
+        cpi_transfer.invoke()?;
+        Ok(())
+    }
+
+    pub fn lock_nft(&self, item: &StakeManyItem<'info>, metadata: &MetadataAccount) -> Result<()> {
+        let staker_key = &self.staker.key();
+        let nft_auth_bump = &self.staker.nft_auth_bump;
+        let token_owner = &self.signer.to_account_info();
+        let nft_authority = &self.nft_authority.to_account_info();
+        let spl_token_program = &self.token_program.to_account_info();
+        let auth_rules_program = self.auth_rules_program.as_ref();
+        let auth_rules = self.auth_rules.as_ref();
+
+        let txn_signer: &[&[u8]; 4] = &[
+            &b"STAKE"[..],
+            staker_key.as_ref(),
+            &b"nft-authority"[..],
+            &[*nft_auth_bump],
+        ];
+
+        if matches!(
+            metadata.token_standard,
+            Some(TokenStandard::ProgrammableNonFungible)
+        ) {
+            let mut cpi_delegate = DelegateUtilityV1CpiBuilder::new(&self.metadata_program);
+            cpi_delegate
+                .delegate(nft_authority)
+                .token(item.nft_token)
+                .mint(item.nft_mint)
+                .metadata(item.nft_metadata)
+                .master_edition(Some(item.nft_edition))
+                .authority(token_owner)
+                .payer(token_owner)
+                .system_program(&self.system_program)
+                .sysvar_instructions(&self.sysvar_instructions)
+                .spl_token_program(Some(spl_token_program))
+                .authorization_rules_program(auth_rules_program)
+                .authorization_rules(auth_rules)
+                .token_record(item.owner_token_record)
+                .amount(1);
+
+            cpi_delegate.invoke()?;
+        } else {
+            let mut cpi_delegate = DelegateStandardV1CpiBuilder::new(&self.metadata_program);
+            cpi_delegate
+                .delegate(nft_authority)
+                .token(item.nft_token)
+                .mint(item.nft_mint)
+                .metadata(item.nft_metadata)
+                .master_edition(Some(item.nft_edition))
+                .authority(token_owner)
+                .payer(token_owner)
+                .system_program(&self.system_program)
+                .sysvar_instructions(&self.sysvar_instructions)
+                .spl_token_program(Some(spl_token_program))
+                .amount(1);
+
+            cpi_delegate.invoke()?;
+        };
+
+        let mut cpi_lock = LockV1CpiBuilder::new(&self.metadata_program);
+        cpi_lock
+            .token(item.nft_token)
+            .token_owner(Some(token_owner))
+            .mint(item.nft_mint)
+            .metadata(item.nft_metadata)
+            .edition(Some(item.nft_edition))
+            .authority(nft_authority)
+            .payer(token_owner)
+            .system_program(&self.system_program)
+            .sysvar_instructions(&self.sysvar_instructions)
+            .spl_token_program(Some(spl_token_program))
+            .authorization_rules_program(auth_rules_program)
+            .authorization_rules(auth_rules)
+            .token_record(item.owner_token_record);
+
+        cpi_lock.invoke_signed(&[txn_signer])?;
+
+        Ok(())
+    }
+
+    pub fn init_stake_record(
+        &self,
+        item: &StakeManyItem<'info>,
+        emissions: Vec<Pubkey>,
+        staked_at: i64,
+        pending_claim: u64,
+        can_claim_at: i64,
+        distribution_debt: u128,
+        reward_debt: u128,
+        multiplier: u64,
+    ) -> Result<()> {
+        let staker_key = self.staker.key();
+        let nft_mint = item.nft_mint.key();
+
+        let (stake_record_key, bump) = Pubkey::find_program_address(
+            &[
+                b"STAKE",
+                staker_key.as_ref(),
+                nft_mint.as_ref(),
+                b"stake-record",
+            ],
+            &crate::ID,
+        );
+
+        require_keys_eq!(
+            item.stake_record.key(),
+            stake_record_key,
+            StakeError::UnexpectedRemainingAccounts
+        );
+
+        create_pda_account(
+            &self.signer.to_account_info(),
+            item.stake_record,
+            &self.system_program.to_account_info(),
+            StakeRecord::LEN,
+            &[
+                &b"STAKE"[..],
+                staker_key.as_ref(),
+                nft_mint.as_ref(),
+                &b"stake-record"[..],
+                &[bump],
+            ],
+        )?;
+
+        let mut stake_record = StakeRecord::init(
+            staker_key,
+            self.signer.key(),
+            nft_mint,
+            emissions,
+            staked_at,
+            pending_claim,
+            can_claim_at,
+            bump,
+        );
+        stake_record.distribution_debt = distribution_debt;
+        stake_record.reward_debt = reward_debt;
+        stake_record.multiplier = multiplier;
+
+        let mut data = item.stake_record.try_borrow_mut_data()?;
+        stake_record.try_serialize(&mut &mut data[..])
+    }
+
+    pub fn init_nft_record(&self, item: &StakeManyItem<'info>) -> Result<()> {
+        let nft_record = item
+            .nft_record
+            .ok_or(StakeError::UnexpectedRemainingAccounts)?;
+        let staker_key = self.staker.key();
+        let nft_mint = item.nft_mint.key();
+
+        let (nft_record_key, bump) = Pubkey::find_program_address(
+            &[
+                b"STAKE",
+                staker_key.as_ref(),
+                nft_mint.as_ref(),
+                b"nft-record",
+            ],
+            &crate::ID,
+        );
+
+        require_keys_eq!(
+            nft_record.key(),
+            nft_record_key,
+            StakeError::UnexpectedRemainingAccounts
+        );
+
+        // the nft record persists between stakes
+        if nft_record.owner == &crate::ID {
+            return Ok(());
+        }
+
+        create_pda_account(
+            &self.signer.to_account_info(),
+            nft_record,
+            &self.system_program.to_account_info(),
+            NftRecord::LEN,
+            &[
+                &b"STAKE"[..],
+                staker_key.as_ref(),
+                nft_mint.as_ref(),
+                &b"nft-record"[..],
+                &[bump],
+            ],
+        )?;
+
+        let mut data = nft_record.try_borrow_mut_data()?;
+        NftRecord::init(nft_mint, bump).try_serialize(&mut &mut data[..])
+    }
+}
+
+pub fn stake_many_handler<'info>(
+    ctx: Context<'_, '_, 'info, 'info, StakeMany<'info>>,
+    selection: Option<u64>,
+    multipliers: Vec<Option<MultiplierProof>>,
+) -> Result<()> {
+    let staker = &ctx.accounts.staker;
+    let collection = &ctx.accounts.collection;
+    let current_time = Clock::get().unwrap().unix_timestamp;
+
+    collection.check_live(&staker.authority, &ctx.accounts.signer.key(), current_time)?;
+
+    let Staker {
+        is_active: staker_active,
+        ..
+    } = **staker.as_ref();
+
+    let Collection {
+        custodial,
+        current_stakers_count: current_stakers,
+        max_stakers_count: max_stakers,
+        is_active: collection_is_active,
+        ..
+    } = **collection.as_ref();
+
+    if !staker_active {
+        require_keys_eq!(
+            staker.authority,
+            ctx.accounts.signer.key(),
+            StakeError::StakeInactive
+        )
+    }
+
+    if !collection_is_active {
+        require_keys_eq!(
+            staker.authority,
+            ctx.accounts.signer.key(),
+            StakeError::CollectionInactive
+        )
+    }
+
+    // emissions are passed first, in the order they are recorded on the stake record
+    let emission_keys = collection.emissions();
+
+    let num_emissions = emission_keys.len();
+
+    require_gte!(
+        ctx.remaining_accounts.len(),
+        num_emissions,
+        StakeError::EmissionsMissing
+    );
+
+    let (emission_accounts, nft_accounts) = ctx.remaining_accounts.split_at(num_emissions);
+
+    require!(
+        !nft_accounts.is_empty() && nft_accounts.len() % StakeManyItem::LEN == 0,
+        StakeError::UnexpectedRemainingAccounts
+    );
+
+    let items: Vec<StakeManyItem<'info>> = nft_accounts
+        .chunks(StakeManyItem::LEN)
+        .map(StakeManyItem::from_accounts)
+        .collect();
+
+    let num_items = items.len() as u64;
+
+    require_gte!(
+        max_stakers,
+        current_stakers
+            .checked_add(num_items)
+            .ok_or(StakeError::ProgramAddError)?,
+        StakeError::MaxStakersReached
+    );
+
+    let mut emissions = emission_keys
+        .iter()
+        .zip(emission_accounts.iter())
+        .map(|(key, account)| {
+            require_keys_eq!(account.key(), *key, StakeError::InvalidEmission);
+            let emission = Account::<'info, Emission>::try_from(account)?;
+            require!(emission.active, StakeError::EmissionNotActive);
+            Ok(emission)
+        })
+        .collect::<Result<Vec<Account<'info, Emission>>>>()?;
+
+    // proofs are given in item order, trailing items without one only stake when
+    // no emission has a multiplier root
+    require_gte!(
+        num_items as usize,
+        multipliers.len(),
+        StakeError::UnexpectedRemainingAccounts
+    );
+
+    let has_points = Option::is_some(&collection.points_emission);
+
+    for (index, item) in items.iter().enumerate() {
+        let nft_metadata = ctx.accounts.validate_nft(item)?;
+        let multiplier = multipliers.get(index).cloned().flatten();
+
+        let mut pending_claim: u64 = 0;
+        let mut can_claim_at: i64 = 0;
+        let mut distribution_debt: u128 = 0;
+        let mut reward_debt: u128 = 0;
+        let mut verified_multiplier = MULTIPLIER_BASE;
+
+        for emission in emissions.iter_mut() {
+            let item_multiplier = emission.verify_multiplier(&item.nft_mint.key(), &multiplier)?;
+            if emission.merkle_root.is_some() {
+                verified_multiplier = item_multiplier;
+            }
+
+            // lock tiers can only be chosen through `stake`, so these count without a boost
+            let amount = emission.item_amount(item_multiplier, MULTIPLIER_BASE)?;
+
+            match emission.reward_type.clone() {
+                RewardType::Token | RewardType::Points => {
+                    emission.update_staked_weight_for_amount(current_time, amount, true)?;
+
+                    if emission.shared {
+                        let token_vault = emission.is_vault_funded(staker);
+                        emission.update_reward_per_item(current_time, token_vault)?;
+                        reward_debt = emission.reward_per_item;
+                    }
+                }
+                RewardType::Selection { options } => {
+                    let index = selection.ok_or(StakeError::EmissionSelectionRequired)? as usize;
+                    require_gt!(options.len(), index, StakeError::InvalidIndex);
+                    let option = options[index];
+
+                    let balance_owing = option
+                        .reward
+                        .checked_mul(option.duration as u64)
+                        .ok_or(StakeError::ProgramMulError)?;
+
+                    require_gte!(
+                        emission.current_balance,
+                        balance_owing,
+                        StakeError::InsufficientBalanceInVault
+                    );
+
+                    emission.staked_weight = emission
+                        .staked_weight
+                        .checked_add(balance_owing.into())
+                        .ok_or(StakeError::ProgramAddError)?;
+
+                    pending_claim = balance_owing;
+                    can_claim_at = current_time + option.duration;
+                }
+                RewardType::Distribution => {
+                    distribution_debt = emission.reward_per_item;
+                }
+            }
+
+            emission.increase_staked_amount(amount)?;
+        }
+
+        if custodial {
+            ctx.accounts.transfer_nft(item)?;
+        } else {
+            ctx.accounts.lock_nft(item, &nft_metadata)?;
+        }
+
+        if has_points {
+            ctx.accounts.init_nft_record(item)?;
+        }
+
+        ctx.accounts.init_stake_record(
+            item,
+            emission_keys.clone(),
+            current_time,
+            pending_claim,
+            can_claim_at,
+            distribution_debt,
+            reward_debt,
+            verified_multiplier,
+        )?;
+    }
+
+    for emission in emissions.iter() {
+        emission.exit(ctx.program_id)?;
+    }
+
+    let tx_fee = match staker.get_subscription() {
+        Subscription::Custom {
+            amount: _,
+            stake_fee,
+            unstake_fee: _,
+            claim_fee: _,
+        } => stake_fee,
+        _ => ctx.accounts.program_config.stake_fee,
+    };
+
+    // the fee is charged once for each staked item
+    let tx_fee = calc_tx_fee(staker, tx_fee)
+        .checked_mul(num_items)
+        .ok_or(StakeError::ProgramMulError)?;
+
+    if tx_fee > 0 {
+        let ix = anchor_lang::solana_program::system_instruction::transfer(
+            &ctx.accounts.signer.key(),
+            &ctx.accounts.fees_wallet.key(),
+            tx_fee,
+        );
+
+        anchor_lang::solana_program::program::invoke(
+            &ix,
+            &[
+                ctx.accounts.signer.to_account_info(),
+                ctx.accounts.fees_wallet.to_account_info(),
+            ],
+        )?;
+    }
+
+    for _ in 0..num_items {
+        ctx.accounts.collection.increase_staker_count()?;
+        ctx.accounts.staker.increase_staker_count()?;
+    }
+
+    Ok(())
+}
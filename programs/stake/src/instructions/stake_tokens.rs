@@ -0,0 +1,257 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::{
+    constants::FEES_WALLET,
+    state::{Collection, Emission, PoolRecord, ProgramConfig, Staker, Subscription},
+    utils::calc_tx_fee,
+    StakeError, STAKING_ENDS,
+};
+
+#[derive(Accounts)]
+pub struct StakeTokens<'info> {
+    #[account(mut)]
+    pub staker: Box<Account<'info, Staker>>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"STAKE",
+            staker.key().as_ref(),
+            collection.collection_mint.as_ref(),
+            b"collection",
+        ],
+        bump = collection.bump,
+        constraint = collection.is_pool() @ StakeError::NotATokenPool
+    )]
+    pub collection: Box<Account<'info, Collection>>,
+
+    #[account(
+        mut,
+        has_one = collection,
+        constraint = collection.token_emission == Some(emission.key()) @ StakeError::InvalidEmission
+    )]
+    pub emission: Option<Box<Account<'info, Emission>>>,
+
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = PoolRecord::LEN,
+        seeds = [
+            b"STAKE",
+            staker.key().as_ref(),
+            collection.key().as_ref(),
+            signer.key().as_ref(),
+            b"pool-record",
+        ],
+        bump
+    )]
+    pub pool_record: Box<Account<'info, PoolRecord>>,
+
+    #[account(
+        seeds = [b"program-config"],
+        bump
+    )]
+    pub program_config: Box<Account<'info, ProgramConfig>>,
+
+    #[account(address = collection.collection_mint)]
+    pub stake_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = stake_mint,
+        associated_token::authority = signer,
+        associated_token::token_program = token_program
+    )]
+    pub token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = stake_mint,
+        associated_token::authority = nft_authority,
+        associated_token::token_program = token_program
+    )]
+    pub pool_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// CHECK: this account is not read or written
+    #[account(
+        seeds = [
+            b"STAKE",
+            staker.key().as_ref(),
+            b"nft-authority",
+        ],
+        bump = staker.nft_auth_bump
+    )]
+    pub nft_authority: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    #[account(mut, address = FEES_WALLET)]
+    pub fees_wallet: SystemAccount<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> StakeTokens<'info> {
+    pub fn transfer_token_ctx(&self) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let cpi_accounts = TransferChecked {
+            from: self.token_account.to_account_info(),
+            mint: self.stake_mint.to_account_info(),
+            to: self.pool_vault.to_account_info(),
+            authority: self.signer.to_account_info(),
+        };
+
+        let cpi_program = self.token_program.to_account_info();
+
+        CpiContext::new(cpi_program, cpi_accounts)
+    }
+
+    /// Transfers into the pool vault, returning the amount received after any transfer fee
+    pub fn transfer_to_vault(&mut self, amount: u64) -> Result<u64> {
+        let balance_before = self.pool_vault.amount;
+
+        transfer_checked(self.transfer_token_ctx(), amount, self.stake_mint.decimals)?;
+
+        self.pool_vault.reload()?;
+
+        self.pool_vault
+            .amount
+            .checked_sub(balance_before)
+            .ok_or(error!(StakeError::ProgramSubError))
+    }
+}
+
+pub fn stake_tokens_handler(ctx: Context<StakeTokens>, amount: u64) -> Result<()> {
+    let staker = &ctx.accounts.staker;
+    let collection = &ctx.accounts.collection;
+    let current_time = Clock::get().unwrap().unix_timestamp;
+
+    collection.check_live(&staker.authority, &ctx.accounts.signer.key(), current_time)?;
+
+    require_gt!(amount, 0, StakeError::AmountTooLow);
+
+    let Staker {
+        is_active: staker_active,
+        ..
+    } = **staker.as_ref();
+
+    let Collection {
+        current_stakers_count: current_stakers,
+        max_stakers_count: max_stakers,
+        is_active: collection_is_active,
+        token_emission,
+        ..
+    } = **collection.as_ref();
+
+    if !staker_active {
+        require_keys_eq!(
+            staker.authority,
+            ctx.accounts.signer.key(),
+            StakeError::StakeInactive
+        )
+    }
+
+    if !collection_is_active {
+        require_keys_eq!(
+            staker.authority,
+            ctx.accounts.signer.key(),
+            StakeError::CollectionInactive
+        )
+    }
+
+    require!(
+        token_emission.is_none() || ctx.accounts.emission.is_some(),
+        StakeError::EmissionsMissing
+    );
+
+    // records are closed when fully withdrawn, so an empty record is a new depositor
+    let is_new_deposit = ctx.accounts.pool_record.amount == 0;
+
+    if is_new_deposit {
+        require_gt!(max_stakers, current_stakers, StakeError::MaxStakersReached);
+    } else {
+        // deposits accrue from the emission they were made under, withdraw to move to a new one
+        require!(
+            ctx.accounts.pool_record.emission == token_emission,
+            StakeError::InvalidEmission
+        );
+    }
+
+    let tx_fee = match staker.get_subscription() {
+        Subscription::Custom {
+            amount: _,
+            stake_fee,
+            unstake_fee: _,
+            claim_fee: _,
+        } => stake_fee,
+        _ => ctx.accounts.program_config.stake_fee,
+    };
+
+    let tx_fee = calc_tx_fee(staker, tx_fee);
+
+    let received = ctx.accounts.transfer_to_vault(amount)?;
+
+    let staker_key = ctx.accounts.staker.key();
+    let collection_key = ctx.accounts.collection.key();
+    let signer_key = ctx.accounts.signer.key();
+    let pool_record = &mut ctx.accounts.pool_record;
+
+    if is_new_deposit {
+        ***pool_record = PoolRecord::init(
+            staker_key,
+            collection_key,
+            signer_key,
+            token_emission,
+            current_time,
+            ctx.bumps.pool_record,
+        );
+    }
+
+    if let Some(emission) = ctx.accounts.emission.as_mut() {
+        require!(emission.active, StakeError::EmissionNotActive);
+
+        let token_vault = emission.is_vault_funded(&ctx.accounts.staker);
+        pool_record.settle_rewards(emission, current_time, token_vault)?;
+
+        if current_time < emission.end_time.unwrap_or(STAKING_ENDS) {
+            emission.update_staked_weight_for_amount(current_time, received, true)?;
+        }
+
+        emission.increase_staked_amount(received)?;
+
+        if token_vault {
+            emission.check_pool_funding(current_time)?;
+        }
+    }
+
+    pool_record.increase_amount(received)?;
+
+    if tx_fee > 0 {
+        let ix = anchor_lang::solana_program::system_instruction::transfer(
+            &ctx.accounts.signer.key(),
+            &ctx.accounts.fees_wallet.key(),
+            tx_fee,
+        );
+
+        anchor_lang::solana_program::program::invoke(
+            &ix,
+            &[
+                ctx.accounts.signer.to_account_info(),
+                ctx.accounts.fees_wallet.to_account_info(),
+            ],
+        )?;
+    }
+
+    if is_new_deposit {
+        ctx.accounts.collection.increase_staker_count()?;
+        ctx.accounts.staker.increase_staker_count()?;
+    }
+
+    Ok(())
+}
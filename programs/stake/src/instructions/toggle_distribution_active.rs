@@ -0,0 +1,31 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    state::{Distribution, Staker},
+    StakeError,
+};
+
+#[derive(Accounts)]
+pub struct ToggleDistributionActive<'info> {
+    #[account(
+        has_one = authority @ StakeError::Unauthorized
+    )]
+    pub staker: Account<'info, Staker>,
+
+    #[account(
+        mut,
+        has_one = staker
+    )]
+    pub distribution: Account<'info, Distribution>,
+
+    pub authority: Signer<'info>,
+}
+
+pub fn toggle_distribution_active_handler(
+    ctx: Context<ToggleDistributionActive>,
+    active: bool,
+) -> Result<()> {
+    let distribution = &mut ctx.accounts.distribution;
+    distribution.active = active;
+    Ok(())
+}
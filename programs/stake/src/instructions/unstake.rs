@@ -11,19 +11,17 @@ use anchor_spl::{
         },
         Metadata, MetadataAccount, TokenRecordAccount,
     },
-    token::{
-        close_account, mint_to, transfer, CloseAccount, Mint, MintTo, Token, TokenAccount, Transfer,
-    },
+    token::{close_account, CloseAccount, Mint, Token, TokenAccount},
+    token_interface::{self, TokenInterface},
 };
 
 use crate::{
     constants::FEES_WALLET,
     state::{
-        Collection, Emission, NftRecord, ProgramConfig, RewardType, StakeRecord, Staker,
-        Subscription,
+        Collection, NftRecord, ProgramConfig, StakeRecord, Staker, Subscription, VestingRecord,
     },
-    utils::{calc_reward, calc_tx_fee},
-    StakeError, STAKING_ENDS,
+    utils::{calc_tx_fee, SettleAccounts},
+    StakeError,
 };
 
 #[derive(Accounts)]
@@ -78,22 +76,61 @@ pub struct Unstake<'info> {
     pub nft_record: Option<Box<Account<'info, NftRecord>>>,
 
     #[account(mut)]
-    pub reward_mint: Option<Box<Account<'info, Mint>>>,
+    pub reward_mint: Option<Box<InterfaceAccount<'info, token_interface::Mint>>>,
 
     #[account(
         mut,
         associated_token::mint = reward_mint,
-        associated_token::authority = token_authority
+        associated_token::authority = token_authority,
+        associated_token::token_program = reward_token_program
     )]
-    pub stake_token_vault: Option<Box<Account<'info, TokenAccount>>>,
+    pub stake_token_vault: Option<Box<InterfaceAccount<'info, token_interface::TokenAccount>>>,
 
     #[account(
         init_if_needed,
         payer = owner,
         associated_token::mint = reward_mint,
-        associated_token::authority = owner
+        associated_token::authority = owner,
+        associated_token::token_program = reward_token_program
+    )]
+    pub reward_receive_account: Option<Box<InterfaceAccount<'info, token_interface::TokenAccount>>>,
+
+    #[account(
+        mut,
+        token::mint = reward_mint,
+        token::token_program = reward_token_program
+    )]
+    pub penalty_treasury: Option<Box<InterfaceAccount<'info, token_interface::TokenAccount>>>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = VestingRecord::LEN,
+        seeds = [
+            b"STAKE",
+            staker.key().as_ref(),
+            owner.key().as_ref(),
+            b"vesting-record",
+        ],
+        bump
+    )]
+    pub vesting_record: Option<Box<Account<'info, VestingRecord>>>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        seeds = [
+            b"STAKE",
+            staker.key().as_ref(),
+            owner.key().as_ref(),
+            b"vesting-vault",
+        ],
+        bump,
+        token::mint = reward_mint,
+        token::authority = token_authority,
+        token::token_program = reward_token_program
     )]
-    pub reward_receive_account: Option<Box<Account<'info, TokenAccount>>>,
+    pub vesting_vault: Option<Box<InterfaceAccount<'info, token_interface::TokenAccount>>>,
 
     #[account(
         mint::decimals = 0,
@@ -170,6 +207,7 @@ pub struct Unstake<'info> {
     pub owner: Signer<'info>,
 
     pub token_program: Program<'info, Token>,
+    pub reward_token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
     pub metadata_program: Program<'info, Metadata>,
@@ -182,52 +220,39 @@ pub struct Unstake<'info> {
 }
 
 impl<'info> Unstake<'info> {
-    pub fn transfer_token_ctx(&self) -> CpiContext<'_, '_, '_, 'info, Transfer<'info>> {
-        let cpi_accounts = Transfer {
-            from: self
+    /// Accounts settling the stake record's emissions
+    pub fn settle_accounts(&mut self, vesting_record_bump: u8) -> SettleAccounts<'_, 'info> {
+        SettleAccounts {
+            staker: &self.staker,
+            collection: &self.collection,
+            stake_record: &self.stake_record,
+            nft_record: self.nft_record.as_deref_mut(),
+            reward_mint: self.reward_mint.as_deref(),
+            stake_token_vault: self
                 .stake_token_vault
                 .as_ref()
-                .expect("stake_token_vault missing")
-                .to_account_info(),
-            to: self
+                .map(|vault| vault.to_account_info()),
+            reward_receive_account: self
                 .reward_receive_account
                 .as_ref()
-                .expect("reward_receive_account missing")
-                .to_account_info(),
-            authority: self
-                .token_authority
+                .map(|receiver| receiver.to_account_info()),
+            penalty_treasury: self
+                .penalty_treasury
                 .as_ref()
-                .expect("token_authority expected")
-                .to_account_info(),
-        };
-
-        let cpi_program = self.token_program.to_account_info();
-
-        CpiContext::new(cpi_program, cpi_accounts)
-    }
-
-    pub fn mint_token_ctx(&self) -> CpiContext<'_, '_, '_, 'info, MintTo<'info>> {
-        let cpi_accounts = MintTo {
-            mint: self
-                .reward_mint
+                .map(|treasury| treasury.to_account_info()),
+            vesting_record: self.vesting_record.as_deref_mut(),
+            vesting_record_bump,
+            vesting_vault: self
+                .vesting_vault
                 .as_ref()
-                .expect("reward_mint expected")
-                .to_account_info(),
-            to: self
-                .reward_receive_account
-                .as_ref()
-                .expect("reward_receive_account expected")
-                .to_account_info(),
-            authority: self
+                .map(|vault| vault.to_account_info()),
+            token_authority: self
                 .token_authority
                 .as_ref()
-                .expect("token_authority expected")
-                .to_account_info(),
-        };
-
-        let cpi_program = self.token_program.to_account_info();
-
-        CpiContext::new(cpi_program, cpi_accounts)
+                .map(|token_authority| token_authority.to_account_info()),
+            owner: self.owner.to_account_info(),
+            token_program: self.reward_token_program.to_account_info(),
+        }
     }
 
     pub fn close_account_ctx(&self) -> CpiContext<'_, '_, '_, 'info, CloseAccount<'info>> {
@@ -391,21 +416,26 @@ impl<'info> Unstake<'info> {
     }
 }
 
+/// Releases an NFT whose rewards were settled by `request_unstake`
+pub fn complete_unstake_handler<'info>(
+    ctx: Context<'_, '_, 'info, 'info, Unstake<'info>>,
+) -> Result<()> {
+    require!(
+        ctx.accounts.stake_record.is_unbonding(),
+        StakeError::NotUnbonding
+    );
+
+    unstake_handler(ctx)
+}
+
 pub fn unstake_handler<'info>(ctx: Context<'_, '_, 'info, 'info, Unstake<'info>>) -> Result<()> {
     let staker = &ctx.accounts.staker;
     let staker_key = staker.key();
     let nft_auth_bump = staker.nft_auth_bump;
     let token_auth_bump = staker.token_auth_bump;
     let current_time = Clock::get().unwrap().unix_timestamp;
-    let staker = &ctx.accounts.staker;
-    let claimer = &ctx.accounts.owner;
     let collection = &ctx.accounts.collection;
     let stake_record = &ctx.accounts.stake_record;
-    let nft_record = &mut ctx.accounts.nft_record.as_ref();
-    let amount = &stake_record.sol_balance;
-    let pending_claim = stake_record.pending_claim;
-    let can_claim_at = stake_record.can_claim_at;
-    let pending_claim = stake_record.pending_claim;
 
     // check unchecked master edition account is as metatdata program account
     require_eq!(
@@ -424,187 +454,34 @@ pub fn unstake_handler<'info>(ctx: Context<'_, '_, 'info, 'info, Unstake<'info>>
 
     let binding = &[&authority_seed[..]];
 
-    let staked_at = stake_record.staked_at;
-
-    if stake_record.can_claim_at > current_time {
-        return err!(StakeError::MinimumPeriodNotReached);
-    }
-
-    if stake_record
-        .emissions
-        .contains(&collection.token_emission.unwrap_or(Pubkey::default()))
+    // selection emissions can allow leaving early, this is checked when settling them below
+    if stake_record.can_claim_at > current_time
+        && !stake_record
+            .emissions
+            .contains(&collection.selection_emission.unwrap_or(Pubkey::default()))
     {
-        let account = ctx
-            .remaining_accounts
-            .iter()
-            .find(|acc| collection.token_emission.unwrap().to_bytes() == acc.key().to_bytes())
-            .unwrap();
-
-        let mut token_emission =
-            Account::<'info, Emission>::try_from(account).expect("Expected emission to be passed");
-
-        require_keys_eq!(
-            token_emission.key(),
-            collection.token_emission.unwrap(),
-            StakeError::InvalidEmission
-        );
-
-        let Emission {
-            end_time,
-            minimum_period,
-            ..
-        } = *token_emission;
-
-        let reward_record = &token_emission.reward;
-        let reward_change_time_record = &token_emission.reward_change_time;
-
-        let (mut reward_tokens, current_time, is_eligible_for_reward) = calc_reward(
-            staked_at,
-            minimum_period.unwrap_or(0),
-            reward_record,
-            reward_change_time_record,
-            end_time,
-        )
-        .unwrap();
-
-        if is_eligible_for_reward && reward_tokens > 0 {
-            if staker.token_vault {
-                transfer(
-                    ctx.accounts.transfer_token_ctx().with_signer(binding),
-                    reward_tokens,
-                )?;
-                token_emission.decrease_current_balance(staked_at, current_time)?;
-            } else {
-                mint_to(
-                    ctx.accounts.mint_token_ctx().with_signer(binding),
-                    reward_tokens,
-                )?;
-            }
-        }
-
-        token_emission.decrease_staked_items()?;
-
-        token_emission.exit(ctx.program_id)?;
+        return err!(StakeError::MinimumPeriodNotReached);
     }
 
-    if stake_record.emissions.contains(
-        &collection
-            .distribution_emission
-            .unwrap_or(Pubkey::default()),
-    ) {
-        let account = ctx
-            .remaining_accounts
-            .iter()
-            .find(|acc| {
-                collection.distribution_emission.unwrap().to_bytes() == acc.key().to_bytes()
-            })
-            .unwrap();
-
-        let mut distribution_emission =
-            Account::<'info, Emission>::try_from(account).expect("Expected emission to be passed");
-
-        require_keys_eq!(
-            distribution_emission.key(),
-            collection.distribution_emission.unwrap(),
-            StakeError::InvalidEmission
-        );
+    require_gte!(
+        current_time,
+        stake_record.locked_until,
+        StakeError::StakeLocked
+    );
 
-        distribution_emission.decrease_staked_items()?;
+    stake_record.check_unbonded(collection.unbonding_period, current_time)?;
 
-        distribution_emission.exit(ctx.program_id)?;
-    }
-
-    if stake_record
-        .emissions
-        .contains(&collection.points_emission.unwrap_or(Pubkey::default()))
-    {
-        // let account = ctx
-        //     .remaining_accounts
-        //     .iter()
-        //     .find(|acc| collection.points_emission.unwrap().to_bytes() == acc.key().to_bytes())
-        //     .unwrap();
-
-        // let mut points_emission =
-        //     Account::<'info, Emission>::try_from(account).expect("Expected emission to be passed");
-
-        // require_keys_eq!(
-        //     points_emission.key(),
-        //     collection.points_emission.unwrap(),
-        //     StakeError::InvalidEmission
-        // );
-
-        // let Emission {
-        //     end_time,
-        //     minimum_period,
-        //     token_vault,
-        //     ..
-        // } = *points_emission;
-
-        // let reward_record = &points_emission.reward;
-        // let reward_change_time_record = &points_emission.reward_change_time;
-
-        // let (mut reward_tokens, current_time, is_eligible_for_reward) = calc_reward(
-        //     staked_at,
-        //     minimum_period.unwrap_or(0),
-        //     reward_record,
-        //     reward_change_time_record,
-        //     end_time,
-        // )
-        // .unwrap();
-
-        // nft_record.unwrap().add_points(reward_tokens);
-    }
-
-    if stake_record
-        .emissions
-        .contains(&collection.selection_emission.unwrap_or(Pubkey::default()))
-    {
-        let account = ctx
-            .remaining_accounts
-            .iter()
-            .find(|acc| collection.selection_emission.unwrap().to_bytes() == acc.key().to_bytes())
-            .unwrap();
-
-        let mut selection_emission =
-            Account::<'info, Emission>::try_from(account).expect("Expected emission to be passed");
-
-        require_keys_eq!(
-            selection_emission.key(),
-            collection.selection_emission.unwrap(),
-            StakeError::InvalidEmission
-        );
+    let vesting_record_bump = ctx.bumps.vesting_record;
 
-        require_gte!(
+    ctx.accounts
+        .settle_accounts(vesting_record_bump)
+        .settle_emissions(
+            ctx.remaining_accounts,
             current_time,
-            can_claim_at,
-            StakeError::MinimumPeriodNotReached
-        );
-
-        let Emission {
-            end_time,
-            minimum_period,
-            ..
-        } = *selection_emission;
-
-        if pending_claim > 0 {
-            if staker.token_vault {
-                transfer(
-                    ctx.accounts.transfer_token_ctx().with_signer(binding),
-                    pending_claim,
-                )?;
-                selection_emission.decrease_current_balance(staked_at, current_time)?;
-            } else {
-                mint_to(
-                    ctx.accounts.mint_token_ctx().with_signer(binding),
-                    pending_claim,
-                )?;
-            }
-        }
-
-        selection_emission.decrease_staked_items()?;
-
-        selection_emission.exit(ctx.program_id)?;
-    }
+            binding,
+            false,
+            ctx.program_id,
+        )?;
 
     // let stake_record: &mut Box<Account<'_, StakeRecord>> = &mut ctx.accounts.stake_record;
 
@@ -626,6 +503,8 @@ pub fn unstake_handler<'info>(ctx: Context<'_, '_, 'info, 'info, Unstake<'info>>
         ctx.accounts.unlock_nft()?;
     }
 
+    let staker = &ctx.accounts.staker;
+
     let tx_fee = match staker.get_subscription() {
         Subscription::Custom {
             amount: _,
@@ -0,0 +1,364 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+use mpl_bubblegum::{
+    instructions::TransferCpiBuilder,
+    programs::{MPL_BUBBLEGUM_ID, SPL_ACCOUNT_COMPRESSION_ID, SPL_NOOP_ID},
+    utils::get_asset_id,
+};
+
+use crate::{
+    constants::FEES_WALLET,
+    state::{
+        Collection, NftRecord, ProgramConfig, StakeRecord, Staker, Subscription, VestingRecord,
+    },
+    utils::{calc_tx_fee, SettleAccounts},
+    StakeError,
+};
+
+#[derive(Accounts)]
+pub struct UnstakeCnft<'info> {
+    #[account(
+        seeds = [b"program-config"],
+        bump
+    )]
+    pub program_config: Box<Account<'info, ProgramConfig>>,
+
+    #[account(mut)]
+    pub staker: Box<Account<'info, Staker>>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"STAKE",
+            staker.key().as_ref(),
+            collection.collection_mint.as_ref(),
+            b"collection",
+        ],
+        bump = collection.bump
+    )]
+    pub collection: Box<Account<'info, Collection>>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"STAKE",
+            staker.key().as_ref(),
+            stake_record.nft_mint.as_ref(),
+            b"stake-record",
+        ],
+        bump = stake_record.bump,
+        has_one = owner @ StakeError::Unauthorized,
+        close = owner
+    )]
+    pub stake_record: Box<Account<'info, StakeRecord>>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"STAKE",
+            staker.key().as_ref(),
+            stake_record.nft_mint.as_ref(),
+            b"nft-record",
+        ],
+        bump = nft_record.bump,
+    )]
+    pub nft_record: Option<Box<Account<'info, NftRecord>>>,
+
+    #[account(mut)]
+    pub reward_mint: Option<Box<InterfaceAccount<'info, Mint>>>,
+
+    #[account(
+        mut,
+        associated_token::mint = reward_mint,
+        associated_token::authority = token_authority,
+        associated_token::token_program = token_program
+    )]
+    pub stake_token_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        associated_token::mint = reward_mint,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program
+    )]
+    pub reward_receive_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(
+        mut,
+        token::mint = reward_mint,
+        token::token_program = token_program
+    )]
+    pub penalty_treasury: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = VestingRecord::LEN,
+        seeds = [
+            b"STAKE",
+            staker.key().as_ref(),
+            owner.key().as_ref(),
+            b"vesting-record",
+        ],
+        bump
+    )]
+    pub vesting_record: Option<Box<Account<'info, VestingRecord>>>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        seeds = [
+            b"STAKE",
+            staker.key().as_ref(),
+            owner.key().as_ref(),
+            b"vesting-vault",
+        ],
+        bump,
+        token::mint = reward_mint,
+        token::authority = token_authority,
+        token::token_program = token_program
+    )]
+    pub vesting_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// CHECK: this account is constrained to a specific address
+    #[account(mut, address = FEES_WALLET)]
+    pub fees_wallet: SystemAccount<'info>,
+
+    /// CHECK: this account is not read or written
+    #[account(
+        seeds = [
+            b"STAKE",
+            staker.key().as_ref(),
+            b"token-authority",
+        ],
+        bump = staker.token_auth_bump
+    )]
+    pub token_authority: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: this account is not read or written
+    #[account(
+        seeds = [
+            b"STAKE",
+            staker.key().as_ref(),
+            b"nft-authority",
+        ],
+        bump = staker.nft_auth_bump
+    )]
+    pub nft_authority: UncheckedAccount<'info>,
+
+    /// CHECK: checked in CPI call
+    #[account(
+        seeds = [merkle_tree.key().as_ref()],
+        seeds::program = bubblegum_program.key(),
+        bump
+    )]
+    pub tree_config: UncheckedAccount<'info>,
+
+    /// CHECK: checked in CPI call
+    #[account(mut)]
+    pub merkle_tree: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// CHECK: this account is constrained to a specific address
+    #[account(address = MPL_BUBBLEGUM_ID)]
+    pub bubblegum_program: UncheckedAccount<'info>,
+    /// CHECK: this account is constrained to a specific address
+    #[account(address = SPL_NOOP_ID)]
+    pub log_wrapper: UncheckedAccount<'info>,
+    /// CHECK: this account is constrained to a specific address
+    #[account(address = SPL_ACCOUNT_COMPRESSION_ID)]
+    pub compression_program: UncheckedAccount<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> UnstakeCnft<'info> {
+    /// Accounts settling the stake record's emissions
+    pub fn settle_accounts(&mut self, vesting_record_bump: u8) -> SettleAccounts<'_, 'info> {
+        SettleAccounts {
+            staker: &self.staker,
+            collection: &self.collection,
+            stake_record: &self.stake_record,
+            nft_record: self.nft_record.as_deref_mut(),
+            reward_mint: self.reward_mint.as_deref(),
+            stake_token_vault: self
+                .stake_token_vault
+                .as_ref()
+                .map(|vault| vault.to_account_info()),
+            reward_receive_account: self
+                .reward_receive_account
+                .as_ref()
+                .map(|receiver| receiver.to_account_info()),
+            penalty_treasury: self
+                .penalty_treasury
+                .as_ref()
+                .map(|treasury| treasury.to_account_info()),
+            vesting_record: self.vesting_record.as_deref_mut(),
+            vesting_record_bump,
+            vesting_vault: self
+                .vesting_vault
+                .as_ref()
+                .map(|vault| vault.to_account_info()),
+            token_authority: self
+                .token_authority
+                .as_ref()
+                .map(|token_authority| token_authority.to_account_info()),
+            owner: self.owner.to_account_info(),
+            token_program: self.token_program.to_account_info(),
+        }
+    }
+
+    pub fn transfer_cnft(
+        &self,
+        proof: &[AccountInfo<'info>],
+        root: [u8; 32],
+        data_hash: [u8; 32],
+        creator_hash: [u8; 32],
+        nonce: u64,
+        index: u32,
+    ) -> Result<()> {
+        let staker_key = self.staker.key();
+        let bubblegum_program = self.bubblegum_program.to_account_info();
+        let nft_authority = self.nft_authority.to_account_info();
+
+        let proof_accounts: Vec<(&AccountInfo<'info>, bool, bool)> =
+            proof.iter().map(|node| (node, false, false)).collect();
+
+        let txn_signer: &[&[u8]; 4] = &[
+            &b"STAKE"[..],
+            staker_key.as_ref(),
+            &b"nft-authority"[..],
+            &[self.staker.nft_auth_bump],
+        ];
+
+        TransferCpiBuilder::new(&bubblegum_program)
+            .tree_config(&self.tree_config.to_account_info())
+            .leaf_owner(&nft_authority, true)
+            .leaf_delegate(&nft_authority, false)
+            .new_leaf_owner(&self.owner.to_account_info())
+            .merkle_tree(&self.merkle_tree.to_account_info())
+            .log_wrapper(&self.log_wrapper.to_account_info())
+            .compression_program(&self.compression_program.to_account_info())
+            .system_program(&self.system_program.to_account_info())
+            .root(root)
+            .data_hash(data_hash)
+            .creator_hash(creator_hash)
+            .nonce(nonce)
+            .index(index)
+            .add_remaining_accounts(&proof_accounts)
+            .invoke_signed(&[txn_signer])?;
+
+        Ok(())
+    }
+}
+
+pub fn unstake_cnft_handler<'info>(
+    ctx: Context<'_, '_, 'info, 'info, UnstakeCnft<'info>>,
+    root: [u8; 32],
+    data_hash: [u8; 32],
+    creator_hash: [u8; 32],
+    nonce: u64,
+    index: u32,
+) -> Result<()> {
+    let staker = &ctx.accounts.staker;
+    let staker_key = staker.key();
+    let token_auth_bump = staker.token_auth_bump;
+    let current_time = Clock::get().unwrap().unix_timestamp;
+    let collection = &ctx.accounts.collection;
+    let stake_record = &ctx.accounts.stake_record;
+
+    require_keys_eq!(
+        get_asset_id(&ctx.accounts.merkle_tree.key(), nonce),
+        stake_record.nft_mint,
+        StakeError::InvalidAsset
+    );
+
+    // selection emissions can allow leaving early, this is checked when settling them below
+    if stake_record.can_claim_at > current_time
+        && !stake_record
+            .emissions
+            .contains(&collection.selection_emission.unwrap_or_default())
+    {
+        return err!(StakeError::MinimumPeriodNotReached);
+    }
+
+    stake_record.check_unbonded(collection.unbonding_period, current_time)?;
+
+    let authority_seed = &[
+        &b"STAKE"[..],
+        staker_key.as_ref(),
+        &b"token-authority"[..],
+        &[token_auth_bump],
+    ];
+
+    let binding = &[&authority_seed[..]];
+
+    // emissions are passed first, in the order they are recorded on the stake record,
+    // followed by the proof nodes for the leaf, so there is no room for the accounts of
+    // emissions paying in a mint other than `reward_mint`, or of a pro-rata distribution, whose
+    // share has to be claimed before the cNFT unstakes
+    let num_emissions = stake_record.emissions.len();
+
+    require_gte!(
+        ctx.remaining_accounts.len(),
+        num_emissions,
+        StakeError::EmissionsMissing
+    );
+
+    let (emission_accounts, proof) = ctx.remaining_accounts.split_at(num_emissions);
+
+    let vesting_record_bump = ctx.bumps.vesting_record;
+
+    ctx.accounts
+        .settle_accounts(vesting_record_bump)
+        .settle_emissions(
+            emission_accounts,
+            current_time,
+            binding,
+            false,
+            ctx.program_id,
+        )?;
+
+    ctx.accounts
+        .transfer_cnft(proof, root, data_hash, creator_hash, nonce, index)?;
+
+    let staker = &ctx.accounts.staker;
+
+    let tx_fee = match staker.get_subscription() {
+        Subscription::Custom {
+            amount: _,
+            stake_fee: _,
+            unstake_fee,
+            claim_fee: _,
+        } => unstake_fee,
+        _ => ctx.accounts.program_config.unstake_fee,
+    };
+
+    let tx_fee = calc_tx_fee(staker, tx_fee);
+
+    if tx_fee > 0 {
+        let ix = anchor_lang::solana_program::system_instruction::transfer(
+            &ctx.accounts.owner.key(),
+            &ctx.accounts.fees_wallet.key(),
+            tx_fee,
+        );
+
+        anchor_lang::solana_program::program::invoke(
+            &ix,
+            &[
+                ctx.accounts.owner.to_account_info(),
+                ctx.accounts.fees_wallet.to_account_info(),
+            ],
+        )?;
+    }
+
+    ctx.accounts.collection.decrease_staker_count()?;
+    ctx.accounts.staker.decrease_staker_count()
+}
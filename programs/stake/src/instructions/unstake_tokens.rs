@@ -0,0 +1,342 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{
+        mint_to, transfer_checked, Mint, MintTo, TokenAccount, TokenInterface, TransferChecked,
+    },
+};
+
+use crate::{
+    constants::FEES_WALLET,
+    state::{Collection, Emission, PoolRecord, ProgramConfig, Staker, Subscription},
+    utils::calc_tx_fee,
+    StakeError, STAKING_ENDS,
+};
+
+#[derive(Accounts)]
+pub struct UnstakeTokens<'info> {
+    #[account(
+        seeds = [b"program-config"],
+        bump
+    )]
+    pub program_config: Box<Account<'info, ProgramConfig>>,
+
+    #[account(mut)]
+    pub staker: Box<Account<'info, Staker>>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"STAKE",
+            staker.key().as_ref(),
+            collection.collection_mint.as_ref(),
+            b"collection",
+        ],
+        bump = collection.bump,
+        constraint = collection.is_pool() @ StakeError::NotATokenPool
+    )]
+    pub collection: Box<Account<'info, Collection>>,
+
+    #[account(
+        mut,
+        constraint = pool_record.emission == Some(emission.key()) @ StakeError::InvalidEmission
+    )]
+    pub emission: Option<Box<Account<'info, Emission>>>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"STAKE",
+            staker.key().as_ref(),
+            collection.key().as_ref(),
+            owner.key().as_ref(),
+            b"pool-record",
+        ],
+        bump = pool_record.bump,
+        has_one = owner @ StakeError::Unauthorized,
+    )]
+    pub pool_record: Box<Account<'info, PoolRecord>>,
+
+    #[account(address = collection.collection_mint)]
+    pub stake_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        associated_token::mint = stake_mint,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program
+    )]
+    pub token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = stake_mint,
+        associated_token::authority = nft_authority,
+        associated_token::token_program = token_program
+    )]
+    pub pool_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        address = emission
+            .as_ref()
+            .and_then(|emission| emission.reward_mint(&staker))
+            .unwrap_or_default() @ StakeError::InvalidRewardToken
+    )]
+    pub reward_mint: Option<Box<InterfaceAccount<'info, Mint>>>,
+
+    #[account(
+        mut,
+        associated_token::mint = reward_mint,
+        associated_token::authority = token_authority,
+        associated_token::token_program = reward_token_program
+    )]
+    pub stake_token_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        associated_token::mint = reward_mint,
+        associated_token::authority = owner,
+        associated_token::token_program = reward_token_program
+    )]
+    pub reward_receive_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// CHECK: this account is not read or written
+    #[account(
+        seeds = [
+            b"STAKE",
+            staker.key().as_ref(),
+            b"token-authority",
+        ],
+        bump = staker.token_auth_bump
+    )]
+    pub token_authority: UncheckedAccount<'info>,
+
+    /// CHECK: this account is not read or written
+    #[account(
+        seeds = [
+            b"STAKE",
+            staker.key().as_ref(),
+            b"nft-authority",
+        ],
+        bump = staker.nft_auth_bump
+    )]
+    pub nft_authority: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(mut, address = FEES_WALLET)]
+    pub fees_wallet: SystemAccount<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub reward_token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> UnstakeTokens<'info> {
+    pub fn withdraw_token_ctx(&self) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let cpi_accounts = TransferChecked {
+            from: self.pool_vault.to_account_info(),
+            mint: self.stake_mint.to_account_info(),
+            to: self.token_account.to_account_info(),
+            authority: self.nft_authority.to_account_info(),
+        };
+
+        let cpi_program = self.token_program.to_account_info();
+
+        CpiContext::new(cpi_program, cpi_accounts)
+    }
+
+    pub fn transfer_token_ctx(&self) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let cpi_accounts = TransferChecked {
+            from: self
+                .stake_token_vault
+                .as_ref()
+                .expect("stake_token_vault expected")
+                .to_account_info(),
+            mint: self
+                .reward_mint
+                .as_ref()
+                .expect("reward_mint expected")
+                .to_account_info(),
+            to: self
+                .reward_receive_account
+                .as_ref()
+                .expect("reward_receive_account expected")
+                .to_account_info(),
+            authority: self.token_authority.to_account_info(),
+        };
+
+        let cpi_program = self.reward_token_program.to_account_info();
+
+        CpiContext::new(cpi_program, cpi_accounts)
+    }
+
+    pub fn mint_token_ctx(&self) -> CpiContext<'_, '_, '_, 'info, MintTo<'info>> {
+        let cpi_accounts = MintTo {
+            mint: self
+                .reward_mint
+                .as_ref()
+                .expect("reward_mint expected")
+                .to_account_info(),
+            to: self
+                .reward_receive_account
+                .as_ref()
+                .expect("reward_receive_account expected")
+                .to_account_info(),
+            authority: self.token_authority.to_account_info(),
+        };
+
+        let cpi_program = self.reward_token_program.to_account_info();
+
+        CpiContext::new(cpi_program, cpi_accounts)
+    }
+}
+
+pub fn unstake_tokens_handler(ctx: Context<UnstakeTokens>, amount: u64) -> Result<()> {
+    let staker = &ctx.accounts.staker;
+    let staker_key = staker.key();
+    let current_time = Clock::get().unwrap().unix_timestamp;
+
+    let Staker {
+        token_auth_bump,
+        nft_auth_bump,
+        ..
+    } = **staker.as_ref();
+
+    require_gt!(amount, 0, StakeError::AmountTooLow);
+    require_gte!(
+        ctx.accounts.pool_record.amount,
+        amount,
+        StakeError::AmountTooLow
+    );
+
+    let tx_fee = match staker.get_subscription() {
+        Subscription::Custom {
+            amount: _,
+            stake_fee: _,
+            unstake_fee,
+            claim_fee: _,
+        } => unstake_fee,
+        _ => ctx.accounts.program_config.unstake_fee,
+    };
+
+    let tx_fee = calc_tx_fee(staker, tx_fee);
+
+    require!(
+        ctx.accounts.pool_record.emission.is_none() || ctx.accounts.emission.is_some(),
+        StakeError::EmissionsMissing
+    );
+
+    let token_vault = ctx
+        .accounts
+        .emission
+        .as_ref()
+        .is_some_and(|emission| emission.is_vault_funded(staker));
+
+    let pool_record = &mut ctx.accounts.pool_record;
+    let mut reward_tokens: u64 = 0;
+
+    if let Some(emission) = ctx.accounts.emission.as_mut() {
+        pool_record.settle_rewards(emission, current_time, token_vault)?;
+
+        reward_tokens = pool_record.pending_claim;
+        pool_record.pending_claim = 0;
+
+        // rewards still within the minimum period of the last deposit are returned to the emission
+        if pool_record.locked_claim > 0 {
+            if token_vault {
+                emission.increase_current_balance(pool_record.locked_claim)?;
+            }
+            pool_record.locked_claim = 0;
+        }
+
+        if current_time < emission.end_time.unwrap_or(STAKING_ENDS) {
+            emission.update_staked_weight_for_amount(current_time, amount, false)?;
+        }
+
+        emission.decrease_staked_amount(amount)?;
+    }
+
+    pool_record.decrease_amount(amount)?;
+
+    let is_fully_withdrawn = pool_record.amount == 0;
+
+    let token_auth_seed = &[
+        &b"STAKE"[..],
+        staker_key.as_ref(),
+        &b"token-authority"[..],
+        &[token_auth_bump],
+    ];
+
+    if reward_tokens > 0 {
+        if token_vault {
+            let reward_decimals = ctx
+                .accounts
+                .reward_mint
+                .as_ref()
+                .expect("reward_mint expected")
+                .decimals;
+
+            transfer_checked(
+                ctx.accounts
+                    .transfer_token_ctx()
+                    .with_signer(&[&token_auth_seed[..]]),
+                reward_tokens,
+                reward_decimals,
+            )?;
+        } else {
+            mint_to(
+                ctx.accounts
+                    .mint_token_ctx()
+                    .with_signer(&[&token_auth_seed[..]]),
+                reward_tokens,
+            )?;
+        }
+    }
+
+    let nft_auth_seed = &[
+        &b"STAKE"[..],
+        staker_key.as_ref(),
+        &b"nft-authority"[..],
+        &[nft_auth_bump],
+    ];
+
+    transfer_checked(
+        ctx.accounts
+            .withdraw_token_ctx()
+            .with_signer(&[&nft_auth_seed[..]]),
+        amount,
+        ctx.accounts.stake_mint.decimals,
+    )?;
+
+    if tx_fee > 0 {
+        let ix = anchor_lang::solana_program::system_instruction::transfer(
+            &ctx.accounts.owner.key(),
+            &ctx.accounts.fees_wallet.key(),
+            tx_fee,
+        );
+
+        anchor_lang::solana_program::program::invoke(
+            &ix,
+            &[
+                ctx.accounts.owner.to_account_info(),
+                ctx.accounts.fees_wallet.to_account_info(),
+            ],
+        )?;
+    }
+
+    if is_fully_withdrawn {
+        ctx.accounts
+            .pool_record
+            .close(ctx.accounts.owner.to_account_info())?;
+        ctx.accounts.collection.decrease_staker_count()?;
+        ctx.accounts.staker.decrease_staker_count()?;
+    }
+
+    Ok(())
+}
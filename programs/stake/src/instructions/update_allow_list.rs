@@ -0,0 +1,43 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    state::{Collection, Staker},
+    StakeError,
+};
+
+#[derive(Accounts)]
+pub struct UpdateAllowList<'info> {
+    #[account(
+        has_one = authority @ StakeError::Unauthorized
+    )]
+    pub staker: Account<'info, Staker>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"STAKE",
+            staker.key().as_ref(),
+            collection.collection_mint.as_ref(),
+            b"collection"
+        ],
+        bump = collection.bump,
+        realloc = Collection::LEN,
+        realloc::payer = authority,
+        realloc::zero = false
+    )]
+    pub collection: Account<'info, Collection>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn update_allow_list_handler(
+    ctx: Context<UpdateAllowList>,
+    allow_list: Option<[u8; 32]>,
+) -> Result<()> {
+    let collection = &mut ctx.accounts.collection;
+    collection.allow_list = allow_list.map(Pubkey::new_from_array);
+    Ok(())
+}
@@ -0,0 +1,276 @@
+use std::cmp::Ordering;
+
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{
+    transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked,
+};
+
+use crate::{
+    state::{Collection, Emission, RewardType, Staker},
+    utils::{calc_actual_balance, calc_total_emission, RewardAccounts},
+    StakeError, STAKING_ENDS,
+};
+
+#[derive(Accounts)]
+pub struct UpdateCollectionCapacity<'info> {
+    #[account(
+        has_one = authority @ StakeError::Unauthorized
+    )]
+    pub staker: Account<'info, Staker>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"STAKE",
+            staker.key().as_ref(),
+            collection.collection_mint.as_ref(),
+            b"collection",
+        ],
+        bump = collection.bump,
+        has_one = staker
+    )]
+    pub collection: Account<'info, Collection>,
+
+    pub reward_mint: Option<Box<InterfaceAccount<'info, Mint>>>,
+
+    #[account(
+        mut,
+        associated_token::mint = reward_mint,
+        associated_token::authority = authority,
+        associated_token::token_program = token_program
+    )]
+    pub token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(
+        mut,
+        associated_token::mint = reward_mint,
+        associated_token::authority = token_authority,
+        associated_token::token_program = token_program
+    )]
+    pub stake_token_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// CHECK: the account is not read or written
+    #[account(
+        seeds = [b"STAKE", staker.key().as_ref(), b"token-authority"],
+        bump = staker.token_auth_bump
+    )]
+    pub token_authority: Option<UncheckedAccount<'info>>,
+
+    pub authority: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> UpdateCollectionCapacity<'info> {
+    /// Accounts moving the emission's funds, the named accounts when it pays in
+    /// `reward_mint`, otherwise accounts passed in remaining accounts
+    pub fn funding_accounts(
+        &self,
+        emission: &Emission,
+        remaining_accounts: &[AccountInfo<'info>],
+    ) -> Result<RewardAccounts<'info>> {
+        let mint = emission
+            .reward_mint(&self.staker)
+            .ok_or(StakeError::NoRewardMint)?;
+
+        match &self.reward_mint {
+            Some(reward_mint) if reward_mint.key() == mint => RewardAccounts::named(
+                reward_mint,
+                self.stake_token_vault
+                    .as_ref()
+                    .map(|vault| vault.to_account_info()),
+                self.token_account
+                    .as_ref()
+                    .map(|token_account| token_account.to_account_info()),
+                true,
+            ),
+            _ => RewardAccounts::find(
+                remaining_accounts,
+                &mint,
+                &self.authority.key(),
+                &self.token_authority.as_ref().unwrap().key(),
+                &self.token_program.key(),
+                true,
+            ),
+        }
+    }
+
+    /// Transfers into the vault, returning the amount received after any transfer fee
+    pub fn transfer_to_vault(
+        &self,
+        funding_accounts: &RewardAccounts<'info>,
+        amount: u64,
+    ) -> Result<u64> {
+        let vault = funding_accounts.vault.clone().unwrap();
+        let balance_before =
+            TokenAccount::try_deserialize(&mut &vault.try_borrow_data()?[..])?.amount;
+
+        let cpi_accounts = TransferChecked {
+            from: funding_accounts.receiver.clone(),
+            mint: funding_accounts.mint.clone(),
+            to: vault.clone(),
+            authority: self.authority.to_account_info(),
+        };
+
+        transfer_checked(
+            CpiContext::new(self.token_program.to_account_info(), cpi_accounts),
+            amount,
+            funding_accounts.decimals,
+        )?;
+
+        let balance_after =
+            TokenAccount::try_deserialize(&mut &vault.try_borrow_data()?[..])?.amount;
+
+        balance_after
+            .checked_sub(balance_before)
+            .ok_or(error!(StakeError::ProgramSubError))
+    }
+
+    pub fn transfer_from_vault(
+        &self,
+        funding_accounts: &RewardAccounts<'info>,
+        amount: u64,
+    ) -> Result<()> {
+        let staker = &self.staker;
+        let staker_key = staker.key();
+
+        let token_auth_seed = &[
+            &b"STAKE"[..],
+            staker_key.as_ref(),
+            &b"token-authority"[..],
+            &[staker.token_auth_bump],
+        ];
+
+        funding_accounts.pay(
+            funding_accounts.receiver.clone(),
+            self.token_authority.as_ref().unwrap().to_account_info(),
+            self.token_program.to_account_info(),
+            amount,
+            &[&token_auth_seed[..]],
+        )
+    }
+}
+
+/// Vault-funded token emissions passed in remaining accounts are topped up from the
+/// authority when the cap is raised, and release their surplus when it is lowered.
+pub fn update_collection_capacity_handler<'info>(
+    ctx: Context<'_, '_, 'info, 'info, UpdateCollectionCapacity<'info>>,
+    max_stakers_count: u64,
+) -> Result<()> {
+    let staker = &ctx.accounts.staker;
+    let collection = &ctx.accounts.collection;
+    let current_time = Clock::get().unwrap().unix_timestamp;
+
+    let Collection {
+        max_stakers_count: current_max_stakers_count,
+        current_stakers_count,
+        ..
+    } = **collection;
+
+    require_gt!(max_stakers_count, 0, StakeError::InvalidCapacity);
+    require_gte!(
+        max_stakers_count,
+        current_stakers_count,
+        StakeError::InvalidCapacity
+    );
+
+    // token pools are funded by the staked amount, so only vault-funded token
+    // emissions have a liability that depends on the number of stakers
+    let is_funded = !collection.is_pool() && max_stakers_count != current_max_stakers_count;
+
+    let token_emissions = if is_funded {
+        collection.token_emissions()
+    } else {
+        vec![]
+    };
+
+    for key in token_emissions {
+        let account = ctx
+            .remaining_accounts
+            .iter()
+            .find(|acc| acc.key() == key)
+            .ok_or(StakeError::EmissionsMissing)?;
+
+        let mut emission = Account::<'info, Emission>::try_from(account)?;
+
+        // a shared emission's budget doesn't depend on the number of stakers
+        if !matches!(emission.reward_type, RewardType::Token)
+            || !emission.is_vault_funded(staker)
+            || emission.shared
+            || emission.end_time.unwrap_or(STAKING_ENDS) <= current_time
+        {
+            continue;
+        }
+
+        require!(
+            ctx.accounts.token_authority.is_some(),
+            StakeError::FundingAccountsRequired
+        );
+
+        // stakers already earning keep their accrued rewards, only the
+        // remaining emission needs to be covered
+        let stakers_count = if emission.stake_unit() > 1 {
+            emission.staked_items
+        } else {
+            current_stakers_count
+        };
+
+        let (current_actual_balance, _accrued_reward, _new_staked_weight) = calc_actual_balance(
+            stakers_count,
+            emission.staked_weight,
+            *emission.reward.last().unwrap(),
+            *emission.reward_change_time.last().unwrap(),
+            emission.end_time,
+            current_time,
+            emission.current_balance,
+            None,
+            emission.stake_unit(),
+            &emission.reward_schedule,
+        )?;
+
+        let total_emission = calc_total_emission(
+            *emission.reward.last().unwrap(),
+            &emission.reward_schedule,
+            max_stakers_count,
+            current_time.max(emission.start_time),
+            emission.end_time.unwrap_or(STAKING_ENDS),
+            emission.max_multiplier(),
+        )?;
+
+        match current_actual_balance.cmp(&total_emission) {
+            Ordering::Less => {
+                let shortfall = total_emission - current_actual_balance;
+                let funding_accounts = ctx
+                    .accounts
+                    .funding_accounts(&emission, ctx.remaining_accounts)?;
+                let received = ctx
+                    .accounts
+                    .transfer_to_vault(&funding_accounts, shortfall)?;
+
+                // transfer fees can leave the vault short of the new liability
+                require_gte!(received, shortfall, StakeError::InsufficientBalanceInVault);
+
+                emission.increase_current_balance(received)?;
+            }
+            Ordering::Greater if max_stakers_count < current_max_stakers_count => {
+                let surplus = current_actual_balance - total_emission;
+                let funding_accounts = ctx
+                    .accounts
+                    .funding_accounts(&emission, ctx.remaining_accounts)?;
+                ctx.accounts
+                    .transfer_from_vault(&funding_accounts, surplus)?;
+
+                emission.current_balance = emission
+                    .current_balance
+                    .checked_sub(surplus)
+                    .ok_or(StakeError::ProgramSubError)?;
+            }
+            _ => {}
+        }
+
+        emission.exit(ctx.program_id)?;
+    }
+
+    let collection = &mut ctx.accounts.collection;
+    collection.max_stakers_count = max_stakers_count;
+    Ok(())
+}
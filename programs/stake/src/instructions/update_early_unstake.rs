@@ -0,0 +1,59 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    state::{Collection, EarlyUnstake, Emission, RewardType, Staker},
+    StakeError, MULTIPLIER_BASE,
+};
+
+#[derive(Accounts)]
+pub struct UpdateEarlyUnstake<'info> {
+    #[account(
+        has_one = authority @ StakeError::Unauthorized
+    )]
+    pub staker: Account<'info, Staker>,
+
+    #[account(
+        seeds = [
+            b"STAKE",
+            staker.key().as_ref(),
+            collection.collection_mint.as_ref(),
+            b"collection",
+        ],
+        bump = collection.bump,
+    )]
+    pub collection: Account<'info, Collection>,
+
+    #[account(
+        mut,
+        has_one = collection,
+        realloc = emission.current_len(),
+        realloc::payer = authority,
+        realloc::zero = false
+    )]
+    pub emission: Account<'info, Emission>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn update_early_unstake_handler(
+    ctx: Context<UpdateEarlyUnstake>,
+    early_unstake: Option<EarlyUnstake>,
+) -> Result<()> {
+    let emission = &mut ctx.accounts.emission;
+
+    match emission.reward_type {
+        RewardType::Token | RewardType::Selection { .. } => {}
+        _ => return err!(StakeError::InvalidEmission),
+    }
+
+    if let Some(EarlyUnstake { penalty, .. }) = early_unstake {
+        require_gte!(MULTIPLIER_BASE, penalty, StakeError::InvalidPenalty);
+    }
+
+    emission.early_unstake = early_unstake;
+
+    Ok(())
+}
@@ -0,0 +1,120 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    state::{Collection, Emission, RewardType, Staker},
+    utils::calc_total_emission,
+    StakeError, STAKING_ENDS,
+};
+
+#[derive(Accounts)]
+pub struct UpdateEmissionMultipliers<'info> {
+    #[account(
+        has_one = authority @ StakeError::Unauthorized
+    )]
+    pub staker: Account<'info, Staker>,
+
+    #[account(
+        seeds = [
+            b"STAKE",
+            staker.key().as_ref(),
+            collection.collection_mint.as_ref(),
+            b"collection",
+        ],
+        bump = collection.bump,
+    )]
+    pub collection: Account<'info, Collection>,
+
+    #[account(
+        mut,
+        has_one = collection
+    )]
+    pub emission: Account<'info, Emission>,
+
+    pub authority: Signer<'info>,
+}
+
+pub fn update_emission_multipliers_handler<'info>(
+    ctx: Context<'_, '_, 'info, 'info, UpdateEmissionMultipliers<'info>>,
+    merkle_root: Option<[u8; 32]>,
+    max_multiplier: u64,
+) -> Result<()> {
+    let staker = &ctx.accounts.staker;
+    let collection = &ctx.accounts.collection;
+    let emission = &ctx.accounts.emission;
+    let current_time = Clock::get().unwrap().unix_timestamp;
+
+    require!(!collection.is_pool(), StakeError::InvalidPoolEmission);
+
+    match emission.reward_type {
+        RewardType::Token | RewardType::Points => {}
+        _ => return err!(StakeError::InvalidEmission),
+    }
+
+    // stakers' weights are recorded with their multiplier, so it can only change while empty
+    require_eq!(emission.staked_items, 0, StakeError::CollectionHasStakers);
+    require_gt!(max_multiplier, 0, StakeError::InvalidMultiplier);
+
+    // stake records hold a single multiplier, so every emission of the collection
+    // has to verify it against the same root
+    if let Some(merkle_root) = merkle_root {
+        let keys = collection
+            .token_emissions()
+            .into_iter()
+            .chain(collection.points_emission);
+
+        for key in keys {
+            if key == emission.key() {
+                continue;
+            }
+
+            let account = ctx
+                .remaining_accounts
+                .iter()
+                .find(|acc| acc.key() == key)
+                .ok_or(StakeError::EmissionsMissing)?;
+
+            let other = Account::<'info, Emission>::try_from(account)?;
+
+            if let Some(other_root) = other.merkle_root {
+                require_keys_eq!(
+                    other_root,
+                    Pubkey::new_from_array(merkle_root),
+                    StakeError::MerkleRootMismatch
+                );
+            }
+        }
+    }
+
+    let emission = &mut ctx.accounts.emission;
+
+    emission.merkle_root = merkle_root.map(Pubkey::new_from_array);
+    emission.max_multiplier = max_multiplier;
+    emission.update_stake_unit();
+
+    // multipliers don't change a shared emission's budget
+    if emission.is_vault_funded(staker)
+        && matches!(emission.reward_type, RewardType::Token)
+        && !emission.shared
+    {
+        let end_time = emission.end_time.unwrap_or(STAKING_ENDS);
+
+        if end_time > current_time {
+            let total_emission = calc_total_emission(
+                *emission.reward.last().unwrap(),
+                &emission.reward_schedule,
+                collection.max_stakers_count,
+                current_time.max(emission.start_time),
+                end_time,
+                emission.max_multiplier(),
+            )?;
+
+            require_gte!(
+                emission.current_balance,
+                total_emission,
+                StakeError::InsufficientBalanceInVault
+            );
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,95 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    state::{Collection, Emission, LockTier, RewardType, Staker},
+    utils::calc_total_emission,
+    StakeError, MULTIPLIER_BASE, STAKING_ENDS,
+};
+
+#[derive(Accounts)]
+#[instruction(lock_tiers: Vec<LockTier>)]
+pub struct UpdateLockTiers<'info> {
+    #[account(
+        has_one = authority @ StakeError::Unauthorized
+    )]
+    pub staker: Account<'info, Staker>,
+
+    #[account(
+        seeds = [
+            b"STAKE",
+            staker.key().as_ref(),
+            collection.collection_mint.as_ref(),
+            b"collection",
+        ],
+        bump = collection.bump,
+    )]
+    pub collection: Account<'info, Collection>,
+
+    #[account(
+        mut,
+        has_one = collection,
+        realloc = emission.current_len() + lock_tiers.len() * 16,
+        realloc::payer = authority,
+        realloc::zero = false
+    )]
+    pub emission: Account<'info, Emission>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn update_lock_tiers_handler(
+    ctx: Context<UpdateLockTiers>,
+    lock_tiers: Vec<LockTier>,
+) -> Result<()> {
+    let staker = &ctx.accounts.staker;
+    let collection = &ctx.accounts.collection;
+    let emission = &ctx.accounts.emission;
+    let current_time = Clock::get().unwrap().unix_timestamp;
+
+    require!(!collection.is_pool(), StakeError::InvalidPoolEmission);
+    require!(
+        matches!(emission.reward_type, RewardType::Token),
+        StakeError::InvalidEmission
+    );
+
+    // stakers' weights are recorded with their boost, so tiers can only change while empty
+    require_eq!(emission.staked_items, 0, StakeError::CollectionHasStakers);
+    require_gte!(5, lock_tiers.len(), StakeError::InvalidLockTier);
+
+    for tier in lock_tiers.iter() {
+        require_gt!(tier.duration, 0, StakeError::InvalidLockTier);
+        require_gte!(tier.boost, MULTIPLIER_BASE, StakeError::InvalidLockTier);
+    }
+
+    let emission = &mut ctx.accounts.emission;
+
+    emission.lock_tiers = lock_tiers;
+    emission.update_stake_unit();
+
+    // boosts don't change a shared emission's budget
+    if emission.is_vault_funded(staker) && !emission.shared {
+        let end_time = emission.end_time.unwrap_or(STAKING_ENDS);
+
+        if end_time > current_time {
+            let total_emission = calc_total_emission(
+                *emission.reward.last().unwrap(),
+                &emission.reward_schedule,
+                collection.max_stakers_count,
+                current_time.max(emission.start_time),
+                end_time,
+                emission.max_multiplier(),
+            )?;
+
+            require_gte!(
+                emission.current_balance,
+                total_emission,
+                StakeError::InsufficientBalanceInVault
+            );
+        }
+    }
+
+    Ok(())
+}
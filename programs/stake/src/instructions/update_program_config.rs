@@ -9,7 +9,10 @@ pub struct UpdateProgramConfig<'info> {
         seeds = [
             b"program-config"
         ],
-        bump
+        bump,
+        realloc = program_config.current_len(),
+        realloc::payer = authority,
+        realloc::zero = false,
     )]
     pub program_config: Account<'info, ProgramConfig>,
 
@@ -25,6 +28,8 @@ pub struct UpdateProgramConfig<'info> {
         constraint = program_data.upgrade_authority_address == Some(authority.key()) @ StakeError::AdminOnly
     )]
     pub program_data: Account<'info, ProgramData>,
+
+    pub system_program: Program<'info, System>,
 }
 
 pub fn clear_slugs_handler(ctx: Context<UpdateProgramConfig>) -> Result<()> {
@@ -45,6 +50,7 @@ pub fn update_program_config_handler(
     extra_collection_fee: Option<u64>,
     remove_branding_fee: Option<u64>,
     own_domain_fee: Option<u64>,
+    claim_fee_per_record: Option<bool>,
 ) -> Result<()> {
     let program_config = &mut ctx.accounts.program_config;
 
@@ -62,6 +68,8 @@ pub fn update_program_config_handler(
     program_config.remove_branding_fee =
         remove_branding_fee.unwrap_or(program_config.remove_branding_fee);
     program_config.own_domain_fee = own_domain_fee.unwrap_or(program_config.own_domain_fee);
+    program_config.claim_fee_per_record =
+        claim_fee_per_record.unwrap_or(program_config.claim_fee_per_record);
 
     Ok(())
 }
@@ -0,0 +1,47 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    state::{Collection, Staker},
+    StakeError,
+};
+
+#[derive(Accounts)]
+pub struct UpdateStakingStartsAt<'info> {
+    #[account(
+        has_one = authority @ StakeError::Unauthorized
+    )]
+    pub staker: Account<'info, Staker>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"STAKE",
+            staker.key().as_ref(),
+            collection.collection_mint.as_ref(),
+            b"collection"
+        ],
+        bump = collection.bump,
+        realloc = Collection::LEN,
+        realloc::payer = authority,
+        realloc::zero = false
+    )]
+    pub collection: Account<'info, Collection>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn update_staking_starts_at_handler(
+    ctx: Context<UpdateStakingStartsAt>,
+    staking_starts_at: i64,
+) -> Result<()> {
+    let current_time = Clock::get().unwrap().unix_timestamp;
+
+    require_gte!(staking_starts_at, current_time, StakeError::StartTimeInPast);
+
+    let collection = &mut ctx.accounts.collection;
+    collection.staking_starts_at = staking_starts_at;
+    Ok(())
+}
@@ -0,0 +1,45 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    state::{Collection, Staker},
+    StakeError,
+};
+
+#[derive(Accounts)]
+pub struct UpdateUnbondingPeriod<'info> {
+    #[account(
+        has_one = authority @ StakeError::Unauthorized
+    )]
+    pub staker: Account<'info, Staker>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"STAKE",
+            staker.key().as_ref(),
+            collection.collection_mint.as_ref(),
+            b"collection"
+        ],
+        bump = collection.bump,
+        realloc = Collection::LEN,
+        realloc::payer = authority,
+        realloc::zero = false
+    )]
+    pub collection: Account<'info, Collection>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn update_unbonding_period_handler(
+    ctx: Context<UpdateUnbondingPeriod>,
+    unbonding_period: i64,
+) -> Result<()> {
+    require_gte!(unbonding_period, 0, StakeError::InvalidUnbondingPeriod);
+
+    let collection = &mut ctx.accounts.collection;
+    collection.unbonding_period = unbonding_period;
+    Ok(())
+}
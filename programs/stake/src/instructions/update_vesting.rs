@@ -0,0 +1,63 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    state::{Collection, Emission, RewardType, Staker, Vesting},
+    StakeError,
+};
+
+#[derive(Accounts)]
+pub struct UpdateVesting<'info> {
+    #[account(
+        has_one = authority @ StakeError::Unauthorized
+    )]
+    pub staker: Account<'info, Staker>,
+
+    #[account(
+        seeds = [
+            b"STAKE",
+            staker.key().as_ref(),
+            collection.collection_mint.as_ref(),
+            b"collection",
+        ],
+        bump = collection.bump,
+    )]
+    pub collection: Account<'info, Collection>,
+
+    #[account(
+        mut,
+        has_one = collection,
+        realloc = emission.current_len(),
+        realloc::payer = authority,
+        realloc::zero = false
+    )]
+    pub emission: Account<'info, Emission>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Rewards claimed after this change vest, rewards already vesting keep their schedule
+pub fn update_vesting_handler(ctx: Context<UpdateVesting>, vesting: Option<Vesting>) -> Result<()> {
+    let emission = &mut ctx.accounts.emission;
+
+    require!(
+        matches!(emission.reward_type, RewardType::Token),
+        StakeError::InvalidEmission
+    );
+
+    if let Some(Vesting { cliff, duration }) = vesting {
+        // the vesting vault holds the staker's token, released by `withdraw_vested`
+        require!(
+            emission.reward_mint(&ctx.accounts.staker) == ctx.accounts.staker.token_mint,
+            StakeError::VestingMintMismatch
+        );
+        require_gt!(duration, 0, StakeError::InvalidVesting);
+        require!((0..=duration).contains(&cliff), StakeError::InvalidVesting);
+    }
+
+    emission.vesting = vesting;
+
+    Ok(())
+}
@@ -0,0 +1,116 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::{
+    state::{Staker, VestingRecord},
+    StakeError,
+};
+
+#[derive(Accounts)]
+pub struct WithdrawVested<'info> {
+    pub staker: Box<Account<'info, Staker>>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"STAKE",
+            staker.key().as_ref(),
+            owner.key().as_ref(),
+            b"vesting-record",
+        ],
+        bump = vesting_record.bump,
+        has_one = staker,
+        has_one = owner @ StakeError::Unauthorized,
+    )]
+    pub vesting_record: Box<Account<'info, VestingRecord>>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"STAKE",
+            staker.key().as_ref(),
+            owner.key().as_ref(),
+            b"vesting-vault",
+        ],
+        bump,
+        token::mint = token_mint,
+        token::authority = token_authority,
+        token::token_program = token_program
+    )]
+    pub vesting_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(address = staker.token_mint.unwrap())]
+    pub token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        associated_token::mint = token_mint,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program
+    )]
+    pub reward_receive_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// CHECK: this account is not read or written
+    #[account(
+        seeds = [
+            b"STAKE",
+            staker.key().as_ref(),
+            b"token-authority"
+        ],
+        bump = staker.token_auth_bump
+    )]
+    pub token_authority: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> WithdrawVested<'info> {
+    pub fn transfer_token_ctx(&self) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let cpi_accounts = TransferChecked {
+            from: self.vesting_vault.to_account_info(),
+            mint: self.token_mint.to_account_info(),
+            to: self.reward_receive_account.to_account_info(),
+            authority: self.token_authority.to_account_info(),
+        };
+
+        let cpi_program = self.token_program.to_account_info();
+
+        CpiContext::new(cpi_program, cpi_accounts)
+    }
+}
+
+pub fn withdraw_vested_handler(ctx: Context<WithdrawVested>) -> Result<()> {
+    let current_time = Clock::get().unwrap().unix_timestamp;
+    let staker = &ctx.accounts.staker;
+    let staker_key = staker.key();
+    let token_auth_bump = staker.token_auth_bump;
+    let decimals = ctx.accounts.token_mint.decimals;
+
+    let amount = ctx.accounts.vesting_record.withdraw(current_time)?;
+
+    require_gt!(amount, 0, StakeError::NothingToWithdraw);
+
+    let authority_seed = &[
+        &b"STAKE"[..],
+        staker_key.as_ref(),
+        &b"token-authority"[..],
+        &[token_auth_bump],
+    ];
+
+    transfer_checked(
+        ctx.accounts
+            .transfer_token_ctx()
+            .with_signer(&[&authority_seed[..]]),
+        amount,
+        decimals,
+    )
+}
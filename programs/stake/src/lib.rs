@@ -33,6 +33,18 @@ pub const STAKING_ENDS: i64 = 2015762363;
 #[constant]
 pub const WEIGHT: u128 = 1_000_000_000;
 
+/// reward multipliers are in basis points
+#[constant]
+pub const MULTIPLIER_BASE: u64 = 10_000;
+
+/// token emissions a collection can run alongside its primary token emission
+#[constant]
+pub const MAX_EXTRA_EMISSIONS: usize = 4;
+
+/// pro-rata distributions track the amount owed per staked item at this precision
+#[constant]
+pub const REWARD_PER_ITEM_PRECISION: u128 = 1_000_000_000_000;
+
 #[derive(Accounts)]
 pub struct Test<'info> {
     #[account()]
@@ -40,7 +52,9 @@ pub struct Test<'info> {
 }
 
 use crate::state::FontStyles;
-use crate::state::RewardType;
+use crate::state::{
+    EarlyUnstake, LockTier, MultiplierProof, PointsItemKind, RewardStep, RewardType, Vesting,
+};
 
 #[program]
 pub mod stake {
@@ -79,8 +93,9 @@ pub mod stake {
         uri: String,
         num_shares: u32,
         amount: u64,
+        ends_at: Option<i64>,
     ) -> Result<()> {
-        init_distribution_handler(ctx, label, uri, num_shares, amount)
+        init_distribution_handler(ctx, label, uri, num_shares, amount, ends_at)
     }
 
     pub fn init_collection(
@@ -93,6 +108,14 @@ pub mod stake {
         init_collection_handler(ctx, custodial, staking_starts_at, max_stakers_count)
     }
 
+    pub fn init_pool(
+        ctx: Context<InitPool>,
+        staking_starts_at: Option<i64>,
+        max_stakers_count: u64,
+    ) -> Result<()> {
+        init_pool_handler(ctx, staking_starts_at, max_stakers_count)
+    }
+
     pub fn delegate_stake(ctx: Context<DelegateStake>) -> Result<()> {
         handle_delegate_stake(ctx)
     }
@@ -105,6 +128,8 @@ pub mod stake {
         duration: Option<i64>,
         minimum_period: Option<i64>,
         starting_balance: Option<u64>,
+        reward_schedule: Vec<RewardStep>,
+        shared: bool,
     ) -> Result<()> {
         add_emission_handler(
             ctx,
@@ -114,6 +139,8 @@ pub mod stake {
             duration,
             minimum_period,
             starting_balance,
+            reward_schedule,
+            shared,
         )
     }
 
@@ -121,6 +148,45 @@ pub mod stake {
         distribute_handler(ctx, amount)
     }
 
+    pub fn distribute_pro_rata(ctx: Context<DistributeProRata>, amount: u64) -> Result<()> {
+        distribute_pro_rata_handler(ctx, amount)
+    }
+
+    pub fn claim_share(ctx: Context<ClaimShare>) -> Result<()> {
+        claim_share_handler(ctx)
+    }
+
+    pub fn set_distribution_root(
+        ctx: Context<SetDistributionRoot>,
+        merkle_root: [u8; 32],
+        num_shares: u32,
+        amount: u64,
+    ) -> Result<()> {
+        set_distribution_root_handler(ctx, merkle_root, num_shares, amount)
+    }
+
+    pub fn claim_merkle_share(
+        ctx: Context<ClaimMerkleShare>,
+        index: u32,
+        amount: u64,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        claim_merkle_share_handler(ctx, index, amount, proof)
+    }
+
+    pub fn reclaim_distribution<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ReclaimDistribution<'info>>,
+    ) -> Result<()> {
+        reclaim_distribution_handler(ctx)
+    }
+
+    pub fn toggle_distribution_active(
+        ctx: Context<ToggleDistributionActive>,
+        active: bool,
+    ) -> Result<()> {
+        toggle_distribution_active_handler(ctx, active)
+    }
+
     pub fn close_emission<'info>(
         ctx: Context<'_, '_, 'info, 'info, CloseEmission<'info>>,
     ) -> Result<()> {
@@ -167,24 +233,106 @@ pub mod stake {
     pub fn stake<'info>(
         ctx: Context<'_, '_, 'info, 'info, Stake<'info>>,
         selection: Option<u64>,
+        proof: Option<Vec<[u8; 32]>>,
+        multiplier: Option<MultiplierProof>,
+        lock_tier: Option<u8>,
     ) -> Result<()> {
-        stake_handler(ctx, selection)
+        stake_handler(ctx, selection, proof, multiplier, lock_tier)
     }
 
-    pub fn claim(ctx: Context<Claim>) -> Result<()> {
+    pub fn stake_many<'info>(
+        ctx: Context<'_, '_, 'info, 'info, StakeMany<'info>>,
+        selection: Option<u64>,
+        multipliers: Vec<Option<MultiplierProof>>,
+    ) -> Result<()> {
+        stake_many_handler(ctx, selection, multipliers)
+    }
+
+    pub fn claim<'info>(ctx: Context<'_, '_, 'info, 'info, Claim<'info>>) -> Result<()> {
         claim_handler(ctx)
     }
 
+    pub fn claim_all<'info>(ctx: Context<'_, '_, 'info, 'info, ClaimAll<'info>>) -> Result<()> {
+        claim_all_handler(ctx)
+    }
+
     pub fn unstake<'info>(ctx: Context<'_, '_, 'info, 'info, Unstake<'info>>) -> Result<()> {
         unstake_handler(ctx)
     }
 
+    pub fn request_unstake<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RequestUnstake<'info>>,
+    ) -> Result<()> {
+        request_unstake_handler(ctx)
+    }
+
+    pub fn complete_unstake<'info>(
+        ctx: Context<'_, '_, 'info, 'info, Unstake<'info>>,
+    ) -> Result<()> {
+        complete_unstake_handler(ctx)
+    }
+
+    pub fn stake_cnft<'info>(
+        ctx: Context<'_, '_, 'info, 'info, StakeCnft<'info>>,
+        root: [u8; 32],
+        nonce: u64,
+        index: u32,
+        metadata: Vec<u8>,
+        selection: Option<u64>,
+        multiplier: Option<MultiplierProof>,
+    ) -> Result<()> {
+        stake_cnft_handler(ctx, root, nonce, index, metadata, selection, multiplier)
+    }
+
+    pub fn unstake_cnft<'info>(
+        ctx: Context<'_, '_, 'info, 'info, UnstakeCnft<'info>>,
+        root: [u8; 32],
+        data_hash: [u8; 32],
+        creator_hash: [u8; 32],
+        nonce: u64,
+        index: u32,
+    ) -> Result<()> {
+        unstake_cnft_handler(ctx, root, data_hash, creator_hash, nonce, index)
+    }
+
+    pub fn stake_core<'info>(
+        ctx: Context<'_, '_, 'info, 'info, StakeCore<'info>>,
+        selection: Option<u64>,
+        multiplier: Option<MultiplierProof>,
+    ) -> Result<()> {
+        stake_core_handler(ctx, selection, multiplier)
+    }
+
+    pub fn unstake_core<'info>(
+        ctx: Context<'_, '_, 'info, 'info, UnstakeCore<'info>>,
+    ) -> Result<()> {
+        unstake_core_handler(ctx)
+    }
+
+    pub fn force_unstake_core<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ForceUnstakeCore<'info>>,
+    ) -> Result<()> {
+        force_unstake_core_handler(ctx)
+    }
+
     pub fn force_unstake<'info>(
         ctx: Context<'_, '_, 'info, 'info, ForceUnstake<'info>>,
     ) -> Result<()> {
         force_unstake_handler(ctx)
     }
 
+    pub fn stake_tokens(ctx: Context<StakeTokens>, amount: u64) -> Result<()> {
+        stake_tokens_handler(ctx, amount)
+    }
+
+    pub fn unstake_tokens(ctx: Context<UnstakeTokens>, amount: u64) -> Result<()> {
+        unstake_tokens_handler(ctx, amount)
+    }
+
+    pub fn claim_pool(ctx: Context<ClaimPool>) -> Result<()> {
+        claim_pool_handler(ctx)
+    }
+
     pub fn extend_emission(ctx: Context<ExtendEmission>, new_ending_time: i64) -> Result<()> {
         extend_emission_handler(ctx, new_ending_time)
     }
@@ -205,6 +353,77 @@ pub mod stake {
         close_handler(ctx)
     }
 
+    pub fn update_allow_list(
+        ctx: Context<UpdateAllowList>,
+        allow_list: Option<[u8; 32]>,
+    ) -> Result<()> {
+        update_allow_list_handler(ctx, allow_list)
+    }
+
+    pub fn update_collection_capacity<'info>(
+        ctx: Context<'_, '_, 'info, 'info, UpdateCollectionCapacity<'info>>,
+        max_stakers_count: u64,
+    ) -> Result<()> {
+        update_collection_capacity_handler(ctx, max_stakers_count)
+    }
+
+    pub fn update_staking_starts_at(
+        ctx: Context<UpdateStakingStartsAt>,
+        staking_starts_at: i64,
+    ) -> Result<()> {
+        update_staking_starts_at_handler(ctx, staking_starts_at)
+    }
+
+    pub fn update_unbonding_period(
+        ctx: Context<UpdateUnbondingPeriod>,
+        unbonding_period: i64,
+    ) -> Result<()> {
+        update_unbonding_period_handler(ctx, unbonding_period)
+    }
+
+    pub fn update_early_unstake(
+        ctx: Context<UpdateEarlyUnstake>,
+        early_unstake: Option<EarlyUnstake>,
+    ) -> Result<()> {
+        update_early_unstake_handler(ctx, early_unstake)
+    }
+
+    pub fn update_vesting(ctx: Context<UpdateVesting>, vesting: Option<Vesting>) -> Result<()> {
+        update_vesting_handler(ctx, vesting)
+    }
+
+    pub fn withdraw_vested(ctx: Context<WithdrawVested>) -> Result<()> {
+        withdraw_vested_handler(ctx)
+    }
+
+    pub fn update_emission_multipliers<'info>(
+        ctx: Context<'_, '_, 'info, 'info, UpdateEmissionMultipliers<'info>>,
+        merkle_root: Option<[u8; 32]>,
+        max_multiplier: u64,
+    ) -> Result<()> {
+        update_emission_multipliers_handler(ctx, merkle_root, max_multiplier)
+    }
+
+    pub fn update_lock_tiers(
+        ctx: Context<UpdateLockTiers>,
+        lock_tiers: Vec<LockTier>,
+    ) -> Result<()> {
+        update_lock_tiers_handler(ctx, lock_tiers)
+    }
+
+    pub fn add_points_item(
+        ctx: Context<AddPointsItem>,
+        kind: PointsItemKind,
+        price: u64,
+        supply: Option<u64>,
+    ) -> Result<()> {
+        add_points_item_handler(ctx, kind, price, supply)
+    }
+
+    pub fn redeem_points(ctx: Context<RedeemPoints>) -> Result<()> {
+        redeem_points_handler(ctx)
+    }
+
     pub fn update_stake_subscription(
         ctx: Context<UpdateStake>,
         subscription: Subscription,
@@ -239,6 +458,10 @@ pub mod stake {
         resize_handler(ctx)
     }
 
+    pub fn migrate(ctx: Context<Migrate>) -> Result<()> {
+        migrate_handler(ctx)
+    }
+
     pub fn add_token(ctx: Context<AddToken>, token_vault: bool) -> Result<()> {
         add_token_handler(ctx, token_vault)
     }
@@ -280,6 +503,7 @@ pub mod stake {
         extra_collection_fee: Option<u64>,
         remove_branding_fee: Option<u64>,
         own_domain_fee: Option<u64>,
+        claim_fee_per_record: Option<bool>,
     ) -> Result<()> {
         update_program_config_handler(
             ctx,
@@ -292,6 +516,7 @@ pub mod stake {
             extra_collection_fee,
             remove_branding_fee,
             own_domain_fee,
+            claim_fee_per_record,
         )
     }
 }
@@ -459,4 +684,102 @@ pub enum StakeError {
     AmountTooLow,
     #[msg("The total shares have already been funded for this distribution")]
     TotalSharesFunded,
+    #[msg("Compressed NFT metadata could not be deserialized")]
+    InvalidCnftMetadata,
+    #[msg("Asset does not match the stake record")]
+    InvalidAsset,
+    #[msg("Account is not a valid Core asset")]
+    InvalidCoreAsset,
+    #[msg("Only token emissions can be added to a token pool")]
+    InvalidPoolEmission,
+    #[msg("This instruction is only available for token pools")]
+    NotATokenPool,
+    #[msg("This collection has no allow list")]
+    NoAllowList,
+    #[msg("Merkle proof is invalid")]
+    InvalidProof,
+    #[msg("A multiplier proof is required for this emission")]
+    MultiplierProofRequired,
+    #[msg("Multiplier must be greater than 0 and no more than the max multiplier")]
+    InvalidMultiplier,
+    #[msg("Lock tiers must have a duration and a boost of at least 1x, up to 5 tiers")]
+    InvalidLockTier,
+    #[msg("This NFT is locked until its lock tier expires")]
+    StakeLocked,
+    #[msg("Penalty can't be more than 10000 basis points")]
+    InvalidPenalty,
+    #[msg("Penalty treasury account is required")]
+    PenaltyTreasuryRequired,
+    #[msg("This collection has an unbonding period, request the unstake first")]
+    UnbondingRequired,
+    #[msg("The unbonding period has not passed yet")]
+    UnbondingNotComplete,
+    #[msg("This NFT is unbonding")]
+    Unbonding,
+    #[msg("This NFT is not unbonding")]
+    NotUnbonding,
+    #[msg("Unbonding period can't be negative")]
+    InvalidUnbondingPeriod,
+    #[msg("Vesting duration must be greater than 0 and the cliff no longer than the duration")]
+    InvalidVesting,
+    #[msg("Vesting record and vault are required for this emission")]
+    VestingAccountsRequired,
+    #[msg("Rewards from this emission vest, claim them individually")]
+    VestingClaimRequired,
+    #[msg("Nothing has unlocked yet")]
+    NothingToWithdraw,
+    #[msg("Max stakers must be greater than 0 and at least the current number of stakers")]
+    InvalidCapacity,
+    #[msg("Reward mint, token accounts and token authority are required to fund this emission")]
+    FundingAccountsRequired,
+    #[msg("This collection can't hold any more token emissions")]
+    TooManyEmissions,
+    #[msg("Mint and token accounts for this emission's reward token are missing")]
+    RewardAccountsMissing,
+    #[msg("Only emissions paid in the staker's token can vest")]
+    VestingMintMismatch,
+    #[msg("Points items need a price, and token bundles an amount and a supply")]
+    InvalidPointsItem,
+    #[msg("This points item has no supply left")]
+    PointsItemSoldOut,
+    #[msg("This NFT doesn't have enough points")]
+    NotEnoughPoints,
+    #[msg("NFT record is required for collections with a points emission")]
+    NftRecordRequired,
+    #[msg("Token mint, token account and vault are required for token distributions")]
+    DistributionAccountsRequired,
+    #[msg("Claims for this distribution are not open")]
+    DistributionInactive,
+    #[msg("A Merkle root can only be posted once, before any shares are funded")]
+    MerkleRootLocked,
+    #[msg("Shares of this distribution are claimed with a Merkle proof")]
+    MerkleDistribution,
+    #[msg("This distribution has no Merkle root")]
+    NoMerkleRoot,
+    #[msg("Share index is out of range")]
+    InvalidShareIndex,
+    #[msg("This share has already been claimed")]
+    ShareAlreadyClaimed,
+    #[msg("This distribution is funded in a different mode")]
+    DistributionModeMismatch,
+    #[msg("There are no staked items to distribute to")]
+    NoStakedItems,
+    #[msg("Distribution claim deadline must be in the future")]
+    InvalidDistributionEnd,
+    #[msg("The claim deadline for this distribution has passed")]
+    DistributionEnded,
+    #[msg("The claim deadline for this distribution hasn't passed")]
+    DistributionNotEnded,
+    #[msg("Reward steps must be ordered and fall between the emission's start and end")]
+    InvalidRewardSchedule,
+    #[msg("A shared emission must be the first token emission of an NFT collection")]
+    InvalidSharedEmission,
+    #[msg("The pool emission's balance can't cover the rewards of this deposit")]
+    PoolUnderfunded,
+    #[msg("Every emission of the collection must use the same multiplier root")]
+    MerkleRootMismatch,
+    #[msg("Only collections, emissions and stake records of this program can be migrated")]
+    InvalidMigration,
+    #[msg("Every share record must be closed before the distribution can be reclaimed")]
+    ShareRecordsOpen,
 }
@@ -0,0 +1,50 @@
+use anchor_lang::prelude::*;
+
+use crate::StakeError;
+
+#[account]
+pub struct ClaimBitmap {
+    /// distribution this bitmap belongs to (32)
+    pub distribution: Pubkey,
+    /// one bit per Merkle leaf, set once claimed (4 + (num_shares + 7) / 8)
+    pub bits: Vec<u8>,
+    /// Bump of the Claim Bitmap PDA (1)
+    pub bump: u8,
+}
+
+impl ClaimBitmap {
+    pub fn len(num_shares: u32) -> usize {
+        8 + 32 + (4 + Self::num_bytes(num_shares)) + 1
+    }
+
+    fn num_bytes(num_shares: u32) -> usize {
+        (num_shares as usize).div_ceil(8)
+    }
+
+    pub fn init(distribution: Pubkey, num_shares: u32, bump: u8) -> Self {
+        Self {
+            distribution,
+            bits: vec![0; Self::num_bytes(num_shares)],
+            bump,
+        }
+    }
+
+    pub fn is_claimed(&self, index: u32) -> bool {
+        self.bits
+            .get(index as usize / 8)
+            .map(|byte| byte & (1 << (index % 8)) != 0)
+            .unwrap_or(false)
+    }
+
+    pub fn set_claimed(&mut self, index: u32) -> Result<()> {
+        require!(!self.is_claimed(index), StakeError::ShareAlreadyClaimed);
+
+        let byte = self
+            .bits
+            .get_mut(index as usize / 8)
+            .ok_or(StakeError::InvalidShareIndex)?;
+        *byte |= 1 << (index % 8);
+
+        Ok(())
+    }
+}
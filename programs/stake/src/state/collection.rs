@@ -1,6 +1,15 @@
 use anchor_lang::prelude::*;
 
-use crate::StakeError;
+use crate::{StakeError, MAX_EXTRA_EMISSIONS};
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CollectionKind {
+    Nft,
+    /// a pool of a fungible token, deposited in any amount
+    Fungible {
+        decimals: u8,
+    },
+}
 
 #[account]
 pub struct Collection {
@@ -12,7 +21,7 @@ pub struct Collection {
     pub creators: Vec<Pubkey>,
     /// Collection custody type (1)
     pub custodial: bool,
-    /// Merkle root of allowlist (1 + 32)
+    /// Merkle root of mints that can be staked without a verified collection or creator (1 + 32)
     pub allow_list: Option<Pubkey>,
     /// pubkey of token emission config (1 + 32)
     pub token_emission: Option<Pubkey>,
@@ -30,17 +39,43 @@ pub struct Collection {
     pub current_stakers_count: u64,
     /// Bump of the Collection PDA (1)
     pub bump: u8,
+    /// kind of items staked in the collection (1 + 1)
+    pub kind: CollectionKind,
+    /// cooldown in seconds between requesting an unstake and releasing the NFT (8)
+    pub unbonding_period: i64,
+    /// timestamp staking opens at for everyone but the staker's authority (8)
+    pub staking_starts_at: i64,
+    /// token emissions running alongside `token_emission` (4 + 32 * MAX_EXTRA_EMISSIONS)
+    pub extra_emissions: Vec<Pubkey>,
 }
 
 impl Collection {
-    pub const LEN: usize =
-        8 + 32 + 32 + 4 + 1 + (1 + 32) + (1 + 32) + (1 + 32) + (1 + 32) + (1 + 32) + 1 + 8 + 8 + 1;
+    pub const LEN: usize = 8
+        + 32
+        + 32
+        + 4
+        + 1
+        + (1 + 32)
+        + (1 + 32)
+        + (1 + 32)
+        + (1 + 32)
+        + (1 + 32)
+        + 1
+        + 8
+        + 8
+        + 1
+        + (1 + 1)
+        + 8
+        + 8
+        + (4 + 32 * MAX_EXTRA_EMISSIONS);
 
     pub fn init(
         staker: Pubkey,
         collection_mint: Pubkey,
         custodial: bool,
         max_stakers_count: u64,
+        kind: CollectionKind,
+        staking_starts_at: i64,
         bump: u8,
     ) -> Self {
         Self {
@@ -49,7 +84,6 @@ impl Collection {
             custodial,
             // todo: add this
             creators: vec![],
-            // todo: add this
             allow_list: None,
             token_emission: None,
             selection_emission: None,
@@ -59,6 +93,92 @@ impl Collection {
             max_stakers_count,
             current_stakers_count: 0,
             bump,
+            kind,
+            unbonding_period: 0,
+            staking_starts_at,
+            extra_emissions: vec![],
+        }
+    }
+
+    /// Only the staker's authority can stake before the collection goes live
+    pub fn check_live(&self, authority: &Pubkey, signer: &Pubkey, current_time: i64) -> Result<()> {
+        require!(
+            current_time >= self.staking_starts_at || authority == signer,
+            StakeError::StakeNotLive
+        );
+
+        Ok(())
+    }
+
+    /// The primary token emission followed by any extra token emissions
+    pub fn token_emissions(&self) -> Vec<Pubkey> {
+        self.token_emission
+            .iter()
+            .chain(self.extra_emissions.iter())
+            .copied()
+            .collect()
+    }
+
+    pub fn is_token_emission(&self, emission: &Pubkey) -> bool {
+        self.token_emission.as_ref() == Some(emission) || self.extra_emissions.contains(emission)
+    }
+
+    /// Every emission of the collection, in the order they are recorded on stake records
+    pub fn emissions(&self) -> Vec<Pubkey> {
+        let mut emissions = self.token_emissions();
+        emissions.extend(
+            [
+                self.points_emission,
+                self.selection_emission,
+                self.distribution_emission,
+            ]
+            .into_iter()
+            .flatten(),
+        );
+        emissions
+    }
+
+    /// Adds a token emission, filling the primary slot first
+    pub fn add_token_emission(&mut self, emission: Pubkey) -> Result<()> {
+        if self.token_emission.is_none() {
+            self.token_emission = Some(emission);
+            return Ok(());
+        }
+
+        require_gt!(
+            MAX_EXTRA_EMISSIONS,
+            self.extra_emissions.len(),
+            StakeError::TooManyEmissions
+        );
+
+        self.extra_emissions.push(emission);
+        Ok(())
+    }
+
+    /// Removes a token emission, promoting the first extra emission to the primary slot
+    pub fn remove_token_emission(&mut self, emission: &Pubkey) {
+        if self.token_emission.as_ref() == Some(emission) {
+            self.token_emission = if self.extra_emissions.is_empty() {
+                None
+            } else {
+                Some(self.extra_emissions.remove(0))
+            };
+        } else {
+            self.extra_emissions.retain(|extra| extra != emission);
+        }
+    }
+
+    pub fn is_pool(&self) -> bool {
+        matches!(self.kind, CollectionKind::Fungible { .. })
+    }
+
+    /// The amount of the staked mint that earns one emission reward per second
+    pub fn stake_unit(&self) -> Result<u64> {
+        match self.kind {
+            CollectionKind::Nft => Ok(1),
+            CollectionKind::Fungible { decimals } => Ok(10u64
+                .checked_pow(decimals.into())
+                .ok_or(StakeError::ProgramMulError)?),
         }
     }
 
@@ -32,11 +32,35 @@ pub struct Distribution {
     pub active: bool,
     /// bump of the vault authority
     pub vault_authority_bump: u8,
+    /// the root hash of (index, owner or mint, amount) airdrop leaves (1 + 32)
+    pub merkle_root: Option<Pubkey>,
+    /// distribution emission this is shared pro-rata through (1 + 32)
+    pub emission: Option<Pubkey>,
+    /// claim deadline, after which the staker can reclaim what's left (1 + 8)
+    pub ends_at: Option<i64>,
+    /// share records funded and not yet claimed or closed (4)
+    pub share_records_open: u32,
 }
 
 impl Distribution {
-    pub const LEN: usize =
-        8 + 32 + (4 + 20) + (1 + 32) + (4 + 63) + 8 + 8 + 4 + 4 + 8 + 8 + 1 + 1 + 1;
+    pub const LEN: usize = 8
+        + 32
+        + (4 + 20)
+        + (1 + 32)
+        + (4 + 63)
+        + 8
+        + 8
+        + 4
+        + 4
+        + 8
+        + 8
+        + 1
+        + 1
+        + 1
+        + (1 + 32)
+        + (1 + 32)
+        + (1 + 8)
+        + 4;
 
     pub fn init(
         staker: Pubkey,
@@ -48,6 +72,7 @@ impl Distribution {
         created_at: i64,
         amount: u64,
         vault_authority_bump: u8,
+        ends_at: Option<i64>,
     ) -> Self {
         Self {
             staker,
@@ -64,9 +89,17 @@ impl Distribution {
             balance: amount,
             total_amount: amount,
             vault_authority_bump,
+            merkle_root: None,
+            emission: None,
+            ends_at,
+            share_records_open: 0,
         }
     }
 
+    pub fn has_ended(&self, current_time: i64) -> bool {
+        self.ends_at.is_some_and(|ends_at| current_time >= ends_at)
+    }
+
     pub fn iterate_funded(&mut self) {
         self.shares_funded += 1;
         if self.shares_funded >= self.num_shares {
@@ -74,6 +107,14 @@ impl Distribution {
         }
     }
 
+    pub fn open_share_record(&mut self) {
+        self.share_records_open += 1;
+    }
+
+    pub fn close_share_record(&mut self) {
+        self.share_records_open -= 1;
+    }
+
     pub fn add_to_claimed(&mut self, amount: u64) {
         self.claimed_amount += amount;
         self.balance -= amount;
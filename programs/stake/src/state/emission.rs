@@ -1,6 +1,11 @@
-use anchor_lang::prelude::*;
+use anchor_lang::{prelude::*, solana_program::keccak::hashv};
 
-use crate::{StakeError, STAKING_ENDS, WEIGHT};
+use crate::{
+    utils::{calc_actual_balance, calc_scheduled_reward, calc_total_emission, verify_merkle_proof},
+    StakeError, MULTIPLIER_BASE, REWARD_PER_ITEM_PRECISION, STAKING_ENDS, WEIGHT,
+};
+
+use super::Staker;
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
 pub struct Choice {
@@ -20,11 +25,55 @@ pub enum RewardType {
     Distribution,
 }
 
+/// A proof that an NFT is committed to a multiplier in an emission's Merkle root
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct MultiplierProof {
+    /// reward multiplier in basis points
+    pub multiplier: u64,
+    pub proof: Vec<[u8; 32]>,
+}
+
+/// Lets stakers leave before the minimum period by forfeiting part of their rewards
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub struct EarlyUnstake {
+    /// share of the rewards forfeited in basis points
+    pub penalty: u64,
+    /// token account receiving forfeited rewards, they return to the emission if not set
+    pub treasury: Option<Pubkey>,
+}
+
+/// Linear vesting schedule for claimed token rewards
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub struct Vesting {
+    /// seconds after claiming before anything unlocks
+    pub cliff: i64,
+    /// seconds after claiming for all rewards to unlock
+    pub duration: i64,
+}
+
+/// A voluntary lock-up that boosts a staker's token rewards
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub struct LockTier {
+    /// time in seconds the NFT can't be unstaked for
+    pub duration: i64,
+    /// reward boost in basis points
+    pub boost: u64,
+}
+
+/// A reward change that takes effect at a set time in the future
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub struct RewardStep {
+    /// time the new reward applies from
+    pub time: i64,
+    /// reward per second
+    pub reward: u64,
+}
+
 #[account]
 pub struct Emission {
     /// the collection the emission belongs to (32)
     pub collection: Pubkey,
-    /// the root hash (1 + 32),
+    /// the root hash of (mint, multiplier) reward multipliers (1 + 32),
     pub merkle_root: Option<Pubkey>,
     /// The type of emission (1 + 32 + 1)
     pub reward_type: RewardType,
@@ -34,9 +83,9 @@ pub struct Emission {
     pub reward_change_time: Vec<i64>,
     /// Starting time of the staking (8)
     pub start_time: i64,
-    /// optional token mint (1 + 32)
+    /// mint rewards are paid in, the staker's token if not set (1 + 32)
     pub token_mint: Option<Pubkey>,
-    /// is token vault (1)
+    /// rewards are paid from the vault rather than minted (1)
     pub token_vault: bool,
     /// The period for which the staking is funded (1 + 8)
     pub end_time: Option<i64>,
@@ -50,6 +99,31 @@ pub struct Emission {
     pub staked_items: u64,
     /// is the emission active (1)
     pub active: bool,
+    /// amount of the staked mint that earns `reward` per second, 1 for NFTs (8)
+    pub stake_unit: u64,
+    /// the highest multiplier in the merkle root, in basis points (8)
+    pub max_multiplier: u64,
+    /// lock-up tiers stakers can choose from (4 + 16 * n)
+    pub lock_tiers: Vec<LockTier>,
+    /// optional early unstake mode (1 + 8 + 1 + 32)
+    pub early_unstake: Option<EarlyUnstake>,
+    /// total rewards forfeited by early unstakes, individual forfeits are only logged (8)
+    pub penalties_collected: u64,
+    /// optional vesting schedule for claimed rewards (1 + 8 + 8)
+    pub vesting: Option<Vesting>,
+    /// pro-rata distribution funded through this emission (1 + 32)
+    pub distribution: Option<Pubkey>,
+    /// amount distributed per staked item, or earned per staked unit of a shared
+    /// emission, scaled by REWARD_PER_ITEM_PRECISION (16)
+    pub reward_per_item: u128,
+    /// reward per item when the linked distribution was first funded (16)
+    pub distribution_start: u128,
+    /// scheduled reward changes, ordered by time (4 + 16 * n)
+    pub reward_schedule: Vec<RewardStep>,
+    /// `reward` is paid per second to the whole pool and split between the staked items (1)
+    pub shared: bool,
+    /// time a shared emission's reward per item was last brought up to date (8)
+    pub reward_per_item_updated_at: i64,
 }
 
 impl Emission {
@@ -77,11 +151,40 @@ impl Emission {
             minimum_period,
             staked_items: 0,
             active: true,
+            stake_unit: 1,
+            max_multiplier: MULTIPLIER_BASE,
+            lock_tiers: vec![],
+            early_unstake: None,
+            penalties_collected: 0,
+            vesting: None,
+            distribution: None,
+            reward_per_item: 0,
+            distribution_start: 0,
+            reward_schedule: vec![],
+            shared: false,
+            reward_per_item_updated_at: start_time,
         }
     }
 
     pub fn current_len(&self) -> usize {
-        std::mem::size_of::<Emission>() + self.reward.len() * 16
+        std::mem::size_of::<Emission>()
+            + self.reward.len() * 16
+            + self.lock_tiers.len() * 16
+            + self.reward_schedule.len() * 16
+    }
+
+    /// The mint rewards are paid in, emissions created before rewards were configured
+    /// per emission pay in the staker's token
+    pub fn reward_mint(&self, staker: &Staker) -> Option<Pubkey> {
+        self.token_mint.or(staker.token_mint)
+    }
+
+    /// Whether rewards are paid from the token vault rather than minted
+    pub fn is_vault_funded(&self, staker: &Staker) -> bool {
+        match self.token_mint {
+            Some(_) => self.token_vault,
+            None => staker.token_vault,
+        }
     }
 
     pub fn change_reward(&mut self, new_reward: u64, current_time: i64) {
@@ -89,21 +192,350 @@ impl Emission {
         self.reward_change_time.push(current_time);
     }
 
+    /// Moves the scheduled steps that have taken effect into the reward history
+    pub fn apply_due_steps(&mut self, current_time: i64) {
+        let due = self
+            .reward_schedule
+            .iter()
+            .take_while(|step| step.time <= current_time)
+            .count();
+
+        for step in self.reward_schedule.drain(..due) {
+            self.reward.push(step.reward);
+            self.reward_change_time.push(step.time);
+        }
+    }
+
+    /// The span staked weight is measured in, time unless rewards are scheduled to
+    /// change, in which case it's the rewards an item earns between `from` and `to`
+    pub fn weight_span(&self, from: i64, to: i64) -> Result<u128> {
+        if self.reward_schedule.is_empty() {
+            // directly converting to u128 since it can't be negative
+            return Ok(to.checked_sub(from).ok_or(StakeError::ProgramSubError)? as u128);
+        }
+
+        let last_reward = *self.reward.last().unwrap();
+
+        Ok(calc_scheduled_reward(last_reward, &self.reward_schedule, from, to)?.into())
+    }
+
     pub fn increase_staked_items(&mut self) -> Result<()> {
+        self.increase_staked_amount(1)
+    }
+
+    pub fn decrease_staked_items(&mut self) -> Result<()> {
+        self.decrease_staked_amount(1)
+    }
+
+    /// For token pools the staked items are counted in base units of the staked mint
+    pub fn increase_staked_amount(&mut self, amount: u64) -> Result<()> {
         self.staked_items = self
             .staked_items
-            .checked_add(1)
+            .checked_add(amount)
             .ok_or(StakeError::ProgramAddError)?;
 
         Ok(())
     }
 
-    pub fn decrease_staked_items(&mut self) -> Result<()> {
+    pub fn decrease_staked_amount(&mut self, amount: u64) -> Result<()> {
         self.staked_items = self
             .staked_items
-            .checked_sub(1)
+            .checked_sub(amount)
+            .ok_or(StakeError::ProgramSubError)?;
+
+        Ok(())
+    }
+
+    /// Shares a pro-rata distribution between the items staked right now
+    pub fn add_distribution(&mut self, amount: u64) -> Result<()> {
+        require_gt!(self.staked_items, 0, StakeError::NoStakedItems);
+
+        let per_item = (amount as u128)
+            .checked_mul(REWARD_PER_ITEM_PRECISION)
+            .ok_or(StakeError::ProgramMulError)?
+            .checked_div(self.staked_items.into())
+            .ok_or(StakeError::ProgramDivError)?;
+
+        self.reward_per_item = self
+            .reward_per_item
+            .checked_add(per_item)
+            .ok_or(StakeError::ProgramAddError)?;
+
+        Ok(())
+    }
+
+    /// Links a pro-rata distribution, items are only owed what's shared after this point
+    pub fn link_distribution(&mut self, distribution: Pubkey) {
+        self.distribution = Some(distribution);
+        self.distribution_start = self.reward_per_item;
+    }
+
+    /// The pro-rata amount an item is owed since its debt was last settled, shares of
+    /// distributions that have since been reclaimed aren't owed
+    pub fn distribution_owed(&self, distribution_debt: u128) -> Result<u64> {
+        let owed = self
+            .reward_per_item
+            .checked_sub(distribution_debt.max(self.distribution_start))
+            .ok_or(StakeError::ProgramSubError)?
+            / REWARD_PER_ITEM_PRECISION;
+
+        match u64::try_from(owed) {
+            Ok(owed) => Ok(owed),
+            _ => err!(StakeError::ProgramMulError),
+        }
+    }
+
+    /// Brings a shared emission's reward per item up to `current_time`. What the staked items
+    /// earned is taken out of the balance of vault funded emissions straight away, so this has
+    /// to run before every change to the staked amount
+    pub fn update_reward_per_item(&mut self, current_time: i64, token_vault: bool) -> Result<()> {
+        let cutoff_time = current_time.min(self.end_time.unwrap_or(STAKING_ENDS));
+
+        if !self.shared || cutoff_time <= self.reward_per_item_updated_at {
+            return Ok(());
+        }
+
+        // nothing is earned while the pool is empty, so that part of the budget is kept
+        if self.staked_items > 0 {
+            let earned = calc_scheduled_reward(
+                *self.reward.last().unwrap(),
+                &self.reward_schedule,
+                self.reward_per_item_updated_at,
+                cutoff_time,
+            )?;
+
+            let per_item = (earned as u128)
+                .checked_mul(REWARD_PER_ITEM_PRECISION)
+                .ok_or(StakeError::ProgramMulError)?
+                .checked_div(self.staked_items.into())
+                .ok_or(StakeError::ProgramDivError)?;
+
+            self.reward_per_item = self
+                .reward_per_item
+                .checked_add(per_item)
+                .ok_or(StakeError::ProgramAddError)?;
+
+            if token_vault {
+                self.current_balance = self
+                    .current_balance
+                    .checked_sub(earned)
+                    .ok_or(StakeError::ProgramSubError)?;
+            }
+        }
+
+        self.reward_per_item_updated_at = cutoff_time;
+
+        Ok(())
+    }
+
+    /// Settles what `amount` of a shared emission's staked units earned since `reward_debt`,
+    /// moving the debt up to the current reward per item
+    pub fn settle_shared_reward(
+        &mut self,
+        reward_debt: &mut u128,
+        amount: u64,
+        current_time: i64,
+        token_vault: bool,
+    ) -> Result<u64> {
+        self.update_reward_per_item(current_time, token_vault)?;
+
+        let owed = self
+            .reward_per_item
+            .checked_sub(*reward_debt)
+            .ok_or(StakeError::ProgramSubError)?
+            .checked_mul(amount.into())
+            .ok_or(StakeError::ProgramMulError)?
+            / REWARD_PER_ITEM_PRECISION;
+
+        *reward_debt = self.reward_per_item;
+
+        match u64::try_from(owed) {
+            Ok(owed) => Ok(owed),
+            _ => err!(StakeError::ProgramMulError),
+        }
+    }
+
+    /// emissions created before token pools have no unit stored
+    pub fn stake_unit(&self) -> u64 {
+        self.stake_unit.max(1)
+    }
+
+    /// Checks the NFT's multiplier against the merkle root, returning the amount
+    /// the NFT is staked with. Emissions without multipliers count each NFT once.
+    pub fn verify_multiplier(
+        &self,
+        nft_mint: &Pubkey,
+        multiplier: &Option<MultiplierProof>,
+    ) -> Result<u64> {
+        let merkle_root = match self.merkle_root {
+            Some(merkle_root) => merkle_root,
+            None => return Ok(1),
+        };
+
+        let MultiplierProof { multiplier, proof } = multiplier
+            .as_ref()
+            .ok_or(StakeError::MultiplierProofRequired)?;
+
+        let leaf = hashv(&[nft_mint.as_ref(), &multiplier.to_le_bytes()]).to_bytes();
+
+        require!(
+            verify_merkle_proof(proof, merkle_root.to_bytes(), leaf),
+            StakeError::InvalidProof
+        );
+        require_gte!(
+            self.max_multiplier,
+            *multiplier,
+            StakeError::InvalidMultiplier
+        );
+
+        Ok(*multiplier)
+    }
+
+    /// Emissions with multipliers or lock tiers count items in basis points
+    pub fn update_stake_unit(&mut self) {
+        self.stake_unit = if self.merkle_root.is_some() || !self.lock_tiers.is_empty() {
+            MULTIPLIER_BASE
+        } else {
+            1
+        };
+    }
+
+    pub fn lock_tier(&self, index: u8) -> Result<LockTier> {
+        self.lock_tiers
+            .get(usize::from(index))
+            .copied()
+            .ok_or(error!(StakeError::InvalidIndex))
+    }
+
+    /// The amount a staked NFT counts for, its multiplier boosted by its lock tier
+    pub fn item_amount(&self, multiplier: u64, lock_boost: u64) -> Result<u64> {
+        if self.stake_unit() == 1 {
+            return Ok(1);
+        }
+
+        let multiplier = if self.merkle_root.is_some() {
+            multiplier
+        } else {
+            MULTIPLIER_BASE
+        };
+
+        if self.lock_tiers.is_empty() {
+            return Ok(multiplier);
+        }
+
+        let amount = (multiplier as u128)
+            .checked_mul(lock_boost.into())
+            .ok_or(StakeError::ProgramMulError)?
+            .checked_div(MULTIPLIER_BASE.into())
+            .ok_or(StakeError::ProgramDivError)?;
+
+        match u64::try_from(amount) {
+            Ok(amount) => Ok(amount),
+            _ => err!(StakeError::ProgramMulError),
+        }
+    }
+
+    /// The highest amount an NFT can count for, used to fund the emission's liability
+    pub fn max_multiplier(&self) -> u64 {
+        let multiplier = if self.merkle_root.is_some() {
+            self.max_multiplier
+        } else {
+            MULTIPLIER_BASE
+        };
+
+        let max_boost = self
+            .lock_tiers
+            .iter()
+            .map(|tier| tier.boost)
+            .max()
+            .unwrap_or(MULTIPLIER_BASE)
+            .max(MULTIPLIER_BASE);
+
+        u64::try_from(multiplier as u128 * max_boost as u128 / MULTIPLIER_BASE as u128)
+            .unwrap_or(u64::MAX)
+    }
+
+    /// Splits the rewards of an early unstake into the staker's payout and the forfeited penalty
+    pub fn early_unstake_penalty(&self, reward: u64) -> Result<(u64, u64)> {
+        let EarlyUnstake { penalty, .. } = self
+            .early_unstake
+            .ok_or(StakeError::MinimumPeriodNotReached)?;
+
+        let forfeited = (reward as u128)
+            .checked_mul(penalty.into())
+            .ok_or(StakeError::ProgramMulError)?
+            .checked_div(MULTIPLIER_BASE.into())
+            .ok_or(StakeError::ProgramDivError)? as u64; // can't exceed reward since penalty <= MULTIPLIER_BASE
+
+        let payout = reward
+            .checked_sub(forfeited)
             .ok_or(StakeError::ProgramSubError)?;
 
+        Ok((payout, forfeited))
+    }
+
+    pub fn add_penalty(&mut self, forfeited: u64) -> Result<()> {
+        self.penalties_collected = self
+            .penalties_collected
+            .checked_add(forfeited)
+            .ok_or(StakeError::ProgramAddError)?;
+
+        Ok(())
+    }
+
+    /// Scales a per unit reward to the staked amount
+    pub fn reward_for_amount(&self, reward: u64, amount: u64) -> Result<u64> {
+        let reward = (reward as u128)
+            .checked_mul(amount.into())
+            .ok_or(StakeError::ProgramMulError)?
+            .checked_div(self.stake_unit().into())
+            .ok_or(StakeError::ProgramDivError)?;
+
+        match u64::try_from(reward) {
+            Ok(reward) => Ok(reward),
+            _ => err!(StakeError::ProgramMulError),
+        }
+    }
+
+    /// Token pools are funded with a starting balance rather than per slot, so deposits are
+    /// only taken while the balance covers what the pool accrues until the end
+    pub fn check_pool_funding(&self, current_time: i64) -> Result<()> {
+        let end_time = self.end_time.unwrap_or(STAKING_ENDS);
+
+        if current_time >= end_time {
+            return Ok(());
+        }
+
+        let last_reward = *self.reward.last().unwrap();
+
+        let (current_actual_balance, _accrued_reward, _new_staked_weight) = calc_actual_balance(
+            self.staked_items,
+            self.staked_weight,
+            last_reward,
+            *self.reward_change_time.last().unwrap(),
+            self.end_time,
+            current_time,
+            self.current_balance,
+            None,
+            self.stake_unit(),
+            &self.reward_schedule,
+        )?;
+
+        let remaining_emission = calc_total_emission(
+            self.reward_for_amount(last_reward, self.staked_items)?,
+            &[],
+            1,
+            current_time,
+            end_time,
+            MULTIPLIER_BASE,
+        )?;
+
+        require_gte!(
+            current_actual_balance,
+            remaining_emission,
+            StakeError::PoolUnderfunded
+        );
+
         Ok(())
     }
 
@@ -112,7 +544,19 @@ impl Emission {
     }
 
     pub fn update_staked_weight(&mut self, stake_time: i64, increase_weight: bool) -> Result<()> {
-        let weight = self.get_staked_weight(stake_time)?;
+        self.update_staked_weight_for_amount(stake_time, 1, increase_weight)
+    }
+
+    pub fn update_staked_weight_for_amount(
+        &mut self,
+        stake_time: i64,
+        amount: u64,
+        increase_weight: bool,
+    ) -> Result<()> {
+        let weight = self
+            .get_staked_weight(stake_time)?
+            .checked_mul(amount.into())
+            .ok_or(StakeError::ProgramMulError)?;
 
         if increase_weight {
             self.staked_weight = self
@@ -129,22 +573,16 @@ impl Emission {
         Ok(())
     }
 
-    pub fn get_staked_weight(&mut self, stake_time: i64) -> Result<(u128)> {
+    pub fn get_staked_weight(&self, stake_time: i64) -> Result<u128> {
         let last_reward_time = *self.reward_change_time.last().unwrap();
 
-        let base = self
-            .end_time
-            .unwrap_or(STAKING_ENDS)
-            .checked_sub(last_reward_time)
-            .ok_or(StakeError::ProgramSubError)? as u128; // directly converting to u128 since it can't be negative
+        let end_time = self.end_time.unwrap_or(STAKING_ENDS);
+
+        let base = self.weight_span(last_reward_time, end_time)?;
 
         let weight_time = stake_time.max(last_reward_time);
 
-        let mut num = self
-            .end_time
-            .unwrap_or(STAKING_ENDS)
-            .checked_sub(weight_time)
-            .ok_or(StakeError::ProgramSubError)? as u128; // directly converting to u128 since it can't be negative
+        let mut num = self.weight_span(weight_time, end_time)?;
 
         num = num.checked_mul(WEIGHT).ok_or(StakeError::ProgramMulError)?;
 
@@ -166,6 +604,20 @@ impl Emission {
     }
 
     pub fn decrease_current_balance(&mut self, staked_at: i64, current_time: i64) -> Result<()> {
+        self.decrease_current_balance_for_amount(staked_at, current_time, self.stake_unit())
+    }
+
+    pub fn decrease_current_balance_for_amount(
+        &mut self,
+        staked_at: i64,
+        current_time: i64,
+        amount: u64,
+    ) -> Result<()> {
+        // shared emissions take rewards out of the balance as they are earned
+        if self.shared {
+            return Ok(());
+        }
+
         let last_reward_time = *self.reward_change_time.last().unwrap();
         let last_reward = *self.reward.last().unwrap();
 
@@ -176,16 +628,14 @@ impl Emission {
             .checked_sub(reward_time)
             .ok_or(StakeError::ProgramSubError)?;
 
-        let rewardable_time_u64 = match u64::try_from(rewardable_time_since_change) {
-            Ok(time) => time,
-            _ => {
-                return err!(StakeError::FailedTimeConversion);
-            }
-        };
+        if u64::try_from(rewardable_time_since_change).is_err() {
+            return err!(StakeError::FailedTimeConversion);
+        }
 
-        let reward_since_change = last_reward
-            .checked_mul(rewardable_time_u64)
-            .ok_or(StakeError::ProgramMulError)?;
+        let reward_since_change =
+            calc_scheduled_reward(last_reward, &self.reward_schedule, reward_time, cutoff_time)?;
+
+        let reward_since_change = self.reward_for_amount(reward_since_change, amount)?;
 
         msg!("REMOVING {}, {}", reward_since_change, self.current_balance);
 
@@ -1,19 +1,29 @@
+pub use claim_bitmap::*;
 pub use collection::*;
 pub use distribution::*;
 pub use emission::*;
 pub use nft_record::*;
+pub use points_item::*;
+pub use points_receipt::*;
+pub use pool_record::*;
 pub use program_config::*;
 pub use share_record::*;
 pub use stake_record::*;
 pub use staker::*;
 pub use theme::*;
+pub use vesting_record::*;
 
+pub mod claim_bitmap;
 pub mod collection;
 pub mod distribution;
 pub mod emission;
 pub mod nft_record;
+pub mod points_item;
+pub mod points_receipt;
+pub mod pool_record;
 pub mod program_config;
 pub mod share_record;
 pub mod stake_record;
 pub mod staker;
 pub mod theme;
+pub mod vesting_record;
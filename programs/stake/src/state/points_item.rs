@@ -0,0 +1,71 @@
+use anchor_lang::prelude::*;
+
+use crate::StakeError;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PointsItemKind {
+    /// a bundle of tokens paid from the vault
+    Token { mint: Pubkey, amount: u64 },
+    /// a whitelist spot, the receipt is the on-chain record
+    Whitelist,
+}
+
+#[account]
+pub struct PointsItem {
+    /// staker this item belongs to (32)
+    pub staker: Pubkey,
+    /// what redeeming the item pays out (1 + 32 + 8)
+    pub kind: PointsItemKind,
+    /// points spent to redeem the item (8)
+    pub price: u64,
+    /// redemptions left, unlimited if not set (1 + 8)
+    pub supply: Option<u64>,
+    /// number of redemptions so far, indexes the receipts (8)
+    pub redeemed: u64,
+    /// tokens held in the vault for this item (8)
+    pub current_balance: u64,
+}
+
+impl PointsItem {
+    pub const LEN: usize = 8 + 32 + (1 + 32 + 8) + 8 + (1 + 8) + 8 + 8;
+
+    pub fn init(staker: Pubkey, kind: PointsItemKind, price: u64, supply: Option<u64>) -> Self {
+        Self {
+            staker,
+            kind,
+            price,
+            supply,
+            redeemed: 0,
+            current_balance: 0,
+        }
+    }
+
+    /// Tokens paid out for each redemption
+    pub fn token_amount(&self) -> u64 {
+        match self.kind {
+            PointsItemKind::Token { amount, .. } => amount,
+            PointsItemKind::Whitelist => 0,
+        }
+    }
+
+    pub fn redeem(&mut self) -> Result<()> {
+        if let Some(supply) = self.supply {
+            require_gt!(supply, 0, StakeError::PointsItemSoldOut);
+            self.supply = Some(supply - 1);
+        }
+
+        let amount = self.token_amount();
+
+        self.current_balance = self
+            .current_balance
+            .checked_sub(amount)
+            .ok_or(StakeError::InsufficientBalanceInVault)?;
+
+        self.redeemed = self
+            .redeemed
+            .checked_add(1)
+            .ok_or(StakeError::ProgramAddError)?;
+
+        Ok(())
+    }
+}
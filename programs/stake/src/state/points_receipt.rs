@@ -0,0 +1,25 @@
+use anchor_lang::prelude::*;
+
+#[account]
+pub struct PointsReceipt {
+    /// staker this receipt belongs to (32)
+    pub staker: Pubkey,
+    /// item that was redeemed (32)
+    pub item: Pubkey,
+    /// wallet that redeemed the item (32)
+    pub owner: Pubkey,
+    /// NFT the points were spent from (32)
+    pub nft_mint: Pubkey,
+    /// points spent (8)
+    pub points: u64,
+    /// position of this redemption in the item's redemptions (8)
+    pub index: u64,
+    /// timestamp of the redemption (8)
+    pub redeemed_at: i64,
+    /// Bump of the Points Receipt PDA (1)
+    pub bump: u8,
+}
+
+impl PointsReceipt {
+    pub const LEN: usize = 8 + 32 + 32 + 32 + 32 + 8 + 8 + 8 + 1;
+}
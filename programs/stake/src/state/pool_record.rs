@@ -0,0 +1,142 @@
+use anchor_lang::prelude::*;
+
+use crate::{utils::calc_reward, StakeError, STAKING_ENDS};
+
+use super::Emission;
+
+#[account]
+pub struct PoolRecord {
+    /// staker that this record belongs to (32)
+    pub staker: Pubkey,
+    /// the token pool the deposit was made to (32)
+    pub collection: Pubkey,
+    /// owner of the deposit (32)
+    pub owner: Pubkey,
+    /// amount of the staked mint deposited (8)
+    pub amount: u64,
+    /// token emission the deposit accrues rewards from (1 + 32)
+    pub emission: Option<Pubkey>,
+    /// rewards that served the minimum period, yet to be claimed (8)
+    pub pending_claim: u64,
+    /// timestamp rewards are accrued from (8)
+    pub staked_at: i64,
+    /// Bump of the Pool Record PDA (1)
+    pub bump: u8,
+    /// rewards still within the minimum period of the last deposit, forfeited on an early withdrawal (8)
+    pub locked_claim: u64,
+}
+
+impl PoolRecord {
+    pub const LEN: usize = 8 + 32 + 32 + 32 + 8 + (1 + 32) + 8 + 8 + 1 + 8;
+
+    pub fn init(
+        staker: Pubkey,
+        collection: Pubkey,
+        owner: Pubkey,
+        emission: Option<Pubkey>,
+        staked_at: i64,
+        bump: u8,
+    ) -> Self {
+        Self {
+            staker,
+            collection,
+            owner,
+            amount: 0,
+            emission,
+            pending_claim: 0,
+            staked_at,
+            bump,
+            locked_claim: 0,
+        }
+    }
+
+    pub fn increase_amount(&mut self, amount: u64) -> Result<()> {
+        self.amount = self
+            .amount
+            .checked_add(amount)
+            .ok_or(StakeError::ProgramAddError)?;
+
+        Ok(())
+    }
+
+    pub fn decrease_amount(&mut self, amount: u64) -> Result<()> {
+        self.amount = self
+            .amount
+            .checked_sub(amount)
+            .ok_or(StakeError::ProgramSubError)?;
+
+        Ok(())
+    }
+
+    pub fn add_pending_claim(&mut self, reward: u64) -> Result<()> {
+        self.pending_claim = self
+            .pending_claim
+            .checked_add(reward)
+            .ok_or(StakeError::ProgramAddError)?;
+
+        Ok(())
+    }
+
+    /// Moves the rewards accrued since `staked_at` into the pending claim once the minimum
+    /// period has passed, or into the locked claim until it has, and restarts accrual, and
+    /// the deposit's weight, from the current time. Rewards that already served the minimum
+    /// period stay claimable when a new deposit restarts the clock
+    pub fn settle_rewards(
+        &mut self,
+        emission: &mut Emission,
+        current_time: i64,
+        token_vault: bool,
+    ) -> Result<()> {
+        let end_time = emission.end_time.unwrap_or(STAKING_ENDS);
+
+        if self.amount > 0 && self.staked_at < end_time {
+            let (reward, _, _) = calc_reward(
+                self.staked_at,
+                0,
+                &emission.reward,
+                &emission.reward_change_time,
+                emission.end_time,
+                &emission.reward_schedule,
+            )?;
+
+            let reward = emission.reward_for_amount(reward, self.amount)?;
+
+            if token_vault && reward > 0 {
+                emission.decrease_current_balance_for_amount(
+                    self.staked_at,
+                    current_time,
+                    self.amount,
+                )?;
+            }
+
+            if current_time < end_time {
+                emission.update_staked_weight_for_amount(self.staked_at, self.amount, false)?;
+                emission.update_staked_weight_for_amount(current_time, self.amount, true)?;
+            }
+
+            let reward_eligible_time = self
+                .staked_at
+                .checked_add(emission.minimum_period.unwrap_or(0))
+                .ok_or(StakeError::ProgramAddError)?;
+
+            if current_time >= reward_eligible_time {
+                // locked rewards were accrued before `staked_at`, so they are eligible too
+                let reward = reward
+                    .checked_add(self.locked_claim)
+                    .ok_or(StakeError::ProgramAddError)?;
+
+                self.locked_claim = 0;
+                self.add_pending_claim(reward)?;
+            } else {
+                self.locked_claim = self
+                    .locked_claim
+                    .checked_add(reward)
+                    .ok_or(StakeError::ProgramAddError)?;
+            }
+        }
+
+        self.staked_at = current_time;
+
+        Ok(())
+    }
+}
@@ -24,10 +24,12 @@ pub struct ProgramConfig {
     pub slugs: Vec<String>,
     /// bump for the program config account (1)
     pub bump: u8,
+    /// charge the claim fee per stake record when batch claiming (1)
+    pub claim_fee_per_record: bool,
 }
 
 impl ProgramConfig {
-    pub const LEN: usize = 8 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 4 + 1;
+    pub const LEN: usize = 8 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 4 + 1 + 1;
 
     pub fn init(
         stake_fee: u64,
@@ -53,6 +55,7 @@ impl ProgramConfig {
             own_domain_fee,
             slugs: vec![],
             bump,
+            claim_fee_per_record: false,
         }
     }
 
@@ -1,6 +1,6 @@
 use anchor_lang::prelude::*;
 
-use crate::StakeError;
+use crate::{StakeError, MAX_EXTRA_EMISSIONS, MULTIPLIER_BASE};
 
 #[account]
 pub struct StakeRecord {
@@ -8,9 +8,9 @@ pub struct StakeRecord {
     pub staker: Pubkey,
     /// owner of the NFT 32
     pub owner: Pubkey,
-    /// mint of the staked NFT (32)
+    /// mint of the staked NFT, or asset id for compressed NFTs (32)
     pub nft_mint: Pubkey,
-    /// emissions (4 + 32 * 4),
+    /// emissions (4 + 32 * (4 + MAX_EXTRA_EMISSIONS)),
     pub emissions: Vec<Pubkey>,
     /// pending token balance to claim (8)
     pub pending_claim: u64,
@@ -22,10 +22,40 @@ pub struct StakeRecord {
     pub staked_at: i64,
     /// Bump of the Stake Record PDA (1)
     pub bump: u8,
+    /// reward multiplier in basis points, for emissions with multipliers (8)
+    pub multiplier: u64,
+    /// reward boost in basis points of the chosen lock tier (8)
+    pub lock_boost: u64,
+    /// timestamp the NFT is locked until (8)
+    pub locked_until: i64,
+    /// timestamp the unstake was requested at, if unbonding (1 + 8)
+    pub unbonding_at: Option<i64>,
+    /// the distribution emission's reward per item when last settled (16)
+    pub distribution_debt: u128,
+    /// the shared token emission's reward per item when last settled (16)
+    pub reward_debt: u128,
 }
 
 impl StakeRecord {
-    pub const LEN: usize = 8 + 32 + 32 + (4 + 32 * 4) + 32 + 32 + 8 + 8 + 8 + 32 + 8 + 8 + 1;
+    pub const LEN: usize = 8
+        + 32
+        + 32
+        + (4 + 32 * (4 + MAX_EXTRA_EMISSIONS))
+        + 32
+        + 32
+        + 8
+        + 8
+        + 8
+        + 32
+        + 8
+        + 8
+        + 1
+        + 8
+        + 8
+        + 8
+        + (1 + 8)
+        + 16
+        + 16;
 
     pub fn init(
         staker: Pubkey,
@@ -47,6 +77,12 @@ impl StakeRecord {
             can_claim_at,
             sol_balance: 0,
             bump,
+            multiplier: MULTIPLIER_BASE,
+            lock_boost: MULTIPLIER_BASE,
+            locked_until: 0,
+            unbonding_at: None,
+            distribution_debt: 0,
+            reward_debt: 0,
         }
     }
 
@@ -58,4 +94,24 @@ impl StakeRecord {
 
         Ok(())
     }
+
+    pub fn is_unbonding(&self) -> bool {
+        self.unbonding_at.is_some()
+    }
+
+    /// Collections with a cooldown release NFTs only once their unbonding period has passed
+    pub fn check_unbonded(&self, unbonding_period: i64, current_time: i64) -> Result<()> {
+        match self.unbonding_at {
+            Some(unbonding_at) => {
+                let unbonded_at = unbonding_at
+                    .checked_add(unbonding_period)
+                    .ok_or(StakeError::ProgramAddError)?;
+
+                require_gte!(current_time, unbonded_at, StakeError::UnbondingNotComplete);
+            }
+            None => require_eq!(unbonding_period, 0, StakeError::UnbondingRequired),
+        }
+
+        Ok(())
+    }
 }
@@ -0,0 +1,113 @@
+use anchor_lang::prelude::*;
+
+use crate::StakeError;
+
+use super::Vesting;
+
+#[account]
+pub struct VestingRecord {
+    /// staker that this record belongs to (32)
+    pub staker: Pubkey,
+    /// owner of the vesting rewards (32)
+    pub owner: Pubkey,
+    /// rewards vesting in the current schedule (8)
+    pub total: u64,
+    /// rewards of the current schedule already withdrawn (8)
+    pub released: u64,
+    /// rewards unlocked by previous schedules, yet to be withdrawn (8)
+    pub unlocked: u64,
+    /// timestamp the current schedule started at (8)
+    pub start_time: i64,
+    /// seconds before anything unlocks (8)
+    pub cliff: i64,
+    /// seconds for the whole schedule to unlock (8)
+    pub duration: i64,
+    /// Bump of the Vesting Record PDA (1)
+    pub bump: u8,
+}
+
+impl VestingRecord {
+    pub const LEN: usize = 8 + 32 + 32 + 8 + 8 + 8 + 8 + 8 + 8 + 1;
+
+    pub fn init(staker: Pubkey, owner: Pubkey, bump: u8) -> Self {
+        Self {
+            staker,
+            owner,
+            total: 0,
+            released: 0,
+            unlocked: 0,
+            start_time: 0,
+            cliff: 0,
+            duration: 0,
+            bump,
+        }
+    }
+
+    /// Rewards of the current schedule unlocked by `current_time`
+    pub fn vested_amount(&self, current_time: i64) -> Result<u64> {
+        let elapsed = current_time
+            .checked_sub(self.start_time)
+            .ok_or(StakeError::ProgramSubError)?;
+
+        if elapsed < self.cliff {
+            return Ok(0);
+        }
+
+        if elapsed >= self.duration {
+            return Ok(self.total);
+        }
+
+        let vested = (self.total as u128)
+            .checked_mul(elapsed as u128)
+            .ok_or(StakeError::ProgramMulError)?
+            .checked_div(self.duration as u128)
+            .ok_or(StakeError::ProgramDivError)?;
+
+        // less than total since elapsed < duration
+        Ok(vested as u64)
+    }
+
+    /// Adds claimed rewards, restarting the schedule with the rewards that are still locked.
+    /// The schedule is copied so later changes to the emission don't affect vesting rewards.
+    pub fn deposit(&mut self, amount: u64, vesting: Vesting, current_time: i64) -> Result<()> {
+        let vested = self.vested_amount(current_time)?;
+
+        self.unlocked = self
+            .unlocked
+            .checked_add(vested)
+            .ok_or(StakeError::ProgramAddError)?
+            .checked_sub(self.released)
+            .ok_or(StakeError::ProgramSubError)?;
+
+        self.total = self
+            .total
+            .checked_sub(vested)
+            .ok_or(StakeError::ProgramSubError)?
+            .checked_add(amount)
+            .ok_or(StakeError::ProgramAddError)?;
+
+        self.released = 0;
+        self.start_time = current_time;
+        self.cliff = vesting.cliff;
+        self.duration = vesting.duration;
+
+        Ok(())
+    }
+
+    /// Releases everything unlocked so far, returning the amount to withdraw
+    pub fn withdraw(&mut self, current_time: i64) -> Result<u64> {
+        let vested = self.vested_amount(current_time)?;
+
+        let amount = self
+            .unlocked
+            .checked_add(vested)
+            .ok_or(StakeError::ProgramAddError)?
+            .checked_sub(self.released)
+            .ok_or(StakeError::ProgramSubError)?;
+
+        self.unlocked = 0;
+        self.released = vested;
+
+        Ok(amount)
+    }
+}
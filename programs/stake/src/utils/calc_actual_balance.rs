@@ -1,6 +1,6 @@
 use anchor_lang::prelude::*;
 
-use crate::{StakeError, STAKING_ENDS, WEIGHT};
+use crate::{state::RewardStep, utils::calc_scheduled_reward, StakeError, STAKING_ENDS, WEIGHT};
 
 pub fn calc_actual_balance(
     current_stakers_count: u64,
@@ -11,8 +11,25 @@ pub fn calc_actual_balance(
     current_time: i64,
     current_balance: u64,
     new_end_time: Option<i64>,
+    stake_unit: u64,
+    reward_schedule: &[RewardStep],
 ) -> Result<(u64, u64, u128)> {
     let staking_ends_at = staking_ends_at.unwrap_or(STAKING_ENDS);
+
+    // staked weight is measured in time, or in rewards earned when the reward is scheduled to change
+    let span = |from: i64, to: i64| -> Result<u64> {
+        if !reward_schedule.is_empty() {
+            return calc_scheduled_reward(last_reward_rate, reward_schedule, from, to);
+        }
+
+        let time = to.checked_sub(from).ok_or(StakeError::ProgramSubError)?;
+
+        match u64::try_from(time) {
+            Ok(time) => Ok(time),
+            _ => err!(StakeError::FailedTimeConversion),
+        }
+    };
+
     // if no current stakers, return the full balance
     if current_stakers_count == 0 {
         return Ok((current_balance, 0, 0));
@@ -29,16 +46,7 @@ pub fn calc_actual_balance(
     msg!("FIRST SUB");
 
     // total time since last reward change to stake end
-    let total_time = staking_ends_at
-        .checked_sub(last_reward_time)
-        .ok_or(StakeError::ProgramSubError)?;
-
-    let total_time_u128 = match u128::try_from(total_time) {
-        Ok(time) => time,
-        _ => {
-            return err!(StakeError::FailedTimeConversion);
-        }
-    };
+    let total_time_u128 = span(last_reward_time, staking_ends_at)? as u128;
 
     // time between average staking time and stake end;
     let stake_to_end_time_weighted = total_time_u128
@@ -62,16 +70,7 @@ pub fn calc_actual_balance(
     let rewardable_time = if staking_ends_at > current_time {
         // if the current time is less than the stake end time
         // subtract the unaccrued time from the stake to end time
-        let unaccrued_time = staking_ends_at
-            .checked_sub(current_time)
-            .ok_or(StakeError::ProgramSubError)?;
-
-        let unaccrued_time_u64 = match u64::try_from(unaccrued_time) {
-            Ok(time) => time,
-            _ => {
-                return err!(StakeError::FailedTimeConversion);
-            }
-        };
+        let unaccrued_time_u64 = span(current_time, staking_ends_at)?;
 
         msg!("THIRD SUB");
 
@@ -82,71 +81,51 @@ pub fn calc_actual_balance(
         msg!("FOURTH SUB");
         // if the current time is greater or equal to the stake end time
         // add seconds since the stake end time to the rewardable time
-        let accrued_time = current_time
-            .checked_sub(staking_ends_at)
-            .ok_or(StakeError::ProgramSubError)?;
+        let accrued_time_u64 = span(staking_ends_at, current_time)?;
 
         msg!("FOURTH SUB SUCCESS");
 
-        let accrued_time_u64 = match u64::try_from(accrued_time) {
-            Ok(time) => time,
-            _ => {
-                return err!(StakeError::FailedTimeConversion);
-            }
-        };
-
         stake_to_end_time
             .checked_add(accrued_time_u64)
             .ok_or(StakeError::ProgramAddError)?
     };
 
     // the rewards yet to be paid (per staker)
-    let accrued_reward = last_reward_rate
-        .checked_mul(rewardable_time)
-        .ok_or(StakeError::ProgramMulError)?;
+    let accrued_reward = if reward_schedule.is_empty() {
+        last_reward_rate
+            .checked_mul(rewardable_time)
+            .ok_or(StakeError::ProgramMulError)?
+    } else {
+        rewardable_time
+    };
 
-    // the rewards yet to be paid (all stakers)
-    let accrued_reward = accrued_reward
-        .checked_mul(current_stakers_count)
-        .ok_or(StakeError::ProgramMulError)?;
+    // the rewards yet to be paid (all stakers), token pools count stakers in base units
+    let accrued_reward = (accrued_reward as u128)
+        .checked_mul(current_stakers_count as u128)
+        .ok_or(StakeError::ProgramMulError)?
+        .checked_div(stake_unit.max(1) as u128)
+        .ok_or(StakeError::ProgramDivError)?;
+
+    let accrued_reward = match u64::try_from(accrued_reward) {
+        Ok(reward) => reward,
+        _ => {
+            return err!(StakeError::ProgramMulError);
+        }
+    };
 
     // the calculation of the new staked weight
     let new_staked_weight = match new_end_time {
         Some(new_time) => {
             msg!("STAKE TO OLD END {}", stake_to_end_time);
-            let stake_to_old_end = match i64::try_from(stake_to_end_time) {
-                Ok(time) => time,
-                _ => {
-                    return err!(StakeError::FailedTimeConversion);
-                }
-            };
-
-            let time_added = new_time
-                .checked_sub(staking_ends_at)
-                .ok_or(StakeError::ProgramSubError)?;
+            let time_added = span(staking_ends_at, new_time)?;
 
             // add extended time to stake period
-            let stake_to_new_end = stake_to_old_end
+            let stake_to_new_end = stake_to_end_time
                 .checked_add(time_added)
                 .ok_or(StakeError::ProgramAddError)?;
 
-            let new_base = new_time
-                .checked_sub(last_reward_time)
-                .ok_or(StakeError::ProgramSubError)?;
-
-            let stake_to_new_end_u128 = match u128::try_from(stake_to_new_end) {
-                Ok(time) => time,
-                _ => {
-                    return err!(StakeError::FailedTimeConversion);
-                }
-            };
-
-            let new_base_u128 = match u128::try_from(new_base) {
-                Ok(time) => time,
-                _ => {
-                    return err!(StakeError::FailedTimeConversion);
-                }
-            };
+            let stake_to_new_end_u128 = stake_to_new_end as u128;
+            let new_base_u128 = span(last_reward_time, new_time)? as u128;
 
             let new_num = stake_to_new_end_u128
                 .checked_mul(WEIGHT)
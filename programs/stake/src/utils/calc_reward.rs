@@ -1,6 +1,6 @@
 use anchor_lang::prelude::*;
 
-use crate::{StakeError, STAKING_ENDS};
+use crate::{state::RewardStep, utils::calc_scheduled_reward, StakeError, STAKING_ENDS};
 
 pub fn calc_reward(
     staked_at: i64,
@@ -8,6 +8,7 @@ pub fn calc_reward(
     reward_emission: &Vec<u64>,
     reward_change_time: &Vec<i64>,
     staking_ends_at: Option<i64>,
+    reward_schedule: &[RewardStep],
 ) -> Result<(u64, i64, bool)> {
     let clock = Clock::get().unwrap();
     let current_time = clock.unix_timestamp;
@@ -62,9 +63,14 @@ pub fn calc_reward(
             }
         };
 
-        let reward = rewardable_time
-            .checked_mul(reward_emission[ix])
-            .ok_or(StakeError::ProgramMulError)?;
+        // scheduled changes only follow the current reward
+        let reward = if ix == total_changes {
+            calc_scheduled_reward(reward_emission[ix], reward_schedule, sml_num, big_num)?
+        } else {
+            rewardable_time
+                .checked_mul(reward_emission[ix])
+                .ok_or(StakeError::ProgramMulError)?
+        };
 
         reward_tokens = reward_tokens
             .checked_add(reward)
@@ -0,0 +1,55 @@
+use anchor_lang::prelude::*;
+
+use crate::{state::RewardStep, StakeError};
+
+/// Rewards one item earns between `from` and `to`, paying `reward` per second until the
+/// scheduled steps take over. Steps at or before `from` are already in effect
+pub fn calc_scheduled_reward(
+    reward: u64,
+    reward_schedule: &[RewardStep],
+    from: i64,
+    to: i64,
+) -> Result<u64> {
+    let mut rate = reward;
+    let mut time = from;
+    let mut total: u64 = 0;
+
+    for step in reward_schedule {
+        if step.time >= to {
+            break;
+        }
+
+        if step.time > time {
+            let elapsed = match u64::try_from(step.time - time) {
+                Ok(time) => time,
+                _ => {
+                    return err!(StakeError::FailedTimeConversion);
+                }
+            };
+
+            total = elapsed
+                .checked_mul(rate)
+                .and_then(|reward| total.checked_add(reward))
+                .ok_or(StakeError::ProgramMulError)?;
+            time = step.time;
+        }
+
+        rate = step.reward;
+    }
+
+    if to > time {
+        let elapsed = match u64::try_from(to - time) {
+            Ok(time) => time,
+            _ => {
+                return err!(StakeError::FailedTimeConversion);
+            }
+        };
+
+        total = elapsed
+            .checked_mul(rate)
+            .and_then(|reward| total.checked_add(reward))
+            .ok_or(StakeError::ProgramMulError)?;
+    }
+
+    Ok(total)
+}
@@ -1,29 +1,43 @@
-use crate::StakeError;
+use crate::{state::RewardStep, utils::calc_scheduled_reward, StakeError, MULTIPLIER_BASE};
 use anchor_lang::prelude::*;
 
 pub fn calc_total_emission(
     reward: u64,
+    reward_schedule: &[RewardStep],
     max_stakers_count: u64,
     staking_starts_at: i64,
     staking_ends_at: i64,
+    max_multiplier: u64,
 ) -> Result<u64> {
     let total_staking_period = staking_ends_at
         .checked_sub(staking_starts_at)
         .ok_or(StakeError::ProgramSubError)?;
 
-    let rewardable_time_u64 = match u64::try_from(total_staking_period) {
-        Ok(time) => time,
-        _ => {
-            return err!(StakeError::FailedTimeConversion);
-        }
-    };
+    if u64::try_from(total_staking_period).is_err() {
+        return err!(StakeError::FailedTimeConversion);
+    }
 
-    let total_rewardable_time = rewardable_time_u64
+    // the rewards a single staker earns over the whole period
+    let staker_emission =
+        calc_scheduled_reward(reward, reward_schedule, staking_starts_at, staking_ends_at)?;
+
+    let total_emission = staker_emission
         .checked_mul(max_stakers_count)
         .ok_or(StakeError::ProgramMulError)?;
-    let total_emission = total_rewardable_time
-        .checked_mul(reward)
-        .ok_or(StakeError::ProgramMulError)?;
+
+    // every staker could hold the highest multiplier
+    let total_emission = (total_emission as u128)
+        .checked_mul(max_multiplier.into())
+        .ok_or(StakeError::ProgramMulError)?
+        .checked_div(MULTIPLIER_BASE.into())
+        .ok_or(StakeError::ProgramDivError)?;
+
+    let total_emission = match u64::try_from(total_emission) {
+        Ok(total) => total,
+        _ => {
+            return err!(StakeError::ProgramMulError);
+        }
+    };
 
     Ok(total_emission)
 }
@@ -0,0 +1,71 @@
+use anchor_lang::{
+    prelude::*,
+    system_program::{
+        allocate, assign, create_account, transfer, Allocate, Assign, CreateAccount, Transfer,
+    },
+};
+
+pub fn create_pda_account<'info>(
+    payer: &AccountInfo<'info>,
+    account: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    space: usize,
+    signer_seeds: &[&[u8]],
+) -> Result<()> {
+    let rent = Rent::get()?.minimum_balance(space);
+    let current_lamports = account.lamports();
+
+    if current_lamports == 0 {
+        create_account(
+            CpiContext::new(
+                system_program.clone(),
+                CreateAccount {
+                    from: payer.clone(),
+                    to: account.clone(),
+                },
+            )
+            .with_signer(&[signer_seeds]),
+            rent,
+            space as u64,
+            &crate::ID,
+        )?;
+
+        return Ok(());
+    }
+
+    // the account may already hold lamports, in which case only top up to rent exemption
+    if rent > current_lamports {
+        transfer(
+            CpiContext::new(
+                system_program.clone(),
+                Transfer {
+                    from: payer.clone(),
+                    to: account.clone(),
+                },
+            ),
+            rent - current_lamports,
+        )?;
+    }
+
+    allocate(
+        CpiContext::new(
+            system_program.clone(),
+            Allocate {
+                account_to_allocate: account.clone(),
+            },
+        )
+        .with_signer(&[signer_seeds]),
+        space as u64,
+    )?;
+
+    assign(
+        CpiContext::new(
+            system_program.clone(),
+            Assign {
+                account_to_assign: account.clone(),
+            },
+        )
+        .with_signer(&[signer_seeds]),
+        &crate::ID,
+    )
+}
@@ -1,11 +1,25 @@
 pub use calc_actual_balance::*;
 pub use calc_pro_rata_fee::*;
 pub use calc_reward::*;
+pub use calc_scheduled_reward::*;
 pub use calc_total_emission::*;
 pub use calc_tx_fee::*;
+pub use create_pda_account::*;
+pub use mpl_core::*;
+pub use reward_accounts::*;
+pub use settle_distribution::*;
+pub use settle_emissions::*;
+pub use verify_merkle_proof::*;
 
 pub mod calc_actual_balance;
 pub mod calc_pro_rata_fee;
 pub mod calc_reward;
+pub mod calc_scheduled_reward;
 pub mod calc_total_emission;
 pub mod calc_tx_fee;
+pub mod create_pda_account;
+pub mod mpl_core;
+pub mod reward_accounts;
+pub mod settle_distribution;
+pub mod settle_emissions;
+pub mod verify_merkle_proof;
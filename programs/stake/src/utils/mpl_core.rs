@@ -0,0 +1,207 @@
+use anchor_lang::{
+    prelude::*,
+    solana_program::{
+        instruction::{AccountMeta, Instruction},
+        program::invoke_signed,
+        pubkey,
+    },
+};
+
+use crate::StakeError;
+
+/// There is no Core crate compatible with this program's dependencies,
+/// so the handful of instructions used for staking are built by hand.
+pub const MPL_CORE_ID: Pubkey = pubkey!("CoREENxT6tW1HoK8ypY1SxRMZTcVPm7R94rH4PZNhX7d");
+
+const ASSET_V1_KEY: u8 = 1;
+const PLUGIN_HEADER_V1_KEY: u8 = 3;
+const FREEZE_DELEGATE_PLUGIN: u8 = 1;
+
+const ADD_PLUGIN_V1: u8 = 2;
+const REMOVE_PLUGIN_V1: u8 = 4;
+const UPDATE_PLUGIN_V1: u8 = 6;
+const APPROVE_PLUGIN_AUTHORITY_V1: u8 = 8;
+const REVOKE_PLUGIN_AUTHORITY_V1: u8 = 10;
+const TRANSFER_V1: u8 = 14;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub enum CoreUpdateAuthority {
+    None,
+    Address(Pubkey),
+    Collection(Pubkey),
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub enum CorePluginAuthority {
+    None,
+    Owner,
+    UpdateAuthority,
+    Address { address: Pubkey },
+}
+
+#[derive(AnchorDeserialize)]
+struct PluginHeader {
+    key: u8,
+    plugin_registry_offset: u64,
+}
+
+#[derive(AnchorDeserialize)]
+struct RegistryRecord {
+    plugin_type: u8,
+    authority: CorePluginAuthority,
+    _offset: u64,
+}
+
+#[derive(AnchorDeserialize)]
+struct PluginRegistry {
+    _key: u8,
+    registry: Vec<RegistryRecord>,
+}
+
+/// The base of a Core asset account
+#[derive(AnchorDeserialize)]
+pub struct CoreAsset {
+    pub key: u8,
+    pub owner: Pubkey,
+    pub update_authority: CoreUpdateAuthority,
+    pub name: String,
+    pub uri: String,
+    pub seq: Option<u64>,
+    /// authority of the freeze delegate plugin, if the asset has one
+    #[borsh_skip]
+    pub freeze_delegate: Option<CorePluginAuthority>,
+}
+
+impl CoreAsset {
+    pub fn load(account: &AccountInfo) -> Result<Self> {
+        require_keys_eq!(*account.owner, MPL_CORE_ID, StakeError::InvalidCoreAsset);
+
+        let data = account.try_borrow_data()?;
+        let mut remaining: &[u8] = &data;
+
+        let mut asset =
+            CoreAsset::deserialize(&mut remaining).map_err(|_| StakeError::InvalidCoreAsset)?;
+
+        require_eq!(asset.key, ASSET_V1_KEY, StakeError::InvalidCoreAsset);
+
+        // plugins are stored after the base asset, with a registry at the end of the account
+        if let Ok(header) = PluginHeader::deserialize(&mut remaining) {
+            require_eq!(
+                header.key,
+                PLUGIN_HEADER_V1_KEY,
+                StakeError::InvalidCoreAsset
+            );
+
+            let mut registry_data = data
+                .get(header.plugin_registry_offset as usize..)
+                .ok_or(StakeError::InvalidCoreAsset)?;
+
+            let registry = PluginRegistry::deserialize(&mut registry_data)
+                .map_err(|_| StakeError::InvalidCoreAsset)?;
+
+            asset.freeze_delegate = registry
+                .registry
+                .into_iter()
+                .find(|record| record.plugin_type == FREEZE_DELEGATE_PLUGIN)
+                .map(|record| record.authority);
+        }
+
+        Ok(asset)
+    }
+}
+
+/// The accounts shared by the Core plugin and transfer instructions
+pub struct CoreAccounts<'a, 'info> {
+    pub core_program: &'a AccountInfo<'info>,
+    pub asset: &'a AccountInfo<'info>,
+    pub collection: &'a AccountInfo<'info>,
+    pub payer: &'a AccountInfo<'info>,
+    pub authority: &'a AccountInfo<'info>,
+    pub system_program: &'a AccountInfo<'info>,
+}
+
+impl<'a, 'info> CoreAccounts<'a, 'info> {
+    fn invoke(
+        &self,
+        data: Vec<u8>,
+        new_owner: Option<&AccountInfo<'info>>,
+        signer_seeds: &[&[&[u8]]],
+    ) -> Result<()> {
+        let mut accounts = vec![
+            AccountMeta::new(self.asset.key(), false),
+            AccountMeta::new(self.collection.key(), false),
+            AccountMeta::new(self.payer.key(), true),
+            AccountMeta::new_readonly(self.authority.key(), true),
+        ];
+        let mut account_infos = vec![
+            self.asset.clone(),
+            self.collection.clone(),
+            self.payer.clone(),
+            self.authority.clone(),
+        ];
+
+        if let Some(new_owner) = new_owner {
+            accounts.push(AccountMeta::new_readonly(new_owner.key(), false));
+            account_infos.push(new_owner.clone());
+        }
+
+        // the log wrapper is not used, optional accounts are passed as the Core program id
+        accounts.push(AccountMeta::new_readonly(self.system_program.key(), false));
+        accounts.push(AccountMeta::new_readonly(MPL_CORE_ID, false));
+        account_infos.push(self.system_program.clone());
+        account_infos.push(self.core_program.clone());
+
+        let ix = Instruction {
+            program_id: MPL_CORE_ID,
+            accounts,
+            data,
+        };
+
+        invoke_signed(&ix, &account_infos, signer_seeds)?;
+        Ok(())
+    }
+
+    /// Adds a frozen freeze delegate plugin with the delegate as its authority
+    pub fn add_freeze_delegate(&self, delegate: Pubkey, signer_seeds: &[&[&[u8]]]) -> Result<()> {
+        let mut data = vec![ADD_PLUGIN_V1, FREEZE_DELEGATE_PLUGIN, 1];
+        Some(CorePluginAuthority::Address { address: delegate }).serialize(&mut data)?;
+        self.invoke(data, None, signer_seeds)
+    }
+
+    /// Sets the delegate as the authority of an existing freeze delegate plugin
+    pub fn approve_freeze_delegate(
+        &self,
+        delegate: Pubkey,
+        signer_seeds: &[&[&[u8]]],
+    ) -> Result<()> {
+        let mut data = vec![APPROVE_PLUGIN_AUTHORITY_V1, FREEZE_DELEGATE_PLUGIN];
+        CorePluginAuthority::Address { address: delegate }.serialize(&mut data)?;
+        self.invoke(data, None, signer_seeds)
+    }
+
+    pub fn set_frozen(&self, frozen: bool, signer_seeds: &[&[&[u8]]]) -> Result<()> {
+        let data = vec![UPDATE_PLUGIN_V1, FREEZE_DELEGATE_PLUGIN, frozen as u8];
+        self.invoke(data, None, signer_seeds)
+    }
+
+    pub fn remove_freeze_delegate(&self, signer_seeds: &[&[&[u8]]]) -> Result<()> {
+        let data = vec![REMOVE_PLUGIN_V1, FREEZE_DELEGATE_PLUGIN];
+        self.invoke(data, None, signer_seeds)
+    }
+
+    /// Returns the freeze delegate plugin authority to the owner
+    pub fn revoke_freeze_delegate(&self, signer_seeds: &[&[&[u8]]]) -> Result<()> {
+        let data = vec![REVOKE_PLUGIN_AUTHORITY_V1, FREEZE_DELEGATE_PLUGIN];
+        self.invoke(data, None, signer_seeds)
+    }
+
+    pub fn transfer(
+        &self,
+        new_owner: &AccountInfo<'info>,
+        signer_seeds: &[&[&[u8]]],
+    ) -> Result<()> {
+        // no compression proof
+        let data = vec![TRANSFER_V1, 0];
+        self.invoke(data, Some(new_owner), signer_seeds)
+    }
+}
@@ -0,0 +1,131 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::get_associated_token_address_with_program_id,
+    token_interface::{mint_to, transfer_checked, Mint, MintTo, TransferChecked},
+};
+
+use crate::StakeError;
+
+/// Accounts paying an emission's rewards. Emissions paying in the instruction's reward
+/// mint use its named accounts, others are passed in remaining accounts and found by
+/// address.
+pub struct RewardAccounts<'info> {
+    pub mint: AccountInfo<'info>,
+    pub decimals: u8,
+    /// the token authority's vault, for emissions paid from the vault
+    pub vault: Option<AccountInfo<'info>>,
+    /// the owner's associated token account
+    pub receiver: AccountInfo<'info>,
+}
+
+pub fn find_remaining_account<'info>(
+    remaining_accounts: &[AccountInfo<'info>],
+    key: &Pubkey,
+) -> Result<AccountInfo<'info>> {
+    remaining_accounts
+        .iter()
+        .find(|acc| acc.key == key)
+        .cloned()
+        .ok_or(error!(StakeError::RewardAccountsMissing))
+}
+
+impl<'info> RewardAccounts<'info> {
+    pub fn named(
+        mint: &InterfaceAccount<'info, Mint>,
+        vault: Option<AccountInfo<'info>>,
+        receiver: Option<AccountInfo<'info>>,
+        token_vault: bool,
+    ) -> Result<Self> {
+        let vault = if token_vault {
+            Some(vault.ok_or(StakeError::RewardAccountsMissing)?)
+        } else {
+            None
+        };
+
+        Ok(Self {
+            mint: mint.to_account_info(),
+            decimals: mint.decimals,
+            vault,
+            receiver: receiver.ok_or(StakeError::RewardAccountsMissing)?,
+        })
+    }
+
+    pub fn find(
+        remaining_accounts: &[AccountInfo<'info>],
+        mint: &Pubkey,
+        owner: &Pubkey,
+        token_authority: &Pubkey,
+        token_program: &Pubkey,
+        token_vault: bool,
+    ) -> Result<Self> {
+        let mint = find_remaining_account(remaining_accounts, mint)?;
+
+        require_keys_eq!(*mint.owner, *token_program, StakeError::InvalidRewardToken);
+
+        let decimals = Mint::try_deserialize(&mut &mint.try_borrow_data()?[..])?.decimals;
+
+        let vault = if token_vault {
+            Some(find_remaining_account(
+                remaining_accounts,
+                &get_associated_token_address_with_program_id(
+                    token_authority,
+                    mint.key,
+                    token_program,
+                ),
+            )?)
+        } else {
+            None
+        };
+
+        let receiver = find_remaining_account(
+            remaining_accounts,
+            &get_associated_token_address_with_program_id(owner, mint.key, token_program),
+        )?;
+
+        Ok(Self {
+            mint,
+            decimals,
+            vault,
+            receiver,
+        })
+    }
+
+    /// Transfers `amount` from the vault, or mints it, into `to`
+    pub fn pay(
+        &self,
+        to: AccountInfo<'info>,
+        token_authority: AccountInfo<'info>,
+        token_program: AccountInfo<'info>,
+        amount: u64,
+        signer: &[&[&[u8]]],
+    ) -> Result<()> {
+        match &self.vault {
+            Some(vault) => {
+                let cpi_accounts = TransferChecked {
+                    from: vault.clone(),
+                    mint: self.mint.clone(),
+                    to,
+                    authority: token_authority,
+                };
+
+                transfer_checked(
+                    CpiContext::new_with_signer(token_program, cpi_accounts, signer),
+                    amount,
+                    self.decimals,
+                )
+            }
+            None => {
+                let cpi_accounts = MintTo {
+                    mint: self.mint.clone(),
+                    to,
+                    authority: token_authority,
+                };
+
+                mint_to(
+                    CpiContext::new_with_signer(token_program, cpi_accounts, signer),
+                    amount,
+                )
+            }
+        }
+    }
+}
@@ -0,0 +1,112 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::get_associated_token_address_with_program_id,
+    token_interface::{transfer_checked, Mint, TransferChecked},
+};
+
+use crate::{
+    state::{Distribution, Emission},
+    utils::find_remaining_account,
+    StakeError,
+};
+
+/// Pays the share of the pro-rata distribution linked to `emission` an item is still owed as it
+/// leaves the emission. The distribution is passed in remaining accounts, along with its mint,
+/// vault authority, vault and the owner's token account when it pays out a token. Shares of a
+/// distribution that has ended are left for `reclaim_distribution` to sweep
+pub fn settle_distribution<'info>(
+    emission: &Emission,
+    distribution_debt: u128,
+    staker: &Pubkey,
+    owner: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+    remaining_accounts: &'info [AccountInfo<'info>],
+    current_time: i64,
+) -> Result<()> {
+    let Some(distribution_key) = emission.distribution else {
+        return Ok(());
+    };
+
+    let owed = emission.distribution_owed(distribution_debt)?;
+
+    if owed == 0 {
+        return Ok(());
+    }
+
+    let account = remaining_accounts
+        .iter()
+        .find(|acc| acc.key == &distribution_key)
+        .ok_or(StakeError::DistributionAccountsRequired)?;
+    let mut distribution = Account::<'info, Distribution>::try_from(account)?;
+
+    if distribution.has_ended(current_time) {
+        return Ok(());
+    }
+
+    match distribution.token_mint {
+        None => {
+            distribution.sub_lamports(owed)?;
+            owner.add_lamports(owed)?;
+        }
+        Some(token_mint) => {
+            let mint = remaining_accounts
+                .iter()
+                .find(|acc| acc.key == &token_mint)
+                .ok_or(StakeError::RewardAccountsMissing)?;
+            require_keys_eq!(
+                *mint.owner,
+                token_program.key(),
+                StakeError::InvalidRewardToken
+            );
+            let decimals = InterfaceAccount::<Mint>::try_from(mint)?.decimals;
+
+            let authority_seed = &[
+                &b"STAKE"[..],
+                staker.as_ref(),
+                distribution_key.as_ref(),
+                &b"distribution-vault"[..],
+                &[distribution.vault_authority_bump],
+            ];
+
+            let vault_authority_key = Pubkey::create_program_address(authority_seed, &crate::ID)
+                .map_err(|_| StakeError::DistributionAccountsRequired)?;
+            let vault_authority = find_remaining_account(remaining_accounts, &vault_authority_key)?;
+            let distribution_vault = find_remaining_account(
+                remaining_accounts,
+                &get_associated_token_address_with_program_id(
+                    &vault_authority_key,
+                    &token_mint,
+                    token_program.key,
+                ),
+            )?;
+            let reward_receive_account = find_remaining_account(
+                remaining_accounts,
+                &get_associated_token_address_with_program_id(
+                    owner.key,
+                    &token_mint,
+                    token_program.key,
+                ),
+            )?;
+
+            let cpi_accounts = TransferChecked {
+                from: distribution_vault,
+                mint: mint.clone(),
+                to: reward_receive_account,
+                authority: vault_authority,
+            };
+
+            transfer_checked(
+                CpiContext::new_with_signer(
+                    token_program.clone(),
+                    cpi_accounts,
+                    &[&authority_seed[..]],
+                ),
+                owed,
+                decimals,
+            )?;
+        }
+    }
+
+    distribution.add_to_claimed(owed);
+    distribution.exit(&crate::ID)
+}
@@ -0,0 +1,387 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{mint_to, transfer_checked, Mint, MintTo, TransferChecked};
+
+use crate::{
+    state::{Collection, Emission, NftRecord, StakeRecord, Staker, VestingRecord},
+    utils::{calc_reward, find_remaining_account, settle_distribution, RewardAccounts},
+    StakeError, STAKING_ENDS,
+};
+
+/// Accounts settling a stake record's emissions as it leaves them, shared by the unstake
+/// instructions and `request_unstake`
+pub struct SettleAccounts<'a, 'info> {
+    pub staker: &'a Account<'info, Staker>,
+    pub collection: &'a Account<'info, Collection>,
+    pub stake_record: &'a Account<'info, StakeRecord>,
+    pub nft_record: Option<&'a mut Account<'info, NftRecord>>,
+    pub reward_mint: Option<&'a InterfaceAccount<'info, Mint>>,
+    pub stake_token_vault: Option<AccountInfo<'info>>,
+    pub reward_receive_account: Option<AccountInfo<'info>>,
+    pub penalty_treasury: Option<AccountInfo<'info>>,
+    pub vesting_record: Option<&'a mut Account<'info, VestingRecord>>,
+    pub vesting_record_bump: u8,
+    pub vesting_vault: Option<AccountInfo<'info>>,
+    pub token_authority: Option<AccountInfo<'info>>,
+    pub owner: AccountInfo<'info>,
+    pub token_program: AccountInfo<'info>,
+}
+
+impl<'a, 'info> SettleAccounts<'a, 'info> {
+    fn token_authority(&self) -> Result<AccountInfo<'info>> {
+        self.token_authority
+            .clone()
+            .ok_or(error!(StakeError::RewardAccountsMissing))
+    }
+
+    /// Moves the emission's rewards into the owner's vesting vault, released by `withdraw_vested`
+    pub fn vest_rewards(
+        &self,
+        emission: &Emission,
+        amount: u64,
+        signer: &[&[&[u8]]],
+    ) -> Result<()> {
+        let vesting_vault = self
+            .vesting_vault
+            .clone()
+            .ok_or(StakeError::VestingAccountsRequired)?;
+        let mint = self
+            .reward_mint
+            .ok_or(StakeError::VestingAccountsRequired)?;
+
+        require!(
+            emission.reward_mint(self.staker) == Some(mint.key()),
+            StakeError::VestingMintMismatch
+        );
+
+        let authority = self.token_authority()?;
+        let cpi_program = self.token_program.clone();
+
+        if emission.is_vault_funded(self.staker) {
+            let cpi_accounts = TransferChecked {
+                from: self
+                    .stake_token_vault
+                    .clone()
+                    .ok_or(StakeError::RewardAccountsMissing)?,
+                mint: mint.to_account_info(),
+                to: vesting_vault,
+                authority,
+            };
+
+            transfer_checked(
+                CpiContext::new_with_signer(cpi_program, cpi_accounts, signer),
+                amount,
+                mint.decimals,
+            )
+        } else {
+            let cpi_accounts = MintTo {
+                mint: mint.to_account_info(),
+                to: vesting_vault,
+                authority,
+            };
+
+            mint_to(
+                CpiContext::new_with_signer(cpi_program, cpi_accounts, signer),
+                amount,
+            )
+        }
+    }
+
+    /// Accounts paying the emission's rewards, the named reward accounts when it pays in
+    /// `reward_mint`, otherwise accounts passed in remaining accounts
+    pub fn reward_accounts(
+        &self,
+        emission: &Emission,
+        remaining_accounts: &[AccountInfo<'info>],
+    ) -> Result<RewardAccounts<'info>> {
+        let mint = emission
+            .reward_mint(self.staker)
+            .ok_or(StakeError::NoRewardMint)?;
+        let token_vault = emission.is_vault_funded(self.staker);
+
+        match self.reward_mint {
+            Some(reward_mint) if reward_mint.key() == mint => RewardAccounts::named(
+                reward_mint,
+                self.stake_token_vault.clone(),
+                self.reward_receive_account.clone(),
+                token_vault,
+            ),
+            _ => RewardAccounts::find(
+                remaining_accounts,
+                &mint,
+                self.owner.key,
+                &self
+                    .token_authority
+                    .as_ref()
+                    .ok_or(StakeError::RewardAccountsMissing)?
+                    .key(),
+                self.token_program.key,
+                token_vault,
+            ),
+        }
+    }
+
+    /// Forfeited rewards are sent to the emission's treasury, or returned to its balance.
+    /// Only the emission-wide total is kept, per-stake forfeits are in the `FORFEITED` log.
+    pub fn forfeit_rewards(
+        &self,
+        emission: &mut Emission,
+        forfeited: u64,
+        reward_accounts: &RewardAccounts<'info>,
+        remaining_accounts: &[AccountInfo<'info>],
+        signer: &[&[&[u8]]],
+    ) -> Result<()> {
+        if forfeited == 0 {
+            return Ok(());
+        }
+
+        match emission
+            .early_unstake
+            .and_then(|early_unstake| early_unstake.treasury)
+        {
+            Some(treasury) => {
+                // treasuries of emissions paying in other mints are passed in remaining accounts
+                let penalty_treasury = match &self.penalty_treasury {
+                    Some(penalty_treasury) if penalty_treasury.key() == treasury => {
+                        penalty_treasury.clone()
+                    }
+                    _ => find_remaining_account(remaining_accounts, &treasury)
+                        .map_err(|_| error!(StakeError::PenaltyTreasuryRequired))?,
+                };
+
+                reward_accounts.pay(
+                    penalty_treasury,
+                    self.token_authority()?,
+                    self.token_program.clone(),
+                    forfeited,
+                    signer,
+                )?;
+            }
+            None => {
+                if emission.is_vault_funded(self.staker) {
+                    emission.increase_current_balance(forfeited)?;
+                }
+            }
+        }
+
+        msg!("FORFEITED {}", forfeited);
+
+        emission.add_penalty(forfeited)
+    }
+
+    /// Pays out what the stake record earned and removes it from each of its emissions.
+    /// Items that are unbonding stop adding to the emissions' staked weight.
+    pub fn settle_emissions(
+        &mut self,
+        remaining_accounts: &'info [AccountInfo<'info>],
+        current_time: i64,
+        signer: &[&[&[u8]]],
+        unbonding: bool,
+        program_id: &Pubkey,
+    ) -> Result<()> {
+        let collection = self.collection;
+        let stake_record = self.stake_record;
+
+        let StakeRecord {
+            staked_at,
+            can_claim_at,
+            pending_claim,
+            multiplier,
+            lock_boost,
+            reward_debt,
+            distribution_debt,
+            ..
+        } = **stake_record;
+
+        // emissions closed since the item was staked have nothing left to settle
+        let emission_keys = collection.emissions();
+
+        for key in stake_record
+            .emissions
+            .iter()
+            .filter(|key| emission_keys.contains(key))
+        {
+            let account = remaining_accounts
+                .iter()
+                .find(|acc| acc.key() == *key)
+                .ok_or(StakeError::EmissionsMissing)?;
+
+            let mut emission = Account::<'info, Emission>::try_from(account)?;
+
+            if collection.is_token_emission(key) {
+                let Emission {
+                    end_time,
+                    minimum_period,
+                    ..
+                } = *emission;
+
+                let (reward_tokens, current_time, is_eligible_for_reward) = calc_reward(
+                    staked_at,
+                    minimum_period.unwrap_or(0),
+                    &emission.reward,
+                    &emission.reward_change_time,
+                    end_time,
+                    &emission.reward_schedule,
+                )?;
+
+                let amount = emission.item_amount(multiplier, lock_boost)?;
+                let reward_tokens = emission.reward_for_amount(reward_tokens, amount)?;
+
+                // shared emissions pay the item's share of what the pool earned instead
+                let reward_tokens = if emission.shared {
+                    let mut reward_debt = reward_debt;
+                    let token_vault = emission.is_vault_funded(self.staker);
+                    emission.settle_shared_reward(
+                        &mut reward_debt,
+                        amount,
+                        current_time,
+                        token_vault,
+                    )?
+                } else {
+                    reward_tokens
+                };
+
+                let (reward_tokens, forfeited) = if is_eligible_for_reward {
+                    (reward_tokens, 0)
+                } else if emission.early_unstake.is_some() {
+                    emission.early_unstake_penalty(reward_tokens)?
+                } else {
+                    (0, 0)
+                };
+
+                if reward_tokens > 0 || forfeited > 0 {
+                    let reward_accounts = self.reward_accounts(&emission, remaining_accounts)?;
+
+                    if reward_tokens > 0 {
+                        if let Some(vesting) = emission.vesting {
+                            let staker_key = self.staker.key();
+                            let owner = self.owner.key();
+                            let bump = self.vesting_record_bump;
+                            let vesting_record = self
+                                .vesting_record
+                                .as_mut()
+                                .ok_or(StakeError::VestingAccountsRequired)?;
+
+                            if vesting_record.owner.eq(&Pubkey::default()) {
+                                ***vesting_record = VestingRecord::init(staker_key, owner, bump);
+                            }
+
+                            vesting_record.deposit(reward_tokens, vesting, current_time)?;
+                            self.vest_rewards(&emission, reward_tokens, signer)?;
+                        } else {
+                            reward_accounts.pay(
+                                reward_accounts.receiver.clone(),
+                                self.token_authority()?,
+                                self.token_program.clone(),
+                                reward_tokens,
+                                signer,
+                            )?;
+                        }
+                    }
+
+                    if emission.is_vault_funded(self.staker) {
+                        emission.decrease_current_balance_for_amount(
+                            staked_at,
+                            current_time,
+                            amount,
+                        )?;
+                    }
+
+                    self.forfeit_rewards(
+                        &mut emission,
+                        forfeited,
+                        &reward_accounts,
+                        remaining_accounts,
+                        signer,
+                    )?;
+                }
+
+                // no further rewards accrue while unbonding
+                if unbonding && current_time < emission.end_time.unwrap_or(STAKING_ENDS) {
+                    emission.update_staked_weight_for_amount(staked_at, amount, false)?;
+                }
+
+                emission.decrease_staked_amount(amount)?;
+            } else if collection.points_emission == Some(*key) {
+                let Emission {
+                    end_time,
+                    minimum_period,
+                    ..
+                } = *emission;
+
+                let (points, _, is_eligible_for_reward) = calc_reward(
+                    staked_at,
+                    minimum_period.unwrap_or(0),
+                    &emission.reward,
+                    &emission.reward_change_time,
+                    end_time,
+                    &emission.reward_schedule,
+                )?;
+
+                let amount = emission.item_amount(multiplier, lock_boost)?;
+                let points = emission.reward_for_amount(points, amount)?;
+
+                // points accrued since the last claim are credited before the item leaves
+                let nft_record = self
+                    .nft_record
+                    .as_mut()
+                    .ok_or(StakeError::NftRecordRequired)?;
+
+                if is_eligible_for_reward && points > 0 {
+                    nft_record.add_points(points)?;
+                }
+
+                emission.decrease_staked_amount(amount)?;
+            } else if collection.selection_emission == Some(*key) {
+                let (pending_claim, forfeited) = if current_time < can_claim_at {
+                    emission.early_unstake_penalty(pending_claim)?
+                } else {
+                    (pending_claim, 0)
+                };
+
+                if pending_claim > 0 || forfeited > 0 {
+                    let reward_accounts = self.reward_accounts(&emission, remaining_accounts)?;
+
+                    if pending_claim > 0 {
+                        reward_accounts.pay(
+                            reward_accounts.receiver.clone(),
+                            self.token_authority()?,
+                            self.token_program.clone(),
+                            pending_claim,
+                            signer,
+                        )?;
+
+                        if emission.is_vault_funded(self.staker) {
+                            emission.decrease_current_balance(staked_at, current_time)?;
+                        }
+                    }
+
+                    self.forfeit_rewards(
+                        &mut emission,
+                        forfeited,
+                        &reward_accounts,
+                        remaining_accounts,
+                        signer,
+                    )?;
+                }
+
+                emission.decrease_staked_items()?;
+            } else if collection.distribution_emission == Some(*key) {
+                settle_distribution(
+                    &emission,
+                    distribution_debt,
+                    &self.staker.key(),
+                    &self.owner,
+                    &self.token_program,
+                    remaining_accounts,
+                    current_time,
+                )?;
+
+                emission.decrease_staked_items()?;
+            }
+
+            emission.exit(program_id)?;
+        }
+
+        Ok(())
+    }
+}
@@ -33,7 +33,13 @@ import {
   findCollectionAuthorityRecordPda,
 } from "@metaplex-foundation/mpl-token-metadata"
 import { createToken } from "../helpers/create-token"
-import { AuthorityType, fetchToken, safeFetchToken, setAuthority } from "@metaplex-foundation/mpl-toolbox"
+import {
+  AuthorityType,
+  SPL_TOKEN_PROGRAM_ID,
+  fetchToken,
+  safeFetchToken,
+  setAuthority,
+} from "@metaplex-foundation/mpl-toolbox"
 import { assertErrorCode, expectFail, mintNfts } from "../helpers/utils"
 import { assert } from "chai"
 import { BN } from "bn.js"
@@ -132,6 +138,7 @@ describe.only("Emissions", () => {
       await creatorProgram.methods
         .closeEmission()
         .accounts({
+          tokenProgram: SPL_TOKEN_PROGRAM_ID,
           staker: staker.publicKey,
           emission: emission.publicKey,
           tokenMint: token,
@@ -265,6 +272,7 @@ describe.only("Emissions", () => {
       await creatorProgram.methods
         .closeEmission()
         .accounts({
+          tokenProgram: SPL_TOKEN_PROGRAM_ID,
           staker: staker.publicKey,
           emission: emission.publicKey,
           tokenMint: token,
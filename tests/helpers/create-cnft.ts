@@ -0,0 +1,101 @@
+import {
+  MetadataArgsArgs,
+  TokenProgramVersion,
+  TokenStandard,
+  createTree,
+  fetchTreeConfigFromSeeds,
+  findLeafAssetIdPda,
+  getMerkleProof,
+  getMerkleRoot,
+  hashLeaf,
+  mintToCollectionV1,
+} from "@metaplex-foundation/mpl-bubblegum"
+import { generateSigner, none, publicKey, some, type PublicKey, type Umi } from "@metaplex-foundation/umi"
+
+export type Cnft = {
+  merkleTree: PublicKey
+  leafIndex: number
+  assetId: PublicKey
+  collection: PublicKey
+  metadata: MetadataArgsArgs
+  owner: PublicKey
+}
+
+const MAX_DEPTH = 5
+
+// the local validator has no DAS API, so leaves are tracked here to build proofs
+const trees: Record<string, PublicKey[]> = {}
+
+export async function createCnftTree(umi: Umi) {
+  const merkleTree = generateSigner(umi)
+
+  await (
+    await createTree(umi, {
+      merkleTree,
+      maxDepth: MAX_DEPTH,
+      maxBufferSize: 8,
+    })
+  ).sendAndConfirm(umi)
+
+  trees[merkleTree.publicKey] = []
+  return merkleTree.publicKey
+}
+
+export async function createCnft(umi: Umi, merkleTree: PublicKey, collection: PublicKey, owner: PublicKey) {
+  const treeConfig = await fetchTreeConfigFromSeeds(umi, { merkleTree })
+  const leafIndex = Number(treeConfig.numMinted)
+
+  const metadata: MetadataArgsArgs = {
+    name: "Test cNFT",
+    symbol: "",
+    uri: "",
+    sellerFeeBasisPoints: 0,
+    primarySaleHappened: false,
+    isMutable: true,
+    editionNonce: none(),
+    tokenStandard: some(TokenStandard.NonFungible),
+    collection: some({ key: collection, verified: false }),
+    uses: none(),
+    tokenProgramVersion: TokenProgramVersion.Original,
+    creators: [],
+  }
+
+  await mintToCollectionV1(umi, {
+    leafOwner: owner,
+    merkleTree,
+    collectionMint: collection,
+    metadata,
+  }).sendAndConfirm(umi)
+
+  const cnft: Cnft = {
+    merkleTree,
+    leafIndex,
+    assetId: findLeafAssetIdPda(umi, { merkleTree, leafIndex })[0],
+    collection,
+    // the collection is verified when minting to it
+    metadata: { ...metadata, collection: some({ key: collection, verified: true }) },
+    owner,
+  }
+
+  trees[merkleTree][leafIndex] = publicKey(
+    hashLeaf(umi, { merkleTree, owner, delegate: owner, leafIndex, metadata: cnft.metadata })
+  )
+
+  return cnft
+}
+
+// transfers reset the leaf delegate to the new owner
+export function setCnftOwner(umi: Umi, cnft: Cnft, owner: PublicKey) {
+  const { merkleTree, leafIndex, metadata } = cnft
+  cnft.owner = owner
+  trees[merkleTree][leafIndex] = publicKey(hashLeaf(umi, { merkleTree, owner, delegate: owner, leafIndex, metadata }))
+}
+
+export function getCnftProof(cnft: Cnft) {
+  const leaves = trees[cnft.merkleTree]
+
+  return {
+    root: getMerkleRoot(leaves, MAX_DEPTH),
+    proof: getMerkleProof(leaves, MAX_DEPTH, leaves[cnft.leafIndex]),
+  }
+}
@@ -0,0 +1,28 @@
+import { createCollectionV1, createV1 } from "@metaplex-foundation/mpl-core"
+import { generateSigner, type PublicKey, type Umi } from "@metaplex-foundation/umi"
+
+export async function createCoreCollection(umi: Umi) {
+  const collection = generateSigner(umi)
+
+  await createCollectionV1(umi, {
+    collection,
+    name: "Test Core Collection",
+    uri: "",
+  }).sendAndConfirm(umi)
+
+  return collection.publicKey
+}
+
+export async function createCoreAsset(umi: Umi, collection: PublicKey, owner: PublicKey) {
+  const asset = generateSigner(umi)
+
+  await createV1(umi, {
+    asset,
+    collection,
+    name: "Test Core Asset",
+    uri: "",
+    owner,
+  }).sendAndConfirm(umi)
+
+  return asset.publicKey
+}
@@ -4,6 +4,18 @@ import type { Signer, Umi } from "@metaplex-foundation/umi"
 import { createAssociatedToken, findAssociatedTokenPda, mintTokensTo } from "@metaplex-foundation/mpl-toolbox"
 import { getTokenAccount } from "./pdas"
 import { PublicKey } from "@solana/web3.js"
+import * as anchor from "@coral-xyz/anchor"
+import {
+  ExtensionType,
+  TOKEN_2022_PROGRAM_ID,
+  createAssociatedTokenAccountIdempotentInstruction,
+  createInitializeMintInstruction,
+  createInitializeTransferFeeConfigInstruction,
+  createMintToInstruction,
+  getAssociatedTokenAddressSync,
+  getMintLen,
+} from "@solana/spl-token"
+import { fromWeb3JsPublicKey } from "@metaplex-foundation/umi-web3js-adapters"
 
 export async function createToken(
   umi: Umi,
@@ -31,3 +43,42 @@ export async function createToken(
 
   return mint.publicKey
 }
+
+// Token-2022 mint with an uncapped transfer fee, minted to the provider's wallet
+export async function createTransferFeeToken(
+  provider: anchor.Provider,
+  amount: bigint,
+  transferFeeBasisPoints: number,
+  decimals: number = 0
+) {
+  const mint = anchor.web3.Keypair.generate()
+  const payer = provider.publicKey
+  const space = getMintLen([ExtensionType.TransferFeeConfig])
+  const lamports = await provider.connection.getMinimumBalanceForRentExemption(space)
+  const token = getAssociatedTokenAddressSync(mint.publicKey, payer, false, TOKEN_2022_PROGRAM_ID)
+
+  const txn = new anchor.web3.Transaction().add(
+    anchor.web3.SystemProgram.createAccount({
+      fromPubkey: payer,
+      newAccountPubkey: mint.publicKey,
+      space,
+      lamports,
+      programId: TOKEN_2022_PROGRAM_ID,
+    }),
+    createInitializeTransferFeeConfigInstruction(
+      mint.publicKey,
+      payer,
+      payer,
+      transferFeeBasisPoints,
+      amount,
+      TOKEN_2022_PROGRAM_ID
+    ),
+    createInitializeMintInstruction(mint.publicKey, decimals, payer, null, TOKEN_2022_PROGRAM_ID),
+    createAssociatedTokenAccountIdempotentInstruction(payer, token, payer, mint.publicKey, TOKEN_2022_PROGRAM_ID),
+    createMintToInstruction(mint.publicKey, token, payer, amount, [], TOKEN_2022_PROGRAM_ID)
+  )
+
+  await provider.sendAndConfirm(txn, [mint])
+
+  return fromWeb3JsPublicKey(mint.publicKey)
+}
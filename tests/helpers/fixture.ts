@@ -0,0 +1,71 @@
+import * as anchor from "@coral-xyz/anchor"
+import { Keypair, PublicKey } from "@metaplex-foundation/umi"
+import { DigitalAsset } from "@metaplex-foundation/mpl-token-metadata"
+import { AuthorityType, setAuthority } from "@metaplex-foundation/mpl-toolbox"
+import { Stake } from "../../target/types/stake"
+import { createNewUser, programPaidBy } from "../helper"
+import { createCollection } from "./create-collection"
+import { createToken } from "./create-token"
+import { addToken, init, initCollection } from "./instructions"
+import { findStakooorCollectionId } from "./pdas"
+import { umi } from "./umi"
+import { mintNfts } from "./utils"
+
+export type Fixture = {
+  creator: Keypair
+  creatorProgram: anchor.Program<Stake>
+  user: Keypair
+  userProgram: anchor.Program<Stake>
+  collectionNft: DigitalAsset
+  collection: PublicKey
+  nfts: DigitalAsset[]
+  token: PublicKey
+}
+
+export type FixtureOptions = {
+  // NFTs of the collection minted to the user
+  numNfts?: number
+  tokenSupply?: number
+  // whether the staker pays rewards from its vault or mints them, null leaves the staker without a token
+  tokenVault?: boolean | null
+  maxStakersCount?: number
+}
+
+// a creator's staker with a collection, and a user holding NFTs of the collection. Emissions and
+// activation are left to the suite
+export async function createFixture(
+  staker: Keypair,
+  slug: string,
+  name: string,
+  { numNfts = 2, tokenSupply = 10_000, tokenVault = null, maxStakersCount = 10 }: FixtureOptions = {}
+): Promise<Fixture> {
+  const creator = await createNewUser()
+  const user = await createNewUser()
+  const creatorProgram = programPaidBy(creator)
+  const userProgram = programPaidBy(user)
+  const collectionNft = await createCollection(umi)
+  const collection = findStakooorCollectionId(staker.publicKey, collectionNft.publicKey)
+  const nfts = await mintNfts(collectionNft.publicKey, numNfts, false, user.publicKey)
+  const token = await createToken(umi, BigInt(tokenSupply), 0, undefined, creator.publicKey)
+
+  // minted rewards need the creator to hold the mint authority when adding the token
+  if (tokenVault === false) {
+    await setAuthority(umi, {
+      owned: token,
+      owner: umi.identity.publicKey,
+      newAuthority: creator.publicKey,
+      authorityType: AuthorityType.MintTokens,
+    }).sendAndConfirm(umi)
+  }
+
+  if (tokenVault === null) {
+    await init(creatorProgram, staker, slug, name)
+  } else {
+    await init(creatorProgram, staker, slug, name, token)
+    await addToken(creatorProgram, staker.publicKey, token, tokenVault)
+  }
+
+  await initCollection(creatorProgram, staker.publicKey, collectionNft.publicKey, false, null, maxStakersCount)
+
+  return { creator, creatorProgram, user, userProgram, collectionNft, collection, nfts, token }
+}
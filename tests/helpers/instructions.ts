@@ -4,6 +4,7 @@ import {
   Keypair,
   PublicKey,
   publicKey,
+  publicKeyBytes,
   sol,
   tokenAmount,
   unwrapOption,
@@ -14,13 +15,29 @@ import {
   MPL_TOKEN_METADATA_PROGRAM_ID,
   TokenStandard,
   fetchDigitalAsset,
+  safeFetchMetadataFromSeeds,
 } from "@metaplex-foundation/mpl-token-metadata"
+import { SPL_TOKEN_PROGRAM_ID } from "@metaplex-foundation/mpl-toolbox"
+import {
+  MPL_BUBBLEGUM_PROGRAM_ID,
+  SPL_ACCOUNT_COMPRESSION_PROGRAM_ID,
+  SPL_NOOP_PROGRAM_ID,
+  findTreeConfigPda,
+  getMetadataArgsSerializer,
+  hashMetadataCreators,
+  hashMetadataData,
+} from "@metaplex-foundation/mpl-bubblegum"
+import { MPL_CORE_PROGRAM_ID } from "@metaplex-foundation/mpl-core"
 import { umi } from "./umi"
+import { Cnft, getCnftProof, setCnftOwner } from "./create-cnft"
 import {
+  findClaimBitmapPda,
   findNftAuthorityPda,
   findNftMasterEditionPda,
   findNftMetadataPda,
   findNftRecordPda,
+  findPointsReceiptPda,
+  findPoolRecordPda,
   findProgramConfigPda,
   findProgramDataAddress,
   findShareRecordPda,
@@ -28,6 +45,8 @@ import {
   findStakooorCollectionId,
   findTokenAuthorityPda,
   findVaultAuthorityPda,
+  findVestingRecordPda,
+  findVestingVaultPda,
   getTokenAccount,
   getTokenRecordPda,
 } from "./pdas"
@@ -35,7 +54,7 @@ import { fromWeb3JsPublicKey, toWeb3JsKeypair, toWeb3JsPublicKey } from "@metapl
 
 import { assert } from "chai"
 import { BN } from "bn.js"
-import { FEES_WALLET, USDC } from "./utils"
+import { FEES_WALLET, USDC, getTokenProgram } from "./utils"
 
 import { compact, findIndex, isEqual } from "lodash"
 import { adminProgram } from "../helper"
@@ -43,7 +62,78 @@ import { Stake } from "../../target/types/stake"
 
 export const sleep = async (ms: number) => new Promise((resolve) => setTimeout(resolve, ms))
 
-export async function stake(program: anchor.Program<Stake>, staker: PublicKey, nft: DigitalAsset) {
+// emissions paying in a mint other than the instruction's reward mint are paid through remaining
+// accounts: the mint, the token authority's vault when vault funded, and the owner's token account
+async function otherMintRewardAccounts(
+  program: anchor.Program<Stake>,
+  staker: PublicKey,
+  emissions: anchor.web3.PublicKey[],
+  rewardMint: PublicKey | null,
+  owner: PublicKey
+) {
+  const emissionAccounts = await Promise.all(emissions.map((key) => program.account.emission.fetch(key)))
+  const tokenAuthority = findTokenAuthorityPda(staker)
+
+  const mints = emissionAccounts.filter(
+    (account) => account.tokenMint && fromWeb3JsPublicKey(account.tokenMint) !== rewardMint
+  )
+
+  return mints.flatMap((account) => {
+    const mint = fromWeb3JsPublicKey(account.tokenMint)
+    return compact([
+      mint,
+      account.tokenVault ? getTokenAccount(mint, tokenAuthority) : null,
+      getTokenAccount(mint, owner),
+    ]).map((pubkey) => ({
+      pubkey: toWeb3JsPublicKey(pubkey),
+      isSigner: false,
+      isWritable: true,
+    }))
+  })
+}
+
+// pro-rata distributions pay an item's unclaimed share as it leaves the distribution emission
+async function distributionAccounts(
+  program: anchor.Program<Stake>,
+  staker: PublicKey,
+  emissions: anchor.web3.PublicKey[],
+  owner: PublicKey
+) {
+  const emissionAccounts = await Promise.all(emissions.map((key) => program.account.emission.fetch(key)))
+  const emissionAccount = emissionAccounts.find((account) => account.distribution)
+
+  if (!emissionAccount) {
+    return []
+  }
+
+  const distribution = fromWeb3JsPublicKey(emissionAccount.distribution)
+  const distributionAccount = await program.account.distribution.fetch(distribution)
+  const mint = distributionAccount.tokenMint ? fromWeb3JsPublicKey(distributionAccount.tokenMint) : null
+  const vaultAuthority = findVaultAuthorityPda(staker, distribution)
+
+  return compact([
+    distribution,
+    mint,
+    mint ? vaultAuthority : null,
+    mint ? getTokenAccount(mint, vaultAuthority) : null,
+    mint ? getTokenAccount(mint, owner) : null,
+  ]).map((pubkey) => ({
+    pubkey: toWeb3JsPublicKey(pubkey),
+    isSigner: false,
+    isWritable: pubkey !== vaultAuthority,
+  }))
+}
+
+export async function stake(
+  program: anchor.Program<Stake>,
+  staker: PublicKey,
+  nft: DigitalAsset,
+  proof: number[][] | null = null,
+  multiplier: MultiplierProof | null = null,
+  lockTier: number | null = null,
+  // allow listed NFTs don't need to be in the collection
+  collectionMint: PublicKey = unwrapOption(nft.metadata.collection).key
+) {
   const authRules = unwrapOptionRecursively(nft.metadata.programmableConfig)?.ruleSet ?? null
   const ownerTokenRecord =
     unwrapOption(nft.metadata.tokenStandard) === TokenStandard.ProgrammableNonFungible
@@ -55,11 +145,12 @@ export async function stake(program: anchor.Program<Stake>, staker: PublicKey, n
       ? getTokenRecordPda(nft.publicKey, nftAuthority)
       : null
 
-  const collection = findStakooorCollectionId(staker, unwrapOption(nft.metadata.collection).key)
+  const collection = findStakooorCollectionId(staker, collectionMint)
   let collectionAccount = await program.account.collection.fetchNullable(collection)
 
   const emissions = compact([
     collectionAccount.tokenEmission,
+    ...collectionAccount.extraEmissions,
     collectionAccount.pointsEmission,
     collectionAccount.distributionEmission,
     collectionAccount.selectionEmission,
@@ -78,7 +169,12 @@ export async function stake(program: anchor.Program<Stake>, staker: PublicKey, n
   const stakeRecord = findStakeRecordPda(staker, nft.publicKey)
 
   return await program.methods
-    .stake(null)
+    .stake(
+      null,
+      proof,
+      multiplier && { multiplier: new anchor.BN(multiplier.multiplier), proof: multiplier.proof },
+      lockTier
+    )
     .accounts({
       staker,
       collection,
@@ -110,6 +206,73 @@ export async function stake(program: anchor.Program<Stake>, staker: PublicKey, n
     .rpc()
 }
 
+export type MultiplierProof = { multiplier: number; proof: number[][] }
+
+export async function stakeMany(
+  program: anchor.Program<Stake>,
+  staker: PublicKey,
+  nfts: DigitalAsset[],
+  multipliers: (MultiplierProof | null)[] = []
+) {
+  const [first] = nfts
+  const authRules = unwrapOptionRecursively(first.metadata.programmableConfig)?.ruleSet ?? null
+  const nftAuthority = findNftAuthorityPda(staker)
+  const owner = fromWeb3JsPublicKey(program.provider.publicKey)
+  const programId = fromWeb3JsPublicKey(program.programId)
+
+  const collection = findStakooorCollectionId(staker, unwrapOption(first.metadata.collection).key)
+  const collectionAccount = await program.account.collection.fetch(collection)
+
+  const emissions = compact([
+    collectionAccount.tokenEmission,
+    ...collectionAccount.extraEmissions,
+    collectionAccount.pointsEmission,
+    collectionAccount.selectionEmission,
+    collectionAccount.distributionEmission,
+  ])
+
+  const nftAccounts = nfts.flatMap((nft) => {
+    const isPnft = unwrapOption(nft.metadata.tokenStandard) === TokenStandard.ProgrammableNonFungible
+    return [
+      nft.publicKey,
+      getTokenAccount(nft.publicKey, owner),
+      findNftMetadataPda(nft.publicKey),
+      findNftMasterEditionPda(nft.publicKey),
+      isPnft ? getTokenRecordPda(nft.publicKey, owner) : programId,
+      isPnft && collectionAccount.custodial ? getTokenRecordPda(nft.publicKey, nftAuthority) : programId,
+      collectionAccount.custodial ? getTokenAccount(nft.publicKey, nftAuthority) : programId,
+      findStakeRecordPda(staker, nft.publicKey),
+      collectionAccount.pointsEmission ? findNftRecordPda(staker, nft.publicKey) : programId,
+    ]
+  })
+
+  return await program.methods
+    .stakeMany(
+      null,
+      multipliers.map((item) => item && { multiplier: new anchor.BN(item.multiplier), proof: item.proof })
+    )
+    .accounts({
+      staker,
+      collection,
+      programConfig: findProgramConfigPda(),
+      nftAuthority,
+      feesWallet: FEES_WALLET,
+      authRules,
+      authRulesProgram: RULES_PROGRAM_ID,
+      sysvarInstructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+      metadataProgram: MPL_TOKEN_METADATA_PROGRAM_ID,
+    })
+    .preInstructions([anchor.web3.ComputeBudgetProgram.setComputeUnitLimit({ units: 1_400_000 })])
+    .remainingAccounts(
+      [...emissions, ...nftAccounts.map(toWeb3JsPublicKey)].map((pubkey) => ({
+        pubkey,
+        isSigner: false,
+        isWritable: true,
+      }))
+    )
+    .rpc()
+}
+
 export async function initDistribution(
   program: anchor.Program<Stake>,
   staker: PublicKey,
@@ -119,14 +282,18 @@ export async function initDistribution(
   uri: string,
   numShares: number,
   amount: anchor.BN,
-  tokenMint?: PublicKey
+  tokenMint?: PublicKey,
+  endsAt: anchor.BN | null = null
 ) {
-  const tokenAccount = tokenMint ? getTokenAccount(tokenMint, fromWeb3JsPublicKey(program.provider.publicKey)) : null
+  const tokenProgram = tokenMint ? await getTokenProgram(tokenMint) : SPL_TOKEN_PROGRAM_ID
+  const tokenAccount = tokenMint
+    ? getTokenAccount(tokenMint, fromWeb3JsPublicKey(program.provider.publicKey), tokenProgram)
+    : null
   const vaultAuthority = findVaultAuthorityPda(staker, distribution.publicKey)
-  const tokenVault = tokenMint ? getTokenAccount(tokenMint, vaultAuthority) : null
+  const tokenVault = tokenMint ? getTokenAccount(tokenMint, vaultAuthority, tokenProgram) : null
 
   return await program.methods
-    .initDistribution(label, uri, numShares, amount)
+    .initDistribution(label, uri, numShares, amount, endsAt)
     .accounts({
       staker,
       collection,
@@ -135,6 +302,7 @@ export async function initDistribution(
       tokenAccount,
       tokenVault,
       vaultAuthority,
+      tokenProgram,
     })
     .signers([toWeb3JsKeypair(distribution)])
     .rpc()
@@ -151,8 +319,9 @@ export async function distribute(
   const shareRecord = findShareRecordPda(distribution, fromWeb3JsPublicKey(stakeRecordAccount.nftMint))
   const distributionAccount = await program.account.distribution.fetch(distribution)
   const collection = distributionAccount.collection
-
-  console.log({ distribution, collection, stakeRecord, shareRecord })
+  const tokenMint = distributionAccount.tokenMint ? fromWeb3JsPublicKey(distributionAccount.tokenMint) : null
+  const vaultAuthority = findVaultAuthorityPda(staker, distribution)
+  const tokenProgram = tokenMint ? await getTokenProgram(tokenMint) : null
 
   return await program.methods
     .distribute(amount)
@@ -162,12 +331,194 @@ export async function distribute(
       collection,
       stakeRecord,
       shareRecord,
+      tokenMint,
+      tokenAccount: tokenMint
+        ? getTokenAccount(tokenMint, fromWeb3JsPublicKey(program.provider.publicKey), tokenProgram)
+        : null,
+      tokenVault: tokenMint ? getTokenAccount(tokenMint, vaultAuthority, tokenProgram) : null,
+      vaultAuthority,
+      tokenProgram,
+    })
+    .rpc()
+}
+
+export async function setDistributionRoot(
+  program: anchor.Program<Stake>,
+  staker: PublicKey,
+  distribution: PublicKey,
+  merkleRoot: number[],
+  numShares: number,
+  amount: anchor.BN
+) {
+  const distributionAccount = await program.account.distribution.fetch(distribution)
+  const tokenMint = distributionAccount.tokenMint ? fromWeb3JsPublicKey(distributionAccount.tokenMint) : null
+  const vaultAuthority = findVaultAuthorityPda(staker, distribution)
+  const tokenProgram = tokenMint ? await getTokenProgram(tokenMint) : null
+
+  return await program.methods
+    .setDistributionRoot(merkleRoot, numShares, amount)
+    .accounts({
+      staker,
+      distribution,
+      claimBitmap: findClaimBitmapPda(distribution),
+      tokenMint,
+      tokenAccount: tokenMint
+        ? getTokenAccount(tokenMint, fromWeb3JsPublicKey(program.provider.publicKey), tokenProgram)
+        : null,
+      tokenVault: tokenMint ? getTokenAccount(tokenMint, vaultAuthority, tokenProgram) : null,
+      vaultAuthority,
+      tokenProgram,
+    })
+    .rpc()
+}
+
+export async function claimMerkleShare(
+  program: anchor.Program<Stake>,
+  staker: PublicKey,
+  distribution: PublicKey,
+  index: number,
+  amount: anchor.BN,
+  proof: number[][],
+  nftMint: PublicKey | null = null,
+  staked: boolean = false
+) {
+  const owner = fromWeb3JsPublicKey(program.provider.publicKey)
+  const distributionAccount = await program.account.distribution.fetch(distribution)
+  const tokenMint = distributionAccount.tokenMint ? fromWeb3JsPublicKey(distributionAccount.tokenMint) : null
+  const vaultAuthority = findVaultAuthorityPda(staker, distribution)
+  const tokenProgram = tokenMint ? await getTokenProgram(tokenMint) : null
+
+  return await program.methods
+    .claimMerkleShare(index, amount, proof)
+    .accounts({
+      staker,
+      distribution,
+      claimBitmap: findClaimBitmapPda(distribution),
+      stakeRecord: nftMint && staked ? findStakeRecordPda(staker, nftMint) : null,
+      nftToken: nftMint && !staked ? getTokenAccount(nftMint, owner) : null,
+      tokenMint,
+      tokenVault: tokenMint ? getTokenAccount(tokenMint, vaultAuthority, tokenProgram) : null,
+      rewardReceiveAccount: tokenMint ? getTokenAccount(tokenMint, owner, tokenProgram) : null,
+      vaultAuthority,
+      tokenProgram,
+      associatedTokenProgram: tokenMint ? anchor.utils.token.ASSOCIATED_PROGRAM_ID : null,
+    })
+    .rpc()
+}
+
+export async function reclaimDistribution(
+  program: anchor.Program<Stake>,
+  staker: PublicKey,
+  distribution: PublicKey,
+  shareRecords: PublicKey[] = []
+) {
+  const distributionAccount = await program.account.distribution.fetch(distribution)
+  const tokenMint = distributionAccount.tokenMint ? fromWeb3JsPublicKey(distributionAccount.tokenMint) : null
+  const vaultAuthority = findVaultAuthorityPda(staker, distribution)
+
+  return await program.methods
+    .reclaimDistribution()
+    .accounts({
+      staker,
+      distribution,
+      emission: distributionAccount.emission,
+      claimBitmap: distributionAccount.merkleRoot ? findClaimBitmapPda(distribution) : null,
+      tokenMint,
+      tokenAccount: tokenMint ? getTokenAccount(tokenMint, fromWeb3JsPublicKey(program.provider.publicKey)) : null,
+      tokenVault: tokenMint ? getTokenAccount(tokenMint, vaultAuthority) : null,
+      vaultAuthority,
+      tokenProgram: tokenMint ? SPL_TOKEN_PROGRAM_ID : null,
+      associatedTokenProgram: tokenMint ? anchor.utils.token.ASSOCIATED_PROGRAM_ID : null,
+    })
+    .remainingAccounts(
+      shareRecords.map((pubkey) => ({
+        pubkey,
+        isSigner: false,
+        isWritable: true,
+      }))
+    )
+    .rpc()
+}
+
+export async function toggleDistributionActive(
+  program: anchor.Program<Stake>,
+  staker: PublicKey,
+  distribution: PublicKey,
+  active: boolean
+) {
+  return await program.methods
+    .toggleDistributionActive(active)
+    .accounts({
+      staker,
+      distribution,
+    })
+    .rpc()
+}
+
+export async function distributeProRata(
+  program: anchor.Program<Stake>,
+  staker: PublicKey,
+  distribution: PublicKey,
+  amount: anchor.BN
+) {
+  const distributionAccount = await program.account.distribution.fetch(distribution)
+  const collection = fromWeb3JsPublicKey(distributionAccount.collection)
+  const collectionAccount = await program.account.collection.fetch(collection)
+  const tokenMint = distributionAccount.tokenMint ? fromWeb3JsPublicKey(distributionAccount.tokenMint) : null
+  const vaultAuthority = findVaultAuthorityPda(staker, distribution)
+
+  return await program.methods
+    .distributeProRata(amount)
+    .accounts({
+      staker,
+      collection,
+      emission: collectionAccount.distributionEmission,
+      distribution,
+      tokenMint,
+      tokenAccount: tokenMint ? getTokenAccount(tokenMint, fromWeb3JsPublicKey(program.provider.publicKey)) : null,
+      tokenVault: tokenMint ? getTokenAccount(tokenMint, vaultAuthority) : null,
+      vaultAuthority,
+      tokenProgram: tokenMint ? SPL_TOKEN_PROGRAM_ID : null,
+    })
+    .rpc()
+}
+
+export async function claimShare(
+  program: anchor.Program<Stake>,
+  staker: PublicKey,
+  distribution: PublicKey,
+  nftMint: PublicKey
+) {
+  const distributionAccount = await program.account.distribution.fetch(distribution)
+  const tokenMint = distributionAccount.tokenMint ? fromWeb3JsPublicKey(distributionAccount.tokenMint) : null
+  const vaultAuthority = findVaultAuthorityPda(staker, distribution)
+  const tokenProgram = tokenMint ? await getTokenProgram(tokenMint) : null
+
+  return await program.methods
+    .claimShare()
+    .accounts({
+      staker,
+      distribution,
+      shareRecord: findShareRecordPda(distribution, nftMint),
+      tokenMint,
+      tokenVault: tokenMint ? getTokenAccount(tokenMint, vaultAuthority, tokenProgram) : null,
+      rewardReceiveAccount: tokenMint
+        ? getTokenAccount(tokenMint, fromWeb3JsPublicKey(program.provider.publicKey), tokenProgram)
+        : null,
+      vaultAuthority,
+      tokenProgram,
+      associatedTokenProgram: tokenMint ? anchor.utils.token.ASSOCIATED_PROGRAM_ID : null,
     })
     .rpc()
-    .catch((err) => console.log(err))
 }
 
-export async function unstake(program: anchor.Program<Stake>, staker: PublicKey, nft: DigitalAsset) {
+export async function unstake(
+  program: anchor.Program<Stake>,
+  staker: PublicKey,
+  nft: DigitalAsset,
+  penaltyTreasury: PublicKey | null = null,
+  complete: boolean = false
+) {
   const collection = findStakooorCollectionId(staker, unwrapOption(nft.metadata.collection).key)
   const stakeAccount = await program.account.staker.fetch(staker)
   const collectionAccount = await program.account.collection.fetch(collection)
@@ -202,14 +553,35 @@ export async function unstake(program: anchor.Program<Stake>, staker: PublicKey,
 
   const emissions = compact([
     collectionAccount.tokenEmission,
+    ...collectionAccount.extraEmissions,
     collectionAccount.pointsEmission,
     collectionAccount.distributionEmission,
     collectionAccount.selectionEmission,
   ])
 
-  return await program.methods
-    .unstake()
+  const owner = fromWeb3JsPublicKey(program.provider.publicKey)
+  const tokenEmission = collectionAccount.tokenEmission
+    ? await program.account.emission.fetch(collectionAccount.tokenEmission)
+    : null
+  const vestingRecord = tokenEmission?.vesting ? findVestingRecordPda(staker, owner) : null
+  const vestingVault = tokenEmission?.vesting ? findVestingVaultPda(staker, owner) : null
+  const rewardAccounts = await otherMintRewardAccounts(
+    program,
+    staker,
+    compact([
+      collectionAccount.tokenEmission,
+      ...collectionAccount.extraEmissions,
+      collectionAccount.selectionEmission,
+    ]),
+    tokenMint,
+    owner
+  )
+  const stakeRecordAccount = await program.account.stakeRecord.fetch(stakeRecord)
+  const payoutAccounts = await distributionAccounts(program, staker, stakeRecordAccount.emissions, owner)
+
+  return await (complete ? program.methods.completeUnstake() : program.methods.unstake())
     .accounts({
+      rewardTokenProgram: SPL_TOKEN_PROGRAM_ID,
       staker,
       collection,
       nftRecord,
@@ -217,6 +589,9 @@ export async function unstake(program: anchor.Program<Stake>, staker: PublicKey,
       rewardMint: tokenMint,
       stakeTokenVault,
       rewardReceiveAccount,
+      penaltyTreasury,
+      vestingRecord,
+      vestingVault,
       nftMint: nft.publicKey,
       tokenAuthority,
       nftAuthority,
@@ -229,18 +604,341 @@ export async function unstake(program: anchor.Program<Stake>, staker: PublicKey,
       authRules,
       programConfig,
       feesWallet: FEES_WALLET,
-      authRulesProgram: RULES_PROGRAM_ID,
-      sysvarInstructions: publicKey(anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY),
-      metadataProgram: MPL_TOKEN_METADATA_PROGRAM_ID,
+      authRulesProgram: RULES_PROGRAM_ID,
+      sysvarInstructions: publicKey(anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY),
+      metadataProgram: MPL_TOKEN_METADATA_PROGRAM_ID,
+    })
+    .preInstructions([anchor.web3.ComputeBudgetProgram.setComputeUnitLimit({ units: 350_000 })])
+    .remainingAccounts([
+      ...emissions.map((pubkey) => ({
+        pubkey,
+        isSigner: false,
+        isWritable: true,
+      })),
+      ...rewardAccounts,
+      ...payoutAccounts,
+    ])
+    .rpc()
+}
+
+export async function requestUnstake(
+  program: anchor.Program<Stake>,
+  staker: PublicKey,
+  nft: DigitalAsset,
+  penaltyTreasury: PublicKey | null = null
+) {
+  const collection = findStakooorCollectionId(staker, unwrapOption(nft.metadata.collection).key)
+  const stakeAccount = await program.account.staker.fetch(staker)
+  const collectionAccount = await program.account.collection.fetch(collection)
+
+  const tokenMint =
+    collectionAccount.tokenEmission || collectionAccount.selectionEmission
+      ? fromWeb3JsPublicKey(stakeAccount.tokenMint)
+      : null
+  const tokenAuthority = findTokenAuthorityPda(staker)
+  const stakeTokenVault = tokenMint && stakeAccount.tokenVault ? getTokenAccount(tokenMint, tokenAuthority) : null
+  const rewardReceiveAccount = tokenMint
+    ? getTokenAccount(tokenMint, fromWeb3JsPublicKey(program.provider.publicKey))
+    : null
+
+  const stakeRecord = findStakeRecordPda(staker, nft.publicKey)
+  const stakeRecordAccount = await program.account.stakeRecord.fetch(stakeRecord)
+
+  const owner = fromWeb3JsPublicKey(program.provider.publicKey)
+  const tokenEmission = collectionAccount.tokenEmission
+    ? await program.account.emission.fetch(collectionAccount.tokenEmission)
+    : null
+  const vestingRecord = tokenEmission?.vesting ? findVestingRecordPda(staker, owner) : null
+  const vestingVault = tokenEmission?.vesting ? findVestingVaultPda(staker, owner) : null
+  const rewardAccounts = await otherMintRewardAccounts(program, staker, stakeRecordAccount.emissions, tokenMint, owner)
+  const payoutAccounts = await distributionAccounts(program, staker, stakeRecordAccount.emissions, owner)
+  const nftRecord = collectionAccount.pointsEmission ? findNftRecordPda(staker, nft.publicKey) : null
+
+  return await program.methods
+    .requestUnstake()
+    .accounts({
+      rewardTokenProgram: SPL_TOKEN_PROGRAM_ID,
+      staker,
+      collection,
+      stakeRecord,
+      nftRecord,
+      rewardMint: tokenMint,
+      stakeTokenVault,
+      rewardReceiveAccount,
+      penaltyTreasury,
+      vestingRecord,
+      vestingVault,
+      tokenAuthority,
+    })
+    .remainingAccounts([
+      ...stakeRecordAccount.emissions.map((pubkey) => ({
+        pubkey,
+        isSigner: false,
+        isWritable: true,
+      })),
+      ...rewardAccounts,
+      ...payoutAccounts,
+    ])
+    .rpc()
+}
+
+export async function stakeCnft(
+  program: anchor.Program<Stake>,
+  staker: PublicKey,
+  cnft: Cnft,
+  multiplier: MultiplierProof | null = null
+) {
+  const collection = findStakooorCollectionId(staker, cnft.collection)
+  const collectionAccount = await program.account.collection.fetch(collection)
+  const nftAuthority = findNftAuthorityPda(staker)
+  const { root, proof } = getCnftProof(cnft)
+
+  const emissions = compact([
+    collectionAccount.tokenEmission,
+    ...collectionAccount.extraEmissions,
+    collectionAccount.pointsEmission,
+    collectionAccount.selectionEmission,
+    collectionAccount.distributionEmission,
+  ])
+
+  const sig = await program.methods
+    .stakeCnft(
+      Array.from(publicKeyBytes(root)),
+      new BN(cnft.leafIndex),
+      cnft.leafIndex,
+      Buffer.from(getMetadataArgsSerializer().serialize(cnft.metadata)),
+      null,
+      multiplier && { multiplier: new BN(multiplier.multiplier), proof: multiplier.proof }
+    )
+    .accounts({
+      staker,
+      collection,
+      nftRecord: collectionAccount.pointsEmission ? findNftRecordPda(staker, cnft.assetId) : null,
+      stakeRecord: findStakeRecordPda(staker, cnft.assetId),
+      programConfig: findProgramConfigPda(),
+      nftAuthority,
+      treeConfig: findTreeConfigPda(umi, { merkleTree: cnft.merkleTree })[0],
+      merkleTree: cnft.merkleTree,
+      feesWallet: FEES_WALLET,
+      bubblegumProgram: MPL_BUBBLEGUM_PROGRAM_ID,
+      logWrapper: SPL_NOOP_PROGRAM_ID,
+      compressionProgram: SPL_ACCOUNT_COMPRESSION_PROGRAM_ID,
+    })
+    .remainingAccounts([
+      ...emissions.map((pubkey) => ({ pubkey, isSigner: false, isWritable: true })),
+      ...proof.map((node) => ({ pubkey: toWeb3JsPublicKey(node), isSigner: false, isWritable: false })),
+    ])
+    .rpc()
+
+  setCnftOwner(umi, cnft, nftAuthority)
+  return sig
+}
+
+export async function unstakeCnft(
+  program: anchor.Program<Stake>,
+  staker: PublicKey,
+  cnft: Cnft,
+  penaltyTreasury: PublicKey | null = null
+) {
+  const owner = fromWeb3JsPublicKey(program.provider.publicKey)
+  const stakeAccount = await program.account.staker.fetch(staker)
+  const collection = findStakooorCollectionId(staker, cnft.collection)
+  const collectionAccount = await program.account.collection.fetch(collection)
+  const stakeRecord = findStakeRecordPda(staker, cnft.assetId)
+  const stakeRecordAccount = await program.account.stakeRecord.fetch(stakeRecord)
+  const { root, proof } = getCnftProof(cnft)
+
+  const tokenMint =
+    collectionAccount.tokenEmission || collectionAccount.selectionEmission
+      ? fromWeb3JsPublicKey(stakeAccount.tokenMint)
+      : null
+  const tokenAuthority = findTokenAuthorityPda(staker)
+  const tokenEmission = collectionAccount.tokenEmission
+    ? await program.account.emission.fetch(collectionAccount.tokenEmission)
+    : null
+
+  const sig = await program.methods
+    .unstakeCnft(
+      Array.from(publicKeyBytes(root)),
+      Array.from(hashMetadataData(cnft.metadata)),
+      Array.from(hashMetadataCreators(cnft.metadata.creators)),
+      new BN(cnft.leafIndex),
+      cnft.leafIndex
+    )
+    .accounts({
+      programConfig: findProgramConfigPda(),
+      staker,
+      collection,
+      stakeRecord,
+      nftRecord: collectionAccount.pointsEmission ? findNftRecordPda(staker, cnft.assetId) : null,
+      rewardMint: tokenMint,
+      stakeTokenVault: tokenMint && stakeAccount.tokenVault ? getTokenAccount(tokenMint, tokenAuthority) : null,
+      rewardReceiveAccount: tokenMint ? getTokenAccount(tokenMint, owner) : null,
+      penaltyTreasury,
+      vestingRecord: tokenEmission?.vesting ? findVestingRecordPda(staker, owner) : null,
+      vestingVault: tokenEmission?.vesting ? findVestingVaultPda(staker, owner) : null,
+      feesWallet: FEES_WALLET,
+      tokenAuthority: tokenMint ? tokenAuthority : null,
+      nftAuthority: findNftAuthorityPda(staker),
+      treeConfig: findTreeConfigPda(umi, { merkleTree: cnft.merkleTree })[0],
+      merkleTree: cnft.merkleTree,
+      owner,
+      bubblegumProgram: MPL_BUBBLEGUM_PROGRAM_ID,
+      logWrapper: SPL_NOOP_PROGRAM_ID,
+      compressionProgram: SPL_ACCOUNT_COMPRESSION_PROGRAM_ID,
+      tokenProgram: SPL_TOKEN_PROGRAM_ID,
+      associatedTokenProgram: anchor.utils.token.ASSOCIATED_PROGRAM_ID,
+    })
+    .remainingAccounts([
+      ...stakeRecordAccount.emissions.map((pubkey) => ({ pubkey, isSigner: false, isWritable: true })),
+      ...proof.map((node) => ({ pubkey: toWeb3JsPublicKey(node), isSigner: false, isWritable: false })),
+    ])
+    .rpc()
+
+  setCnftOwner(umi, cnft, owner)
+  return sig
+}
+
+export async function stakeCore(
+  program: anchor.Program<Stake>,
+  staker: PublicKey,
+  asset: PublicKey,
+  coreCollection: PublicKey,
+  multiplier: MultiplierProof | null = null
+) {
+  const collection = findStakooorCollectionId(staker, coreCollection)
+  const collectionAccount = await program.account.collection.fetch(collection)
+
+  const emissions = compact([
+    collectionAccount.tokenEmission,
+    ...collectionAccount.extraEmissions,
+    collectionAccount.pointsEmission,
+    collectionAccount.selectionEmission,
+    collectionAccount.distributionEmission,
+  ])
+
+  return await program.methods
+    .stakeCore(null, multiplier && { multiplier: new BN(multiplier.multiplier), proof: multiplier.proof })
+    .accounts({
+      staker,
+      collection,
+      nftRecord: collectionAccount.pointsEmission ? findNftRecordPda(staker, asset) : null,
+      stakeRecord: findStakeRecordPda(staker, asset),
+      programConfig: findProgramConfigPda(),
+      nftAuthority: findNftAuthorityPda(staker),
+      asset,
+      coreCollection,
+      feesWallet: FEES_WALLET,
+      coreProgram: MPL_CORE_PROGRAM_ID,
+    })
+    .remainingAccounts(emissions.map((pubkey) => ({ pubkey, isSigner: false, isWritable: true })))
+    .rpc()
+}
+
+export async function unstakeCore(
+  program: anchor.Program<Stake>,
+  staker: PublicKey,
+  asset: PublicKey,
+  coreCollection: PublicKey,
+  penaltyTreasury: PublicKey | null = null
+) {
+  const owner = fromWeb3JsPublicKey(program.provider.publicKey)
+  const stakeAccount = await program.account.staker.fetch(staker)
+  const collection = findStakooorCollectionId(staker, coreCollection)
+  const collectionAccount = await program.account.collection.fetch(collection)
+  const stakeRecord = findStakeRecordPda(staker, asset)
+  const stakeRecordAccount = await program.account.stakeRecord.fetch(stakeRecord)
+
+  const tokenMint =
+    collectionAccount.tokenEmission || collectionAccount.selectionEmission
+      ? fromWeb3JsPublicKey(stakeAccount.tokenMint)
+      : null
+  const tokenAuthority = findTokenAuthorityPda(staker)
+  const tokenEmission = collectionAccount.tokenEmission
+    ? await program.account.emission.fetch(collectionAccount.tokenEmission)
+    : null
+  const rewardAccounts = await otherMintRewardAccounts(program, staker, stakeRecordAccount.emissions, tokenMint, owner)
+  const payoutAccounts = await distributionAccounts(program, staker, stakeRecordAccount.emissions, owner)
+
+  return await program.methods
+    .unstakeCore()
+    .accounts({
+      programConfig: findProgramConfigPda(),
+      staker,
+      collection,
+      stakeRecord,
+      nftRecord: collectionAccount.pointsEmission ? findNftRecordPda(staker, asset) : null,
+      rewardMint: tokenMint,
+      stakeTokenVault: tokenMint && stakeAccount.tokenVault ? getTokenAccount(tokenMint, tokenAuthority) : null,
+      rewardReceiveAccount: tokenMint ? getTokenAccount(tokenMint, owner) : null,
+      penaltyTreasury,
+      vestingRecord: tokenEmission?.vesting ? findVestingRecordPda(staker, owner) : null,
+      vestingVault: tokenEmission?.vesting ? findVestingVaultPda(staker, owner) : null,
+      feesWallet: FEES_WALLET,
+      tokenAuthority,
+      nftAuthority: findNftAuthorityPda(staker),
+      asset,
+      coreCollection,
+      owner,
+      coreProgram: MPL_CORE_PROGRAM_ID,
+      tokenProgram: SPL_TOKEN_PROGRAM_ID,
+      associatedTokenProgram: anchor.utils.token.ASSOCIATED_PROGRAM_ID,
+    })
+    .remainingAccounts([
+      ...stakeRecordAccount.emissions.map((pubkey) => ({ pubkey, isSigner: false, isWritable: true })),
+      ...rewardAccounts,
+      ...payoutAccounts,
+    ])
+    .rpc()
+}
+
+export async function forceUnstakeCore(
+  program: anchor.Program<Stake>,
+  staker: PublicKey,
+  asset: PublicKey,
+  coreCollection: PublicKey
+) {
+  const stakeAccount = await program.account.staker.fetch(staker)
+  const collection = findStakooorCollectionId(staker, coreCollection)
+  const collectionAccount = await program.account.collection.fetch(collection)
+  const stakeRecord = findStakeRecordPda(staker, asset)
+  const stakeRecordAccount = await program.account.stakeRecord.fetch(stakeRecord)
+  const owner = fromWeb3JsPublicKey(stakeRecordAccount.owner)
+
+  const tokenMint =
+    collectionAccount.tokenEmission || collectionAccount.selectionEmission
+      ? fromWeb3JsPublicKey(stakeAccount.tokenMint)
+      : null
+  const tokenAuthority = findTokenAuthorityPda(staker)
+  const rewardAccounts = await otherMintRewardAccounts(program, staker, stakeRecordAccount.emissions, tokenMint, owner)
+  const payoutAccounts = await distributionAccounts(program, staker, stakeRecordAccount.emissions, owner)
+
+  return await program.methods
+    .forceUnstakeCore()
+    .accounts({
+      programConfig: findProgramConfigPda(),
+      staker,
+      collection,
+      stakeRecord,
+      nftRecord: collectionAccount.pointsEmission ? findNftRecordPda(staker, asset) : null,
+      rewardMint: tokenMint,
+      stakeTokenVault: tokenMint && stakeAccount.tokenVault ? getTokenAccount(tokenMint, tokenAuthority) : null,
+      rewardReceiveAccount: tokenMint ? getTokenAccount(tokenMint, owner) : null,
+      feesWallet: FEES_WALLET,
+      tokenAuthority,
+      nftAuthority: findNftAuthorityPda(staker),
+      asset,
+      coreCollection,
+      owner,
+      coreProgram: MPL_CORE_PROGRAM_ID,
+      tokenProgram: SPL_TOKEN_PROGRAM_ID,
+      associatedTokenProgram: anchor.utils.token.ASSOCIATED_PROGRAM_ID,
     })
-    .preInstructions([anchor.web3.ComputeBudgetProgram.setComputeUnitLimit({ units: 350_000 })])
-    .remainingAccounts(
-      emissions.map((pubkey) => ({
-        pubkey,
-        isSigner: false,
-        isWritable: true,
-      }))
-    )
+    .remainingAccounts([
+      ...stakeRecordAccount.emissions.map((pubkey) => ({ pubkey, isSigner: false, isWritable: true })),
+      ...rewardAccounts,
+      ...payoutAccounts,
+    ])
     .rpc()
 }
 
@@ -275,40 +973,62 @@ export async function addEmission(
   minimumPeriod: number = 0,
   startTime: anchor.BN | null = null,
   duration: number | null = null,
-  startingBalance: number | null = null
+  startingBalance: number | null = null,
+  rewardMint: PublicKey | null = null,
+  rewardSchedule: { time: number; reward: number }[] = [],
+  shared: boolean = false
 ) {
   const stakerAccount = await program.account.staker.fetch(staker)
   const collectionAccount = await program.account.collection.fetch(collection)
-  const collectionMint = await fetchDigitalAsset(umi, fromWeb3JsPublicKey(collectionAccount.collectionMint))
+  // Core collections have no token metadata
+  const collectionMetadata = await safeFetchMetadataFromSeeds(umi, {
+    mint: fromWeb3JsPublicKey(collectionAccount.collectionMint),
+  })
   const rewardBn = new BN(reward)
   const durationBn = duration === null ? null : new BN(duration)
   const startingBalanceBn = startingBalance === null ? null : new BN(startingBalance)
   const minimumPeriodBn = new BN(minimumPeriod)
+  const rewardScheduleBn = rewardSchedule.map(({ time, reward }) => ({ time: new BN(time), reward: new BN(reward) }))
   const tokenAuthority = findTokenAuthorityPda(staker)
-  const { tokenMint, tokenVault } = stakerAccount
+  // rewards in a mint other than the staker's token are always paid from the vault
+  const tokenMint = rewardMint ? toWeb3JsPublicKey(rewardMint) : stakerAccount.tokenMint
+  const isStakerMint =
+    !rewardMint || (stakerAccount.tokenMint && rewardMint === fromWeb3JsPublicKey(stakerAccount.tokenMint))
+  const tokenVault = isStakerMint ? stakerAccount.tokenVault : true
+
+  const tokenProgram = tokenMint ? await getTokenProgram(fromWeb3JsPublicKey(tokenMint)) : SPL_TOKEN_PROGRAM_ID
 
   const stakeTokenVault =
-    tokenMint && tokenVault ? getTokenAccount(fromWeb3JsPublicKey(tokenMint), tokenAuthority) : null
+    tokenMint && tokenVault ? getTokenAccount(fromWeb3JsPublicKey(tokenMint), tokenAuthority, tokenProgram) : null
   const tokenAccount = tokenMint
-    ? getTokenAccount(fromWeb3JsPublicKey(tokenMint), fromWeb3JsPublicKey(program.provider.publicKey))
+    ? getTokenAccount(fromWeb3JsPublicKey(tokenMint), fromWeb3JsPublicKey(program.provider.publicKey), tokenProgram)
     : null
 
   const sig = await program.methods
-    .addEmission(rewardType, rewardBn, startTime, durationBn, minimumPeriodBn, startingBalanceBn)
+    .addEmission(
+      rewardType,
+      rewardBn,
+      startTime,
+      durationBn,
+      minimumPeriodBn,
+      startingBalanceBn,
+      rewardScheduleBn,
+      shared
+    )
     .accounts({
+      tokenProgram,
       staker,
       collection,
       tokenAccount,
       tokenMint,
       stakeTokenVault,
-      collectionMetadata: collectionMint.metadata.publicKey,
-      collectionMint: collectionMint.publicKey,
+      collectionMetadata: collectionMetadata?.publicKey ?? null,
+      collectionMint: collectionMetadata?.mint ?? null,
       emission: emission.publicKey,
       tokenAuthority,
     })
     .signers([toWeb3JsKeypair(emission)])
     .rpc()
-    .catch((err) => console.log(err))
 
   return sig
 }
@@ -322,6 +1042,135 @@ export async function toggleCollection(
   return await program.methods.toggleCollectionActive(active).accounts({ staker, collection }).rpc()
 }
 
+export async function updateAllowList(
+  program: anchor.Program<Stake>,
+  staker: PublicKey,
+  collection: PublicKey,
+  allowList: number[] | null
+) {
+  return await program.methods.updateAllowList(allowList).accounts({ staker, collection }).rpc()
+}
+
+export async function updateCollectionCapacity(
+  program: anchor.Program<Stake>,
+  staker: PublicKey,
+  collection: PublicKey,
+  maxStakersCount: number
+) {
+  const stakeAccount = await program.account.staker.fetch(staker)
+  const collectionAccount = await program.account.collection.fetch(collection)
+  const emissions = compact([collectionAccount.tokenEmission, ...collectionAccount.extraEmissions])
+  const tokenMint =
+    stakeAccount.tokenVault && emissions.length ? fromWeb3JsPublicKey(stakeAccount.tokenMint) : null
+  const tokenProgram = tokenMint ? await getTokenProgram(tokenMint) : SPL_TOKEN_PROGRAM_ID
+  const tokenAuthority = findTokenAuthorityPda(staker)
+  const authority = fromWeb3JsPublicKey(program.provider.publicKey)
+  const rewardAccounts = await otherMintRewardAccounts(program, staker, emissions, tokenMint, authority)
+
+  return await program.methods
+    .updateCollectionCapacity(new anchor.BN(maxStakersCount))
+    .accounts({
+      staker,
+      collection,
+      rewardMint: tokenMint,
+      tokenAccount: tokenMint ? getTokenAccount(tokenMint, authority, tokenProgram) : null,
+      stakeTokenVault: tokenMint ? getTokenAccount(tokenMint, tokenAuthority, tokenProgram) : null,
+      tokenAuthority: emissions.length ? tokenAuthority : null,
+      tokenProgram,
+    })
+    .remainingAccounts([
+      ...emissions.map((pubkey) => ({
+        pubkey,
+        isSigner: false,
+        isWritable: true,
+      })),
+      ...rewardAccounts,
+    ])
+    .rpc()
+}
+
+export async function updateStakingStartsAt(
+  program: anchor.Program<Stake>,
+  staker: PublicKey,
+  collection: PublicKey,
+  stakingStartsAt: anchor.BN
+) {
+  return await program.methods.updateStakingStartsAt(stakingStartsAt).accounts({ staker, collection }).rpc()
+}
+
+export async function updateUnbondingPeriod(
+  program: anchor.Program<Stake>,
+  staker: PublicKey,
+  collection: PublicKey,
+  unbondingPeriod: number
+) {
+  return await program.methods
+    .updateUnbondingPeriod(new anchor.BN(unbondingPeriod))
+    .accounts({ staker, collection })
+    .rpc()
+}
+
+export async function updateVesting(
+  program: anchor.Program<Stake>,
+  staker: PublicKey,
+  collection: PublicKey,
+  emission: PublicKey,
+  vesting: { cliff: number; duration: number } | null
+) {
+  return await program.methods
+    .updateVesting(
+      vesting ? { cliff: new anchor.BN(vesting.cliff), duration: new anchor.BN(vesting.duration) } : null
+    )
+    .accounts({ staker, collection, emission })
+    .rpc()
+}
+
+export async function updateEarlyUnstake(
+  program: anchor.Program<Stake>,
+  staker: PublicKey,
+  collection: PublicKey,
+  emission: PublicKey,
+  earlyUnstake: { penalty: number; treasury: PublicKey | null } | null
+) {
+  return await program.methods
+    .updateEarlyUnstake(
+      earlyUnstake ? { penalty: new anchor.BN(earlyUnstake.penalty), treasury: earlyUnstake.treasury } : null
+    )
+    .accounts({ staker, collection, emission })
+    .rpc()
+}
+
+export async function updateEmissionMultipliers(
+  program: anchor.Program<Stake>,
+  staker: PublicKey,
+  collection: PublicKey,
+  emission: PublicKey,
+  merkleRoot: number[] | null,
+  maxMultiplier: number,
+  otherEmissions: PublicKey[] = []
+) {
+  return await program.methods
+    .updateEmissionMultipliers(merkleRoot, new anchor.BN(maxMultiplier))
+    .accounts({ staker, collection, emission })
+    .remainingAccounts(otherEmissions.map((pubkey) => ({ pubkey, isWritable: false, isSigner: false })))
+    .rpc()
+}
+
+export async function updateLockTiers(
+  program: anchor.Program<Stake>,
+  staker: PublicKey,
+  collection: PublicKey,
+  emission: PublicKey,
+  lockTiers: { duration: number; boost: number }[]
+) {
+  return await program.methods
+    .updateLockTiers(
+      lockTiers.map(({ duration, boost }) => ({ duration: new anchor.BN(duration), boost: new anchor.BN(boost) }))
+    )
+    .accounts({ staker, collection, emission })
+    .rpc()
+}
+
 export async function toggleStake(program: anchor.Program<Stake>, staker: PublicKey, active: boolean) {
   return await program.methods.toggleStakeActive(active).accounts({ staker }).rpc()
 }
@@ -331,11 +1180,13 @@ export async function initCollection(
   staker: PublicKey,
   collectionMintPk: PublicKey,
   custodial: boolean = false,
-  startTime: anchor.BN | null = null
+  startTime: anchor.BN | null = null,
+  maxStakersCount: number | null = null
 ) {
   let stakerAccount = await program.account.staker.fetch(staker)
-  const collectionMint = await fetchDigitalAsset(umi, collectionMintPk)
-  const maxStakerCount = unwrapOption(collectionMint.metadata.collectionDetails).size
+  // Core collections have no collection details, so their size is passed in
+  const maxStakerCount =
+    maxStakersCount ?? unwrapOption((await fetchDigitalAsset(umi, collectionMintPk)).metadata.collectionDetails).size
 
   const collection = findStakooorCollectionId(staker, collectionMintPk)
   const tokenAuthority = findTokenAuthorityPda(staker)
@@ -415,12 +1266,13 @@ export async function addToken(
   tokenMint: PublicKey,
   useTokenVault: boolean
 ) {
-  const tokenAccount = getTokenAccount(tokenMint, fromWeb3JsPublicKey(program.provider.publicKey))
+  const tokenProgram = await getTokenProgram(tokenMint)
+  const tokenAccount = getTokenAccount(tokenMint, fromWeb3JsPublicKey(program.provider.publicKey), tokenProgram)
   const tokenAuthority = findTokenAuthorityPda(staker)
-  const tokenVault = getTokenAccount(tokenMint, tokenAuthority)
+  const tokenVault = getTokenAccount(tokenMint, tokenAuthority, tokenProgram)
   const sig = await program.methods
     .addToken(useTokenVault)
-    .accounts({ staker, tokenMint, tokenAccount, tokenAuthority, tokenVault })
+    .accounts({ staker, tokenMint, tokenAccount, tokenAuthority, tokenVault, tokenProgram })
     .rpc()
 
   return sig
@@ -437,26 +1289,61 @@ export async function claim(program: anchor.Program<Stake>, staker: PublicKey, n
 
   const isToken = isEqual(emissionAccount.rewardType, { token: {} })
 
-  const tokenMint = isToken ? fromWeb3JsPublicKey(stakeAccount.tokenMint) : null
+  // pro-rata distributions pay from the distribution linked to the emission
+  const distribution = emissionAccount.distribution ? fromWeb3JsPublicKey(emissionAccount.distribution) : null
+  const distributionVaultAuthority = distribution ? findVaultAuthorityPda(staker, distribution) : null
+  const distributionMint =
+    distribution && emissionAccount.tokenMint ? fromWeb3JsPublicKey(emissionAccount.tokenMint) : null
+
+  // emissions pay in their own mint and mode, falling back to the staker's token
+  const tokenMint = isToken
+    ? fromWeb3JsPublicKey(emissionAccount.tokenMint || stakeAccount.tokenMint)
+    : distributionMint
+  const tokenVault = emissionAccount.tokenMint ? emissionAccount.tokenVault : stakeAccount.tokenVault
+  const tokenProgram = tokenMint ? await getTokenProgram(tokenMint) : SPL_TOKEN_PROGRAM_ID
   const rewardReceiveAccount =
-    tokenMint && (collectionAccount.tokenEmission || collectionAccount.selectionEmission)
-      ? getTokenAccount(tokenMint, fromWeb3JsPublicKey(program.provider.publicKey))
+    tokenMint && (collectionAccount.tokenEmission || collectionAccount.selectionEmission || distributionMint)
+      ? getTokenAccount(tokenMint, fromWeb3JsPublicKey(program.provider.publicKey), tokenProgram)
       : null
   const tokenAuthority = findTokenAuthorityPda(staker)
-  const stakeTokenVault = stakeAccount.tokenVault ? getTokenAccount(tokenMint, tokenAuthority) : null
+  const stakeTokenVault =
+    isToken && tokenMint && tokenVault ? getTokenAccount(tokenMint, tokenAuthority, tokenProgram) : null
+  const distributionVault = distributionMint
+    ? getTokenAccount(distributionMint, distributionVaultAuthority, tokenProgram)
+    : null
   const programConfig = findProgramConfigPda()
 
   const stakeRecord = findStakeRecordPda(staker, nft.publicKey)
 
+  // claiming a token emission settles the collection's other token emissions too
+  const otherEmissions = isToken
+    ? compact([collectionAccount.tokenEmission, ...collectionAccount.extraEmissions]).filter(
+        (key) => fromWeb3JsPublicKey(key) !== emission
+      )
+    : []
+  const otherEmissionAccounts = await Promise.all(otherEmissions.map((key) => program.account.emission.fetch(key)))
+
+  const owner = fromWeb3JsPublicKey(program.provider.publicKey)
+  const isVesting = isToken && [emissionAccount, ...otherEmissionAccounts].some((account) => !!account.vesting)
+  const vestingRecord = isVesting ? findVestingRecordPda(staker, owner) : null
+  const vestingVault = isVesting ? findVestingVaultPda(staker, owner) : null
+  const rewardAccounts = await otherMintRewardAccounts(program, staker, otherEmissions, tokenMint, owner)
+
   return await program.methods
     .claim()
     .accounts({
+      tokenProgram,
       staker,
       collection,
       nftRecord,
       rewardReceiveAccount,
       tokenAuthority,
       stakeTokenVault,
+      vestingRecord,
+      vestingVault,
+      distribution,
+      distributionVault,
+      distributionVaultAuthority,
       tokenMint,
       emission,
       programConfig,
@@ -464,6 +1351,283 @@ export async function claim(program: anchor.Program<Stake>, staker: PublicKey, n
       owner: program.provider.publicKey,
       feesWallet: FEES_WALLET,
     })
+    .remainingAccounts([
+      ...otherEmissions.map((pubkey) => ({
+        pubkey,
+        isSigner: false,
+        isWritable: true,
+      })),
+      ...rewardAccounts,
+    ])
+    .rpc()
+}
+
+export async function withdrawVested(program: anchor.Program<Stake>, staker: PublicKey) {
+  const stakeAccount = await program.account.staker.fetch(staker)
+  const owner = fromWeb3JsPublicKey(program.provider.publicKey)
+  const tokenMint = fromWeb3JsPublicKey(stakeAccount.tokenMint)
+
+  return await program.methods
+    .withdrawVested()
+    .accounts({
+      staker,
+      vestingRecord: findVestingRecordPda(staker, owner),
+      vestingVault: findVestingVaultPda(staker, owner),
+      tokenMint,
+      rewardReceiveAccount: getTokenAccount(tokenMint, owner),
+      tokenAuthority: findTokenAuthorityPda(staker),
+      tokenProgram: SPL_TOKEN_PROGRAM_ID,
+    })
+    .rpc()
+}
+
+type PointsItemKind = { token: { mint: anchor.web3.PublicKey; amount: anchor.BN } } | { whitelist: {} }
+
+export async function addPointsItem(
+  program: anchor.Program<Stake>,
+  pointsItem: Keypair,
+  staker: PublicKey,
+  kind: PointsItemKind,
+  price: number,
+  supply: number | null = null
+) {
+  const authority = fromWeb3JsPublicKey(program.provider.publicKey)
+  const tokenAuthority = findTokenAuthorityPda(staker)
+  const tokenMint = "token" in kind ? fromWeb3JsPublicKey(kind.token.mint) : null
+
+  return await program.methods
+    .addPointsItem(kind, new BN(price), supply === null ? null : new BN(supply))
+    .accounts({
+      staker,
+      pointsItem: pointsItem.publicKey,
+      tokenMint,
+      tokenAccount: tokenMint ? getTokenAccount(tokenMint, authority) : null,
+      stakeTokenVault: tokenMint ? getTokenAccount(tokenMint, tokenAuthority) : null,
+      tokenAuthority,
+      tokenProgram: SPL_TOKEN_PROGRAM_ID,
+    })
+    .signers([toWeb3JsKeypair(pointsItem)])
+    .rpc()
+}
+
+export async function redeemPoints(
+  program: anchor.Program<Stake>,
+  staker: PublicKey,
+  pointsItem: PublicKey,
+  nftMint: PublicKey,
+  staked: boolean = true
+) {
+  const owner = fromWeb3JsPublicKey(program.provider.publicKey)
+  const pointsItemAccount = await program.account.pointsItem.fetch(pointsItem)
+  const tokenAuthority = findTokenAuthorityPda(staker)
+  const rewardMint = "token" in pointsItemAccount.kind ? fromWeb3JsPublicKey(pointsItemAccount.kind.token.mint) : null
+
+  return await program.methods
+    .redeemPoints()
+    .accounts({
+      staker,
+      pointsItem,
+      nftRecord: findNftRecordPda(staker, nftMint),
+      stakeRecord: staked ? findStakeRecordPda(staker, nftMint) : null,
+      nftToken: staked ? null : getTokenAccount(nftMint, owner),
+      pointsReceipt: findPointsReceiptPda(pointsItem, BigInt(pointsItemAccount.redeemed.toString())),
+      rewardMint,
+      stakeTokenVault: rewardMint ? getTokenAccount(rewardMint, tokenAuthority) : null,
+      rewardReceiveAccount: rewardMint ? getTokenAccount(rewardMint, owner) : null,
+      tokenAuthority: rewardMint ? tokenAuthority : null,
+      tokenProgram: SPL_TOKEN_PROGRAM_ID,
+    })
+    .rpc()
+}
+
+export async function initPool(
+  program: anchor.Program<Stake>,
+  staker: PublicKey,
+  stakeMint: PublicKey,
+  maxStakers: number,
+  startTime: anchor.BN | null = null
+) {
+  const collection = findStakooorCollectionId(staker, stakeMint)
+  const nftAuthority = findNftAuthorityPda(staker)
+
+  return await program.methods
+    .initPool(startTime, new anchor.BN(maxStakers))
+    .accounts({
+      staker,
+      collection,
+      stakeMint,
+      poolVault: getTokenAccount(stakeMint, nftAuthority),
+      nftAuthority,
+      tokenProgram: SPL_TOKEN_PROGRAM_ID,
+    })
+    .rpc()
+}
+
+export async function stakeTokens(
+  program: anchor.Program<Stake>,
+  staker: PublicKey,
+  stakeMint: PublicKey,
+  amount: anchor.BN
+) {
+  const owner = fromWeb3JsPublicKey(program.provider.publicKey)
+  const collection = findStakooorCollectionId(staker, stakeMint)
+  const collectionAccount = await program.account.collection.fetch(collection)
+  const nftAuthority = findNftAuthorityPda(staker)
+
+  return await program.methods
+    .stakeTokens(amount)
+    .accounts({
+      staker,
+      collection,
+      emission: collectionAccount.tokenEmission,
+      poolRecord: findPoolRecordPda(staker, collection, owner),
+      programConfig: findProgramConfigPda(),
+      stakeMint,
+      tokenAccount: getTokenAccount(stakeMint, owner),
+      poolVault: getTokenAccount(stakeMint, nftAuthority),
+      nftAuthority,
+      feesWallet: FEES_WALLET,
+      tokenProgram: SPL_TOKEN_PROGRAM_ID,
+    })
+    .rpc()
+}
+
+export async function unstakeTokens(
+  program: anchor.Program<Stake>,
+  staker: PublicKey,
+  stakeMint: PublicKey,
+  amount: anchor.BN
+) {
+  const owner = fromWeb3JsPublicKey(program.provider.publicKey)
+  const stakeAccount = await program.account.staker.fetch(staker)
+  const collection = findStakooorCollectionId(staker, stakeMint)
+  const poolRecord = findPoolRecordPda(staker, collection, owner)
+  const poolRecordAccount = await program.account.poolRecord.fetch(poolRecord)
+  const nftAuthority = findNftAuthorityPda(staker)
+  const tokenAuthority = findTokenAuthorityPda(staker)
+
+  const rewardMint = poolRecordAccount.emission ? fromWeb3JsPublicKey(stakeAccount.tokenMint) : null
+
+  return await program.methods
+    .unstakeTokens(amount)
+    .accounts({
+      programConfig: findProgramConfigPda(),
+      staker,
+      collection,
+      emission: poolRecordAccount.emission,
+      poolRecord,
+      stakeMint,
+      tokenAccount: getTokenAccount(stakeMint, owner),
+      poolVault: getTokenAccount(stakeMint, nftAuthority),
+      rewardMint,
+      stakeTokenVault: rewardMint && stakeAccount.tokenVault ? getTokenAccount(rewardMint, tokenAuthority) : null,
+      rewardReceiveAccount: rewardMint ? getTokenAccount(rewardMint, owner) : null,
+      tokenAuthority,
+      nftAuthority,
+      owner,
+      feesWallet: FEES_WALLET,
+      tokenProgram: SPL_TOKEN_PROGRAM_ID,
+      rewardTokenProgram: SPL_TOKEN_PROGRAM_ID,
+    })
+    .rpc()
+}
+
+export async function claimPool(program: anchor.Program<Stake>, staker: PublicKey, stakeMint: PublicKey) {
+  const owner = fromWeb3JsPublicKey(program.provider.publicKey)
+  const stakeAccount = await program.account.staker.fetch(staker)
+  const collection = findStakooorCollectionId(staker, stakeMint)
+  const poolRecord = findPoolRecordPda(staker, collection, owner)
+  const poolRecordAccount = await program.account.poolRecord.fetch(poolRecord)
+  const tokenMint = fromWeb3JsPublicKey(stakeAccount.tokenMint)
+  const tokenAuthority = findTokenAuthorityPda(staker)
+
+  return await program.methods
+    .claimPool()
+    .accounts({
+      programConfig: findProgramConfigPda(),
+      staker,
+      collection,
+      emission: poolRecordAccount.emission,
+      poolRecord,
+      tokenMint,
+      stakeTokenVault: stakeAccount.tokenVault ? getTokenAccount(tokenMint, tokenAuthority) : null,
+      rewardReceiveAccount: getTokenAccount(tokenMint, owner),
+      tokenAuthority,
+      owner,
+      feesWallet: FEES_WALLET,
+      tokenProgram: SPL_TOKEN_PROGRAM_ID,
+    })
+    .rpc()
+}
+
+export async function claimAll(
+  program: anchor.Program<Stake>,
+  staker: PublicKey,
+  nfts: DigitalAsset[],
+  withNftRecords: boolean = true
+) {
+  const stakeAccount = await program.account.staker.fetch(staker)
+  const collectionMintPk = unwrapOption(nfts[0].metadata.collection).key
+  const collection = findStakooorCollectionId(staker, collectionMintPk)
+  const collectionAccount = await program.account.collection.fetch(collection)
+
+  const emissions = compact([
+    collectionAccount.tokenEmission,
+    ...collectionAccount.extraEmissions,
+    collectionAccount.pointsEmission,
+    collectionAccount.selectionEmission,
+    collectionAccount.distributionEmission,
+  ])
+
+  const isToken = !!(collectionAccount.tokenEmission || collectionAccount.selectionEmission)
+  const tokenMint = isToken ? fromWeb3JsPublicKey(stakeAccount.tokenMint) : null
+  const rewardReceiveAccount = tokenMint
+    ? getTokenAccount(tokenMint, fromWeb3JsPublicKey(program.provider.publicKey))
+    : null
+  const tokenAuthority = findTokenAuthorityPda(staker)
+  const stakeTokenVault = tokenMint && stakeAccount.tokenVault ? getTokenAccount(tokenMint, tokenAuthority) : null
+
+  const rewardAccounts = await otherMintRewardAccounts(
+    program,
+    staker,
+    compact([
+      collectionAccount.tokenEmission,
+      ...collectionAccount.extraEmissions,
+      collectionAccount.selectionEmission,
+    ]),
+    tokenMint,
+    fromWeb3JsPublicKey(program.provider.publicKey)
+  )
+
+  const records = nfts.flatMap((nft) =>
+    compact([
+      findStakeRecordPda(staker, nft.publicKey),
+      collectionAccount.pointsEmission && withNftRecords ? findNftRecordPda(staker, nft.publicKey) : null,
+    ])
+  )
+
+  return await program.methods
+    .claimAll()
+    .accounts({
+      tokenProgram: SPL_TOKEN_PROGRAM_ID,
+      staker,
+      collection,
+      rewardReceiveAccount,
+      tokenAuthority,
+      stakeTokenVault,
+      tokenMint,
+      programConfig: findProgramConfigPda(),
+      owner: program.provider.publicKey,
+      feesWallet: FEES_WALLET,
+    })
+    .remainingAccounts([
+      ...[...emissions, ...records.map(toWeb3JsPublicKey)].map((pubkey) => ({
+        pubkey,
+        isSigner: false,
+        isWritable: true,
+      })),
+      ...rewardAccounts,
+    ])
     .rpc()
 }
 
@@ -660,6 +1824,7 @@ export async function addFunds(
   await program.methods
     .addFunds(amount)
     .accounts({
+      tokenProgram: SPL_TOKEN_PROGRAM_ID,
       staker,
       collection,
       rewardMint: collectionAccount.rewardToken,
@@ -683,7 +1848,8 @@ export async function updateProgramConfig(
   ultimateSubscriptionFee: anchor.BN | null = null,
   extraCollectionFee: anchor.BN | null = null,
   removeBrandingFee: anchor.BN | null = null,
-  ownDomainFee: anchor.BN | null = null
+  ownDomainFee: anchor.BN | null = null,
+  claimFeePerRecord: boolean | null = null
 ) {
   return await program.methods
     .updateProgramConfig(
@@ -695,7 +1861,8 @@ export async function updateProgramConfig(
       ultimateSubscriptionFee,
       extraCollectionFee,
       removeBrandingFee,
-      ownDomainFee
+      ownDomainFee,
+      claimFeePerRecord
     )
     .accounts({
       programConfig: findProgramConfigPda(),
@@ -714,6 +1881,7 @@ export async function removeFunds(program: anchor.Program<Stake>, staker: Public
   await program.methods
     .removeFunds()
     .accounts({
+      tokenProgram: SPL_TOKEN_PROGRAM_ID,
       staker,
       collection,
       tokenAuthority,
@@ -777,3 +1945,7 @@ export async function updateTheme(
     .accounts({ staker: stakerId })
     .rpc()
 }
+
+export async function migrate(program: anchor.Program<Stake>, account: PublicKey) {
+  return await program.methods.migrate().accounts({ account }).rpc()
+}
@@ -0,0 +1,65 @@
+import { keccak_256 } from "@noble/hashes/sha3"
+import { PublicKey, publicKeyBytes } from "@metaplex-foundation/umi"
+
+function hash(...parts: Uint8Array[]) {
+  return Buffer.from(keccak_256(Buffer.concat(parts)))
+}
+
+function u32(value: number) {
+  const bytes = Buffer.alloc(4)
+  bytes.writeUInt32LE(value)
+  return bytes
+}
+
+function u64(value: number | bigint) {
+  const bytes = Buffer.alloc(8)
+  bytes.writeBigUInt64LE(BigInt(value))
+  return bytes
+}
+
+export function allowListLeaf(mint: PublicKey) {
+  return hash(publicKeyBytes(mint))
+}
+
+export function multiplierLeaf(mint: PublicKey, multiplier: number) {
+  return hash(publicKeyBytes(mint), u64(multiplier))
+}
+
+export function distributionLeaf(index: number, claimant: PublicKey, amount: number | bigint) {
+  return hash(u32(index), publicKeyBytes(claimant), u64(amount))
+}
+
+// pairs are hashed in sorted order to match the program's proof verification,
+// an odd node out is carried up to the next layer
+export function createMerkleTree(leaves: Buffer[]) {
+  const layers = [leaves]
+
+  while (layers[layers.length - 1].length > 1) {
+    const layer = layers[layers.length - 1]
+    const next: Buffer[] = []
+
+    for (let i = 0; i < layer.length; i += 2) {
+      const [left, right] = [layer[i], layer[i + 1]]
+      next.push(!right ? left : Buffer.compare(left, right) <= 0 ? hash(left, right) : hash(right, left))
+    }
+
+    layers.push(next)
+  }
+
+  return {
+    root: Array.from(layers[layers.length - 1][0]),
+    getProof(index: number) {
+      const proof: number[][] = []
+
+      for (const layer of layers.slice(0, -1)) {
+        const sibling = layer[index % 2 ? index - 1 : index + 1]
+        if (sibling) {
+          proof.push(Array.from(sibling))
+        }
+        index = Math.floor(index / 2)
+      }
+
+      return proof
+    },
+  }
+}
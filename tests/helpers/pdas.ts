@@ -1,8 +1,8 @@
 import { umi } from "./umi"
 import idl from "../../target/idl/stake.json"
 import { PublicKey, publicKey } from "@metaplex-foundation/umi"
-import { string, publicKey as publicKeySerializer } from "@metaplex-foundation/umi-serializers"
-import { findAssociatedTokenPda } from "@metaplex-foundation/mpl-toolbox"
+import { string, u64, publicKey as publicKeySerializer } from "@metaplex-foundation/umi-serializers"
+import { SPL_TOKEN_PROGRAM_ID, findAssociatedTokenPda } from "@metaplex-foundation/mpl-toolbox"
 import { findMasterEditionPda, findMetadataPda, findTokenRecordPda } from "@metaplex-foundation/mpl-token-metadata"
 
 const programId = publicKey(idl.metadata.address)
@@ -78,6 +78,14 @@ export function findShareRecordPda(distribution: PublicKey, nftMint: PublicKey)
   ])[0]
 }
 
+export function findClaimBitmapPda(distribution: PublicKey) {
+  return umi.eddsa.findPda(programId, [
+    string({ size: "variable" }).serialize("STAKE"),
+    publicKeySerializer().serialize(distribution),
+    string({ size: "variable" }).serialize("claim-bitmap"),
+  ])[0]
+}
+
 export function findNftMetadataPda(nftMint: PublicKey) {
   return findMetadataPda(umi, { mint: nftMint })[0]
 }
@@ -86,10 +94,11 @@ export function findNftMasterEditionPda(nftMint: PublicKey) {
   return findMasterEditionPda(umi, { mint: nftMint })[0]
 }
 
-export function getTokenAccount(mint: PublicKey, owner: PublicKey) {
+export function getTokenAccount(mint: PublicKey, owner: PublicKey, tokenProgramId: PublicKey = SPL_TOKEN_PROGRAM_ID) {
   return findAssociatedTokenPda(umi, {
     owner,
     mint,
+    tokenProgramId,
   })[0]
 }
 
@@ -99,3 +108,40 @@ export function getTokenRecordPda(mint: PublicKey, owner: PublicKey) {
     token: getTokenAccount(mint, owner),
   })[0]
 }
+
+export function findPoolRecordPda(staker: PublicKey, collection: PublicKey, owner: PublicKey) {
+  return umi.eddsa.findPda(programId, [
+    string({ size: "variable" }).serialize("STAKE"),
+    publicKeySerializer().serialize(staker),
+    publicKeySerializer().serialize(collection),
+    publicKeySerializer().serialize(owner),
+    string({ size: "variable" }).serialize("pool-record"),
+  ])[0]
+}
+
+export function findPointsReceiptPda(pointsItem: PublicKey, index: number | bigint) {
+  return umi.eddsa.findPda(programId, [
+    string({ size: "variable" }).serialize("STAKE"),
+    publicKeySerializer().serialize(pointsItem),
+    u64().serialize(index),
+    string({ size: "variable" }).serialize("points-receipt"),
+  ])[0]
+}
+
+export function findVestingRecordPda(staker: PublicKey, owner: PublicKey) {
+  return umi.eddsa.findPda(programId, [
+    string({ size: "variable" }).serialize("STAKE"),
+    publicKeySerializer().serialize(staker),
+    publicKeySerializer().serialize(owner),
+    string({ size: "variable" }).serialize("vesting-record"),
+  ])[0]
+}
+
+export function findVestingVaultPda(staker: PublicKey, owner: PublicKey) {
+  return umi.eddsa.findPda(programId, [
+    string({ size: "variable" }).serialize("STAKE"),
+    publicKeySerializer().serialize(staker),
+    publicKeySerializer().serialize(owner),
+    string({ size: "variable" }).serialize("vesting-vault"),
+  ])[0]
+}
@@ -2,6 +2,8 @@ import fs from "fs"
 import { createUmi } from "@metaplex-foundation/umi-bundle-defaults"
 import { mplTokenMetadata } from "@metaplex-foundation/mpl-token-metadata"
 import { mplToolbox } from "@metaplex-foundation/mpl-toolbox"
+import { mplBubblegum } from "@metaplex-foundation/mpl-bubblegum"
+import { mplCore } from "@metaplex-foundation/mpl-core"
 import { Keypair, Signer, createSignerFromKeypair, keypairIdentity, signerIdentity } from "@metaplex-foundation/umi"
 
 const kpfile = "/Users/joefitter/.config/solana/id.json"
@@ -10,6 +12,8 @@ const kp = new Uint8Array(JSON.parse(fs.readFileSync(kpfile).toString()))
 export const umi = createUmi("http://localhost:8899", { commitment: "processed" })
   .use(mplTokenMetadata())
   .use(mplToolbox())
+  .use(mplBubblegum())
+  .use(mplCore())
 
 umi.use(keypairIdentity(umi.eddsa.createKeypairFromSecretKey(kp)))
 
@@ -17,5 +21,7 @@ export function getUmi(kp: Keypair) {
   return createUmi("http://localhost:8899", { commitment: "processed" })
     .use(mplTokenMetadata())
     .use(mplToolbox())
+    .use(mplBubblegum())
+    .use(mplCore())
     .use(keypairIdentity(kp))
 }
@@ -1,5 +1,6 @@
 import * as anchor from "@coral-xyz/anchor"
 import { PublicKey, Umi, publicKey } from "@metaplex-foundation/umi"
+import { SPL_TOKEN_PROGRAM_ID } from "@metaplex-foundation/mpl-toolbox"
 import assert from "assert"
 import { createNft } from "./create-nft"
 import { umi } from "./umi"
@@ -33,6 +34,12 @@ export async function mintNfts(collection: PublicKey, num: number, isPnft: boole
   return await Promise.all(Array.from(new Array(num).keys()).map((async) => createNft(umi, isPnft, collection, owner)))
 }
 
+// Token-2022 mints and their token accounts are owned by the Token-2022 program
+export async function getTokenProgram(mint: PublicKey) {
+  const account = await umi.rpc.getAccount(mint)
+  return account.exists ? account.owner : SPL_TOKEN_PROGRAM_ID
+}
+
 export async function getStakedItemsForUser(program: anchor.Program<Stake>, bytes: string) {
   return program.account.stakeRecord.all([
     {
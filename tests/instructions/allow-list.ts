@@ -0,0 +1,114 @@
+import * as anchor from "@coral-xyz/anchor"
+import { Keypair, PublicKey } from "@metaplex-foundation/umi"
+import { DigitalAsset } from "@metaplex-foundation/mpl-token-metadata"
+import { AuthorityType, setAuthority } from "@metaplex-foundation/mpl-toolbox"
+import { assert } from "chai"
+import { BN } from "bn.js"
+import { Stake } from "../../target/types/stake"
+import { createNewUser, programPaidBy } from "../helper"
+import { createCollection } from "../helpers/create-collection"
+import { createToken } from "../helpers/create-token"
+import {
+  addEmission,
+  addToken,
+  init,
+  initCollection,
+  stake,
+  toggleCollection,
+  toggleStake,
+  updateAllowList,
+} from "../helpers/instructions"
+import { allowListLeaf, createMerkleTree } from "../helpers/merkle"
+import { findStakeRecordPda, findStakooorCollectionId } from "../helpers/pdas"
+import { umi } from "../helpers/umi"
+import { assertErrorCode, expectFail, mintNfts } from "../helpers/utils"
+
+describe("Allow list", () => {
+  let creator: Keypair
+  let creatorProgram: anchor.Program<Stake>
+  let user: Keypair
+  let userProgram: anchor.Program<Stake>
+  let collectionNft: DigitalAsset
+  let collection: PublicKey
+  let outsiders: DigitalAsset[]
+  let allowList: ReturnType<typeof createMerkleTree>
+  let token: PublicKey
+  const staker = umi.eddsa.generateKeypair()
+  const emission = umi.eddsa.generateKeypair()
+
+  function stakeByProof(nft: DigitalAsset, proof: number[][]) {
+    return stake(userProgram, staker.publicKey, nft, proof, null, null, collectionNft.publicKey)
+  }
+
+  before(async () => {
+    creator = await createNewUser()
+    user = await createNewUser()
+    creatorProgram = programPaidBy(creator)
+    userProgram = programPaidBy(user)
+    collectionNft = await createCollection(umi)
+    collection = findStakooorCollectionId(staker.publicKey, collectionNft.publicKey)
+
+    // NFTs from another collection, only the first is on the allow list
+    const otherCollection = await createCollection(umi)
+    outsiders = await mintNfts(otherCollection.publicKey, 2, false, user.publicKey)
+    allowList = createMerkleTree([
+      allowListLeaf(outsiders[0].publicKey),
+      allowListLeaf(umi.eddsa.generateKeypair().publicKey),
+    ])
+
+    token = await createToken(umi, BigInt(10_000), 0, undefined, creator.publicKey)
+    await setAuthority(umi, {
+      owned: token,
+      owner: umi.identity.publicKey,
+      newAuthority: creator.publicKey,
+      authorityType: AuthorityType.MintTokens,
+    }).sendAndConfirm(umi)
+
+    await init(creatorProgram, staker, "allow_list", "Allow list", token)
+    await initCollection(creatorProgram, staker.publicKey, collectionNft.publicKey, false, null, 10)
+    await addToken(creatorProgram, staker.publicKey, token, false)
+    await addEmission(creatorProgram, emission, staker.publicKey, collection, { token: {} }, 1, 0, null, 3600)
+    await toggleCollection(creatorProgram, staker.publicKey, collection, true)
+    await toggleStake(creatorProgram, staker.publicKey, true)
+  })
+
+  it("Cannot stake by proof before an allow list is set", async () => {
+    await expectFail(
+      () => stakeByProof(outsiders[0], allowList.getProof(0)),
+      (err) => assertErrorCode(err, "NoAllowList")
+    )
+  })
+
+  it("Cannot be updated by anyone other than the staker authority", async () => {
+    await expectFail(
+      () => updateAllowList(userProgram, staker.publicKey, collection, allowList.root),
+      (err) => assertErrorCode(err, "Unauthorized")
+    )
+  })
+
+  it("Can set the allow list root", async () => {
+    await updateAllowList(creatorProgram, staker.publicKey, collection, allowList.root)
+
+    const collectionAccount = await creatorProgram.account.collection.fetch(collection)
+    assert.deepEqual(Array.from(collectionAccount.allowList.toBytes()), allowList.root, "Expected the root to be set")
+  })
+
+  it("Cannot stake an NFT that isn't on the allow list", async () => {
+    await expectFail(
+      () => stakeByProof(outsiders[1], allowList.getProof(0)),
+      (err) => assertErrorCode(err, "InvalidProof")
+    )
+  })
+
+  it("Can stake an allow listed NFT from outside the collection", async () => {
+    await stakeByProof(outsiders[0], allowList.getProof(0))
+
+    const record = await userProgram.account.stakeRecord.fetch(
+      findStakeRecordPda(staker.publicKey, outsiders[0].publicKey)
+    )
+    assert.ok(record.owner.equals(userProgram.provider.publicKey), "Expected the staker to own the record")
+
+    const collectionAccount = await userProgram.account.collection.fetch(collection)
+    assert.ok(collectionAccount.currentStakersCount.eq(new BN(1)), "Expected 1 staked item")
+  })
+})
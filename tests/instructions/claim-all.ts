@@ -0,0 +1,177 @@
+import * as anchor from "@coral-xyz/anchor"
+import { Keypair, PublicKey } from "@metaplex-foundation/umi"
+import { DigitalAsset } from "@metaplex-foundation/mpl-token-metadata"
+import { AuthorityType, safeFetchToken, setAuthority } from "@metaplex-foundation/mpl-toolbox"
+import { assert } from "chai"
+import { Stake } from "../../target/types/stake"
+import { adminProgram, createNewUser, programPaidBy } from "../helper"
+import { createCollection } from "../helpers/create-collection"
+import { createToken } from "../helpers/create-token"
+import {
+  addEmission,
+  addToken,
+  claimAll,
+  init,
+  initCollection,
+  sleep,
+  stake,
+  toggleCollection,
+  toggleStake,
+  updateProgramConfig,
+} from "../helpers/instructions"
+import { findNftRecordPda, findProgramConfigPda, findStakooorCollectionId, getTokenAccount } from "../helpers/pdas"
+import { umi } from "../helpers/umi"
+import { FEES_WALLET, assertErrorCode, expectFail, mintNfts } from "../helpers/utils"
+
+describe("Claim all", () => {
+  let creator: Keypair
+  let creatorProgram: anchor.Program<Stake>
+  let user: Keypair
+  let userProgram: anchor.Program<Stake>
+  let collectionNft: DigitalAsset
+  let nfts: DigitalAsset[]
+  let token: PublicKey
+  let collection: PublicKey
+  const staker = umi.eddsa.generateKeypair()
+  const emission = umi.eddsa.generateKeypair()
+
+  before(async () => {
+    creator = await createNewUser()
+    user = await createNewUser()
+    creatorProgram = programPaidBy(creator)
+    userProgram = programPaidBy(user)
+    collectionNft = await createCollection(umi)
+    collection = findStakooorCollectionId(staker.publicKey, collectionNft.publicKey)
+    nfts = await mintNfts(collectionNft.publicKey, 2, false, user.publicKey)
+
+    token = await createToken(umi, BigInt(10_000), 0, undefined, creator.publicKey)
+    await setAuthority(umi, {
+      owned: token,
+      owner: umi.identity.publicKey,
+      newAuthority: creator.publicKey,
+      authorityType: AuthorityType.MintTokens,
+    }).sendAndConfirm(umi)
+
+    await init(creatorProgram, staker, "claim_all", "Claim all", token)
+    await initCollection(creatorProgram, staker.publicKey, collectionNft.publicKey)
+    await addToken(creatorProgram, staker.publicKey, token, false)
+    await addEmission(creatorProgram, emission, staker.publicKey, collection, { token: {} }, 1, 0, null, 3600)
+    await toggleCollection(creatorProgram, staker.publicKey, collection, true)
+    await toggleStake(creatorProgram, staker.publicKey, true)
+
+    for (const nft of nfts) {
+      await stake(userProgram, staker.publicKey, nft)
+    }
+  })
+
+  after(async () => {
+    await updateProgramConfig(adminProgram, null, null, null, null, null, null, null, null, null, false)
+  })
+
+  it("Cannot claim for a stake record owned by someone else", async () => {
+    const newUser = await createNewUser()
+    const newUserProgram = programPaidBy(newUser)
+
+    await expectFail(
+      () => claimAll(newUserProgram, staker.publicKey, nfts),
+      (err) => assertErrorCode(err, "Unauthorized")
+    )
+  })
+
+  it("Charges a single claim fee by default", async () => {
+    await sleep(1_000)
+    const programConfig = await adminProgram.account.programConfig.fetch(findProgramConfigPda())
+    const feesBefore = await umi.rpc.getBalance(FEES_WALLET)
+    const tokensBefore = (await safeFetchToken(umi, getTokenAccount(token, user.publicKey)))?.amount || BigInt(0)
+
+    await claimAll(userProgram, staker.publicKey, nfts)
+
+    const feesAfter = await umi.rpc.getBalance(FEES_WALLET)
+    const tokensAfter = (await safeFetchToken(umi, getTokenAccount(token, user.publicKey)))?.amount || BigInt(0)
+
+    assert.ok(tokensAfter > tokensBefore, "Expected rewards for both records to be claimed")
+    assert.equal(
+      feesAfter.basisPoints - feesBefore.basisPoints,
+      BigInt(programConfig.claimFee.toString()),
+      "Expected one claim fee"
+    )
+  })
+
+  it("Charges the claim fee per record when enabled", async () => {
+    await updateProgramConfig(adminProgram, null, null, null, null, null, null, null, null, null, true)
+    await sleep(1_000)
+    const programConfig = await adminProgram.account.programConfig.fetch(findProgramConfigPda())
+    assert.ok(programConfig.claimFeePerRecord, "Expected per record fees to be enabled")
+
+    const feesBefore = await umi.rpc.getBalance(FEES_WALLET)
+    await claimAll(userProgram, staker.publicKey, nfts)
+    const feesAfter = await umi.rpc.getBalance(FEES_WALLET)
+
+    assert.equal(
+      feesAfter.basisPoints - feesBefore.basisPoints,
+      BigInt(programConfig.claimFee.toString()) * BigInt(nfts.length),
+      "Expected a claim fee for each record"
+    )
+  })
+
+  describe("With a points emission", () => {
+    let pointsCollectionNft: DigitalAsset
+    let pointsCollection: PublicKey
+    let pointsNfts: DigitalAsset[]
+    const pointsStaker = umi.eddsa.generateKeypair()
+    const pointsEmission = umi.eddsa.generateKeypair()
+
+    before(async () => {
+      pointsCollectionNft = await createCollection(umi)
+      pointsCollection = findStakooorCollectionId(pointsStaker.publicKey, pointsCollectionNft.publicKey)
+      pointsNfts = await mintNfts(pointsCollectionNft.publicKey, 1, false, user.publicKey)
+
+      await init(creatorProgram, pointsStaker, "claim_all_points", "Claim all points", token)
+      await initCollection(creatorProgram, pointsStaker.publicKey, pointsCollectionNft.publicKey)
+      await addToken(creatorProgram, pointsStaker.publicKey, token, false)
+      await addEmission(
+        creatorProgram,
+        umi.eddsa.generateKeypair(),
+        pointsStaker.publicKey,
+        pointsCollection,
+        { token: {} },
+        1,
+        0,
+        null,
+        3600
+      )
+      await addEmission(
+        creatorProgram,
+        pointsEmission,
+        pointsStaker.publicKey,
+        pointsCollection,
+        { points: {} },
+        1,
+        0,
+        null,
+        3600
+      )
+      await toggleCollection(creatorProgram, pointsStaker.publicKey, pointsCollection, true)
+      await toggleStake(creatorProgram, pointsStaker.publicKey, true)
+
+      await stake(userProgram, pointsStaker.publicKey, pointsNfts[0])
+    })
+
+    it("Cannot claim without the nft record the points are credited to", async () => {
+      await sleep(1_000)
+      await expectFail(
+        () => claimAll(userProgram, pointsStaker.publicKey, pointsNfts, false),
+        (err) => assertErrorCode(err, "NftRecordRequired")
+      )
+    })
+
+    it("Credits the points along with the token rewards", async () => {
+      await claimAll(userProgram, pointsStaker.publicKey, pointsNfts)
+
+      const nftRecord = await userProgram.account.nftRecord.fetch(
+        findNftRecordPda(pointsStaker.publicKey, pointsNfts[0].publicKey)
+      )
+      assert.ok(nftRecord.points.gtn(0), "Expected points to be credited")
+    })
+  })
+})
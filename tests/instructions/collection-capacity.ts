@@ -0,0 +1,99 @@
+import * as anchor from "@coral-xyz/anchor"
+import { Keypair, PublicKey } from "@metaplex-foundation/umi"
+import { DigitalAsset } from "@metaplex-foundation/mpl-token-metadata"
+import { safeFetchToken } from "@metaplex-foundation/mpl-toolbox"
+import { assert } from "chai"
+import { Stake } from "../../target/types/stake"
+import { createNewUser, programPaidBy } from "../helper"
+import { createCollection } from "../helpers/create-collection"
+import { createToken } from "../helpers/create-token"
+import {
+  addEmission,
+  addToken,
+  init,
+  initCollection,
+  stake,
+  toggleCollection,
+  toggleStake,
+  updateCollectionCapacity,
+} from "../helpers/instructions"
+import { findStakooorCollectionId, getTokenAccount } from "../helpers/pdas"
+import { umi } from "../helpers/umi"
+import { assertErrorCode, expectFail, mintNfts } from "../helpers/utils"
+
+describe("Collection capacity", () => {
+  let creator: Keypair
+  let creatorProgram: anchor.Program<Stake>
+  let user: Keypair
+  let userProgram: anchor.Program<Stake>
+  let collectionNft: DigitalAsset
+  let collection: PublicKey
+  let nfts: DigitalAsset[]
+  let token: PublicKey
+  const staker = umi.eddsa.generateKeypair()
+  const emission = umi.eddsa.generateKeypair()
+
+  async function creatorBalance() {
+    return (await safeFetchToken(umi, getTokenAccount(token, creator.publicKey)))?.amount || BigInt(0)
+  }
+
+  before(async () => {
+    creator = await createNewUser()
+    user = await createNewUser()
+    creatorProgram = programPaidBy(creator)
+    userProgram = programPaidBy(user)
+    collectionNft = await createCollection(umi)
+    collection = findStakooorCollectionId(staker.publicKey, collectionNft.publicKey)
+    nfts = await mintNfts(collectionNft.publicKey, 2, false, user.publicKey)
+    token = await createToken(umi, BigInt(100_000), 0, undefined, creator.publicKey)
+
+    await init(creatorProgram, staker, "collection_capacity", "Collection capacity", token)
+    await addToken(creatorProgram, staker.publicKey, token, true)
+    await initCollection(creatorProgram, staker.publicKey, collectionNft.publicKey, false, null, 2)
+    await addEmission(creatorProgram, emission, staker.publicKey, collection, { token: {} }, 1, 0, null, 3600)
+    await toggleCollection(creatorProgram, staker.publicKey, collection, true)
+    await toggleStake(creatorProgram, staker.publicKey, true)
+
+    for (const nft of nfts) {
+      await stake(userProgram, staker.publicKey, nft)
+    }
+  })
+
+  it("Cannot lower the capacity below the current stakers", async () => {
+    await expectFail(
+      () => updateCollectionCapacity(creatorProgram, staker.publicKey, collection, 1),
+      (err) => assertErrorCode(err, "InvalidCapacity")
+    )
+  })
+
+  it("Funds the emission from the authority when raising the capacity", async () => {
+    const emissionBefore = await creatorProgram.account.emission.fetch(emission.publicKey)
+    const balanceBefore = await creatorBalance()
+
+    await updateCollectionCapacity(creatorProgram, staker.publicKey, collection, 4)
+
+    const emissionAfter = await creatorProgram.account.emission.fetch(emission.publicKey)
+    const balanceAfter = await creatorBalance()
+    const collectionAccount = await creatorProgram.account.collection.fetch(collection)
+
+    assert.equal(collectionAccount.maxStakersCount.toNumber(), 4, "Expected the capacity to be raised")
+    assert.ok(balanceAfter < balanceBefore, "Expected the authority to fund the new capacity")
+    assert.equal(
+      emissionAfter.currentBalance.sub(emissionBefore.currentBalance).toString(),
+      (balanceBefore - balanceAfter).toString(),
+      "Expected the emission to be credited with the funding"
+    )
+  })
+
+  it("Returns the surplus to the authority when lowering the capacity", async () => {
+    const balanceBefore = await creatorBalance()
+
+    await updateCollectionCapacity(creatorProgram, staker.publicKey, collection, 2)
+
+    const balanceAfter = await creatorBalance()
+    const collectionAccount = await creatorProgram.account.collection.fetch(collection)
+
+    assert.equal(collectionAccount.maxStakersCount.toNumber(), 2, "Expected the capacity to be lowered")
+    assert.ok(balanceAfter > balanceBefore, "Expected the surplus to be returned")
+  })
+})
@@ -0,0 +1,456 @@
+import * as anchor from "@coral-xyz/anchor"
+import { Keypair, PublicKey, sol } from "@metaplex-foundation/umi"
+import { DigitalAsset } from "@metaplex-foundation/mpl-token-metadata"
+import { safeFetchToken } from "@metaplex-foundation/mpl-toolbox"
+import { assert } from "chai"
+import { BN } from "bn.js"
+import { Stake } from "../../target/types/stake"
+import { createFixture } from "../helpers/fixture"
+import {
+  addEmission,
+  claim,
+  claimMerkleShare,
+  claimShare,
+  distribute,
+  distributeProRata,
+  initDistribution,
+  reclaimDistribution,
+  setDistributionRoot,
+  sleep,
+  stake,
+  toggleCollection,
+  toggleDistributionActive,
+  toggleStake,
+  unstake,
+} from "../helpers/instructions"
+import { createMerkleTree, distributionLeaf } from "../helpers/merkle"
+import { findShareRecordPda, findStakeRecordPda, findVaultAuthorityPda, getTokenAccount } from "../helpers/pdas"
+import { umi } from "../helpers/umi"
+import { assertErrorCode, expectFail } from "../helpers/utils"
+
+describe("Distributions", () => {
+  let creator: Keypair
+  let creatorProgram: anchor.Program<Stake>
+  let user: Keypair
+  let userProgram: anchor.Program<Stake>
+  let collection: PublicKey
+  let nfts: DigitalAsset[]
+  let token: PublicKey
+  const staker = umi.eddsa.generateKeypair()
+  const emission = umi.eddsa.generateKeypair()
+  const distributionEmission = umi.eddsa.generateKeypair()
+
+  function now() {
+    return Math.floor(Date.now() / 1000)
+  }
+
+  async function balance(owner: PublicKey) {
+    return (await safeFetchToken(umi, getTokenAccount(token, owner)))?.amount || BigInt(0)
+  }
+
+  before(async () => {
+    ;({ creator, creatorProgram, user, userProgram, collection, nfts, token } = await createFixture(
+      staker,
+      "distributions",
+      "Distributions"
+    ))
+
+    await addEmission(creatorProgram, emission, staker.publicKey, collection, { points: {} }, 1, 0, null, 3600)
+    await addEmission(creatorProgram, distributionEmission, staker.publicKey, collection, { distribution: {} })
+    await toggleCollection(creatorProgram, staker.publicKey, collection, true)
+    await toggleStake(creatorProgram, staker.publicKey, true)
+
+    for (const nft of nfts) {
+      await stake(userProgram, staker.publicKey, nft)
+    }
+  })
+
+  describe("Token distribution", () => {
+    const distribution = umi.eddsa.generateKeypair()
+
+    before(async () => {
+      await initDistribution(
+        creatorProgram,
+        staker.publicKey,
+        collection,
+        distribution,
+        "Token distribution",
+        "",
+        1,
+        new BN(0),
+        token
+      )
+    })
+
+    it("Funds a share from the authority into the distribution vault", async () => {
+      const balanceBefore = await balance(creator.publicKey)
+      await distribute(
+        creatorProgram,
+        staker.publicKey,
+        distribution.publicKey,
+        findStakeRecordPda(staker.publicKey, nfts[0].publicKey),
+        new BN(100)
+      )
+      const balanceAfter = await balance(creator.publicKey)
+
+      const distributionAccount = await creatorProgram.account.distribution.fetch(distribution.publicKey)
+      assert.equal(balanceBefore - balanceAfter, BigInt(100), "Expected the share to be paid by the authority")
+      assert.equal(
+        await balance(findVaultAuthorityPda(staker.publicKey, distribution.publicKey)),
+        BigInt(100),
+        "Expected the vault to hold the share"
+      )
+      assert.equal(distributionAccount.balance.toNumber(), 100, "Expected the distribution balance to be updated")
+      assert.equal(distributionAccount.sharesFunded, 1, "Expected a funded share")
+    })
+
+    it("Cannot fund more shares than the distribution has", async () => {
+      await expectFail(
+        () =>
+          distribute(
+            creatorProgram,
+            staker.publicKey,
+            distribution.publicKey,
+            findStakeRecordPda(staker.publicKey, nfts[1].publicKey),
+            new BN(100)
+          ),
+        (err) => assertErrorCode(err, "TotalSharesFunded")
+      )
+    })
+
+    it("Pays the share in tokens from the vault", async () => {
+      await toggleDistributionActive(creatorProgram, staker.publicKey, distribution.publicKey, true)
+
+      const balanceBefore = await balance(user.publicKey)
+      await claimShare(userProgram, staker.publicKey, distribution.publicKey, nfts[0].publicKey)
+      const balanceAfter = await balance(user.publicKey)
+
+      const distributionAccount = await userProgram.account.distribution.fetch(distribution.publicKey)
+      assert.equal(balanceAfter - balanceBefore, BigInt(100), "Expected the share to be paid")
+      assert.equal(distributionAccount.balance.toNumber(), 0, "Expected the vault balance to be spent")
+      assert.equal(distributionAccount.claimedAmount.toNumber(), 100, "Expected the claim to be recorded")
+    })
+  })
+
+  describe("Claim share", () => {
+    const distribution = umi.eddsa.generateKeypair()
+
+    before(async () => {
+      await initDistribution(
+        creatorProgram,
+        staker.publicKey,
+        collection,
+        distribution,
+        "SOL distribution",
+        "",
+        1,
+        new BN(0)
+      )
+      await distribute(
+        creatorProgram,
+        staker.publicKey,
+        distribution.publicKey,
+        findStakeRecordPda(staker.publicKey, nfts[1].publicKey),
+        new BN(String(sol(1).basisPoints))
+      )
+    })
+
+    it("Cannot claim a share before the distribution is active", async () => {
+      await expectFail(
+        () => claimShare(userProgram, staker.publicKey, distribution.publicKey, nfts[1].publicKey),
+        (err) => assertErrorCode(err, "DistributionInactive")
+      )
+    })
+
+    it("Cannot claim someone else's share", async () => {
+      await toggleDistributionActive(creatorProgram, staker.publicKey, distribution.publicKey, true)
+
+      await expectFail(
+        () => claimShare(creatorProgram, staker.publicKey, distribution.publicKey, nfts[1].publicKey),
+        (err) => assertErrorCode(err, "Unauthorized")
+      )
+    })
+
+    it("Pays the share to its owner and closes the share record", async () => {
+      const shareRecord = findShareRecordPda(distribution.publicKey, nfts[1].publicKey)
+      const balanceBefore = await umi.rpc.getBalance(user.publicKey)
+      await claimShare(userProgram, staker.publicKey, distribution.publicKey, nfts[1].publicKey)
+      const balanceAfter = await umi.rpc.getBalance(user.publicKey)
+
+      const distributionAccount = await userProgram.account.distribution.fetch(distribution.publicKey)
+      assert.ok(balanceAfter.basisPoints > balanceBefore.basisPoints, "Expected the share to be paid")
+      assert.isFalse(await umi.rpc.accountExists(shareRecord), "Expected the share record to be closed")
+      assert.equal(
+        distributionAccount.claimedAmount.toString(),
+        String(sol(1).basisPoints),
+        "Expected the claim to be recorded"
+      )
+      assert.equal(distributionAccount.shareRecordsOpen, 0, "Expected the share record to no longer be open")
+    })
+  })
+
+  describe("Merkle airdrop", () => {
+    const distribution = umi.eddsa.generateKeypair()
+    const walletAmount = sol(1).basisPoints
+    const nftAmount = sol(0.5).basisPoints
+    let tree: ReturnType<typeof createMerkleTree>
+
+    before(async () => {
+      // one share is issued to the user's wallet and one to their staked NFT
+      tree = createMerkleTree([
+        distributionLeaf(0, user.publicKey, walletAmount),
+        distributionLeaf(1, nfts[0].publicKey, nftAmount),
+      ])
+      await initDistribution(creatorProgram, staker.publicKey, collection, distribution, "Airdrop", "", 2, new BN(0))
+      await setDistributionRoot(
+        creatorProgram,
+        staker.publicKey,
+        distribution.publicKey,
+        tree.root,
+        2,
+        new BN(String(walletAmount + nftAmount))
+      )
+      await toggleDistributionActive(creatorProgram, staker.publicKey, distribution.publicKey, true)
+    })
+
+    it("Cannot fund individual shares of an airdrop", async () => {
+      await expectFail(
+        () =>
+          distribute(
+            creatorProgram,
+            staker.publicKey,
+            distribution.publicKey,
+            findStakeRecordPda(staker.publicKey, nfts[0].publicKey),
+            new BN(100)
+          ),
+        (err) => assertErrorCode(err, "MerkleDistribution")
+      )
+    })
+
+    it("Cannot claim more than the share in the tree", async () => {
+      await expectFail(
+        () =>
+          claimMerkleShare(
+            userProgram,
+            staker.publicKey,
+            distribution.publicKey,
+            0,
+            new BN(String(walletAmount * BigInt(2))),
+            tree.getProof(0)
+          ),
+        (err) => assertErrorCode(err, "InvalidProof")
+      )
+    })
+
+    it("Can claim a share issued to a wallet", async () => {
+      const balanceBefore = await umi.rpc.getBalance(user.publicKey)
+      await claimMerkleShare(
+        userProgram,
+        staker.publicKey,
+        distribution.publicKey,
+        0,
+        new BN(String(walletAmount)),
+        tree.getProof(0)
+      )
+      const balanceAfter = await umi.rpc.getBalance(user.publicKey)
+
+      assert.ok(balanceAfter.basisPoints > balanceBefore.basisPoints, "Expected the share to be paid")
+    })
+
+    it("Cannot claim the same share twice", async () => {
+      await expectFail(
+        () =>
+          claimMerkleShare(
+            userProgram,
+            staker.publicKey,
+            distribution.publicKey,
+            0,
+            new BN(String(walletAmount)),
+            tree.getProof(0)
+          ),
+        (err) => assertErrorCode(err, "ShareAlreadyClaimed")
+      )
+    })
+
+    it("Can claim a share issued to a staked NFT", async () => {
+      await claimMerkleShare(
+        userProgram,
+        staker.publicKey,
+        distribution.publicKey,
+        1,
+        new BN(String(nftAmount)),
+        tree.getProof(1),
+        nfts[0].publicKey,
+        true
+      )
+
+      const distributionAccount = await userProgram.account.distribution.fetch(distribution.publicKey)
+      assert.equal(distributionAccount.sharesFunded, 2, "Expected both shares to count towards progress")
+      assert.equal(
+        distributionAccount.claimedAmount.toString(),
+        String(walletAmount + nftAmount),
+        "Expected both claims to be recorded"
+      )
+    })
+  })
+
+  describe("Pro-rata distribution", () => {
+    const distribution = umi.eddsa.generateKeypair()
+    const amount = sol(2).basisPoints
+
+    before(async () => {
+      await initDistribution(creatorProgram, staker.publicKey, collection, distribution, "Pro-rata", "", 0, new BN(0))
+    })
+
+    it("Cannot share a distribution that funds individual shares", async () => {
+      const shared = umi.eddsa.generateKeypair()
+      await initDistribution(creatorProgram, staker.publicKey, collection, shared, "Shares", "", 2, new BN(0))
+      await distribute(
+        creatorProgram,
+        staker.publicKey,
+        shared.publicKey,
+        findStakeRecordPda(staker.publicKey, nfts[0].publicKey),
+        new BN(String(sol(0.1).basisPoints))
+      )
+
+      await expectFail(
+        () => distributeProRata(creatorProgram, staker.publicKey, shared.publicKey, new BN(String(amount))),
+        (err) => assertErrorCode(err, "DistributionModeMismatch")
+      )
+    })
+
+    it("Shares a deposit across the staked items", async () => {
+      await distributeProRata(creatorProgram, staker.publicKey, distribution.publicKey, new BN(String(amount)))
+
+      const emissionAccount = await creatorProgram.account.emission.fetch(distributionEmission.publicKey)
+      const distributionAccount = await creatorProgram.account.distribution.fetch(distribution.publicKey)
+      assert.equal(
+        emissionAccount.distribution.toBase58(),
+        distribution.publicKey,
+        "Expected the distribution to be linked to the emission"
+      )
+      assert.equal(distributionAccount.balance.toString(), String(amount), "Expected the deposit to be held")
+    })
+
+    it("Pays each staked item its share through the distribution emission", async () => {
+      const balanceBefore = await umi.rpc.getBalance(user.publicKey)
+      await claim(userProgram, staker.publicKey, nfts[0], distributionEmission.publicKey)
+      const balanceAfter = await umi.rpc.getBalance(user.publicKey)
+
+      const distributionAccount = await userProgram.account.distribution.fetch(distribution.publicKey)
+      assert.equal(
+        distributionAccount.claimedAmount.toString(),
+        String(amount / BigInt(2)),
+        "Expected an even share for each staked item"
+      )
+      assert.ok(balanceAfter.basisPoints > balanceBefore.basisPoints, "Expected the share to be paid")
+    })
+
+    it("Pays the unclaimed share when an item unstakes", async () => {
+      await distributeProRata(creatorProgram, staker.publicKey, distribution.publicKey, new BN(String(amount)))
+
+      const balanceBefore = await umi.rpc.getBalance(user.publicKey)
+      await unstake(userProgram, staker.publicKey, nfts[0])
+      const balanceAfter = await umi.rpc.getBalance(user.publicKey)
+
+      const distributionAccount = await userProgram.account.distribution.fetch(distribution.publicKey)
+      assert.equal(
+        distributionAccount.claimedAmount.toString(),
+        String(amount),
+        "Expected the unstaked item's share to be claimed"
+      )
+      assert.ok(
+        balanceAfter.basisPoints - balanceBefore.basisPoints > amount / BigInt(4),
+        "Expected the share to be paid to the owner"
+      )
+    })
+  })
+
+  describe("Expiry", () => {
+    const distribution = umi.eddsa.generateKeypair()
+    let shareRecord: PublicKey
+
+    before(async () => {
+      shareRecord = findShareRecordPda(distribution.publicKey, nfts[1].publicKey)
+    })
+
+    it("Cannot end a distribution in the past", async () => {
+      await expectFail(
+        () =>
+          initDistribution(
+            creatorProgram,
+            staker.publicKey,
+            collection,
+            umi.eddsa.generateKeypair(),
+            "Expired",
+            "",
+            1,
+            new BN(0),
+            undefined,
+            new BN(now() - 60)
+          ),
+        (err) => assertErrorCode(err, "InvalidDistributionEnd")
+      )
+    })
+
+    it("Cannot reclaim a distribution before it ends", async () => {
+      await initDistribution(
+        creatorProgram,
+        staker.publicKey,
+        collection,
+        distribution,
+        "Expiring",
+        "",
+        1,
+        new BN(0),
+        undefined,
+        new BN(now() + 5)
+      )
+      await distribute(
+        creatorProgram,
+        staker.publicKey,
+        distribution.publicKey,
+        findStakeRecordPda(staker.publicKey, nfts[1].publicKey),
+        new BN(String(sol(1).basisPoints))
+      )
+      await toggleDistributionActive(creatorProgram, staker.publicKey, distribution.publicKey, true)
+
+      await expectFail(
+        () => reclaimDistribution(creatorProgram, staker.publicKey, distribution.publicKey, [shareRecord]),
+        (err) => assertErrorCode(err, "DistributionNotEnded")
+      )
+    })
+
+    it("Cannot claim a share after the distribution ends", async () => {
+      await sleep(6_000)
+      await expectFail(
+        () => claimShare(userProgram, staker.publicKey, distribution.publicKey, nfts[1].publicKey),
+        (err) => assertErrorCode(err, "DistributionEnded")
+      )
+    })
+
+    it("Cannot reclaim while share records are left open", async () => {
+      await expectFail(
+        () => reclaimDistribution(creatorProgram, staker.publicKey, distribution.publicKey),
+        (err) => assertErrorCode(err, "ShareRecordsOpen")
+      )
+    })
+
+    it("Returns unclaimed shares to the authority and closes the distribution", async () => {
+      const distributionAccount = await creatorProgram.account.distribution.fetch(distribution.publicKey)
+      assert.equal(distributionAccount.shareRecordsOpen, 1, "Expected the funded share record to be tracked")
+
+
+      const balanceBefore = await umi.rpc.getBalance(creator.publicKey)
+      await reclaimDistribution(creatorProgram, staker.publicKey, distribution.publicKey, [shareRecord])
+      const balanceAfter = await umi.rpc.getBalance(creator.publicKey)
+
+      assert.ok(
+        balanceAfter.basisPoints - balanceBefore.basisPoints > sol(1).basisPoints,
+        "Expected the unclaimed share and rent to be returned"
+      )
+      assert.isFalse(await umi.rpc.accountExists(shareRecord), "Expected the share record to be closed")
+      assert.isFalse(await umi.rpc.accountExists(distribution.publicKey), "Expected the distribution to be closed")
+    })
+  })
+})
@@ -0,0 +1,98 @@
+import * as anchor from "@coral-xyz/anchor"
+import { Keypair, PublicKey } from "@metaplex-foundation/umi"
+import { DigitalAsset } from "@metaplex-foundation/mpl-token-metadata"
+import { AuthorityType, safeFetchToken, setAuthority } from "@metaplex-foundation/mpl-toolbox"
+import { assert } from "chai"
+import { Stake } from "../../target/types/stake"
+import { createNewUser, programPaidBy } from "../helper"
+import { createCollection } from "../helpers/create-collection"
+import { createToken } from "../helpers/create-token"
+import {
+  addEmission,
+  addToken,
+  init,
+  initCollection,
+  sleep,
+  stake,
+  toggleCollection,
+  toggleStake,
+  unstake,
+  updateEarlyUnstake,
+} from "../helpers/instructions"
+import { findStakooorCollectionId, getTokenAccount } from "../helpers/pdas"
+import { umi } from "../helpers/umi"
+import { assertErrorCode, expectFail, mintNfts } from "../helpers/utils"
+
+describe("Early unstake penalty", () => {
+  let creator: Keypair
+  let creatorProgram: anchor.Program<Stake>
+  let user: Keypair
+  let userProgram: anchor.Program<Stake>
+  let collectionNft: DigitalAsset
+  let collection: PublicKey
+  let nfts: DigitalAsset[]
+  let token: PublicKey
+  const staker = umi.eddsa.generateKeypair()
+  const emission = umi.eddsa.generateKeypair()
+
+  before(async () => {
+    creator = await createNewUser()
+    user = await createNewUser()
+    creatorProgram = programPaidBy(creator)
+    userProgram = programPaidBy(user)
+    collectionNft = await createCollection(umi)
+    collection = findStakooorCollectionId(staker.publicKey, collectionNft.publicKey)
+    nfts = await mintNfts(collectionNft.publicKey, 1, false, user.publicKey)
+
+    token = await createToken(umi, BigInt(10_000), 0, undefined, creator.publicKey)
+    await setAuthority(umi, {
+      owned: token,
+      owner: umi.identity.publicKey,
+      newAuthority: creator.publicKey,
+      authorityType: AuthorityType.MintTokens,
+    }).sendAndConfirm(umi)
+
+    await init(creatorProgram, staker, "early_unstake", "Early unstake", token)
+    await initCollection(creatorProgram, staker.publicKey, collectionNft.publicKey, false, null, 10)
+    await addToken(creatorProgram, staker.publicKey, token, false)
+    // rewards are only earned after an hour
+    await addEmission(creatorProgram, emission, staker.publicKey, collection, { token: {} }, 100, 3600, null, 7200)
+    await toggleCollection(creatorProgram, staker.publicKey, collection, true)
+    await toggleStake(creatorProgram, staker.publicKey, true)
+  })
+
+  it("Cannot set a penalty above 100%", async () => {
+    await expectFail(
+      () =>
+        updateEarlyUnstake(creatorProgram, staker.publicKey, collection, emission.publicKey, {
+          penalty: 10_001,
+          treasury: null,
+        }),
+      (err) => assertErrorCode(err, "InvalidPenalty")
+    )
+  })
+
+  it("Pays the rewards less the penalty when unstaking before the minimum period", async () => {
+    await updateEarlyUnstake(creatorProgram, staker.publicKey, collection, emission.publicKey, {
+      penalty: 5_000,
+      treasury: null,
+    })
+
+    await stake(userProgram, staker.publicKey, nfts[0])
+    await sleep(2_000)
+
+    const balanceBefore = (await safeFetchToken(umi, getTokenAccount(token, user.publicKey)))?.amount || BigInt(0)
+    await unstake(userProgram, staker.publicKey, nfts[0])
+    const balanceAfter = (await safeFetchToken(umi, getTokenAccount(token, user.publicKey)))?.amount || BigInt(0)
+
+    const emissionAccount = await creatorProgram.account.emission.fetch(emission.publicKey)
+    const forfeited = BigInt(emissionAccount.penaltiesCollected.toString())
+
+    assert.ok(balanceAfter > balanceBefore, "Expected part of the rewards to be paid")
+    assert.ok(forfeited > BigInt(0), "Expected part of the rewards to be forfeited")
+    assert.ok(
+      balanceAfter - balanceBefore - forfeited <= BigInt(1),
+      "Expected the rewards to be split evenly with a 50% penalty"
+    )
+  })
+})
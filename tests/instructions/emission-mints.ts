@@ -0,0 +1,122 @@
+import * as anchor from "@coral-xyz/anchor"
+import { Keypair, PublicKey } from "@metaplex-foundation/umi"
+import { DigitalAsset } from "@metaplex-foundation/mpl-token-metadata"
+import { AuthorityType, safeFetchToken, setAuthority } from "@metaplex-foundation/mpl-toolbox"
+import { assert } from "chai"
+import { Stake } from "../../target/types/stake"
+import { createNewUser, programPaidBy } from "../helper"
+import { createCollection } from "../helpers/create-collection"
+import { createToken } from "../helpers/create-token"
+import {
+  addEmission,
+  addToken,
+  claim,
+  init,
+  initCollection,
+  sleep,
+  stake,
+  toggleCollection,
+  toggleStake,
+  updateVesting,
+} from "../helpers/instructions"
+import { findStakooorCollectionId, findTokenAuthorityPda, getTokenAccount } from "../helpers/pdas"
+import { umi } from "../helpers/umi"
+import { assertErrorCode, expectFail, mintNfts } from "../helpers/utils"
+
+describe("Emission reward mints", () => {
+  let creator: Keypair
+  let creatorProgram: anchor.Program<Stake>
+  let user: Keypair
+  let userProgram: anchor.Program<Stake>
+  let collectionNft: DigitalAsset
+  let collection: PublicKey
+  let nfts: DigitalAsset[]
+  let token: PublicKey
+  let otherToken: PublicKey
+  const staker = umi.eddsa.generateKeypair()
+  const emission = umi.eddsa.generateKeypair()
+  const otherEmission = umi.eddsa.generateKeypair()
+
+  async function balance(mint: PublicKey, owner: PublicKey) {
+    return (await safeFetchToken(umi, getTokenAccount(mint, owner)))?.amount || BigInt(0)
+  }
+
+  before(async () => {
+    creator = await createNewUser()
+    user = await createNewUser()
+    creatorProgram = programPaidBy(creator)
+    userProgram = programPaidBy(user)
+    collectionNft = await createCollection(umi)
+    collection = findStakooorCollectionId(staker.publicKey, collectionNft.publicKey)
+    nfts = await mintNfts(collectionNft.publicKey, 1, false, user.publicKey)
+
+    token = await createToken(umi, BigInt(10_000), 0, undefined, creator.publicKey)
+    await setAuthority(umi, {
+      owned: token,
+      owner: umi.identity.publicKey,
+      newAuthority: creator.publicKey,
+      authorityType: AuthorityType.MintTokens,
+    }).sendAndConfirm(umi)
+    otherToken = await createToken(umi, BigInt(100_000), 0, undefined, creator.publicKey)
+
+    await init(creatorProgram, staker, "emission_mints", "Emission mints", token)
+    await initCollection(creatorProgram, staker.publicKey, collectionNft.publicKey, false, null, 10)
+    await addToken(creatorProgram, staker.publicKey, token, false)
+    await addEmission(creatorProgram, emission, staker.publicKey, collection, { token: {} }, 1, 0, null, 3600)
+    await toggleCollection(creatorProgram, staker.publicKey, collection, true)
+    await toggleStake(creatorProgram, staker.publicKey, true)
+  })
+
+  it("Funds an emission in another mint from the vault", async () => {
+    const balanceBefore = await balance(otherToken, creator.publicKey)
+    await addEmission(
+      creatorProgram,
+      otherEmission,
+      staker.publicKey,
+      collection,
+      { token: {} },
+      1,
+      0,
+      null,
+      3600,
+      null,
+      otherToken
+    )
+    const balanceAfter = await balance(otherToken, creator.publicKey)
+
+    const emissionAccount = await creatorProgram.account.emission.fetch(otherEmission.publicKey)
+    assert.equal(emissionAccount.tokenMint.toBase58(), otherToken, "Expected the emission's reward mint to be stored")
+    assert.ok(emissionAccount.tokenVault, "Expected the emission to be paid from the vault")
+    assert.equal(balanceBefore - balanceAfter, BigInt(1 * 10 * 3600), "Expected the full emission to be funded")
+    assert.equal(
+      await balance(otherToken, findTokenAuthorityPda(staker.publicKey)),
+      BigInt(1 * 10 * 3600),
+      "Expected the vault to hold the emission"
+    )
+  })
+
+  it("Cannot vest rewards paid in a mint other than the staker's token", async () => {
+    await expectFail(
+      () =>
+        updateVesting(creatorProgram, staker.publicKey, collection, otherEmission.publicKey, {
+          cliff: 0,
+          duration: 60,
+        }),
+      (err) => assertErrorCode(err, "VestingMintMismatch")
+    )
+  })
+
+  it("Pays each emission in its own mint", async () => {
+    await stake(userProgram, staker.publicKey, nfts[0])
+    await sleep(1_000)
+
+    const tokenBefore = await balance(token, user.publicKey)
+    const otherTokenBefore = await balance(otherToken, user.publicKey)
+    await claim(userProgram, staker.publicKey, nfts[0], otherEmission.publicKey)
+    const tokenAfter = await balance(token, user.publicKey)
+    const otherTokenAfter = await balance(otherToken, user.publicKey)
+
+    assert.ok(otherTokenAfter > otherTokenBefore, "Expected rewards in the emission's mint")
+    assert.ok(tokenAfter > tokenBefore, "Expected the primary emission to be settled in the staker's token")
+  })
+})
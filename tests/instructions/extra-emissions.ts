@@ -0,0 +1,110 @@
+import * as anchor from "@coral-xyz/anchor"
+import { Keypair, PublicKey } from "@metaplex-foundation/umi"
+import { DigitalAsset } from "@metaplex-foundation/mpl-token-metadata"
+import { AuthorityType, safeFetchToken, setAuthority } from "@metaplex-foundation/mpl-toolbox"
+import { assert } from "chai"
+import { Stake } from "../../target/types/stake"
+import { createNewUser, programPaidBy } from "../helper"
+import { createCollection } from "../helpers/create-collection"
+import { createToken } from "../helpers/create-token"
+import {
+  addEmission,
+  addToken,
+  claim,
+  init,
+  initCollection,
+  sleep,
+  stake,
+  toggleCollection,
+  toggleStake,
+} from "../helpers/instructions"
+import { findStakeRecordPda, findStakooorCollectionId, getTokenAccount } from "../helpers/pdas"
+import { umi } from "../helpers/umi"
+import { assertErrorCode, expectFail, mintNfts } from "../helpers/utils"
+
+describe("Extra emissions", () => {
+  let creator: Keypair
+  let creatorProgram: anchor.Program<Stake>
+  let user: Keypair
+  let userProgram: anchor.Program<Stake>
+  let collectionNft: DigitalAsset
+  let collection: PublicKey
+  let nfts: DigitalAsset[]
+  let token: PublicKey
+  const staker = umi.eddsa.generateKeypair()
+  // the primary token emission and the maximum of 4 extra emissions
+  const emissions = Array.from({ length: 5 }, () => umi.eddsa.generateKeypair())
+
+  before(async () => {
+    creator = await createNewUser()
+    user = await createNewUser()
+    creatorProgram = programPaidBy(creator)
+    userProgram = programPaidBy(user)
+    collectionNft = await createCollection(umi)
+    collection = findStakooorCollectionId(staker.publicKey, collectionNft.publicKey)
+    nfts = await mintNfts(collectionNft.publicKey, 1, false, user.publicKey)
+
+    token = await createToken(umi, BigInt(10_000), 0, undefined, creator.publicKey)
+    await setAuthority(umi, {
+      owned: token,
+      owner: umi.identity.publicKey,
+      newAuthority: creator.publicKey,
+      authorityType: AuthorityType.MintTokens,
+    }).sendAndConfirm(umi)
+
+    await init(creatorProgram, staker, "extra_emissions", "Extra emissions", token)
+    await initCollection(creatorProgram, staker.publicKey, collectionNft.publicKey, false, null, 10)
+    await addToken(creatorProgram, staker.publicKey, token, false)
+    await toggleCollection(creatorProgram, staker.publicKey, collection, true)
+    await toggleStake(creatorProgram, staker.publicKey, true)
+  })
+
+  it("Can add extra token emissions to a collection", async () => {
+    for (const emission of emissions) {
+      await addEmission(creatorProgram, emission, staker.publicKey, collection, { token: {} }, 1, 0, null, 3600)
+    }
+
+    const collectionAccount = await creatorProgram.account.collection.fetch(collection)
+    assert.equal(collectionAccount.tokenEmission.toBase58(), emissions[0].publicKey, "Expected a primary emission")
+    assert.deepEqual(
+      collectionAccount.extraEmissions.map((key) => key.toBase58()),
+      emissions.slice(1).map((emission) => emission.publicKey),
+      "Expected the other emissions to be extra emissions"
+    )
+  })
+
+  it("Cannot add more than the maximum extra emissions", async () => {
+    await expectFail(
+      () =>
+        addEmission(
+          creatorProgram,
+          umi.eddsa.generateKeypair(),
+          staker.publicKey,
+          collection,
+          { token: {} },
+          1,
+          0,
+          null,
+          3600
+        ),
+      (err) => assertErrorCode(err, "TooManyEmissions")
+    )
+  })
+
+  it("Earns from every token emission", async () => {
+    await stake(userProgram, staker.publicKey, nfts[0])
+
+    const record = await userProgram.account.stakeRecord.fetch(findStakeRecordPda(staker.publicKey, nfts[0].publicKey))
+    assert.equal(record.emissions.length, emissions.length, "Expected the NFT to be staked in every emission")
+
+    await sleep(1_000)
+    const balanceBefore = (await safeFetchToken(umi, getTokenAccount(token, user.publicKey)))?.amount || BigInt(0)
+    await claim(userProgram, staker.publicKey, nfts[0], emissions[0].publicKey)
+    const balanceAfter = (await safeFetchToken(umi, getTokenAccount(token, user.publicKey)))?.amount || BigInt(0)
+
+    assert.ok(
+      balanceAfter - balanceBefore >= BigInt(emissions.length),
+      "Expected a second of rewards from each emission"
+    )
+  })
+})
@@ -0,0 +1,91 @@
+import * as anchor from "@coral-xyz/anchor"
+import { PublicKey } from "@metaplex-foundation/umi"
+import { DigitalAsset } from "@metaplex-foundation/mpl-token-metadata"
+import { assert } from "chai"
+import { Stake } from "../../target/types/stake"
+import { createFixture } from "../helpers/fixture"
+import {
+  addEmission,
+  sleep,
+  stake,
+  toggleCollection,
+  toggleStake,
+  unstake,
+  updateLockTiers,
+} from "../helpers/instructions"
+import { findStakeRecordPda } from "../helpers/pdas"
+import { umi } from "../helpers/umi"
+import { assertErrorCode, expectFail } from "../helpers/utils"
+
+describe("Lock tiers", () => {
+  let creatorProgram: anchor.Program<Stake>
+  let userProgram: anchor.Program<Stake>
+  let collection: PublicKey
+  let nfts: DigitalAsset[]
+  const staker = umi.eddsa.generateKeypair()
+  const emission = umi.eddsa.generateKeypair()
+
+  before(async () => {
+    ;({ creatorProgram, userProgram, collection, nfts } = await createFixture(staker, "lock_tiers", "Lock tiers", {
+      tokenVault: false,
+    }))
+
+    await addEmission(creatorProgram, emission, staker.publicKey, collection, { token: {} }, 1, 0, null, 3600)
+    await toggleCollection(creatorProgram, staker.publicKey, collection, true)
+    await toggleStake(creatorProgram, staker.publicKey, true)
+  })
+
+  it("Cannot add a tier that reduces rewards", async () => {
+    await expectFail(
+      () =>
+        updateLockTiers(creatorProgram, staker.publicKey, collection, emission.publicKey, [
+          { duration: 3600, boost: 5_000 },
+        ]),
+      (err) => assertErrorCode(err, "InvalidLockTier")
+    )
+  })
+
+  it("Can set lock tiers", async () => {
+    await updateLockTiers(creatorProgram, staker.publicKey, collection, emission.publicKey, [
+      { duration: 3600, boost: 15_000 },
+      { duration: 2, boost: 12_000 },
+    ])
+
+    const emissionAccount = await creatorProgram.account.emission.fetch(emission.publicKey)
+    assert.equal(emissionAccount.lockTiers.length, 2, "Expected 2 lock tiers")
+  })
+
+  it("Cannot stake with a tier that doesn't exist", async () => {
+    await expectFail(
+      () => stake(userProgram, staker.publicKey, nfts[0], null, null, 2),
+      (err) => assertErrorCode(err, "InvalidIndex")
+    )
+  })
+
+  it("Can stake with a lock tier", async () => {
+    const now = Math.floor(Date.now() / 1000)
+    await stake(userProgram, staker.publicKey, nfts[0], null, null, 0)
+
+    const record = await userProgram.account.stakeRecord.fetch(findStakeRecordPda(staker.publicKey, nfts[0].publicKey))
+    assert.equal(record.lockBoost.toNumber(), 15_000, "Expected the tier's boost to be recorded")
+    assert.ok(record.lockedUntil.toNumber() >= now + 3600 - 5, "Expected the NFT to be locked for the tier duration")
+  })
+
+  it("Cannot unstake before the lock ends", async () => {
+    await expectFail(
+      () => unstake(userProgram, staker.publicKey, nfts[0]),
+      (err) => assertErrorCode(err, "StakeLocked")
+    )
+  })
+
+  it("Can unstake once the lock ends", async () => {
+    await stake(userProgram, staker.publicKey, nfts[1], null, null, 1)
+    await sleep(3_000)
+    await unstake(userProgram, staker.publicKey, nfts[1])
+
+    const record = await userProgram.account.stakeRecord.fetchNullable(
+      findStakeRecordPda(staker.publicKey, nfts[1].publicKey)
+    )
+    assert.isNull(record, "Expected the stake record to be closed")
+  })
+})
@@ -0,0 +1,93 @@
+import * as anchor from "@coral-xyz/anchor"
+import { Keypair, publicKey } from "@metaplex-foundation/umi"
+import { assert } from "chai"
+import { Stake } from "../../target/types/stake"
+import { createNewUser, programPaidBy } from "../helper"
+import { migrate } from "../helpers/instructions"
+import { umi } from "../helpers/umi"
+import { assertErrorCode, expectFail } from "../helpers/utils"
+
+// accounts in the layout they had before fields were added, loaded from tests/fixtures
+const OLD_COLLECTION = publicKey("37HC2mNNPZgKC8akrrfHv2UNtpsrXv34aX6NFAovv2bE")
+const OLD_EMISSION = publicKey("5xVfpaskmyWhvfM5pA5EmkcbJZFcfSRVPN1oCBVYWUWt")
+const OLD_STAKE_RECORD = publicKey("CdHvjCpzEosdBpU9pimiUh5gbTRWJbacjjDakrysWWBD")
+// StakeRecord::LEN, with room for the extra token emissions
+const STAKE_RECORD_LEN = 534
+
+describe("Migrate", () => {
+  let user: Keypair
+  let userProgram: anchor.Program<Stake>
+
+  before(async () => {
+    user = await createNewUser()
+    userProgram = programPaidBy(user)
+  })
+
+  it("Cannot migrate an account the program doesn't own", async () => {
+    await expectFail(
+      () => migrate(userProgram, user.publicKey),
+      (err) => assertErrorCode(err, "InvalidMigration")
+    )
+  })
+
+  it("Cannot read an old collection without migrating it", async () => {
+    await expectFail(
+      () => userProgram.account.collection.fetch(OLD_COLLECTION),
+      (err) => assert.ok(err, "Expected the old layout to fail to deserialize")
+    )
+  })
+
+  it("Migrates an old collection to the current layout", async () => {
+    await migrate(userProgram, OLD_COLLECTION)
+
+    const collection = await userProgram.account.collection.fetch(OLD_COLLECTION)
+    assert.deepEqual(collection.kind, { nft: {} }, "Expected an NFT collection")
+    assert.equal(collection.unbondingPeriod.toNumber(), 0, "Expected no unbonding period")
+    assert.isEmpty(collection.extraEmissions, "Expected no extra emissions")
+    assert.equal(collection.maxStakersCount.toNumber(), 100, "Expected the existing fields to be kept")
+    assert.equal(collection.currentStakersCount.toNumber(), 3, "Expected the existing fields to be kept")
+  })
+
+  it("Migrates an old emission to the current layout", async () => {
+    const before = await userProgram.account.emission.fetch(OLD_EMISSION)
+    assert.equal(before.stakeUnit.toNumber(), 0, "Expected the old emission to have no stake unit")
+
+    await migrate(userProgram, OLD_EMISSION)
+
+    const emission = await userProgram.account.emission.fetch(OLD_EMISSION)
+    assert.equal(emission.stakeUnit.toNumber(), 1, "Expected every item to count as one unit")
+    assert.equal(emission.maxMultiplier.toNumber(), 10_000, "Expected no multiplier")
+    assert.equal(emission.currentBalance.toNumber(), 500, "Expected the existing fields to be kept")
+    assert.equal(emission.stakedItems.toNumber(), 3, "Expected the existing fields to be kept")
+  })
+
+  it("Leaves a migrated emission unchanged when migrated again", async () => {
+    const before = await userProgram.account.emission.fetch(OLD_EMISSION)
+    await migrate(userProgram, OLD_EMISSION)
+    const after = await userProgram.account.emission.fetch(OLD_EMISSION)
+
+    assert.equal(after.stakeUnit.toNumber(), before.stakeUnit.toNumber(), "Expected the stake unit to be kept")
+    assert.equal(
+      after.rewardPerItemUpdatedAt.toNumber(),
+      before.rewardPerItemUpdatedAt.toNumber(),
+      "Expected the emission to be unchanged"
+    )
+  })
+
+  it("Migrates an old stake record to the current layout", async () => {
+    const before = await userProgram.account.stakeRecord.fetch(OLD_STAKE_RECORD)
+    assert.equal(before.lockBoost.toNumber(), 0, "Expected the old stake record to have no lock boost")
+
+    await migrate(userProgram, OLD_STAKE_RECORD)
+
+    const stakeRecord = await userProgram.account.stakeRecord.fetch(OLD_STAKE_RECORD)
+    assert.equal(stakeRecord.multiplier.toNumber(), 10_000, "Expected no multiplier")
+    assert.equal(stakeRecord.lockBoost.toNumber(), 10_000, "Expected no lock boost")
+    assert.isNull(stakeRecord.unbondingAt, "Expected the stake record not to be unbonding")
+
+    const account = await umi.rpc.getAccount(OLD_STAKE_RECORD)
+    assert.ok(account.exists && account.data.length === STAKE_RECORD_LEN, "Expected room for extra emissions")
+    assert.equal(stakeRecord.emissions.length, 2, "Expected the existing fields to be kept")
+    assert.equal(stakeRecord.stakedAt.toNumber(), 1_700_000_000, "Expected the existing fields to be kept")
+  })
+})
@@ -0,0 +1,111 @@
+import * as anchor from "@coral-xyz/anchor"
+import { Keypair, PublicKey } from "@metaplex-foundation/umi"
+import { DigitalAsset } from "@metaplex-foundation/mpl-token-metadata"
+import { AuthorityType, setAuthority } from "@metaplex-foundation/mpl-toolbox"
+import { assert } from "chai"
+import { Stake } from "../../target/types/stake"
+import { createNewUser, programPaidBy } from "../helper"
+import { createCollection } from "../helpers/create-collection"
+import { createToken } from "../helpers/create-token"
+import {
+  addEmission,
+  addToken,
+  init,
+  initCollection,
+  stake,
+  toggleCollection,
+  toggleStake,
+  updateEmissionMultipliers,
+} from "../helpers/instructions"
+import { createMerkleTree, multiplierLeaf } from "../helpers/merkle"
+import { findStakeRecordPda, findStakooorCollectionId } from "../helpers/pdas"
+import { umi } from "../helpers/umi"
+import { assertErrorCode, expectFail, mintNfts } from "../helpers/utils"
+
+describe("Emission multipliers", () => {
+  let creator: Keypair
+  let creatorProgram: anchor.Program<Stake>
+  let user: Keypair
+  let userProgram: anchor.Program<Stake>
+  let collectionNft: DigitalAsset
+  let collection: PublicKey
+  let nfts: DigitalAsset[]
+  let multipliers: ReturnType<typeof createMerkleTree>
+  let token: PublicKey
+  const staker = umi.eddsa.generateKeypair()
+  const emission = umi.eddsa.generateKeypair()
+
+  before(async () => {
+    creator = await createNewUser()
+    user = await createNewUser()
+    creatorProgram = programPaidBy(creator)
+    userProgram = programPaidBy(user)
+    collectionNft = await createCollection(umi)
+    collection = findStakooorCollectionId(staker.publicKey, collectionNft.publicKey)
+    nfts = await mintNfts(collectionNft.publicKey, 3, false, user.publicKey)
+
+    // the second NFT's multiplier is above the emission's max
+    multipliers = createMerkleTree([
+      multiplierLeaf(nfts[0].publicKey, 20_000),
+      multiplierLeaf(nfts[1].publicKey, 30_000),
+    ])
+
+    token = await createToken(umi, BigInt(10_000), 0, undefined, creator.publicKey)
+    await setAuthority(umi, {
+      owned: token,
+      owner: umi.identity.publicKey,
+      newAuthority: creator.publicKey,
+      authorityType: AuthorityType.MintTokens,
+    }).sendAndConfirm(umi)
+
+    await init(creatorProgram, staker, "multipliers", "Multipliers", token)
+    await initCollection(creatorProgram, staker.publicKey, collectionNft.publicKey, false, null, 10)
+    await addToken(creatorProgram, staker.publicKey, token, false)
+    await addEmission(creatorProgram, emission, staker.publicKey, collection, { token: {} }, 1, 0, null, 3600)
+    await updateEmissionMultipliers(
+      creatorProgram,
+      staker.publicKey,
+      collection,
+      emission.publicKey,
+      multipliers.root,
+      20_000
+    )
+    await toggleCollection(creatorProgram, staker.publicKey, collection, true)
+    await toggleStake(creatorProgram, staker.publicKey, true)
+  })
+
+  it("Cannot stake without a multiplier proof", async () => {
+    await expectFail(
+      () => stake(userProgram, staker.publicKey, nfts[2]),
+      (err) => assertErrorCode(err, "MultiplierProofRequired")
+    )
+  })
+
+  it("Cannot claim a multiplier that isn't in the root", async () => {
+    await expectFail(
+      () => stake(userProgram, staker.publicKey, nfts[0], null, { multiplier: 30_000, proof: multipliers.getProof(0) }),
+      (err) => assertErrorCode(err, "InvalidProof")
+    )
+  })
+
+  it("Cannot use a multiplier above the emission's max", async () => {
+    await expectFail(
+      () => stake(userProgram, staker.publicKey, nfts[1], null, { multiplier: 30_000, proof: multipliers.getProof(1) }),
+      (err) => assertErrorCode(err, "InvalidMultiplier")
+    )
+  })
+
+  it("Can stake with a multiplier from the root", async () => {
+    await stake(userProgram, staker.publicKey, nfts[0], null, { multiplier: 20_000, proof: multipliers.getProof(0) })
+
+    const record = await userProgram.account.stakeRecord.fetch(findStakeRecordPda(staker.publicKey, nfts[0].publicKey))
+    assert.equal(record.multiplier.toNumber(), 20_000, "Expected the multiplier to be recorded")
+  })
+
+  it("Cannot change the multipliers while items are staked", async () => {
+    await expectFail(
+      () => updateEmissionMultipliers(creatorProgram, staker.publicKey, collection, emission.publicKey, null, 10_000),
+      (err) => assertErrorCode(err, "CollectionHasStakers")
+    )
+  })
+})
@@ -0,0 +1,164 @@
+import * as anchor from "@coral-xyz/anchor"
+import { Keypair, PublicKey } from "@metaplex-foundation/umi"
+import { DigitalAsset } from "@metaplex-foundation/mpl-token-metadata"
+import { createAssociatedToken, safeFetchToken } from "@metaplex-foundation/mpl-toolbox"
+import { toWeb3JsPublicKey } from "@metaplex-foundation/umi-web3js-adapters"
+import { assert } from "chai"
+import { BN } from "bn.js"
+import { Stake } from "../../target/types/stake"
+import { createNewUser, programPaidBy } from "../helper"
+import { createCollection } from "../helpers/create-collection"
+import { createToken } from "../helpers/create-token"
+import {
+  addEmission,
+  addPointsItem,
+  claim,
+  init,
+  initCollection,
+  redeemPoints,
+  sleep,
+  stake,
+  toggleCollection,
+  toggleStake,
+  unstake,
+} from "../helpers/instructions"
+import { findNftRecordPda, findPointsReceiptPda, findStakooorCollectionId, getTokenAccount } from "../helpers/pdas"
+import { umi } from "../helpers/umi"
+import { assertErrorCode, expectFail, mintNfts } from "../helpers/utils"
+
+describe("Points", () => {
+  let creator: Keypair
+  let creatorProgram: anchor.Program<Stake>
+  let user: Keypair
+  let userProgram: anchor.Program<Stake>
+  let collectionNft: DigitalAsset
+  let collection: PublicKey
+  let nfts: DigitalAsset[]
+  let token: PublicKey
+  const staker = umi.eddsa.generateKeypair()
+  const pointsEmission = umi.eddsa.generateKeypair()
+
+  async function points(nft: DigitalAsset) {
+    const nftRecord = await userProgram.account.nftRecord.fetch(findNftRecordPda(staker.publicKey, nft.publicKey))
+    return nftRecord.points.toNumber()
+  }
+
+  before(async () => {
+    creator = await createNewUser()
+    user = await createNewUser()
+    creatorProgram = programPaidBy(creator)
+    userProgram = programPaidBy(user)
+    collectionNft = await createCollection(umi)
+    collection = findStakooorCollectionId(staker.publicKey, collectionNft.publicKey)
+    nfts = await mintNfts(collectionNft.publicKey, 2, false, user.publicKey)
+    token = await createToken(umi, BigInt(10_000), 0, undefined, creator.publicKey)
+
+    await init(creatorProgram, staker, "points", "Points")
+    await initCollection(creatorProgram, staker.publicKey, collectionNft.publicKey, false, null, 10)
+    await addEmission(creatorProgram, pointsEmission, staker.publicKey, collection, { points: {} }, 10, 0, null, 3600)
+    await toggleCollection(creatorProgram, staker.publicKey, collection, true)
+    await toggleStake(creatorProgram, staker.publicKey, true)
+
+    await stake(userProgram, staker.publicKey, nfts[0])
+    await sleep(2_000)
+    await claim(userProgram, staker.publicKey, nfts[0], pointsEmission.publicKey)
+  })
+
+  describe("Redeem points", () => {
+    const whitelistItem = umi.eddsa.generateKeypair()
+    const tokenItem = umi.eddsa.generateKeypair()
+    const expensiveItem = umi.eddsa.generateKeypair()
+
+    before(async () => {
+      await addPointsItem(creatorProgram, whitelistItem, staker.publicKey, { whitelist: {} }, 5, 1)
+      await addPointsItem(creatorProgram, expensiveItem, staker.publicKey, { whitelist: {} }, 1_000_000)
+    })
+
+    it("Cannot add an item without a price", async () => {
+      await expectFail(
+        () => addPointsItem(creatorProgram, umi.eddsa.generateKeypair(), staker.publicKey, { whitelist: {} }, 0),
+        (err) => assertErrorCode(err, "InvalidPointsItem")
+      )
+    })
+
+    it("Funds token items from the authority", async () => {
+      const kind = { token: { mint: toWeb3JsPublicKey(token), amount: new BN(3) } }
+      await addPointsItem(creatorProgram, tokenItem, staker.publicKey, kind, 5, 2)
+
+      const item = await creatorProgram.account.pointsItem.fetch(tokenItem.publicKey)
+      assert.equal(item.currentBalance.toNumber(), 6, "Expected the item to hold tokens for its whole supply")
+    })
+
+    it("Can redeem a whitelist spot and leave a receipt", async () => {
+      const pointsBefore = await points(nfts[0])
+      await redeemPoints(userProgram, staker.publicKey, whitelistItem.publicKey, nfts[0].publicKey)
+
+      assert.equal(pointsBefore - (await points(nfts[0])), 5, "Expected the item's price to be spent")
+
+      const receipt = await userProgram.account.pointsReceipt.fetch(findPointsReceiptPda(whitelistItem.publicKey, 0))
+      assert.equal(receipt.owner.toBase58(), user.publicKey, "Expected the receipt to record the owner")
+      assert.equal(receipt.nftMint.toBase58(), nfts[0].publicKey, "Expected the receipt to record the NFT")
+      assert.equal(receipt.points.toNumber(), 5, "Expected the receipt to record the points spent")
+    })
+
+    it("Cannot redeem an item that has sold out", async () => {
+      await expectFail(
+        () => redeemPoints(userProgram, staker.publicKey, whitelistItem.publicKey, nfts[0].publicKey),
+        (err) => assertErrorCode(err, "PointsItemSoldOut")
+      )
+    })
+
+    it("Cannot redeem an item without enough points", async () => {
+      await expectFail(
+        () => redeemPoints(userProgram, staker.publicKey, expensiveItem.publicKey, nfts[0].publicKey),
+        (err) => assertErrorCode(err, "NotEnoughPoints")
+      )
+    })
+
+    it("Can redeem points for tokens from the vault", async () => {
+      const balanceBefore = (await safeFetchToken(umi, getTokenAccount(token, user.publicKey)))?.amount || BigInt(0)
+      await redeemPoints(userProgram, staker.publicKey, tokenItem.publicKey, nfts[0].publicKey)
+      const balanceAfter = (await safeFetchToken(umi, getTokenAccount(token, user.publicKey)))?.amount || BigInt(0)
+
+      assert.equal(balanceAfter - balanceBefore, BigInt(3), "Expected the item's tokens to be paid")
+    })
+  })
+
+  describe("Unstake", () => {
+    const item = umi.eddsa.generateKeypair()
+
+    before(async () => {
+      await addPointsItem(creatorProgram, item, staker.publicKey, { whitelist: {} }, 1)
+      await stake(userProgram, staker.publicKey, nfts[1])
+    })
+
+    it("Credits the points accrued since the last claim", async () => {
+      await sleep(2_000)
+      const pointsBefore = await points(nfts[1])
+      await unstake(userProgram, staker.publicKey, nfts[1])
+
+      assert.ok((await points(nfts[1])) - pointsBefore >= 10, "Expected at least a second of points to be credited")
+    })
+
+    it("Cannot spend an unstaked NFT's points without holding it", async () => {
+      const newUser = await createNewUser()
+      const newUserProgram = programPaidBy(newUser)
+      await createAssociatedToken(umi, {
+        mint: nfts[1].publicKey,
+        owner: newUser.publicKey,
+      }).sendAndConfirm(umi)
+
+      await expectFail(
+        () => redeemPoints(newUserProgram, staker.publicKey, item.publicKey, nfts[1].publicKey, false),
+        (err) => assertErrorCode(err, "TokenAccountEmpty")
+      )
+    })
+
+    it("Can spend an unstaked NFT's points while holding it", async () => {
+      const pointsBefore = await points(nfts[1])
+      await redeemPoints(userProgram, staker.publicKey, item.publicKey, nfts[1].publicKey, false)
+
+      assert.equal(pointsBefore - (await points(nfts[1])), 1, "Expected the item's price to be spent")
+    })
+  })
+})
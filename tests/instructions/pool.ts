@@ -0,0 +1,137 @@
+import * as anchor from "@coral-xyz/anchor"
+import { Keypair, PublicKey } from "@metaplex-foundation/umi"
+import { AuthorityType, safeFetchToken, setAuthority } from "@metaplex-foundation/mpl-toolbox"
+import { assert } from "chai"
+import { BN } from "bn.js"
+import { Stake } from "../../target/types/stake"
+import { createNewUser, programPaidBy } from "../helper"
+import { createToken } from "../helpers/create-token"
+import {
+  addEmission,
+  addToken,
+  claimPool,
+  init,
+  initPool,
+  sleep,
+  stakeTokens,
+  toggleCollection,
+  toggleStake,
+  unstakeTokens,
+} from "../helpers/instructions"
+import { findPoolRecordPda, findStakooorCollectionId, getTokenAccount } from "../helpers/pdas"
+import { umi } from "../helpers/umi"
+import { assertErrorCode, expectFail } from "../helpers/utils"
+
+describe("Token pools", () => {
+  let creator: Keypair
+  let creatorProgram: anchor.Program<Stake>
+  let user: Keypair
+  let userProgram: anchor.Program<Stake>
+  let stakeMint: PublicKey
+  let collection: PublicKey
+  let token: PublicKey
+  const staker = umi.eddsa.generateKeypair()
+  const emission = umi.eddsa.generateKeypair()
+
+  async function balance(mint: PublicKey, owner: PublicKey) {
+    return (await safeFetchToken(umi, getTokenAccount(mint, owner)))?.amount || BigInt(0)
+  }
+
+  before(async () => {
+    creator = await createNewUser()
+    user = await createNewUser()
+    creatorProgram = programPaidBy(creator)
+    userProgram = programPaidBy(user)
+    stakeMint = await createToken(umi, BigInt(1_000), 0, undefined, user.publicKey)
+    collection = findStakooorCollectionId(staker.publicKey, stakeMint)
+
+    token = await createToken(umi, BigInt(10_000), 0, undefined, creator.publicKey)
+    await setAuthority(umi, {
+      owned: token,
+      owner: umi.identity.publicKey,
+      newAuthority: creator.publicKey,
+      authorityType: AuthorityType.MintTokens,
+    }).sendAndConfirm(umi)
+
+    await init(creatorProgram, staker, "token_pool", "Token pool", token)
+    await addToken(creatorProgram, staker.publicKey, token, false)
+  })
+
+  it("Cannot init a pool without room for stakers", async () => {
+    await expectFail(
+      () => initPool(creatorProgram, staker.publicKey, stakeMint, 0),
+      (err) => assertErrorCode(err, "NotEnoughStakers")
+    )
+  })
+
+  it("Can init a pool with a token emission", async () => {
+    await initPool(creatorProgram, staker.publicKey, stakeMint, 10)
+    await addEmission(creatorProgram, emission, staker.publicKey, collection, { token: {} }, 1, 0, null, 3600)
+    await toggleCollection(creatorProgram, staker.publicKey, collection, true)
+    await toggleStake(creatorProgram, staker.publicKey, true)
+
+    const collectionAccount = await creatorProgram.account.collection.fetch(collection)
+    assert.equal(
+      collectionAccount.tokenEmission?.toBase58(),
+      emission.publicKey,
+      "Expected the emission to be the pool's token emission"
+    )
+  })
+
+  it("Can deposit tokens into the pool", async () => {
+    const balanceBefore = await balance(stakeMint, user.publicKey)
+    await stakeTokens(userProgram, staker.publicKey, stakeMint, new BN(100))
+    const balanceAfter = await balance(stakeMint, user.publicKey)
+
+    assert.equal(balanceBefore - balanceAfter, BigInt(100), "Expected 100 tokens to be deposited")
+
+    const poolRecord = await userProgram.account.poolRecord.fetch(
+      findPoolRecordPda(staker.publicKey, collection, user.publicKey)
+    )
+    assert.ok(poolRecord.amount.eq(new BN(100)), "Expected the pool record to hold the deposit")
+
+    const collectionAccount = await userProgram.account.collection.fetch(collection)
+    assert.ok(collectionAccount.currentStakersCount.eq(new BN(1)), "Expected 1 depositor")
+  })
+
+  it("Can claim rewards in proportion to the deposit", async () => {
+    await sleep(2_000)
+    const balanceBefore = await balance(token, user.publicKey)
+    await claimPool(userProgram, staker.publicKey, stakeMint)
+    const balanceAfter = await balance(token, user.publicKey)
+
+    assert.ok(balanceAfter - balanceBefore >= BigInt(100), "Expected at least a second of rewards for 100 tokens")
+  })
+
+  it("Cannot withdraw more than was deposited", async () => {
+    await expectFail(
+      () => unstakeTokens(userProgram, staker.publicKey, stakeMint, new BN(101)),
+      (err) => assertErrorCode(err, "AmountTooLow")
+    )
+  })
+
+  it("Can withdraw part of the deposit", async () => {
+    const balanceBefore = await balance(stakeMint, user.publicKey)
+    await unstakeTokens(userProgram, staker.publicKey, stakeMint, new BN(40))
+    const balanceAfter = await balance(stakeMint, user.publicKey)
+
+    assert.equal(balanceAfter - balanceBefore, BigInt(40), "Expected 40 tokens to be returned")
+
+    const poolRecord = await userProgram.account.poolRecord.fetch(
+      findPoolRecordPda(staker.publicKey, collection, user.publicKey)
+    )
+    assert.ok(poolRecord.amount.eq(new BN(60)), "Expected 60 tokens to remain deposited")
+  })
+
+  it("Closes the pool record when fully withdrawn", async () => {
+    await unstakeTokens(userProgram, staker.publicKey, stakeMint, new BN(60))
+
+    const poolRecord = await userProgram.account.poolRecord.fetchNullable(
+      findPoolRecordPda(staker.publicKey, collection, user.publicKey)
+    )
+    assert.isNull(poolRecord, "Expected the pool record to be closed")
+
+    const collectionAccount = await userProgram.account.collection.fetch(collection)
+    assert.ok(collectionAccount.currentStakersCount.eq(new BN(0)), "Expected no depositors")
+  })
+})
@@ -0,0 +1,105 @@
+import * as anchor from "@coral-xyz/anchor"
+import { Keypair, PublicKey } from "@metaplex-foundation/umi"
+import { safeFetchToken } from "@metaplex-foundation/mpl-toolbox"
+import { assert } from "chai"
+import { Stake } from "../../target/types/stake"
+import { createFixture } from "../helpers/fixture"
+import { addEmission } from "../helpers/instructions"
+import { getTokenAccount } from "../helpers/pdas"
+import { umi } from "../helpers/umi"
+import { assertErrorCode, expectFail } from "../helpers/utils"
+
+describe("Reward schedules", () => {
+  let creator: Keypair
+  let creatorProgram: anchor.Program<Stake>
+  let collection: PublicKey
+  let token: PublicKey
+  const staker = umi.eddsa.generateKeypair()
+  const emission = umi.eddsa.generateKeypair()
+
+  function now() {
+    return Math.floor(Date.now() / 1000)
+  }
+
+  async function creatorBalance() {
+    return (await safeFetchToken(umi, getTokenAccount(token, creator.publicKey)))?.amount || BigInt(0)
+  }
+
+  before(async () => {
+    ;({ creator, creatorProgram, collection, token } = await createFixture(
+      staker,
+      "reward_schedules",
+      "Reward schedules",
+      { numNfts: 0, tokenSupply: 100_000, tokenVault: true, maxStakersCount: 2 }
+    ))
+  })
+
+  it("Cannot schedule a reward change before the emission starts", async () => {
+    await expectFail(
+      () =>
+        addEmission(
+          creatorProgram,
+          umi.eddsa.generateKeypair(),
+          staker.publicKey,
+          collection,
+          { token: {} },
+          2,
+          0,
+          null,
+          3600,
+          null,
+          null,
+          [{ time: now() - 60, reward: 1 }]
+        ),
+      (err) => assertErrorCode(err, "InvalidRewardSchedule")
+    )
+  })
+
+  it("Cannot schedule a reward change on a points emission", async () => {
+    await expectFail(
+      () =>
+        addEmission(
+          creatorProgram,
+          umi.eddsa.generateKeypair(),
+          staker.publicKey,
+          collection,
+          { points: {} },
+          2,
+          0,
+          null,
+          3600,
+          null,
+          null,
+          [{ time: now() + 1800, reward: 1 }]
+        ),
+      (err) => assertErrorCode(err, "InvalidRewardSchedule")
+    )
+  })
+
+  it("Funds the emission for the whole schedule", async () => {
+    const balanceBefore = await creatorBalance()
+    await addEmission(
+      creatorProgram,
+      emission,
+      staker.publicKey,
+      collection,
+      { token: {} },
+      2,
+      0,
+      null,
+      3600,
+      null,
+      null,
+      [{ time: now() + 1800, reward: 1 }]
+    )
+    const balanceAfter = await creatorBalance()
+
+    const emissionAccount = await creatorProgram.account.emission.fetch(emission.publicKey)
+    const funded = balanceBefore - balanceAfter
+    assert.equal(emissionAccount.rewardSchedule.length, 1, "Expected the schedule to be stored")
+    assert.equal(emissionAccount.rewardSchedule[0].reward.toNumber(), 1, "Expected the scheduled reward to be stored")
+    // half the duration at 2 and half at 1, for each of the 2 stakers
+    assert.ok(funded < BigInt(2 * 3600 * 2), "Expected less than the full duration at the starting reward")
+    assert.ok(funded > BigInt(1 * 3600 * 2), "Expected more than the full duration at the scheduled reward")
+  })
+})
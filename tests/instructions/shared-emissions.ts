@@ -0,0 +1,132 @@
+import * as anchor from "@coral-xyz/anchor"
+import { Keypair, PublicKey } from "@metaplex-foundation/umi"
+import { DigitalAsset } from "@metaplex-foundation/mpl-token-metadata"
+import { safeFetchToken } from "@metaplex-foundation/mpl-toolbox"
+import { assert } from "chai"
+import { Stake } from "../../target/types/stake"
+import { createFixture } from "../helpers/fixture"
+import { addEmission, claim, sleep, stake, toggleCollection, toggleStake } from "../helpers/instructions"
+import { getTokenAccount } from "../helpers/pdas"
+import { umi } from "../helpers/umi"
+import { assertErrorCode, expectFail } from "../helpers/utils"
+
+describe("Shared emissions", () => {
+  let creator: Keypair
+  let creatorProgram: anchor.Program<Stake>
+  let user: Keypair
+  let userProgram: anchor.Program<Stake>
+  let collection: PublicKey
+  let nfts: DigitalAsset[]
+  let token: PublicKey
+  const staker = umi.eddsa.generateKeypair()
+  const emission = umi.eddsa.generateKeypair()
+
+  function now() {
+    return Math.floor(Date.now() / 1000)
+  }
+
+  async function balance(owner: PublicKey) {
+    return (await safeFetchToken(umi, getTokenAccount(token, owner)))?.amount || BigInt(0)
+  }
+
+  before(async () => {
+    ;({ creator, creatorProgram, user, userProgram, collection, nfts, token } = await createFixture(
+      staker,
+      "shared_emissions",
+      "Shared emissions",
+      { tokenSupply: 100_000, tokenVault: true }
+    ))
+  })
+
+  it("Cannot share a points emission", async () => {
+    await expectFail(
+      () =>
+        addEmission(
+          creatorProgram,
+          umi.eddsa.generateKeypair(),
+          staker.publicKey,
+          collection,
+          { points: {} },
+          10,
+          0,
+          null,
+          3600,
+          null,
+          null,
+          [],
+          true
+        ),
+      (err) => assertErrorCode(err, "InvalidSharedEmission")
+    )
+  })
+
+  it("Funds a shared emission for its total reward rather than every slot", async () => {
+    const balanceBefore = await balance(creator.publicKey)
+    await addEmission(
+      creatorProgram,
+      emission,
+      staker.publicKey,
+      collection,
+      { token: {} },
+      10,
+      0,
+      null,
+      3600,
+      null,
+      null,
+      [],
+      true
+    )
+    const balanceAfter = await balance(creator.publicKey)
+
+    const emissionAccount = await creatorProgram.account.emission.fetch(emission.publicKey)
+    assert.ok(emissionAccount.shared, "Expected the emission to be shared")
+    assert.equal(balanceBefore - balanceAfter, BigInt(10 * 3600), "Expected the emission to be funded once")
+  })
+
+  it("Cannot share an extra token emission", async () => {
+    await expectFail(
+      () =>
+        addEmission(
+          creatorProgram,
+          umi.eddsa.generateKeypair(),
+          staker.publicKey,
+          collection,
+          { token: {} },
+          10,
+          0,
+          null,
+          3600,
+          null,
+          null,
+          [],
+          true
+        ),
+      (err) => assertErrorCode(err, "InvalidSharedEmission")
+    )
+  })
+
+  it("Splits the reward between the staked items", async () => {
+    await toggleCollection(creatorProgram, staker.publicKey, collection, true)
+    await toggleStake(creatorProgram, staker.publicKey, true)
+
+    const startedAt = now()
+    for (const nft of nfts) {
+      await stake(userProgram, staker.publicKey, nft)
+    }
+    await sleep(2_000)
+
+    const balanceBefore = await balance(user.publicKey)
+    for (const nft of nfts) {
+      await claim(userProgram, staker.publicKey, nft, emission.publicKey)
+    }
+    const balanceAfter = await balance(user.publicKey)
+    const elapsed = now() - startedAt + 1
+
+    assert.ok(balanceAfter > balanceBefore, "Expected rewards to be paid")
+    assert.ok(
+      balanceAfter - balanceBefore <= BigInt(10 * elapsed),
+      "Expected the staked items to share a single reward"
+    )
+  })
+})
@@ -0,0 +1,150 @@
+import * as anchor from "@coral-xyz/anchor"
+import { Keypair, PublicKey } from "@metaplex-foundation/umi"
+import { DigitalAsset } from "@metaplex-foundation/mpl-token-metadata"
+import { AuthorityType, safeFetchToken, setAuthority } from "@metaplex-foundation/mpl-toolbox"
+import { assert } from "chai"
+import { BN } from "bn.js"
+import { Stake } from "../../target/types/stake"
+import { createNewUser, programPaidBy } from "../helper"
+import { createCollection } from "../helpers/create-collection"
+import { Cnft, createCnft, createCnftTree } from "../helpers/create-cnft"
+import { createToken } from "../helpers/create-token"
+import {
+  addEmission,
+  addToken,
+  init,
+  initCollection,
+  sleep,
+  stakeCnft,
+  toggleCollection,
+  toggleStake,
+  unstakeCnft,
+} from "../helpers/instructions"
+import { findNftRecordPda, findStakeRecordPda, findStakooorCollectionId, getTokenAccount } from "../helpers/pdas"
+import { umi } from "../helpers/umi"
+import { assertErrorCode, expectFail } from "../helpers/utils"
+
+describe("Stake cNFT", () => {
+  let creator: Keypair
+  let creatorProgram: anchor.Program<Stake>
+  let user: Keypair
+  let userProgram: anchor.Program<Stake>
+  let collectionNft: DigitalAsset
+  let collection: PublicKey
+  let merkleTree: PublicKey
+  let cnft: Cnft
+  let token: PublicKey
+  const staker = umi.eddsa.generateKeypair()
+  const emission = umi.eddsa.generateKeypair()
+
+  before(async () => {
+    creator = await createNewUser()
+    user = await createNewUser()
+    creatorProgram = programPaidBy(creator)
+    userProgram = programPaidBy(user)
+    collectionNft = await createCollection(umi)
+    collection = findStakooorCollectionId(staker.publicKey, collectionNft.publicKey)
+    merkleTree = await createCnftTree(umi)
+    cnft = await createCnft(umi, merkleTree, collectionNft.publicKey, user.publicKey)
+
+    token = await createToken(umi, BigInt(10_000), 0, undefined, creator.publicKey)
+    await setAuthority(umi, {
+      owned: token,
+      owner: umi.identity.publicKey,
+      newAuthority: creator.publicKey,
+      authorityType: AuthorityType.MintTokens,
+    }).sendAndConfirm(umi)
+
+    await init(creatorProgram, staker, "stake_cnft", "Stake cNFT", token)
+    await initCollection(creatorProgram, staker.publicKey, collectionNft.publicKey, false, null, 10)
+    await addToken(creatorProgram, staker.publicKey, token, false)
+    await addEmission(creatorProgram, emission, staker.publicKey, collection, { token: {} }, 1, 0, null, 3600)
+    await toggleCollection(creatorProgram, staker.publicKey, collection, true)
+    await toggleStake(creatorProgram, staker.publicKey, true)
+  })
+
+  it("Cannot stake a cNFT from another collection", async () => {
+    const otherCollection = await createCollection(umi)
+    const otherCnft = await createCnft(umi, merkleTree, otherCollection.publicKey, user.publicKey)
+
+    await expectFail(
+      () => stakeCnft(userProgram, staker.publicKey, { ...otherCnft, collection: collectionNft.publicKey }),
+      (err) => assertErrorCode(err, "InvalidCollection")
+    )
+  })
+
+  it("Can stake a cNFT", async () => {
+    await stakeCnft(userProgram, staker.publicKey, cnft)
+
+    const collectionAccount = await userProgram.account.collection.fetch(collection)
+    assert.ok(collectionAccount.currentStakersCount.eq(new BN(1)), "Expected 1 staked item")
+
+    const record = await userProgram.account.stakeRecord.fetch(findStakeRecordPda(staker.publicKey, cnft.assetId))
+    assert.ok(record.owner.equals(userProgram.provider.publicKey), "Expected the staker to own the record")
+    assert.equal(record.nftMint.toBase58(), cnft.assetId, "Expected the record to be keyed by the asset id")
+  })
+
+  it("Cannot unstake someone elses cNFT", async () => {
+    const newUser = await createNewUser()
+    const newUserProgram = programPaidBy(newUser)
+
+    await expectFail(
+      () => unstakeCnft(newUserProgram, staker.publicKey, cnft),
+      (err) => assertErrorCode(err, "Unauthorized")
+    )
+  })
+
+  it("Can unstake a cNFT and receive its rewards", async () => {
+    await sleep(1_000)
+    const balanceBefore = (await safeFetchToken(umi, getTokenAccount(token, user.publicKey)))?.amount || BigInt(0)
+
+    await unstakeCnft(userProgram, staker.publicKey, cnft)
+
+    const balanceAfter = (await safeFetchToken(umi, getTokenAccount(token, user.publicKey)))?.amount || BigInt(0)
+    assert.ok(balanceAfter > balanceBefore, "Expected rewards to be paid")
+
+    const record = await userProgram.account.stakeRecord.fetchNullable(
+      findStakeRecordPda(staker.publicKey, cnft.assetId)
+    )
+    assert.isNull(record, "Expected the stake record to be closed")
+
+    const collectionAccount = await userProgram.account.collection.fetch(collection)
+    assert.ok(collectionAccount.currentStakersCount.eq(new BN(0)), "Expected no staked items")
+  })
+
+  describe("Points", () => {
+    const pointsEmission = umi.eddsa.generateKeypair()
+    let pointsCnft: Cnft
+
+    before(async () => {
+      const pointsCollectionNft = await createCollection(umi)
+      const pointsCollection = findStakooorCollectionId(staker.publicKey, pointsCollectionNft.publicKey)
+      pointsCnft = await createCnft(umi, merkleTree, pointsCollectionNft.publicKey, user.publicKey)
+
+      await initCollection(creatorProgram, staker.publicKey, pointsCollectionNft.publicKey, false, null, 10)
+      await addEmission(
+        creatorProgram,
+        pointsEmission,
+        staker.publicKey,
+        pointsCollection,
+        { points: {} },
+        10,
+        0,
+        null,
+        3600
+      )
+      await toggleCollection(creatorProgram, staker.publicKey, pointsCollection, true)
+    })
+
+    it("Credits points to the cNFT's record on unstake", async () => {
+      await stakeCnft(userProgram, staker.publicKey, pointsCnft)
+      await sleep(1_000)
+      await unstakeCnft(userProgram, staker.publicKey, pointsCnft)
+
+      const nftRecord = await userProgram.account.nftRecord.fetch(
+        findNftRecordPda(staker.publicKey, pointsCnft.assetId)
+      )
+      assert.ok(nftRecord.points.toNumber() >= 10, "Expected at least a second of points to be credited")
+    })
+  })
+})
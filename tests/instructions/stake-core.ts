@@ -0,0 +1,192 @@
+import * as anchor from "@coral-xyz/anchor"
+import { Keypair, PublicKey } from "@metaplex-foundation/umi"
+import { fetchAssetV1 } from "@metaplex-foundation/mpl-core"
+import { AuthorityType, safeFetchToken, setAuthority } from "@metaplex-foundation/mpl-toolbox"
+import { assert } from "chai"
+import { BN } from "bn.js"
+import { Stake } from "../../target/types/stake"
+import { createNewUser, programPaidBy } from "../helper"
+import { createCoreAsset, createCoreCollection } from "../helpers/create-core"
+import { createToken } from "../helpers/create-token"
+import {
+  addEmission,
+  addToken,
+  forceUnstakeCore,
+  init,
+  initCollection,
+  sleep,
+  stakeCore,
+  toggleCollection,
+  toggleStake,
+  unstakeCore,
+} from "../helpers/instructions"
+import {
+  findNftAuthorityPda,
+  findNftRecordPda,
+  findStakeRecordPda,
+  findStakooorCollectionId,
+  getTokenAccount,
+} from "../helpers/pdas"
+import { umi } from "../helpers/umi"
+import { assertErrorCode, expectFail } from "../helpers/utils"
+
+describe("Stake Core", () => {
+  let creator: Keypair
+  let creatorProgram: anchor.Program<Stake>
+  let user: Keypair
+  let userProgram: anchor.Program<Stake>
+  let coreCollection: PublicKey
+  let collection: PublicKey
+  let assets: PublicKey[]
+  let token: PublicKey
+  const staker = umi.eddsa.generateKeypair()
+  const emission = umi.eddsa.generateKeypair()
+
+  before(async () => {
+    creator = await createNewUser()
+    user = await createNewUser()
+    creatorProgram = programPaidBy(creator)
+    userProgram = programPaidBy(user)
+    coreCollection = await createCoreCollection(umi)
+    collection = findStakooorCollectionId(staker.publicKey, coreCollection)
+    assets = [
+      await createCoreAsset(umi, coreCollection, user.publicKey),
+      await createCoreAsset(umi, coreCollection, user.publicKey),
+    ]
+
+    token = await createToken(umi, BigInt(10_000), 0, undefined, creator.publicKey)
+    await setAuthority(umi, {
+      owned: token,
+      owner: umi.identity.publicKey,
+      newAuthority: creator.publicKey,
+      authorityType: AuthorityType.MintTokens,
+    }).sendAndConfirm(umi)
+
+    await init(creatorProgram, staker, "stake_core", "Stake Core", token)
+    await initCollection(creatorProgram, staker.publicKey, coreCollection, false, null, 10)
+    await addToken(creatorProgram, staker.publicKey, token, false)
+    await addEmission(creatorProgram, emission, staker.publicKey, collection, { token: {} }, 1, 0, null, 3600)
+    await toggleCollection(creatorProgram, staker.publicKey, collection, true)
+    await toggleStake(creatorProgram, staker.publicKey, true)
+  })
+
+  it("Cannot stake an asset from another collection", async () => {
+    const otherCollection = await createCoreCollection(umi)
+    const otherAsset = await createCoreAsset(umi, otherCollection, user.publicKey)
+
+    await expectFail(
+      () => stakeCore(userProgram, staker.publicKey, otherAsset, coreCollection),
+      (err) => assertErrorCode(err, "InvalidCollection")
+    )
+  })
+
+  it("Can non-custodially stake a Core asset", async () => {
+    await stakeCore(userProgram, staker.publicKey, assets[0], coreCollection)
+
+    const collectionAccount = await userProgram.account.collection.fetch(collection)
+    assert.ok(collectionAccount.currentStakersCount.eq(new BN(1)), "Expected 1 staked item")
+
+    const record = await userProgram.account.stakeRecord.fetch(findStakeRecordPda(staker.publicKey, assets[0]))
+    assert.ok(record.owner.equals(userProgram.provider.publicKey), "Expected the staker to own the record")
+
+    const asset = await fetchAssetV1(umi, assets[0])
+    assert.equal(asset.owner, user.publicKey, "Expected the staker to still hold the asset")
+    assert.ok(asset.freezeDelegate?.frozen, "Expected the asset to be frozen")
+    assert.equal(
+      asset.freezeDelegate?.authority.address,
+      findNftAuthorityPda(staker.publicKey),
+      "Expected the nft authority to be the freeze delegate"
+    )
+  })
+
+  it("Cannot unstake someone elses asset", async () => {
+    const newUser = await createNewUser()
+    const newUserProgram = programPaidBy(newUser)
+
+    await expectFail(
+      () => unstakeCore(newUserProgram, staker.publicKey, assets[0], coreCollection),
+      (err) => assertErrorCode(err, "Unauthorized")
+    )
+  })
+
+  it("Can unstake a Core asset and receive its rewards", async () => {
+    await sleep(1_000)
+    const balanceBefore = (await safeFetchToken(umi, getTokenAccount(token, user.publicKey)))?.amount || BigInt(0)
+
+    await unstakeCore(userProgram, staker.publicKey, assets[0], coreCollection)
+
+    const balanceAfter = (await safeFetchToken(umi, getTokenAccount(token, user.publicKey)))?.amount || BigInt(0)
+    assert.ok(balanceAfter > balanceBefore, "Expected rewards to be paid")
+
+    const asset = await fetchAssetV1(umi, assets[0])
+    assert.isUndefined(asset.freezeDelegate, "Expected the freeze delegate to be removed")
+
+    const record = await userProgram.account.stakeRecord.fetchNullable(findStakeRecordPda(staker.publicKey, assets[0]))
+    assert.isNull(record, "Expected the stake record to be closed")
+  })
+
+  describe("Force unstake", () => {
+    before(async () => {
+      await stakeCore(userProgram, staker.publicKey, assets[1], coreCollection)
+    })
+
+    it("Cannot be force unstaked by anyone other than the staker authority", async () => {
+      await expectFail(
+        () => forceUnstakeCore(userProgram, staker.publicKey, assets[1], coreCollection),
+        (err) => assertErrorCode(err, "Unauthorized")
+      )
+    })
+
+    it("Can be force unstaked by the staker authority", async () => {
+      await sleep(1_000)
+      const balanceBefore = (await safeFetchToken(umi, getTokenAccount(token, user.publicKey)))?.amount || BigInt(0)
+
+      await forceUnstakeCore(creatorProgram, staker.publicKey, assets[1], coreCollection)
+
+      const balanceAfter = (await safeFetchToken(umi, getTokenAccount(token, user.publicKey)))?.amount || BigInt(0)
+      assert.ok(balanceAfter > balanceBefore, "Expected rewards to be paid to the owner")
+
+      const asset = await fetchAssetV1(umi, assets[1])
+      assert.equal(asset.owner, user.publicKey, "Expected the owner to still hold the asset")
+      assert.isFalse(asset.freezeDelegate?.frozen, "Expected the asset to be thawed")
+
+      const collectionAccount = await creatorProgram.account.collection.fetch(collection)
+      assert.ok(collectionAccount.currentStakersCount.eq(new BN(0)), "Expected no staked items")
+    })
+  })
+
+  describe("Points", () => {
+    const pointsEmission = umi.eddsa.generateKeypair()
+    let pointsCoreCollection: PublicKey
+    let pointsAsset: PublicKey
+
+    before(async () => {
+      pointsCoreCollection = await createCoreCollection(umi)
+      const pointsCollection = findStakooorCollectionId(staker.publicKey, pointsCoreCollection)
+      pointsAsset = await createCoreAsset(umi, pointsCoreCollection, user.publicKey)
+
+      await initCollection(creatorProgram, staker.publicKey, pointsCoreCollection, false, null, 10)
+      await addEmission(
+        creatorProgram,
+        pointsEmission,
+        staker.publicKey,
+        pointsCollection,
+        { points: {} },
+        10,
+        0,
+        null,
+        3600
+      )
+      await toggleCollection(creatorProgram, staker.publicKey, pointsCollection, true)
+    })
+
+    it("Credits points to the asset's record on unstake", async () => {
+      await stakeCore(userProgram, staker.publicKey, pointsAsset, pointsCoreCollection)
+      await sleep(1_000)
+      await unstakeCore(userProgram, staker.publicKey, pointsAsset, pointsCoreCollection)
+
+      const nftRecord = await userProgram.account.nftRecord.fetch(findNftRecordPda(staker.publicKey, pointsAsset))
+      assert.ok(nftRecord.points.toNumber() >= 10, "Expected at least a second of points to be credited")
+    })
+  })
+})
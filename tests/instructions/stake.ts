@@ -26,7 +26,7 @@ import {
 import { assert } from "chai"
 import { BN } from "bn.js"
 import { createToken } from "../helpers/create-token"
-import { init, initCollection, stake } from "../helpers/instructions"
+import { init, initCollection, stake, stakeMany } from "../helpers/instructions"
 import { assertErrorCode, expectFail } from "../helpers/utils"
 import { Program } from "@coral-xyz/anchor"
 import { createNewUser, programPaidBy } from "../helper"
@@ -219,4 +219,36 @@ describe("Stake", () => {
       assert.equal(custodyNftBalance.value.uiAmount, 1, "Custody holds the NFT")
     })
   })
+
+  describe("Batch staking", () => {
+    let collection: DigitalAsset
+    let nfts: DigitalAsset[]
+
+    before(async () => {
+      collection = await createCollection(umi)
+      nfts = await Promise.all(
+        Array.from({ length: 3 }).map(() => createNft(umi, false, collection.publicKey, user.publicKey))
+      )
+      await initCollection(creatorProgram, stakerId, collection.publicKey, false, tokenMint, { mintToken: {} })
+    })
+
+    it("Can stake multiple NFTs in a single instruction", async () => {
+      await stakeMany(userProgram, stakerId, nfts)
+
+      const stakooorCollection = await userProgram.account.collection.fetch(
+        findStakooorCollectionId(stakerId, collection.publicKey)
+      )
+      assert.ok(stakooorCollection.currentStakersCount.eq(new BN(nfts.length)))
+
+      for (const nft of nfts) {
+        const record = await userProgram.account.stakeRecord.fetch(findStakeRecordPda(stakerId, nft.publicKey))
+        assert.ok(record.owner.equals(userProgram.provider.publicKey))
+
+        const nftToken = getTokenAccount(nft.publicKey, user.publicKey)
+        const nftAfter = await fetchDigitalAssetWithToken(umi, nft.publicKey, nftToken)
+        assert.equal(nftAfter.token.state, LegacyState.Frozen)
+        assert.equal(unwrapOption(nftAfter.token.delegate), nftAuthority)
+      }
+    })
+  })
 })
@@ -0,0 +1,115 @@
+import * as anchor from "@coral-xyz/anchor"
+import { Keypair, PublicKey } from "@metaplex-foundation/umi"
+import { DigitalAsset } from "@metaplex-foundation/mpl-token-metadata"
+import { AuthorityType, setAuthority } from "@metaplex-foundation/mpl-toolbox"
+import { assert } from "chai"
+import { BN } from "bn.js"
+import { Stake } from "../../target/types/stake"
+import { createNewUser, programPaidBy } from "../helper"
+import { createCollection } from "../helpers/create-collection"
+import { createNft } from "../helpers/create-nft"
+import { createToken } from "../helpers/create-token"
+import {
+  addEmission,
+  addToken,
+  init,
+  initCollection,
+  sleep,
+  stake,
+  toggleCollection,
+  toggleStake,
+  updateStakingStartsAt,
+} from "../helpers/instructions"
+import { findStakeRecordPda, findStakooorCollectionId } from "../helpers/pdas"
+import { umi } from "../helpers/umi"
+import { assertErrorCode, expectFail } from "../helpers/utils"
+
+describe("Staking start time", () => {
+  let creator: Keypair
+  let creatorProgram: anchor.Program<Stake>
+  let user: Keypair
+  let userProgram: anchor.Program<Stake>
+  let collectionNft: DigitalAsset
+  let collection: PublicKey
+  let userNft: DigitalAsset
+  let creatorNft: DigitalAsset
+  let token: PublicKey
+  const staker = umi.eddsa.generateKeypair()
+  const emission = umi.eddsa.generateKeypair()
+
+  function now() {
+    return Math.floor(Date.now() / 1000)
+  }
+
+  before(async () => {
+    creator = await createNewUser()
+    user = await createNewUser()
+    creatorProgram = programPaidBy(creator)
+    userProgram = programPaidBy(user)
+    collectionNft = await createCollection(umi)
+    collection = findStakooorCollectionId(staker.publicKey, collectionNft.publicKey)
+    userNft = await createNft(umi, false, collectionNft.publicKey, user.publicKey)
+    creatorNft = await createNft(umi, false, collectionNft.publicKey, creator.publicKey)
+
+    token = await createToken(umi, BigInt(10_000), 0, undefined, creator.publicKey)
+    await setAuthority(umi, {
+      owned: token,
+      owner: umi.identity.publicKey,
+      newAuthority: creator.publicKey,
+      authorityType: AuthorityType.MintTokens,
+    }).sendAndConfirm(umi)
+
+    await init(creatorProgram, staker, "staking_starts_at", "Staking starts at", token)
+    await addToken(creatorProgram, staker.publicKey, token, false)
+  })
+
+  it("Cannot init a collection that starts in the past", async () => {
+    await expectFail(
+      () => initCollection(creatorProgram, staker.publicKey, collectionNft.publicKey, false, new BN(now() - 60), 10),
+      (err) => assertErrorCode(err, "StartTimeInPast")
+    )
+  })
+
+  it("Stores the start time on the collection", async () => {
+    const startsAt = now() + 3600
+    await initCollection(creatorProgram, staker.publicKey, collectionNft.publicKey, false, new BN(startsAt), 10)
+    await addEmission(creatorProgram, emission, staker.publicKey, collection, { token: {} }, 1, 0, null, 7200)
+    await toggleCollection(creatorProgram, staker.publicKey, collection, true)
+    await toggleStake(creatorProgram, staker.publicKey, true)
+
+    const collectionAccount = await creatorProgram.account.collection.fetch(collection)
+    assert.equal(collectionAccount.stakingStartsAt.toNumber(), startsAt, "Expected the start time to be stored")
+  })
+
+  it("Cannot stake before the collection goes live", async () => {
+    await expectFail(
+      () => stake(userProgram, staker.publicKey, userNft),
+      (err) => assertErrorCode(err, "StakeNotLive")
+    )
+  })
+
+  it("Lets the staker authority stake before the collection goes live", async () => {
+    await stake(creatorProgram, staker.publicKey, creatorNft)
+
+    const record = await creatorProgram.account.stakeRecord.fetch(
+      findStakeRecordPda(staker.publicKey, creatorNft.publicKey)
+    )
+    assert.ok(record.owner.equals(creatorProgram.provider.publicKey), "Expected the authority to own the record")
+  })
+
+  it("Cannot move the start time into the past", async () => {
+    await expectFail(
+      () => updateStakingStartsAt(creatorProgram, staker.publicKey, collection, new BN(now() - 60)),
+      (err) => assertErrorCode(err, "StartTimeInPast")
+    )
+  })
+
+  it("Can stake once an earlier start time has passed", async () => {
+    await updateStakingStartsAt(creatorProgram, staker.publicKey, collection, new BN(now() + 3))
+    await sleep(5_000)
+    await stake(userProgram, staker.publicKey, userNft)
+
+    const collectionAccount = await userProgram.account.collection.fetch(collection)
+    assert.ok(collectionAccount.currentStakersCount.eq(new BN(2)), "Expected 2 staked items")
+  })
+})
@@ -0,0 +1,101 @@
+import * as anchor from "@coral-xyz/anchor"
+import { Keypair, PublicKey } from "@metaplex-foundation/umi"
+import { DigitalAsset } from "@metaplex-foundation/mpl-token-metadata"
+import { toWeb3JsPublicKey } from "@metaplex-foundation/umi-web3js-adapters"
+import { TOKEN_2022_PROGRAM_ID, getAccount } from "@solana/spl-token"
+import { assert } from "chai"
+import { Stake } from "../../target/types/stake"
+import { createNewUser, programPaidBy } from "../helper"
+import { createCollection } from "../helpers/create-collection"
+import { createTransferFeeToken } from "../helpers/create-token"
+import {
+  addEmission,
+  addToken,
+  claim,
+  init,
+  initCollection,
+  sleep,
+  stake,
+  toggleCollection,
+  toggleStake,
+  updateCollectionCapacity,
+} from "../helpers/instructions"
+import { findStakooorCollectionId, findTokenAuthorityPda, getTokenAccount } from "../helpers/pdas"
+import { umi } from "../helpers/umi"
+import { assertErrorCode, expectFail, mintNfts } from "../helpers/utils"
+
+const TRANSFER_FEE_BPS = 100
+
+describe("Token-2022 rewards", () => {
+  let creator: Keypair
+  let creatorProgram: anchor.Program<Stake>
+  let user: Keypair
+  let userProgram: anchor.Program<Stake>
+  let collectionNft: DigitalAsset
+  let collection: PublicKey
+  let nfts: DigitalAsset[]
+  let token: PublicKey
+  const staker = umi.eddsa.generateKeypair()
+  const emission = umi.eddsa.generateKeypair()
+
+  async function balance(owner: PublicKey) {
+    const account = await getAccount(
+      userProgram.provider.connection,
+      toWeb3JsPublicKey(getTokenAccount(token, owner, TOKEN_2022_PROGRAM_ID)),
+      "processed",
+      TOKEN_2022_PROGRAM_ID
+    ).catch(() => null)
+
+    return account?.amount || BigInt(0)
+  }
+
+  before(async () => {
+    creator = await createNewUser()
+    user = await createNewUser()
+    creatorProgram = programPaidBy(creator)
+    userProgram = programPaidBy(user)
+    collectionNft = await createCollection(umi)
+    collection = findStakooorCollectionId(staker.publicKey, collectionNft.publicKey)
+    nfts = await mintNfts(collectionNft.publicKey, 2, false, user.publicKey)
+    token = await createTransferFeeToken(creatorProgram.provider, BigInt(1_000_000), TRANSFER_FEE_BPS)
+
+    await init(creatorProgram, staker, "token_2022", "Token-2022")
+    await addToken(creatorProgram, staker.publicKey, token, true)
+    await initCollection(creatorProgram, staker.publicKey, collectionNft.publicKey, false, null, 2)
+    await toggleCollection(creatorProgram, staker.publicKey, collection, true)
+    await toggleStake(creatorProgram, staker.publicKey, true)
+  })
+
+  it("Credits the emission with what the vault received after the transfer fee", async () => {
+    const totalEmission = BigInt(1 * 2 * 3600)
+    await addEmission(creatorProgram, emission, staker.publicKey, collection, { token: {} }, 1, 0, null, 3600)
+
+    const emissionAccount = await creatorProgram.account.emission.fetch(emission.publicKey)
+    const vaultBalance = await balance(findTokenAuthorityPda(staker.publicKey))
+
+    assert.ok(vaultBalance < totalEmission, "Expected the transfer fee to be withheld")
+    assert.equal(
+      emissionAccount.currentBalance.toString(),
+      vaultBalance.toString(),
+      "Expected the emission balance to match the vault"
+    )
+  })
+
+  it("Pays rewards in the Token-2022 mint", async () => {
+    await stake(userProgram, staker.publicKey, nfts[0])
+    await sleep(2_000)
+
+    const balanceBefore = await balance(user.publicKey)
+    await claim(userProgram, staker.publicKey, nfts[0], emission.publicKey)
+    const balanceAfter = await balance(user.publicKey)
+
+    assert.ok(balanceAfter > balanceBefore, "Expected Token-2022 rewards to be received")
+  })
+
+  it("Cannot raise the capacity when the transfer fee leaves the vault short", async () => {
+    await expectFail(
+      () => updateCollectionCapacity(creatorProgram, staker.publicKey, collection, 10),
+      (err) => assertErrorCode(err, "InsufficientBalanceInVault")
+    )
+  })
+})
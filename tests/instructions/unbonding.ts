@@ -0,0 +1,109 @@
+import * as anchor from "@coral-xyz/anchor"
+import { Keypair, PublicKey } from "@metaplex-foundation/umi"
+import { DigitalAsset } from "@metaplex-foundation/mpl-token-metadata"
+import { AuthorityType, safeFetchToken, setAuthority } from "@metaplex-foundation/mpl-toolbox"
+import { assert } from "chai"
+import { Stake } from "../../target/types/stake"
+import { createNewUser, programPaidBy } from "../helper"
+import { createCollection } from "../helpers/create-collection"
+import { createToken } from "../helpers/create-token"
+import {
+  addEmission,
+  addToken,
+  init,
+  initCollection,
+  requestUnstake,
+  sleep,
+  stake,
+  toggleCollection,
+  toggleStake,
+  unstake,
+  updateUnbondingPeriod,
+} from "../helpers/instructions"
+import { findStakeRecordPda, findStakooorCollectionId, getTokenAccount } from "../helpers/pdas"
+import { umi } from "../helpers/umi"
+import { assertErrorCode, expectFail, mintNfts } from "../helpers/utils"
+
+describe("Unbonding", () => {
+  let creator: Keypair
+  let creatorProgram: anchor.Program<Stake>
+  let user: Keypair
+  let userProgram: anchor.Program<Stake>
+  let collectionNft: DigitalAsset
+  let collection: PublicKey
+  let nfts: DigitalAsset[]
+  let token: PublicKey
+  const staker = umi.eddsa.generateKeypair()
+  const emission = umi.eddsa.generateKeypair()
+
+  before(async () => {
+    creator = await createNewUser()
+    user = await createNewUser()
+    creatorProgram = programPaidBy(creator)
+    userProgram = programPaidBy(user)
+    collectionNft = await createCollection(umi)
+    collection = findStakooorCollectionId(staker.publicKey, collectionNft.publicKey)
+    nfts = await mintNfts(collectionNft.publicKey, 1, false, user.publicKey)
+
+    token = await createToken(umi, BigInt(10_000), 0, undefined, creator.publicKey)
+    await setAuthority(umi, {
+      owned: token,
+      owner: umi.identity.publicKey,
+      newAuthority: creator.publicKey,
+      authorityType: AuthorityType.MintTokens,
+    }).sendAndConfirm(umi)
+
+    await init(creatorProgram, staker, "unbonding", "Unbonding", token)
+    await initCollection(creatorProgram, staker.publicKey, collectionNft.publicKey, false, null, 10)
+    await addToken(creatorProgram, staker.publicKey, token, false)
+    await addEmission(creatorProgram, emission, staker.publicKey, collection, { token: {} }, 1, 0, null, 3600)
+    await updateUnbondingPeriod(creatorProgram, staker.publicKey, collection, 3)
+    await toggleCollection(creatorProgram, staker.publicKey, collection, true)
+    await toggleStake(creatorProgram, staker.publicKey, true)
+
+    await stake(userProgram, staker.publicKey, nfts[0])
+  })
+
+  it("Cannot unstake without requesting it first", async () => {
+    await expectFail(
+      () => unstake(userProgram, staker.publicKey, nfts[0]),
+      (err) => assertErrorCode(err, "UnbondingRequired")
+    )
+  })
+
+  it("Can request an unstake and receive the rewards earned so far", async () => {
+    await sleep(1_000)
+    const balanceBefore = (await safeFetchToken(umi, getTokenAccount(token, user.publicKey)))?.amount || BigInt(0)
+    await requestUnstake(userProgram, staker.publicKey, nfts[0])
+    const balanceAfter = (await safeFetchToken(umi, getTokenAccount(token, user.publicKey)))?.amount || BigInt(0)
+
+    assert.ok(balanceAfter > balanceBefore, "Expected rewards to be paid")
+
+    const record = await userProgram.account.stakeRecord.fetch(findStakeRecordPda(staker.publicKey, nfts[0].publicKey))
+    assert.ok(record.unbondingAt, "Expected the record to be unbonding")
+  })
+
+  it("Cannot request an unstake twice", async () => {
+    await expectFail(
+      () => requestUnstake(userProgram, staker.publicKey, nfts[0]),
+      (err) => assertErrorCode(err, "Unbonding")
+    )
+  })
+
+  it("Cannot complete the unstake before the unbonding period ends", async () => {
+    await expectFail(
+      () => unstake(userProgram, staker.publicKey, nfts[0], null, true),
+      (err) => assertErrorCode(err, "UnbondingNotComplete")
+    )
+  })
+
+  it("Can complete the unstake once unbonded", async () => {
+    await sleep(4_000)
+    await unstake(userProgram, staker.publicKey, nfts[0], null, true)
+
+    const record = await userProgram.account.stakeRecord.fetchNullable(
+      findStakeRecordPda(staker.publicKey, nfts[0].publicKey)
+    )
+    assert.isNull(record, "Expected the stake record to be closed")
+  })
+})
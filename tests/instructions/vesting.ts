@@ -0,0 +1,96 @@
+import * as anchor from "@coral-xyz/anchor"
+import { Keypair, PublicKey } from "@metaplex-foundation/umi"
+import { DigitalAsset } from "@metaplex-foundation/mpl-token-metadata"
+import { safeFetchToken } from "@metaplex-foundation/mpl-toolbox"
+import { assert } from "chai"
+import { Stake } from "../../target/types/stake"
+import { createFixture } from "../helpers/fixture"
+import {
+  addEmission,
+  claim,
+  sleep,
+  stake,
+  toggleCollection,
+  toggleStake,
+  updateVesting,
+  withdrawVested,
+} from "../helpers/instructions"
+import { findVestingRecordPda, getTokenAccount } from "../helpers/pdas"
+import { umi } from "../helpers/umi"
+import { assertErrorCode, expectFail } from "../helpers/utils"
+
+describe("Vesting", () => {
+  let creatorProgram: anchor.Program<Stake>
+  let user: Keypair
+  let userProgram: anchor.Program<Stake>
+  let collection: PublicKey
+  let nfts: DigitalAsset[]
+  let token: PublicKey
+  const staker = umi.eddsa.generateKeypair()
+  const emission = umi.eddsa.generateKeypair()
+
+  async function balance() {
+    return (await safeFetchToken(umi, getTokenAccount(token, user.publicKey)))?.amount || BigInt(0)
+  }
+
+  before(async () => {
+    ;({ creatorProgram, user, userProgram, collection, nfts, token } = await createFixture(
+      staker,
+      "vesting",
+      "Vesting",
+      { numNfts: 1, tokenVault: false }
+    ))
+
+    await addEmission(creatorProgram, emission, staker.publicKey, collection, { token: {} }, 1, 0, null, 3600)
+    await toggleCollection(creatorProgram, staker.publicKey, collection, true)
+    await toggleStake(creatorProgram, staker.publicKey, true)
+  })
+
+  it("Cannot set a cliff longer than the vesting duration", async () => {
+    await expectFail(
+      () => updateVesting(creatorProgram, staker.publicKey, collection, emission.publicKey, { cliff: 10, duration: 5 }),
+      (err) => assertErrorCode(err, "InvalidVesting")
+    )
+  })
+
+  it("Vests claimed rewards instead of paying them out", async () => {
+    await updateVesting(creatorProgram, staker.publicKey, collection, emission.publicKey, { cliff: 3, duration: 6 })
+    await stake(userProgram, staker.publicKey, nfts[0])
+    await sleep(1_000)
+
+    const balanceBefore = await balance()
+    await claim(userProgram, staker.publicKey, nfts[0], emission.publicKey)
+    const balanceAfter = await balance()
+
+    assert.equal(balanceAfter, balanceBefore, "Expected no rewards to be paid out")
+
+    const vestingRecord = await userProgram.account.vestingRecord.fetch(
+      findVestingRecordPda(staker.publicKey, user.publicKey)
+    )
+    assert.ok(vestingRecord.total.toNumber() > 0, "Expected the rewards to be vesting")
+  })
+
+  it("Cannot withdraw before the cliff", async () => {
+    await expectFail(
+      () => withdrawVested(userProgram, staker.publicKey),
+      (err) => assertErrorCode(err, "NothingToWithdraw")
+    )
+  })
+
+  it("Can withdraw the rewards once vested", async () => {
+    await sleep(7_000)
+    const vestingRecord = await userProgram.account.vestingRecord.fetch(
+      findVestingRecordPda(staker.publicKey, user.publicKey)
+    )
+
+    const balanceBefore = await balance()
+    await withdrawVested(userProgram, staker.publicKey)
+    const balanceAfter = await balance()
+
+    assert.equal(
+      balanceAfter - balanceBefore,
+      BigInt(vestingRecord.total.toString()),
+      "Expected all vested rewards to be withdrawn"
+    )
+  })
+})